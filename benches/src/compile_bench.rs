@@ -9,7 +9,7 @@ use criterion::{criterion_group, criterion_main};
 
 fn base_compile(source: &str) {
     let option = CompileOption {
-        is_native_tag: |t| t != "draggable-header-view" && t != "tree-item",
+        is_native_tag: |t, _| t != "draggable-header-view" && t != "tree-item",
         is_dev: false,
         ..Default::default()
     };
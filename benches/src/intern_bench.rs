@@ -0,0 +1,44 @@
+use compiler::codegen::ScriptMode;
+use compiler::compiler::BaseCompiler;
+use compiler::compiler::{CompileOption, TemplateCompiler, get_base_passes};
+
+use criterion::Criterion;
+use criterion::{criterion_group, criterion_main};
+
+// run with and without `--features intern` to compare
+const MODE: &str = if cfg!(feature = "intern") {
+    "interned"
+} else {
+    "plain"
+};
+
+fn repeated_bindings() -> String {
+    let item = r#"<comp :item-name="name" @update-item="name = $event">{{ name }}</comp>"#;
+    format!("<div>{}</div>", item.repeat(200))
+}
+
+fn compile(source: &str) {
+    let option = CompileOption {
+        is_native_tag: |t, _| t != "comp",
+        is_dev: false,
+        // prefix bindings as `_ctx.name`
+        mode: ScriptMode::Module {
+            runtime_module_name: "vue".into(),
+        },
+        ..Default::default()
+    };
+    let sfc_info = Default::default();
+    let dest = || vec![];
+    let compiler = BaseCompiler::new(dest, get_base_passes, option);
+    compiler.compile(source, &sfc_info).unwrap();
+}
+
+fn bench_intern(c: &mut Criterion) {
+    let source = repeated_bindings();
+    c.bench_function(&format!("repeated_bindings/{}", MODE), |b| {
+        b.iter(|| compile(&source));
+    });
+}
+
+criterion_group!(benches, bench_intern);
+criterion_main!(benches);
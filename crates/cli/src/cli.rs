@@ -2,7 +2,7 @@ use super::CliInput;
 use anyhow::Result;
 use compiler::compiler::{get_base_passes, BaseCompiler, TemplateCompiler};
 use serde_yaml::to_writer;
-use std::io;
+use std::{fs, io};
 
 pub(super) fn compile_to_stdout(debug: CliInput) -> Result<()> {
     let (source, option, show) = debug;
@@ -42,7 +42,20 @@ pub(super) fn compile_to_stdout(debug: CliInput) -> Result<()> {
         to_writer(stdout.lock(), &ir)?;
         println!(r#"======== End of Transform ========"#);
     }
+    // stderr keeps the generated code on stdout intact
+    if show.explain_hoist {
+        for bailout in &ir.top_scope.hoist_bailouts {
+            eprintln!("hoist bailout at {}", bailout);
+        }
+    }
 
-    compiler.generate(ir, &sfc_info)?;
+    if let Some(path) = show.manifest {
+        let output = compiler.generate_output(ir, &sfc_info, &source)?;
+        if let Some(manifest) = output.manifest {
+            fs::write(path, manifest.to_json())?;
+        }
+    } else {
+        compiler.generate(ir, &sfc_info)?;
+    }
     Ok(())
 }
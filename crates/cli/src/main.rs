@@ -45,6 +45,12 @@ struct Opts {
     /// Display the optimized IR after transformation
     #[clap(short = 't', long)]
     dump_transform: bool,
+    /// Write a JSON manifest of inputs and outputs to the file for build systems
+    #[clap(long)]
+    manifest: Option<PathBuf>,
+    /// Hoist static nodes and print why other elements are not hoisted
+    #[clap(long)]
+    explain_hoist: bool,
 }
 
 struct ShowOption {
@@ -52,6 +58,8 @@ struct ShowOption {
     dump_parse: bool,
     dump_convert: bool,
     dump_transform: bool,
+    manifest: Option<PathBuf>,
+    explain_hoist: bool,
 }
 
 fn main() -> Result<()> {
@@ -68,6 +76,9 @@ fn process(opts: Opts) -> Result<CliInput> {
     let option = CompileOption {
         delimiters,
         error_handler: Rc::new(err_handle),
+        hoist_static: opts.explain_hoist,
+        explain_hoist: opts.explain_hoist,
+        manifest: opts.manifest.is_some(),
         ..Default::default()
     };
     let show = ShowOption {
@@ -75,6 +86,8 @@ fn process(opts: Opts) -> Result<CliInput> {
         dump_parse: opts.dump_parse,
         dump_convert: opts.dump_convert,
         dump_transform: opts.dump_transform,
+        manifest: opts.manifest,
+        explain_hoist: opts.explain_hoist,
     };
     Ok((source, option, show))
 }
@@ -7,27 +7,32 @@ use codespan_reporting::{
     },
 };
 use compiler::error::{CompilationError, ErrorHandler};
+use std::cell::Cell;
 
 #[derive(Clone)]
 pub struct PrettyErrorHandler {
     name: String,
     source: String,
+    count: Cell<usize>,
 }
 
 impl PrettyErrorHandler {
     pub fn new(name: String, source: String) -> Self {
-        Self { name, source }
+        Self {
+            name,
+            source,
+            count: Cell::new(0),
+        }
     }
 }
-impl ErrorHandler for PrettyErrorHandler {
-    fn on_error(&self, err: CompilationError) {
+impl PrettyErrorHandler {
+    fn emit(&self, diagnostic: Diagnostic<usize>, err: CompilationError) {
         let mut files = SimpleFiles::new();
         let default_vue = files.add(&self.name, &self.source);
-        let diagnostic = Diagnostic::error().with_labels(vec![Label::primary(
-            default_vue,
-            err.location.clone(),
-        )
-        .with_message(format!("{}", err))]);
+        let diagnostic =
+            diagnostic
+                .with_labels(vec![Label::primary(default_vue, err.location.clone())
+                    .with_message(format!("{}", err))]);
 
         let writer = StandardStream::stderr(ColorChoice::Always);
         let config = codespan_reporting::term::Config::default();
@@ -36,3 +41,16 @@ impl ErrorHandler for PrettyErrorHandler {
             .expect("unable to generate codespan diagnostic");
     }
 }
+
+impl ErrorHandler for PrettyErrorHandler {
+    fn on_error(&self, err: CompilationError) {
+        self.count.set(self.count.get() + 1);
+        self.emit(Diagnostic::error(), err);
+    }
+    fn on_warn(&self, err: CompilationError) {
+        self.emit(Diagnostic::warning(), err);
+    }
+    fn error_count(&self) -> usize {
+        self.count.get()
+    }
+}
@@ -1,17 +1,19 @@
 mod code_writer;
+mod source_map;
+
+pub use source_map::{skip_generated_lines, utf16_len};
 
 use crate::converter::BaseRoot;
+use crate::error::CompileError;
 use crate::SFCInfo;
+use crate::util::{no, CTX_IDENT};
 use crate::ir::{self as C, ConvertInfo, IRNode, IRRoot};
 use code_writer::CodeWriter;
+use rustc_hash::FxHashMap;
 
 use smallvec::{smallvec, SmallVec};
 use std::marker::PhantomData;
-use std::{
-    borrow::Cow,
-    rc::Rc,
-    io::{self, Write as ioWrite},
-};
+use std::{borrow::Cow, rc::Rc, io::Write as ioWrite};
 
 pub trait CodeGenerator {
     type IR<'a>;
@@ -45,15 +47,75 @@ pub enum ScriptMode {
     },
 }
 
+/// Which render function to generate.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum RenderMode {
+    /// `render(_ctx, _cache)` returning vnode tree for client side.
+    Client,
+    /// `ssrRender(_ctx, _push, _parent, _attrs)` pushing HTML strings.
+    /// Only text and comment nodes are supported now.
+    Ssr,
+}
+
+/// How prefixed identifiers access the component instance.
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub enum PrefixStyle {
+    /// `_ctx.foo`, member of the render function's context parameter.
+    CtxMember,
+    /// `this.foo`, for builds that only render with Options API.
+    ThisMember,
+}
+
+/// Indentation of generated code.
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum IndentStyle {
+    /// `indent_size` spaces per level.
+    Spaces,
+    /// One tab per level.
+    Tabs,
+    /// Single line output without line breaks, e.g. for minified builds.
+    None,
+}
+
 #[derive(Clone)]
 pub struct CodeGenerateOption {
     pub is_dev: bool,
     pub mode: ScriptMode,
+    pub render_mode: RenderMode,
+    /// CSS variables object rendered as `style` of SSR root elements.
+    pub ssr_css_vars: Option<String>,
+    /// Generate source map JSON alongside the code.
     pub source_map: bool,
+    /// Template file name used as the `sources` entry of source map.
+    pub filename: String,
+    /// Annotate render function parameters with TypeScript types.
+    /// `with` block is never used since TypeScript does not allow it.
+    pub is_ts: bool,
+    /// TS type of slot props keyed by slot name, for inline TS output.
+    pub slots_types: FxHashMap<String, String>,
     pub helper_strs: &'static [&'static str],
+    /// Pad output so an element's vnode call starts no earlier than its template line.
+    /// This is best-effort: preamble, hoists and multi-line expressions can push
+    /// output past the template line, and lines are never removed to catch up.
+    pub preserve_lines: bool,
+    /// Prefix render function and hoists with JSDoc type hints for
+    /// type-checked JS. Ignored if `is_ts` is set.
+    pub emit_jsdoc: bool,
+    /// Name of the render function parameter holding the component instance.
+    pub ctx_identifier: String,
+    /// Whether prefixed identifiers and `with` block use the parameter or `this`.
+    pub prefix_style: PrefixStyle,
+    pub indent_style: IndentStyle,
+    /// Spaces per indent level, used by `IndentStyle::Spaces` only.
+    pub indent_size: u8,
+    /// Elements rendered without closing tag in SSR output, e.g. `<br>`.
+    pub is_void_tag: fn(&str) -> bool,
 }
 impl CodeGenerateOption {
     fn use_with_scope(&self) -> bool {
+        if self.is_ts {
+            return false;
+        }
         match self.mode {
             ScriptMode::Function {
                 prefix_identifier, ..
@@ -61,6 +123,25 @@ impl CodeGenerateOption {
             ScriptMode::Module { .. } => false,
         }
     }
+    /// object accessed by prefixed identifiers and `with` block
+    fn ctx_object(&self) -> &str {
+        match self.prefix_style {
+            PrefixStyle::CtxMember => &self.ctx_identifier,
+            PrefixStyle::ThisMember => "this",
+        }
+    }
+    /// module to import types from in JSDoc, None if JSDoc is disabled
+    fn jsdoc_module(&self) -> Option<&str> {
+        if !self.emit_jsdoc || self.is_ts {
+            return None;
+        }
+        match &self.mode {
+            ScriptMode::Module {
+                runtime_module_name,
+            } => Some(runtime_module_name),
+            ScriptMode::Function { .. } => Some("vue"),
+        }
+    }
 }
 impl Default for CodeGenerateOption {
     fn default() -> Self {
@@ -70,8 +151,20 @@ impl Default for CodeGenerateOption {
                 prefix_identifier: false,
                 runtime_global_name: "Vue".into(),
             },
+            render_mode: RenderMode::Client,
+            ssr_css_vars: None,
             source_map: false,
+            filename: String::new(),
+            is_ts: false,
+            slots_types: FxHashMap::default(),
             helper_strs: &[],
+            preserve_lines: false,
+            emit_jsdoc: false,
+            ctx_identifier: CTX_IDENT.into(),
+            prefix_style: PrefixStyle::CtxMember,
+            indent_style: IndentStyle::Spaces,
+            indent_size: 2,
+            is_void_tag: no,
         }
     }
 }
@@ -90,6 +183,7 @@ pub trait CoreCodeGenerator<T: ConvertInfo> {
             IR::AlterableSlot(a) => self.generate_alterable_slot(a),
             IR::CacheNode(cache) => self.generate_cache(cache),
             IR::CommentCall(c) => self.generate_comment(c),
+            IR::Hoisted(i) => self.generate_hoisted(i),
         }
     }
     fn generate_prologue(&mut self, t: &mut IRRoot<T>) -> Self::Written;
@@ -104,6 +198,7 @@ pub trait CoreCodeGenerator<T: ConvertInfo> {
     fn generate_cache(&mut self, c: C::CacheIR<T>) -> Self::Written;
     fn generate_js_expr(&mut self, e: T::JsExpression) -> Self::Written;
     fn generate_comment(&mut self, c: T::CommentType) -> Self::Written;
+    fn generate_hoisted(&mut self, index: usize) -> Self::Written;
 }
 
 pub struct CodeGen<T: ioWrite> {
@@ -113,6 +208,8 @@ pub struct CodeGen<T: ioWrite> {
 pub struct CodeGenInfo<'a, T: ioWrite> {
     pub writer: T,
     pub sfc_info: &'a SFCInfo<'a>,
+    /// template source, needed only if source map is enabled
+    pub source: &'a str,
 }
 
 impl<T: ioWrite> CodeGen<T> {
@@ -124,15 +221,50 @@ impl<T: ioWrite> CodeGen<T> {
     }
 }
 
+/// By-products of code generation besides the written code.
+pub struct CodeGenOutput {
+    /// source map JSON if enabled
+    pub map: Option<String>,
+    /// number of `_cache` slots used in render function
+    pub cache_count: usize,
+    /// number of `_hoisted_x` constants
+    pub hoist_count: usize,
+    /// bytes written to writer
+    pub output_bytes: usize,
+    /// bytes of helper imports and hoists before the render function.
+    /// Inline render is injected into setup() without them.
+    pub preamble_bytes: usize,
+}
+
 impl<T: ioWrite> CodeGenerator for CodeGen<T> {
     type IR<'a> = BaseRoot<'a>;
     type Info<'a> = CodeGenInfo<'a, T>;
-    type Output = io::Result<()>;
+    type Output = Result<CodeGenOutput, CompileError>;
 
     fn generate<'a>(&self, root: BaseRoot<'a>, info: Self::Info<'a>) -> Self::Output {
-        let mut imp = CodeWriter::new(info.writer, self.option.clone(), info.sfc_info);
-        imp.generate_root(root)
-            .map_err(|_| imp.writer.get_io_error())
+        let CodeGenInfo {
+            writer,
+            sfc_info,
+            source,
+        } = info;
+        let mut imp = CodeWriter::new(writer, self.option.clone(), sfc_info);
+        if self.option.source_map && !source.is_empty() {
+            imp.track_source(source);
+        }
+        if imp.generate_root(root).is_err() {
+            return Err(match imp.take_unsupported() {
+                Some(description) => CompileError::UnsupportedSyntax { description },
+                None => CompileError::Io(imp.writer.get_io_error()),
+            });
+        }
+        let map = imp.take_source_map();
+        Ok(CodeGenOutput {
+            map: map.map(|m| m.into_json(&self.option.filename)),
+            cache_count: imp.cache_count(),
+            hoist_count: imp.hoist_count(),
+            output_bytes: imp.writer.written_bytes(),
+            preamble_bytes: imp.preamble_bytes(),
+        })
     }
 }
 
@@ -148,4 +280,7 @@ impl<'a> From<&'a str> for DecodedStr<'a> {
     }
 }
 
+/// The second argument is true for attribute value, where legacy named
+/// reference without semicolon followed by `=` or alphanumeric is kept,
+/// e.g. `?a=1&copy=2`.
 pub type EntityDecoder = fn(&str, bool) -> DecodedStr<'_>;
@@ -1,14 +1,18 @@
-use super::{CodeGenerateOption, ScriptMode, CoreCodeGenerator};
+use super::source_map::{utf16_len, SourceMapBuilder};
+use super::{CodeGenerateOption, CoreCodeGenerator, IndentStyle, RenderMode, ScriptMode};
 use crate::flags::{HelperCollector, PatchFlag, RuntimeHelper as RH, SlotFlag};
+use crate::converter::v_bind::{DYNAMIC_ATTR_PREFIX, DYNAMIC_PROP_PREFIX};
 use crate::converter::v_on::get_handler_type;
-use crate::converter::{BaseConvertInfo, BaseIR, BaseRoot, TopScope};
+use crate::converter::{BaseConvertInfo, BaseIR, BaseRoot, Hoist, TopScope};
 use crate::transformer::{
     BaseFor, BaseIf, BaseRenderSlot, BaseSlotFn, BaseText, BaseVNode, BaseVSlot, BaseCache,
+    hoist_static::hoisted_ref,
 };
 use crate::ir::{self as C, IRNode, JsExpr as Js, RenderSlotIR, RuntimeDir, VNodeIR, HandlerType};
-use crate::util::{get_vnode_call_helper, is_simple_identifier, VStr};
-use crate::SFCInfo;
+use crate::util::{get_vnode_call_helper, is_reserved_prop, is_simple_identifier, VStr};
+use crate::{SFCInfo, SourceLocation};
 
+use rustc_hash::FxHashSet;
 use smallvec::SmallVec;
 use std::{
     fmt::{self, Write},
@@ -22,12 +26,34 @@ type Output = fmt::Result;
 pub struct WriteAdaptor<T: ioWrite> {
     inner: T,
     io_error: Option<io::Error>,
+    /// 1-based line number of the current output position
+    line: usize,
+    /// 0-based UTF-16 column of the current output position, for source map
+    column: usize,
+    /// total bytes written
+    bytes: usize,
+    /// spaces held back until non-newline content follows,
+    /// so that lines never end with whitespace
+    pending_spaces: usize,
+    /// tab indentation held back like `pending_spaces`, written before them
+    pending_tabs: usize,
+    /// number of consecutive newlines at the end of output
+    trailing_newlines: usize,
+    /// whitespace in template literal is part of the string, never held back
+    in_template_literal: bool,
 }
 impl<T: ioWrite> WriteAdaptor<T> {
     fn new(inner: T) -> Self {
         Self {
             inner,
             io_error: None,
+            line: 1,
+            column: 0,
+            bytes: 0,
+            pending_spaces: 0,
+            pending_tabs: 0,
+            trailing_newlines: 0,
+            in_template_literal: false,
         }
     }
     pub fn get_io_error(&mut self) -> io::Error {
@@ -35,13 +61,35 @@ impl<T: ioWrite> WriteAdaptor<T> {
             .take()
             .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "unexpected fmt error"))
     }
-}
-
-impl<T: ioWrite> fmt::Write for WriteAdaptor<T> {
-    #[inline(always)]
-    fn write_str(&mut self, s: &str) -> Output {
+    pub fn written_bytes(&self) -> usize {
+        self.bytes
+    }
+    /// column where next content starts, including held back indentation
+    fn next_column(&self) -> usize {
+        self.column + self.pending_tabs + self.pending_spaces
+    }
+    fn write_raw(&mut self, s: &str) -> Output {
+        if s.is_empty() {
+            return Ok(());
+        }
         match self.inner.write_all(s.as_bytes()) {
-            Ok(()) => Ok(()),
+            Ok(()) => {
+                self.bytes += s.len();
+                match s.rfind('\n') {
+                    Some(i) => {
+                        self.line += s.bytes().filter(|&b| b == b'\n').count();
+                        self.column = utf16_len(&s[i + 1..]);
+                    }
+                    None => self.column += utf16_len(s),
+                }
+                let content = s.trim_end_matches('\n');
+                if content.is_empty() {
+                    self.trailing_newlines += s.len();
+                } else {
+                    self.trailing_newlines = s.len() - content.len();
+                }
+                Ok(())
+            }
             Err(err) => {
                 self.io_error = Some(err);
                 Err(fmt::Error)
@@ -50,15 +98,106 @@ impl<T: ioWrite> fmt::Write for WriteAdaptor<T> {
     }
 }
 
+impl<T: ioWrite> fmt::Write for WriteAdaptor<T> {
+    fn write_str(&mut self, s: &str) -> Output {
+        if s.is_empty() {
+            return Ok(());
+        }
+        let tabs = std::mem::take(&mut self.pending_tabs);
+        let mut spaces = std::mem::take(&mut self.pending_spaces);
+        // held back whitespace is dropped at line end
+        if !s.starts_with('\n') || self.in_template_literal {
+            for _ in 0..tabs {
+                self.write_raw("\t")?;
+            }
+            while spaces > 0 {
+                let n = spaces.min(SPACES.len());
+                self.write_raw(&SPACES[..n])?;
+                spaces -= n;
+            }
+        }
+        if self.in_template_literal {
+            return self.write_raw(s);
+        }
+        let content = s.trim_end_matches(' ');
+        self.write_raw(content)?;
+        self.pending_spaces = s.len() - content.len();
+        Ok(())
+    }
+}
+
+const SPACES: &str = "                                ";
+
+/// use LF newline and strip whitespace at line end of multiline user
+/// expressions, except in template literals where it is part of the string.
+/// CR and CRLF are read as LF in template literals anyway.
+fn normalize_lines(s: &str) -> String {
+    let s = s.replace("\r\n", "\n").replace('\r', "\n");
+    let mut ret = String::with_capacity(s.len());
+    // '`' for template literal text, '{' for braces in its substitutions
+    let mut nesting = vec![];
+    let mut quote = None;
+    let mut escaped = false;
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && (quote.is_some() || nesting.last() == Some(&'`')) {
+            escaped = true;
+        } else if let Some(q) = quote {
+            // unterminated quote, e.g. an apostrophe in comment
+            if c == q || c == '\n' {
+                quote = None;
+            }
+        } else if nesting.last() == Some(&'`') {
+            if c == '`' {
+                nesting.pop();
+            } else if c == '$' && chars.peek() == Some(&'{') {
+                ret.push(c);
+                ret.push('{');
+                chars.next();
+                nesting.push('{');
+                continue;
+            }
+        } else {
+            match c {
+                '\'' | '"' => quote = Some(c),
+                '`' => nesting.push('`'),
+                '{' if !nesting.is_empty() => nesting.push('{'),
+                '}' if nesting.last() == Some(&'{') => {
+                    nesting.pop();
+                }
+                _ => (),
+            }
+        }
+        if c == '\n' && quote.is_none() && nesting.last() != Some(&'`') {
+            let line_end = ret.trim_end_matches(|c: char| c.is_whitespace() && c != '\n');
+            let len = line_end.len();
+            ret.truncate(len);
+        }
+        ret.push(c);
+    }
+    ret
+}
+
 pub struct CodeWriter<'a, T: ioWrite> {
     pub writer: WriteAdaptor<T>,
     option: Rc<CodeGenerateOption>,
     sfc_info: &'a SFCInfo<'a>,
     indent_level: usize,
     closing_brackets: usize,
+    /// `_cache` slots are allocated while writing, after all passes ran,
+    /// so indices stay dense and in source order if passes drop nodes.
     cache_count: usize,
+    hoist_count: usize,
+    /// bytes of imports and hoists written before the render function
+    preamble_bytes: usize,
     in_alterable: bool,
     helpers: HelperCollector,
+    source_map: Option<SourceMapBuilder<'a>>,
+    /// set when generation aborts on IR the writer cannot handle,
+    /// fmt::Error alone cannot tell it from io failure
+    unsupported: Option<&'static str>,
 }
 impl<'a, T: ioWrite> CodeWriter<'a, T> {
     pub fn new(writer: T, option: Rc<CodeGenerateOption>, sfc_info: &'a SFCInfo<'a>) -> Self {
@@ -69,10 +208,43 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
             indent_level: 0,
             closing_brackets: 0,
             cache_count: 0,
+            hoist_count: 0,
+            preamble_bytes: 0,
             in_alterable: false,
             helpers: Default::default(),
+            source_map: None,
+            unsupported: None,
         }
     }
+    /// record mappings from template source to output
+    pub fn track_source(&mut self, source: &'a str) {
+        self.source_map = Some(SourceMapBuilder::new(source));
+    }
+    pub fn take_source_map(&mut self) -> Option<SourceMapBuilder<'a>> {
+        self.source_map.take()
+    }
+    /// number of `_cache` slots used in render function
+    pub fn cache_count(&self) -> usize {
+        self.cache_count
+    }
+    /// number of `_hoisted_x` constants generated
+    pub fn hoist_count(&self) -> usize {
+        self.hoist_count
+    }
+    /// length of output before the render function, see [CodeGenOutput]
+    ///
+    /// [CodeGenOutput]: super::CodeGenOutput
+    pub fn preamble_bytes(&self) -> usize {
+        self.preamble_bytes
+    }
+    /// description of the unsupported IR that aborted generation, if any
+    pub fn take_unsupported(&mut self) -> Option<&'static str> {
+        self.unsupported.take()
+    }
+    fn bail_unsupported(&mut self, description: &'static str) -> Output {
+        self.unsupported = Some(description);
+        Err(fmt::Error)
+    }
 }
 
 impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T> {
@@ -82,6 +254,10 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         self.generate_function_signature()?;
         self.generate_with_scope()?;
         self.generate_assets(&root.top_scope)?;
+        if self.option.render_mode == RenderMode::Ssr {
+            // ssrRender pushes strings instead of returning vnode
+            return self.newline();
+        }
         self.write_str("return ")
     }
     fn generate_epilogue(&mut self) -> Output {
@@ -140,8 +316,10 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         if self.in_alterable {
             return self.generate_render_list(f);
         }
-        // write open block
-        self.gen_open_block(f.is_stable, move |gen| {
+        // write open block, unstable fragment should not track its children.
+        // memoized items may be reused from cache, so tracking is disabled too
+        let has_memo = matches!(&*f.child, IRNode::CacheNode(_));
+        self.gen_open_block(!f.is_stable || has_memo, move |gen| {
             gen.write_helper(RH::CREATE_ELEMENT_BLOCK)?;
             gen.write_str("(")?;
             gen_v_for_args(gen, f)?;
@@ -163,7 +341,10 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         let stable_obj = s
             .stable_slots
             .into_iter()
-            .map(|f| (f.name, SlotFn(f.param, f.body)))
+            .map(|f| {
+                let name = static_slot_name(&f.name);
+                (f.name, SlotFn(f.param, f.body, name))
+            })
             .chain(iter::once(flag));
         // no alterable, output object literal. e.g. {default: ... }
         if s.alterable_slots.is_empty() {
@@ -182,21 +363,24 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
     }
     fn generate_cache(&mut self, c: BaseCache<'a>) -> Self::Written {
         use C::CacheKind as CK;
+        // allocate slot before child so nested caches get later indices
+        let index = self.cache_count;
+        self.cache_count += 1;
         match c.kind {
             CK::Once => {
-                write!(self.writer, "_cache[{}] || (", self.cache_count)?;
+                write!(self.writer, "_cache[{}] || (", index)?;
                 self.indent()?;
                 self.write_helper(RH::SET_BLOCK_TRACKING)?;
                 self.write_str("(-1),")?;
                 self.newline()?;
-                write!(self.writer, "_cache[{}] = ", self.cache_count)?;
+                write!(self.writer, "_cache[{}] = ", index)?;
                 self.generate_ir(*c.child)?;
                 self.write_str(",")?;
                 self.newline()?;
                 self.write_helper(RH::SET_BLOCK_TRACKING)?;
                 self.write_str("(1),")?;
                 self.newline()?;
-                write!(self.writer, "_cache[{}]", self.cache_count)?;
+                write!(self.writer, "_cache[{}]", index)?;
                 self.deindent()?;
                 self.write_str(")")?;
             }
@@ -206,40 +390,51 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
                 self.generate_js_expr(expr)?;
                 self.write_str(", () => ")?;
                 self.generate_ir(*c.child)?;
-                write!(self.writer, ", _cache, {})", self.cache_count)?;
+                write!(self.writer, ", _cache, {})", index)?;
             }
             CK::MemoInVFor { expr, v_for_key } => {
                 self.write_str("const _memo=(")?;
                 self.generate_js_expr(expr)?;
                 self.write_str(")")?;
-                self.newline()?;
+                self.end_statement()?;
                 self.write_str("if (_cached")?;
                 if let Some(key) = v_for_key {
-                    self.write_str(" && _cache.key === ")?;
+                    self.write_str(" && _cached.key === ")?;
                     self.generate_js_expr(key)?;
                 }
                 self.write_str(" && ")?;
                 self.write_helper(RH::IS_MEMO_SAME)?;
                 self.write_str("(_cached, _memo)) return _cached")?;
-                self.newline()?;
+                self.end_statement()?;
                 self.write_str("const _item = ")?;
                 self.generate_ir(*c.child)?;
-                self.newline()?;
+                self.end_statement()?;
                 self.write_str("_item.memo = _memo")?;
-                self.newline()?;
+                self.end_statement()?;
                 self.write_str("return _item")?;
             }
         }
-        self.cache_count += 1;
         Ok(())
     }
     fn generate_js_expr(&mut self, expr: Js<'a>) -> Output {
         match expr {
-            Js::Src(s) | Js::Param(s) => self.write_str(s),
+            Js::Src(s) => self.write_str(s),
+            Js::Param(s) => {
+                self.add_mapping(s);
+                self.write_user_src(s)
+            }
             Js::Num(n) => write!(self.writer, "{}", n),
-            Js::StrLit(mut l) => l.be_js_str().write_to(&mut self.writer),
-            Js::Simple(e, _) => e.write_to(&mut self.writer),
+            Js::StrLit(mut l) => {
+                self.add_mapping(l.raw);
+                l.be_js_str().write_to(&mut self.writer)
+            }
+            Js::Simple(e, _) => {
+                self.add_mapping(e.raw);
+                self.write_user_expr(e)
+            }
             Js::Symbol(s) => self.write_helper(s),
+            // vue-next emits null instead of empty object
+            Js::Props(p) if p.is_empty() => self.write_str("null"),
             Js::Props(p) => self.gen_obj_props(p, |gen, v| gen.generate_js_expr(v)),
             Js::Compound(v) => {
                 for e in v {
@@ -260,7 +455,10 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
             }
             Js::FuncSimple { src, cache, .. } => {
                 let ty = get_handler_type(src);
-                gen_handler(self, ty, cache, |gen| src.write_to(&mut gen.writer))
+                gen_handler(self, ty, cache, |gen| {
+                    gen.add_mapping(src.raw);
+                    gen.write_user_expr(src)
+                })
             }
             Js::FuncCompound {
                 body, ty, cache, ..
@@ -276,6 +474,7 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         debug_assert!(self.in_alterable);
         // switch back to normal mode
         self.in_alterable = false;
+        let name = static_slot_name(&s.name);
         self.write_str("{")?;
         self.indent()?;
         self.write_str("name: ")?;
@@ -283,7 +482,7 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         self.write_str(",")?;
         self.newline()?;
         self.write_str("fn: ")?;
-        gen_slot_fn(self, (s.param, s.body))?;
+        gen_slot_fn(self, (s.param, s.body), name)?;
         self.deindent()?;
         self.write_str("}")?;
         self.in_alterable = true;
@@ -294,30 +493,233 @@ impl<'a, T: ioWrite> CoreCodeGenerator<BaseConvertInfo<'a>> for CodeWriter<'a, T
         let call = Js::Call(RH::CREATE_COMMENT, vec![comment]);
         self.generate_js_expr(call)
     }
+    fn generate_hoisted(&mut self, index: usize) -> Output {
+        self.generate_js_expr(hoisted_ref(index))
+    }
 }
 
 impl<'a, T: ioWrite> CodeWriter<'a, T> {
     pub fn generate_root(&mut self, mut root: BaseRoot<'a>) -> Output {
         // get top scope entities
         self.helpers = root.top_scope.helpers.clone();
+        if self.option.render_mode == RenderMode::Ssr {
+            return self.generate_ssr_root(root);
+        }
 
         self.generate_prologue(&mut root)?;
         if root.body.is_empty() {
             self.write_str("null")?;
         } else {
-            let ir = if root.body.len() == 1 {
-                root.body.pop().unwrap()
-            } else {
-                IRNode::VNodeCall(VNodeIR {
-                    tag: Js::Symbol(RH::FRAGMENT),
-                    children: root.body,
-                    ..VNodeIR::default()
-                })
-            };
+            let ir = merge_root_body(root.body);
             self.generate_ir(ir)?;
         }
         self.generate_epilogue()
     }
+    /// ssrRender pushes HTML strings to `_push` instead of building vnodes
+    fn generate_ssr_root(&mut self, mut root: BaseRoot<'a>) -> Output {
+        // client vnode helpers are replaced by server renderer helpers
+        let css_vars = self.option.ssr_css_vars.clone();
+        if css_vars.is_some() {
+            for ir in root.body.iter_mut() {
+                inject_css_vars(ir);
+            }
+        }
+        let mut ssr_helpers = HelperCollector::new();
+        for ir in root.body.iter() {
+            collect_ssr_helpers(ir, &mut ssr_helpers);
+        }
+        self.helpers = HelperCollector::new();
+        root.top_scope.helpers = HelperCollector::new();
+        if !ssr_helpers.is_empty() {
+            let helpers = ssr_helpers.clone();
+            if matches!(self.option.mode, ScriptMode::Module { .. }) {
+                self.gen_helper_import(helpers, SSR_RUNTIME_MODULE)?;
+            } else {
+                let require = format!("require(\"{}\")", SSR_RUNTIME_MODULE);
+                self.gen_helper_destruct(helpers, &require)?;
+            }
+        }
+        self.generate_prologue(&mut root)?;
+        self.helpers = ssr_helpers;
+        if let Some(vars) = css_vars {
+            self.write_str("const _cssVars = { style: ")?;
+            self.write_str(&vars)?;
+            self.write_str(" }")?;
+            self.end_statement()?;
+        }
+        if root.top_scope.is_static {
+            // nothing in a static template changes, push its HTML at once
+            if !root.body.is_empty() {
+                self.gen_ssr_push(root.body)?;
+            }
+        } else {
+            for ir in root.body {
+                self.gen_ssr_push(vec![ir])?;
+            }
+        }
+        self.generate_epilogue()
+    }
+    fn gen_ssr_push(&mut self, nodes: Vec<BaseIR<'a>>) -> Output {
+        self.write_str("_push(`")?;
+        self.writer.in_template_literal = true;
+        for ir in nodes {
+            self.gen_ssr_node(ir)?;
+        }
+        self.writer.in_template_literal = false;
+        self.write_str("`)")?;
+        self.end_statement()
+    }
+    /// write node as HTML inside the template literal of `_push`
+    fn gen_ssr_node(&mut self, ir: BaseIR<'a>) -> Output {
+        match ir {
+            IRNode::TextCall(t) => {
+                for text in t.texts {
+                    self.gen_ssr_text(text)?;
+                }
+                Ok(())
+            }
+            IRNode::CommentCall(c) => {
+                self.write_str("<!--")?;
+                self.write_str(&escape_template_literal(c))?;
+                self.write_str("-->")
+            }
+            IRNode::VNodeCall(v) => self.gen_ssr_element(v),
+            _ => {
+                let description = "SSR codegen for structural directives and slots";
+                self.bail_unsupported(description)
+            }
+        }
+    }
+    fn gen_ssr_element(&mut self, v: BaseVNode<'a>) -> Output {
+        let tag = match v.tag {
+            Js::StrLit(t) if !v.is_component => t.into_string(),
+            _ => return self.bail_unsupported("SSR codegen for components and fragments"),
+        };
+        if !v.directives.is_empty() {
+            return self.bail_unsupported("SSR codegen for runtime directives");
+        }
+        let tag = escape_template_literal(&tag);
+        self.write_str("<")?;
+        self.write_str(&tag)?;
+        if let Some(props) = v.props {
+            self.gen_ssr_attrs(props)?;
+        }
+        self.write_str(">")?;
+        if (self.option.is_void_tag)(&tag) {
+            return Ok(());
+        }
+        for child in v.children {
+            self.gen_ssr_node(child)?;
+        }
+        self.write_str("</")?;
+        self.write_str(&tag)?;
+        self.write_str(">")
+    }
+    fn gen_ssr_attrs(&mut self, props: Js<'a>) -> Output {
+        match props {
+            Js::Props(ps) => {
+                for (key, value) in ps {
+                    self.gen_ssr_attr(key, value)?;
+                }
+                Ok(())
+            }
+            // props merged with v-bind object or normalized for dynamic keys
+            Js::Call(RH::MERGE_PROPS, args) | Js::Call(RH::NORMALIZE_PROPS, args) => {
+                for arg in args {
+                    self.gen_ssr_attrs(arg)?;
+                }
+                Ok(())
+            }
+            // v-on object renders nothing
+            Js::Call(RH::TO_HANDLERS, _) => Ok(()),
+            Js::Call(RH::GUARD_REACTIVE_PROPS, mut args) => {
+                let obj = args.pop().expect("guardReactiveProps takes one argument");
+                self.gen_ssr_attrs(obj)
+            }
+            // keys of v-bind object are validated by ssrRenderAttrs at runtime
+            obj => {
+                self.write_str("${")?;
+                self.write_helper(RH::SSR_RENDER_ATTRS)?;
+                self.write_str("(")?;
+                self.generate_js_expr(obj)?;
+                self.write_str(")}")
+            }
+        }
+    }
+    fn gen_ssr_attr(&mut self, key: Js<'a>, value: Js<'a>) -> Output {
+        let helper = match ssr_attr_helper(&key, &value) {
+            SsrAttr::Skip => return Ok(()),
+            SsrAttr::Static => return self.gen_ssr_static_attr(key, value),
+            SsrAttr::Helper(h) => h,
+        };
+        let value = match value {
+            Js::Call(RH::NORMALIZE_CLASS, mut args) | Js::Call(RH::NORMALIZE_STYLE, mut args) => {
+                args.pop().expect("normalize helpers take one argument")
+            }
+            v => v,
+        };
+        let key = match key {
+            // dynamic .attr key without `^` prefix
+            Js::Compound(mut v) if has_dynamic_prefix(&v, DYNAMIC_ATTR_PREFIX) => v.swap_remove(1),
+            k => k,
+        };
+        match helper {
+            RH::SSR_RENDER_CLASS => self.write_str(" class=\"${")?,
+            RH::SSR_RENDER_STYLE => self.write_str(" style=\"${")?,
+            _ => self.write_str("${")?,
+        }
+        self.write_helper(helper)?;
+        self.write_str("(")?;
+        if helper == RH::SSR_RENDER_ATTR {
+            let name = ssr_attr_name(&key).expect("rendered attribute must have name");
+            VStr::raw(&name).be_js_str().write_to(&mut self.writer)?;
+            self.write_str(", ")?;
+        } else if helper == RH::SSR_RENDER_DYNAMIC_ATTR {
+            // dynamic keys are never interpolated, ssrRenderDynamicAttr validates them
+            self.generate_js_expr(key)?;
+            self.write_str(", ")?;
+        }
+        self.generate_js_expr(value)?;
+        match helper {
+            RH::SSR_RENDER_CLASS | RH::SSR_RENDER_STYLE => self.write_str(")}\""),
+            _ => self.write_str(")}"),
+        }
+    }
+    fn gen_ssr_static_attr(&mut self, key: Js<'a>, value: Js<'a>) -> Output {
+        let name = ssr_attr_name(&key).expect("static attribute must have name");
+        let value = match value {
+            Js::StrLit(v) => v.into_string(),
+            Js::Props(ps) => static_style(&ps).expect("style must be static"),
+            _ => unreachable!("static attribute must have static value"),
+        };
+        self.write_str(" ")?;
+        self.write_str(&escape_template_literal(&name))?;
+        if !value.is_empty() {
+            let escaped = escape_html(&value);
+            write!(self.writer, "=\"{}\"", escape_template_literal(&escaped))?;
+        }
+        Ok(())
+    }
+    fn gen_ssr_text(&mut self, text: Js<'a>) -> Output {
+        match text {
+            Js::StrLit(s) => {
+                let escaped = escape_html(&s.into_string());
+                self.write_str(&escape_template_literal(&escaped))
+            }
+            Js::Call(RH::TO_DISPLAY_STRING, args) => {
+                self.write_str("${")?;
+                self.write_helper(RH::SSR_INTERPOLATE)?;
+                self.write_str("(")?;
+                self.gen_list(args)?;
+                self.write_str(")}")
+            }
+            expr => {
+                self.write_str("${")?;
+                self.generate_js_expr(expr)?;
+                self.write_str("}")
+            }
+        }
+    }
     /// for import helpers or hoist that not in function
     fn generate_preamble(&mut self, top: &mut TopScope<'a>) -> Output {
         match &self.option.clone().mode {
@@ -333,14 +735,19 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
     fn gen_function_preamble(&mut self, top: &mut TopScope<'a>, global_name: &str) -> Output {
         debug_assert!(top.helpers == self.helpers);
         if !self.helpers.is_empty() {
-            if self.option.use_with_scope() {
+            if self.use_with_scope() {
                 self.write_str("const _Vue = ")?;
                 self.write_str(global_name)?;
-                self.newline()?;
+                self.end_statement()?;
                 // helpers are declared inside with block, but hoists
                 // are lifted out so we need extract hoist helper here.
                 if !top.hoists.is_empty() {
-                    let hoist_helpers = self.helpers.hoist_helpers();
+                    let mut hoist_helpers = self.helpers.hoist_helpers();
+                    for hoist in top.hoists.iter() {
+                        if let Hoist::Expr(e) = hoist {
+                            collect_call_helpers(&mut hoist_helpers, e);
+                        }
+                    }
                     self.gen_helper_destruct(hoist_helpers, global_name)?;
                 }
             } else {
@@ -350,8 +757,14 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         }
         self.gen_hoist(top)?;
         self.newline()?;
+        self.preamble_bytes = self.writer.written_bytes();
+        self.gen_render_jsdoc()?;
         self.write_str("return ")
     }
+    /// inline render accesses setup bindings, which `with` cannot see
+    fn use_with_scope(&self) -> bool {
+        self.option.use_with_scope() && !self.sfc_info.inline
+    }
     fn should_gen_scope_id(&self) -> bool {
         self.sfc_info.scope_id.is_some() && matches!(self.option.mode, ScriptMode::Module { .. })
     }
@@ -360,18 +773,33 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
             self.helpers.collect(RH::PUSH_SCOPE_ID);
             self.helpers.collect(RH::POP_SCOPE_ID);
         }
-        if !self.helpers.is_empty() {
-            let helpers = self.helpers.clone();
-            self.gen_helper_import(helpers, module_name)?;
+        // VNode is the return type of TS render function
+        let import_vnode = self.option.is_ts && self.option.render_mode == RenderMode::Client;
+        if !self.helpers.is_empty() || import_vnode {
+            if !self.helpers.is_empty() {
+                let helpers = self.helpers.clone();
+                self.gen_helper_import(helpers, module_name)?;
+            }
+            if import_vnode {
+                write!(
+                    self.writer,
+                    "import type {{ VNode }} from \"{}\"",
+                    module_name
+                )?;
+                self.end_statement()?;
+            }
             self.newline()?;
         }
         self.gen_imports(top)?;
         self.gen_hoist(top)?;
         self.newline()?;
+        self.preamble_bytes = self.writer.written_bytes();
+        self.gen_render_jsdoc()?;
+        // inline render is embedded in setup(), so only standalone one is exported
         if self.sfc_info.inline {
-            self.write_str("export ")
-        } else {
             Ok(())
+        } else {
+            self.write_str("export ")
         }
     }
     fn gen_helper_import(&mut self, helpers: HelperCollector, from: &str) -> Output {
@@ -382,7 +810,7 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         self.write_str("} from \"")?;
         self.write_str(from)?;
         self.write_str("\"")?;
-        self.newline()
+        self.end_statement()
     }
     fn gen_helper_destruct(&mut self, helpers: HelperCollector, from: &str) -> Output {
         self.write_str("const {")?;
@@ -391,7 +819,7 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         self.deindent()?;
         self.write_str("} = ")?;
         self.write_str(from)?;
-        self.newline()
+        self.end_statement()
     }
     fn gen_helper_import_list(&mut self, helpers: HelperCollector, sep: &str) -> Output {
         for rh in helpers.into_iter() {
@@ -414,8 +842,8 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
             self.write_str("import ")?;
             self.generate_js_expr(impt.exp)?;
             self.write_str(" from ")?;
-            self.write_str(impt.path)?;
-            self.newline()?;
+            self.generate_js_expr(Js::StrLit(VStr::raw(impt.path)))?;
+            self.end_statement()?;
         }
         Ok(())
     }
@@ -426,56 +854,152 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         let gen_scope_id = self.should_gen_scope_id();
         if gen_scope_id {
             // generate inlined withScopeId helper
-            self.write_str("const _withScopeId = n => (")?;
+            if self.option.is_ts {
+                self.write_str("const _withScopeId = (n: any) => (")?;
+            } else {
+                self.write_str("const _withScopeId = n => (")?;
+            }
             self.write_helper(RH::PUSH_SCOPE_ID)?;
             let scope_id = self.sfc_info.scope_id.as_ref().unwrap();
-            write!(self.writer, "({}),n=n(),", scope_id)?;
+            write!(self.writer, "(\"{}\"),n=n(),", scope_id)?;
             self.write_helper(RH::POP_SCOPE_ID)?;
             self.write_str("(),n)")?;
-            self.newline()?;
+            self.end_statement()?;
         }
         // take hoists
+        let option = Rc::clone(&self.option);
         let mut hoists = vec![];
         std::mem::swap(&mut hoists, &mut top.hoists);
+        self.hoist_count = hoists.len();
         for (i, hoist) in hoists.into_iter().enumerate() {
-            let scope_id_wrapper = gen_scope_id && matches!(hoist, IRNode::VNodeCall { .. });
+            let scope_id_wrapper =
+                gen_scope_id && matches!(hoist, Hoist::Node(IRNode::VNodeCall { .. }));
             let wrapper = if scope_id_wrapper {
                 "_withScopeId(() => "
             } else {
                 ""
             };
-            write!(self.writer, "const _hoisted_{} = {}", i, wrapper)?;
-            self.generate_ir(hoist)?;
+            let ty = match hoist {
+                Hoist::Node(_) => Some("VNode"),
+                Hoist::Props(_) => Some("VNodeProps"),
+                Hoist::Expr(_) | Hoist::Static(_) => None,
+            };
+            if let (Some(module), Some(ty)) = (option.jsdoc_module(), ty) {
+                write!(self.writer, "/** @type {{import('{}').{}}} */", module, ty)?;
+                self.newline()?;
+            }
+            write!(self.writer, "const _hoisted_{} = {}", i + 1, wrapper)?;
+            match hoist {
+                Hoist::Node(ir) => self.generate_ir(ir)?,
+                Hoist::Props(props) | Hoist::Expr(props) => self.generate_js_expr(props)?,
+                Hoist::Static(code) => self.write_str(&code)?,
+            }
             if scope_id_wrapper {
                 self.write_str(")")?;
             }
-            self.newline()?;
+            self.end_statement()?;
         }
         Ok(())
     }
+    /// JSDoc block documenting render function types for checkJs
+    fn gen_render_jsdoc(&mut self) -> Output {
+        let option = Rc::clone(&self.option);
+        let module = match option.jsdoc_module() {
+            Some(m) => m,
+            None => return Ok(()),
+        };
+        let ctx = format!("import('{}').ComponentPublicInstance", module);
+        let mut params = vec![(&*ctx, &*option.ctx_identifier)];
+        let ret = match option.render_mode {
+            RenderMode::Client => {
+                params.push(("any[]", "_cache"));
+                format!("import('{}').VNode", module)
+            }
+            RenderMode::Ssr => {
+                params.push(("(item: any) => void", "_push"));
+                params.push(("any", "_parent"));
+                params.push(("Record<string, unknown>", "_attrs"));
+                "void".into()
+            }
+        };
+        let info = self.sfc_info;
+        let binding_params: &[_] = if !info.binding_metadata.is_empty() && !info.inline {
+            &["$props", "$setup", "$data", "$options"]
+        } else {
+            &[]
+        };
+        self.write_str("/**")?;
+        self.newline()?;
+        for (ty, name) in params {
+            write!(self.writer, " * @param {{{}}} {}", ty, name)?;
+            self.newline()?;
+        }
+        for name in binding_params {
+            write!(self.writer, " * @param {{any}} {}", name)?;
+            self.newline()?;
+        }
+        write!(self.writer, " * @returns {{{}}}", ret)?;
+        self.newline()?;
+        self.write_str(" */")?;
+        self.newline()
+    }
     /// render() or ssrRender() and their parameters
     fn generate_function_signature(&mut self) -> Output {
         let option = &self.sfc_info;
-        let args = if !option.binding_metadata.is_empty() && !option.inline {
-            "_ctx, _cache, $props, $setup, $data, $options"
+        let with_bindings = !option.binding_metadata.is_empty() && !option.inline;
+        let (name, params, ret): (_, &[_], _) = match self.option.render_mode {
+            RenderMode::Client => ("render", &["_cache"], "VNode"),
+            RenderMode::Ssr => ("ssrRender", &["_push", "_parent", "_attrs"], "void"),
+        };
+        let ctx = Rc::clone(&self.option);
+        let binding_params: &[_] = if with_bindings {
+            &["$props", "$setup", "$data", "$options"]
+        } else {
+            &[]
+        };
+        // inline render is an arrow function embedded in setup()
+        let inline = self.sfc_info.inline;
+        if inline {
+            self.write_str("(")?;
+        } else {
+            write!(self.writer, "function {}(", name)?;
+        }
+        // inline render is created inside setup() before the component exists
+        let ctx_type = if inline {
+            "any"
         } else {
-            "_ctx, _cache"
+            "InstanceType<typeof __VUE_COMPONENT__>"
         };
-        // NB: vue uses arrow func for inline mode.
-        // but it makes no diff in Vue runtime implementation?
-        self.write_str("function render(")?;
-        self.write_str(args)?;
-        self.write_str(") {")?;
+        self.write_str(&ctx.ctx_identifier)?;
+        if ctx.is_ts {
+            self.write_str(": ")?;
+            self.write_str(ctx_type)?;
+        }
+        for param in params.iter().chain(binding_params) {
+            self.write_str(", ")?;
+            self.write_str(param)?;
+            if ctx.is_ts {
+                let ty = if *param == "_cache" { "any[]" } else { "any" };
+                self.write_str(": ")?;
+                self.write_str(ty)?;
+            }
+        }
+        self.write_str(")")?;
+        if ctx.is_ts {
+            self.write_str(": ")?;
+            self.write_str(ret)?;
+        }
+        self.write_str(if inline { " => {" } else { " {" })?;
         self.closing_brackets += 1;
         self.indent()
     }
     /// with (ctx) for not prefixIdentifier
     fn generate_with_scope(&mut self) -> Output {
         let helpers = self.helpers.clone();
-        if !self.option.use_with_scope() {
+        if !self.use_with_scope() {
             return Ok(());
         }
-        self.write_str("with (_ctx) {")?;
+        write!(self.writer, "with ({}) {{", self.option.ctx_object())?;
         self.closing_brackets += 1;
         self.indent()?;
         if helpers.is_empty() {
@@ -520,8 +1044,11 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         } else {
             false
         };
-        if fast {
-            // generate sole text node without []
+        // component slots are passed as an object, not an array
+        let is_slot = matches!(children[0], IRNode::VSlotUse(_));
+        debug_assert!(!is_slot || children.len() == 1);
+        if fast || is_slot {
+            // generate sole text node or slot object without []
             let ir = children.into_iter().next().unwrap();
             return self.generate_ir(ir);
         }
@@ -548,9 +1075,11 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
         let p = f.parse_result;
         let mut params = vec![Some(p.value), p.key, p.index];
         if has_memo {
+            // the memo cache node is the first to allocate a slot in child
+            let index = self.cache_count;
             params.push(Some(Js::Src("_cached")));
             self.gen_func_expr(params, *f.child, /*need_return*/ false)?;
-            write!(self.writer, ", _cache, {}", self.cache_count - 1)?;
+            write!(self.writer, ", _cache, {}", index)?;
         } else {
             self.gen_func_expr(params, *f.child, /*need_return*/ true)?;
         }
@@ -647,13 +1176,20 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
             return self.gen_vnode_with_block(v);
         }
         let dirs = std::mem::take(&mut v.directives);
+        // stepping into withDirectives lands on the first directive
+        self.add_attr_mapping(&dirs[0].location);
         self.write_helper(RH::WITH_DIRECTIVES)?;
         self.write_str("(")?;
         self.gen_vnode_with_block(v)?;
-        self.write_str(", ")?;
-        let dir_arr = runtime_dirs_to_js_arr(dirs);
-        self.generate_js_expr(dir_arr)?;
-        self.write_str(")")
+        self.write_str(", [")?;
+        for (i, dir) in dirs.into_iter().enumerate() {
+            if i > 0 {
+                self.write_str(", ")?;
+            }
+            self.add_attr_mapping(&dir.location);
+            self.generate_js_expr(runtime_dir(dir))?;
+        }
+        self.write_str("])")
     }
     fn gen_vnode_with_block(&mut self, v: BaseVNode<'a>) -> Output {
         if !v.is_block {
@@ -677,13 +1213,50 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
     }
 
     fn newline(&mut self) -> Output {
-        self.write_str("\n")?;
-        // TODO: use exponential adding + lazy static
-        for _ in 0..self.indent_level {
-            self.write_str("  ")?;
+        let style = self.option.indent_style;
+        if style == IndentStyle::None {
+            // single line output, a space held back like indentation
+            if self.writer.written_bytes() > 0 {
+                self.writer.pending_spaces = 1;
+            }
+            return Ok(());
+        }
+        // at most one blank line, unless padding to preserve template lines
+        if self.writer.trailing_newlines < 2 || self.option.preserve_lines {
+            self.write_str("\n")?;
         }
+        // indentation is held back until the line has content
+        let level = self.indent_level;
+        let (tabs, spaces) = match style {
+            IndentStyle::Tabs => (level, 0),
+            _ => (0, level * self.option.indent_size as usize),
+        };
+        self.writer.pending_tabs = tabs;
+        self.writer.pending_spaces = spaces;
         Ok(())
     }
+    /// newline after a statement, which needs `;` if there is no line break
+    fn end_statement(&mut self) -> Output {
+        if self.option.indent_style == IndentStyle::None {
+            self.write_str(";")?;
+        }
+        self.newline()
+    }
+    /// Pad newlines until output reaches the template line, used by preserve_lines.
+    /// Returns true if padding happens. The padded expression is wrapped in
+    /// parenthesis because a newline after `return` would trigger ASI.
+    fn pad_to_line(&mut self, line: u32) -> Result<bool, fmt::Error> {
+        let line = line as usize;
+        let single_line = self.option.indent_style == IndentStyle::None;
+        if !self.option.preserve_lines || single_line || self.writer.line >= line {
+            return Ok(false);
+        }
+        self.write_str("(")?;
+        while self.writer.line < line {
+            self.newline()?;
+        }
+        Ok(true)
+    }
     fn indent(&mut self) -> Output {
         self.indent_level += 1;
         self.newline()
@@ -706,6 +1279,37 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
     fn write_str(&mut self, s: &str) -> Output {
         self.writer.write_str(s)
     }
+    /// only multiline source from template is normalized, the writer
+    /// itself never emits whitespace before a newline
+    fn write_user_src(&mut self, s: &str) -> Output {
+        if s.contains('\n') || s.contains('\r') {
+            self.write_str(&normalize_lines(s))
+        } else {
+            self.write_str(s)
+        }
+    }
+    fn write_user_expr(&mut self, e: VStr<'a>) -> Output {
+        let ctx = self.option.ctx_object();
+        if !e.raw.contains('\n') && !e.raw.contains('\r') {
+            return e.write_with_ctx(ctx, &mut self.writer);
+        }
+        let mut s = String::new();
+        e.write_with_ctx(ctx, &mut s)?;
+        self.write_str(&normalize_lines(&s))
+    }
+    #[inline(always)]
+    fn add_mapping(&mut self, src: &str) {
+        if let Some(map) = &mut self.source_map {
+            map.add(src, self.writer.line, self.writer.next_column());
+        }
+    }
+    /// map helper wrapper like withDirectives to the attribute generating it
+    fn add_attr_mapping(&mut self, loc: &SourceLocation) {
+        if let Some(map) = &mut self.source_map {
+            let range = loc.clone().into();
+            map.add_attr(range, self.writer.line, self.writer.next_column());
+        }
+    }
 
     #[inline(always)]
     fn write_helper(&mut self, h: RH) -> Output {
@@ -715,6 +1319,15 @@ impl<'a, T: ioWrite> CodeWriter<'a, T> {
     }
     #[inline(always)]
     fn write_patch(&mut self, flag: PatchFlag) -> Output {
+        // special flags are negative so Debug would list every bit flag
+        if flag == PatchFlag::HOISTED {
+            let hoisted = if self.option.is_dev {
+                "-1 /*HOISTED*/"
+            } else {
+                "-1"
+            };
+            return self.write_str(hoisted);
+        }
         if self.option.is_dev {
             write!(self.writer, "{} /*{:?}*/", flag.bits(), flag)
         } else {
@@ -734,13 +1347,19 @@ where
     F: FnOnce(&mut CodeWriter<'a, T>) -> Output,
 {
     if cache {
-        write!(gen.writer, "_cache[{}] || (", gen.cache_count)?;
+        let i = gen.cache_count;
+        write!(gen.writer, "_cache[{}] || (_cache[{}] = ", i, i)?;
     }
     match ty {
         HandlerType::FuncExpr => func(gen)?,
         HandlerType::MemberExpr => {
             if cache {
-                gen.write_str("(...args) => ")?;
+                let args = if gen.option.is_ts {
+                    "(...args: any[]) => "
+                } else {
+                    "(...args) => "
+                };
+                gen.write_str(args)?;
             }
             func(gen)?;
             if cache {
@@ -748,7 +1367,12 @@ where
             }
         }
         HandlerType::InlineStmt => {
-            gen.write_str("$event => (")?;
+            let event = if gen.option.is_ts {
+                "($event: any) => ("
+            } else {
+                "$event => ("
+            };
+            gen.write_str(event)?;
             func(gen)?;
             gen.write_str(")")?;
         }
@@ -760,12 +1384,200 @@ where
     Ok(())
 }
 
+/// hoisted expressions like modifier guards call helpers outside of with block
+fn collect_call_helpers(helpers: &mut HelperCollector, exp: &Js) {
+    if let Js::Call(rh, args) = exp {
+        helpers.collect(*rh);
+        for arg in args {
+            collect_call_helpers(helpers, arg);
+        }
+    }
+}
+
+/// multiple root nodes are wrapped in a Fragment
+fn merge_root_body(mut body: Vec<BaseIR<'_>>) -> BaseIR<'_> {
+    if body.len() == 1 {
+        return body.pop().unwrap();
+    }
+    IRNode::VNodeCall(VNodeIR {
+        tag: Js::Symbol(RH::FRAGMENT),
+        children: body,
+        ..VNodeIR::default()
+    })
+}
+
+/// `k:v;` pairs of a static style attribute, which is parsed into object
+fn static_style(props: &[C::Prop]) -> Option<String> {
+    let mut style = String::new();
+    for (k, v) in props {
+        match (k, v) {
+            (Js::StrLit(k), Js::StrLit(v)) => {
+                style.push_str(&k.into_string());
+                style.push(':');
+                style.push_str(&v.into_string());
+                style.push(';');
+            }
+            _ => return None,
+        }
+    }
+    Some(style)
+}
+
+/// How ssrRender writes an element prop into HTML.
+enum SsrAttr {
+    /// vdom only props and event handlers
+    Skip,
+    /// written into the template literal as is
+    Static,
+    /// rendered by server renderer helper at runtime
+    Helper(RH),
+}
+
+/// DOM props reflected to attributes of the same value, by attribute name
+const REFLECTED_PROPS: &[(&str, &str)] = &[
+    ("id", "id"),
+    ("title", "title"),
+    ("lang", "lang"),
+    ("dir", "dir"),
+    ("className", "class"),
+    ("htmlFor", "for"),
+];
+
+/// HTML attribute name of a static prop key. Forced DOM props (`.prop`)
+/// are set on client, only the ones reflected to attributes are rendered.
+fn ssr_attr_name(key: &Js) -> Option<String> {
+    let mut name = match key {
+        Js::StrLit(k) => *k,
+        _ => return None,
+    };
+    let is_prop = VStr::is_prop_prefixed(&name);
+    let name = name.unprefix().into_string();
+    if !is_prop {
+        return Some(name);
+    }
+    REFLECTED_PROPS
+        .iter()
+        .find(|(prop, _)| *prop == name)
+        .map(|(_, attr)| attr.to_string())
+}
+
+/// whether a compound dynamic key is prefixed by .prop or .attr
+fn has_dynamic_prefix(key: &[Js], prefix: &str) -> bool {
+    matches!(key.first(), Some(Js::Src(p)) if *p == prefix)
+}
+
+fn ssr_attr_helper(key: &Js, value: &Js) -> SsrAttr {
+    if matches!(value, Js::FuncSimple { .. } | Js::FuncCompound { .. }) {
+        return SsrAttr::Skip;
+    }
+    let k = match key {
+        Js::StrLit(k) => k,
+        Js::Call(RH::TO_HANDLER_KEY, _) => return SsrAttr::Skip,
+        // dynamic .prop key cannot be checked for reflection
+        Js::Compound(v) if has_dynamic_prefix(v, DYNAMIC_PROP_PREFIX) => return SsrAttr::Skip,
+        _ => return SsrAttr::Helper(RH::SSR_RENDER_DYNAMIC_ATTR),
+    };
+    // key and ref only exist in vdom
+    if VStr::is_handler(k) || is_reserved_prop(k.raw) {
+        return SsrAttr::Skip;
+    }
+    let name = match ssr_attr_name(key) {
+        Some(name) => name,
+        None => return SsrAttr::Skip,
+    };
+    match (&*name, value) {
+        (_, Js::StrLit(_)) => SsrAttr::Static,
+        ("style", Js::Props(ps)) if static_style(ps).is_some() => SsrAttr::Static,
+        ("class", _) => SsrAttr::Helper(RH::SSR_RENDER_CLASS),
+        ("style", _) => SsrAttr::Helper(RH::SSR_RENDER_STYLE),
+        _ => SsrAttr::Helper(RH::SSR_RENDER_ATTR),
+    }
+}
+
+/// bind `_cssVars` on root elements, the only ones whose style
+/// is not controlled by the parent component
+fn inject_css_vars(ir: &mut BaseIR) {
+    let v = match ir {
+        IRNode::VNodeCall(v) if !v.is_component => v,
+        _ => return,
+    };
+    // merge into existing style, or ssrRenderAttrs writes a second one
+    if let Some(Js::Props(ps)) = &mut v.props {
+        let style = ps
+            .iter_mut()
+            .find(|(k, _)| matches!(k, Js::StrLit(k) if k.raw == "style"));
+        if let Some((_, value)) = style {
+            let style = match std::mem::take(value) {
+                Js::Call(RH::NORMALIZE_STYLE, mut args) => {
+                    args.pop().expect("normalizeStyle takes one argument")
+                }
+                style => style,
+            };
+            *value = Js::Array(vec![style, Js::Src("_cssVars.style")]);
+            return;
+        }
+    }
+    let css_vars = Js::Src("_cssVars");
+    v.props = Some(match v.props.take() {
+        Some(props) => Js::Call(RH::MERGE_PROPS, vec![props, css_vars]),
+        None => css_vars,
+    });
+}
+
+/// server renderer helpers are imported before ssrRender is written
+fn collect_ssr_helpers(ir: &BaseIR, helpers: &mut HelperCollector) {
+    match ir {
+        IRNode::TextCall(t) => {
+            for text in t.texts.iter() {
+                if matches!(text, Js::Call(RH::TO_DISPLAY_STRING, _)) {
+                    helpers.collect(RH::SSR_INTERPOLATE);
+                }
+            }
+        }
+        IRNode::VNodeCall(v) => {
+            if let Some(props) = &v.props {
+                collect_ssr_attr_helpers(props, helpers);
+            }
+            for child in v.children.iter() {
+                collect_ssr_helpers(child, helpers);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn collect_ssr_attr_helpers(props: &Js, helpers: &mut HelperCollector) {
+    match props {
+        Js::Props(ps) => {
+            for (key, value) in ps {
+                if let SsrAttr::Helper(h) = ssr_attr_helper(key, value) {
+                    helpers.collect(h);
+                }
+            }
+        }
+        Js::Call(RH::MERGE_PROPS, args)
+        | Js::Call(RH::NORMALIZE_PROPS, args)
+        | Js::Call(RH::GUARD_REACTIVE_PROPS, args) => {
+            for arg in args {
+                collect_ssr_attr_helpers(arg, helpers);
+            }
+        }
+        Js::Call(RH::TO_HANDLERS, _) => (),
+        _ => helpers.collect(RH::SSR_RENDER_ATTRS),
+    }
+}
+
 fn gen_vnode_real<'a, T: ioWrite>(gen: &mut CodeWriter<'a, T>, v: BaseVNode<'a>) -> Output {
+    let padded = gen.pad_to_line(v.location.start.line)?;
     let call_helper = get_vnode_call_helper(&v);
     gen.write_helper(call_helper)?;
     gen.write_str("(")?;
     gen_vnode_call_args(gen, v)?;
-    gen.write_str(")")
+    gen.write_str(")")?;
+    if padded {
+        gen.write_str(")")?;
+    }
+    Ok(())
 }
 
 // no, repeating myself is good. macro is bad
@@ -830,10 +1642,7 @@ fn gen_vnode_call_args<'a, T: ioWrite>(gen: &mut CodeWriter<'a, T>, v: BaseVNode
             gen.write_patch(patch_flag)?;
         }
         !dynamic_props.is_empty(), {
-            let dps = dynamic_props.into_iter().map(Js::StrLit);
-            gen.write_str("[")?;
-            gen.gen_list(dps)?;
-            gen.write_str("]")?;
+            gen.generate_js_expr(stringify_dynamic_prop_names(dynamic_props))?;
         }
     );
     Ok(())
@@ -891,12 +1700,19 @@ fn gen_render_slot_args<'a, T: ioWrite>(
 }
 
 enum Slot<'a> {
-    SlotFn(Option<Js<'a>>, Vec<BaseIR<'a>>),
+    /// param, body and static slot name
+    SlotFn(Option<Js<'a>>, Vec<BaseIR<'a>>, Option<&'a str>),
     Flag(SlotFlag),
 }
+fn static_slot_name<'a>(name: &Js<'a>) -> Option<&'a str> {
+    match name {
+        Js::StrLit(n) => Some(n.raw),
+        _ => None,
+    }
+}
 fn gen_stable_slot_fn<'a, T: ioWrite>(gen: &mut CodeWriter<'a, T>, slot: Slot<'a>) -> Output {
     match slot {
-        Slot::SlotFn(param, body) => gen_slot_fn(gen, (param, body)),
+        Slot::SlotFn(param, body, name) => gen_slot_fn(gen, (param, body), name),
         Slot::Flag(flag) => {
             write!(gen.writer, "{} /*{:?}*/", flag as u8, flag)
         }
@@ -905,12 +1721,22 @@ fn gen_stable_slot_fn<'a, T: ioWrite>(gen: &mut CodeWriter<'a, T>, slot: Slot<'a
 fn gen_slot_fn<'a, T: ioWrite>(
     gen: &mut CodeWriter<'a, T>,
     (param, body): (Option<Js<'a>>, Vec<BaseIR<'a>>),
+    name: Option<&'a str>,
 ) -> Output {
     gen.write_helper(RH::WITH_CTX)?;
     gen.write_str("(")?;
     gen.write_str("(")?;
     if let Some(p) = param {
         gen.generate_js_expr(p)?;
+        // only inline render shares scope with the slot types in script
+        if gen.option.is_ts {
+            let option = Rc::clone(&gen.option);
+            let ty = name
+                .filter(|_| gen.sfc_info.inline)
+                .and_then(|n| option.slots_types.get(n));
+            gen.write_str(": ")?;
+            gen.write_str(ty.map_or("any", String::as_str))?;
+        }
     }
     gen.write_str(") => [")?;
     gen.indent()?;
@@ -943,19 +1769,31 @@ fn gen_assets<'a, T: ioWrite>(
         gen.write_str(" = ")?;
         gen.write_helper(resolver)?;
         gen.write_str("(")?;
-        let raw = if resolver == RH::RESOLVE_COMPONENT {
+        let mut raw = if resolver == RH::RESOLVE_COMPONENT {
             *asset.clone().unbe_component()
         } else {
             *asset.clone().unbe_directive()
         };
-        raw.write_to(&mut gen.writer)?;
+        raw.be_js_str().write_to(&mut gen.writer)?;
         gen.write_str(hint)?;
         gen.write_str(")")?;
-        gen.newline()?;
+        gen.end_statement()?;
     }
     Ok(())
 }
 
+/// Sort dynamic prop names so output does not depend on hash order.
+fn stringify_dynamic_prop_names(props: FxHashSet<VStr>) -> Js {
+    let mut names: Vec<_> = props.into_iter().collect();
+    // props with the same raw name differ in affix, e.g. value and .value
+    names.sort_unstable_by(|a, b| {
+        a.raw
+            .cmp(b.raw)
+            .then_with(|| a.into_string().cmp(&b.into_string()))
+    });
+    Js::Array(names.into_iter().map(Js::StrLit).collect())
+}
+
 fn runtime_dir(dir: RuntimeDir<BaseConvertInfo>) -> Js {
     let arr = vec![Some(dir.name), dir.expr, dir.arg, dir.mods];
     let last = arr
@@ -971,9 +1809,30 @@ fn runtime_dir(dir: RuntimeDir<BaseConvertInfo>) -> Js {
     Js::Array(arr)
 }
 
-fn runtime_dirs_to_js_arr(dirs: Vec<RuntimeDir<BaseConvertInfo>>) -> Js {
-    let dirs = dirs.into_iter().map(runtime_dir).collect();
-    Js::Array(dirs)
+const SSR_RUNTIME_MODULE: &str = "vue/server-renderer";
+
+/// same as @vue/shared escapeHtml, text is decoded in template so encode it back
+fn escape_html(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => ret.push_str("&quot;"),
+            '&' => ret.push_str("&amp;"),
+            '\'' => ret.push_str("&#39;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+// text may be split into chunks, e.g. `$` and `{c}`, so every `$` is
+// escaped instead of only `${`
+fn escape_template_literal(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('`', "\\`")
+        .replace('$', "\\$")
 }
 
 #[cfg(test)]
@@ -994,6 +1853,26 @@ mod test {
         gen(ir, &info)
     }
     #[test]
+    fn test_normalize_lines() {
+        assert_eq!(normalize_lines("a  \r\n  + b  \r\n"), "a\n  + b\n");
+        // whitespace in template literal is part of the string
+        assert_eq!(normalize_lines("`a  \n b` +  \n c"), "`a  \n b` +\n c");
+        let nested = "`x${ {a: 1}  \n }  \n y` \n";
+        assert_eq!(normalize_lines(nested), "`x${ {a: 1}\n }  \n y`\n");
+        // apostrophe in comment does not start a string
+        assert_eq!(normalize_lines("// don't  \n x  \n"), "// don't\n x\n");
+    }
+    #[test]
+    fn test_pending_spaces() {
+        let mut w = WriteAdaptor::new(vec![]);
+        w.write_str("a  ").unwrap();
+        w.write_str("\n").unwrap();
+        w.in_template_literal = true;
+        w.write_str("b  ").unwrap();
+        w.write_str("\n").unwrap();
+        assert_eq!(String::from_utf8(w.inner).unwrap(), "a\nb  \n");
+    }
+    #[test]
     fn test_text() {
         let s = base_gen("hello       world");
         assert!(s.contains(stringify!("hello world")));
@@ -1071,6 +1950,41 @@ mod test {
         assert!(s.contains(r#"class: "test""#), "{}", s);
         assert!(s.contains("FULL_PROPS"), "{}", s);
     }
+    #[test]
+    fn test_props_object() {
+        let s = base_gen("<p :foo='a'/>");
+        assert!(s.contains("{\n      foo: a,\n    }"), "{}", s);
+        let s = base_gen("<p :[dyn]='v'/>");
+        assert!(s.contains("{\n      [(dyn) || '']: v,\n    }"), "{}", s);
+        let s = gen_on("<p @click='fn'/>");
+        assert!(s.contains("{\n      onClick: fn,\n    }"), "{}", s);
+        let s = base_gen("<p v-bind='obj' :foo='a'/>");
+        assert!(
+            s.contains("_mergeProps(obj, {\n      foo: a,\n    })"),
+            "{}",
+            s
+        );
+        let mut ir = base_convert("<p/>");
+        let vn = cast!(&mut ir.body[0], IRNode::VNodeCall);
+        vn.props = Some(Js::Props(vec![]));
+        let s = gen(ir, &SFCInfo::default());
+        assert!(s.contains(r#"_createElementVNode("p", null)"#), "{}", s);
+    }
+
+    #[test]
+    fn test_v_bind_prop_attr() {
+        let s = base_gen("<input :value.prop='a' :custom.attr='b'/>");
+        assert!(s.contains(r#"".value": a"#), "{}", s);
+        assert!(s.contains(r#""^custom": b"#), "{}", s);
+        assert!(s.contains("PROPS"), "{}", s);
+        assert!(s.contains(r#"["^custom", ".value"]"#), "{}", s);
+        let s = base_gen("<p :c='z' :a='x' :b='y'/>");
+        assert!(s.contains(r#"8 /*PROPS*/, ["a", "b", "c"]"#), "{}", s);
+        let s = base_gen("<input .value='a' :[key].prop='b'/>");
+        assert!(s.contains(r#"".value": a"#), "{}", s);
+        assert!(s.contains("['.' + ((key) || '')]: b"), "{}", s);
+        assert!(s.contains("FULL_PROPS"), "{}", s);
+    }
 
     #[test]
     fn test_v_if() {
@@ -1087,6 +2001,42 @@ mod test {
         assert!(s.contains("openBlock"), "{}", s);
     }
     #[test]
+    fn test_v_if_chain() {
+        let s = base_gen("<p v-if='a'/><p v-else-if='b'/><p v-else/>");
+        assert!(s.contains("(a)"), "{}", s);
+        assert!(s.contains("(b)"), "{}", s);
+        assert_eq!(s.matches("? ").count(), 2, "{}", s);
+        assert!(!s.contains("createCommentVNode"), "{}", s);
+        let s = base_gen("<p v-if='a'/><p v-else-if='b'/>");
+        assert!(s.contains("createCommentVNode('v-if', true)"), "{}", s);
+    }
+    #[test]
+    fn test_v_if_key() {
+        use crate::transformer::{mark_patch_flag::PatchFlagMarker, BaseTransformer, Transformer};
+        let gen_key = |s| {
+            let mut ir = base_convert(s);
+            BaseTransformer::transform(&mut ir, PatchFlagMarker);
+            gen(ir, &SFCInfo::default())
+        };
+        let s = gen_key("<p v-if='a'/>");
+        assert!(s.contains("key: 0"), "{}", s);
+        assert!(s.contains("_openBlock()"), "{}", s);
+        let s = gen_key("<p v-if='a'/><p v-else-if='b'/><p v-else/>");
+        assert!(s.contains("key: 0"), "{}", s);
+        assert!(s.contains("key: 1"), "{}", s);
+        assert!(s.contains("key: 2"), "{}", s);
+        let s = gen_key("<template v-if='a'><p/><p/></template>");
+        assert!(s.contains("_Fragment, {"), "{}", s);
+        assert!(s.contains("key: 0"), "{}", s);
+    }
+    #[test]
+    fn test_v_if_in_v_for() {
+        let s = base_gen("<div v-for='x in xs'><p v-if='x'/></div>");
+        assert!(s.contains("_renderList(xs, (x) =>"), "{}", s);
+        assert!(s.contains("(x)"), "{}", s);
+        assert!(s.contains("createCommentVNode"), "{}", s);
+    }
+    #[test]
     fn test_v_if_slot() {
         let s = base_gen("<slot v-if='condition'/>");
         assert!(!s.contains("openBlock"), "{}", s);
@@ -1116,6 +2066,55 @@ mod test {
         assert!(s.contains(r#""fallback""#), "{}", s);
     }
     #[test]
+    fn test_comment() {
+        let s = base_gen("<!-- hi -->");
+        assert!(s.contains(r#"return _createCommentVNode(" hi ")"#), "{}", s);
+        let s = base_gen("<!---->");
+        assert!(s.contains(r#"_createCommentVNode("")"#), "{}", s);
+        let s = base_gen("<!--a\"b\nc\\-->");
+        assert!(s.contains(r#"_createCommentVNode("a\"b\nc\\")"#), "{}", s);
+        let s = base_gen("<div><!--x--><span/></div>");
+        assert!(
+            s.contains(r#"_createCommentVNode("x"), _createElementVNode("span"),"#),
+            "{}",
+            s
+        );
+    }
+    #[test]
+    fn test_runtime_dir() {
+        let s = base_gen("<p v-foo:arg.mod='val'/>");
+        assert!(
+            s.contains(r#"_withDirectives(_createElementVNode("p""#),
+            "{}",
+            s
+        );
+        assert!(s.contains(r#"[_directive_foo, val, "arg", {"#), "{}", s);
+        assert!(s.contains("mod: true,"), "{}", s);
+        // trailing missing args are omitted, middle ones are padded
+        let s = base_gen("<p v-foo v-bar:x/>");
+        assert!(
+            s.contains(r#"[[_directive_foo], [_directive_bar, void 0, "x"]]"#),
+            "{}",
+            s
+        );
+    }
+    #[test]
+    fn test_builtin_runtime_dir() {
+        let info = SFCInfo::default();
+        let mut ir = base_convert("<p v-show='ok'/>");
+        let vn = cast!(&mut ir.body[0], IRNode::VNodeCall);
+        vn.directives[0].name = Js::Symbol(RH(RH::INTERNAL_MAX));
+        ir.top_scope.helpers.ignore_missing();
+        let option = CodeGenerateOption {
+            helper_strs: &["vShow"],
+            ..Default::default()
+        };
+        let mut writer = CodeWriter::new(vec![], Rc::new(option), &info);
+        writer.generate_root(ir).unwrap();
+        let s = String::from_utf8(writer.writer.inner).unwrap();
+        assert!(s.contains("[[_vShow, ok]]"), "{}", s);
+    }
+    #[test]
     fn test_size() {
         let ir_size = std::mem::size_of::<BaseIR<'_>>();
         let vnode_size = std::mem::size_of::<BaseVNode<'_>>();
@@ -1123,8 +2122,8 @@ mod test {
         let js_size = std::mem::size_of::<Js<'_>>();
         let set_size = std::mem::size_of::<std::collections::HashSet<&str>>();
         // TODO: too large
-        assert_eq!(ir_size, 184);
-        assert_eq!(vnode_size, 152);
+        assert_eq!(ir_size, 192);
+        assert_eq!(vnode_size, 184);
         assert_eq!(for_size, 176);
         assert_eq!(js_size, 32);
         assert_eq!(set_size, 48);
@@ -1134,6 +2133,29 @@ mod test {
         let s = base_gen("<component is='test'>test</component>");
         assert!(s.contains("_withCtx"), "{}", s);
     }
+    #[test]
+    fn test_v_slot() {
+        let s = base_gen("<comp><template #a>a</template><template #b>b</template></comp>");
+        assert!(s.contains("a: _withCtx(() => ["), "{}", s);
+        assert!(s.contains("b: _withCtx(() => ["), "{}", s);
+        assert!(s.contains("_: "), "{}", s);
+        let s = base_gen("<comp><template #[name]>h</template></comp>");
+        assert!(s.contains("[name]: _withCtx(() => ["), "{}", s);
+        let s = base_gen("<comp v-slot='{ item }'>{{item}}</comp>");
+        assert!(s.contains("default: _withCtx(({ item }) => ["), "{}", s);
+    }
+    #[test]
+    fn test_alterable_slot() {
+        let s = base_gen("<comp><template #a v-if='ok'>a</template></comp>");
+        assert!(s.contains("_createSlots({"), "{}", s);
+        assert!(s.contains(r#"name: "a","#), "{}", s);
+        assert!(s.contains("fn: _withCtx(() => ["), "{}", s);
+        assert!(s.contains(": undefined"), "{}", s);
+        let s = base_gen("<comp><template v-for='n in list' #[n]='p'>a</template></comp>");
+        assert!(s.contains("_renderList(list, (n) =>"), "{}", s);
+        assert!(s.contains("name: n,"), "{}", s);
+        assert!(s.contains("fn: _withCtx((p) => ["), "{}", s);
+    }
 
     #[test]
     fn test_render_func_args() {
@@ -1151,6 +2173,19 @@ mod test {
         assert!(!s.contains("$setup"), "{}", s);
     }
 
+    #[test]
+    fn test_inline_render_signature() {
+        let option = SFCInfo {
+            inline: true,
+            ..Default::default()
+        };
+        let ir = base_convert("hello world");
+        let s = gen(ir, &option);
+        assert!(s.contains("(_ctx, _cache) => {"), "{}", s);
+        assert!(!s.contains("function render"), "{}", s);
+        assert!(!s.contains("with ("), "{}", s);
+    }
+
     #[test]
     fn test_v_once() {
         let s = base_gen("<p v-once/>");
@@ -1233,5 +2268,108 @@ mod test {
         assert!(s.contains("import"), "{}", s);
         assert!(s.contains("createTextVNode as _createTextVNode"), "{}", s);
         assert!(s.contains("from \"vue\""), "{}", s);
+        assert!(
+            s.contains("export function render(_ctx, _cache) {"),
+            "{}",
+            s
+        );
+        assert!(!s.contains("with (_ctx)"), "{}", s);
+    }
+
+    fn indent_gen(s: &str, indent_style: IndentStyle, indent_size: u8) -> String {
+        let info = SFCInfo::default();
+        let mut ir = base_convert(s);
+        ir.top_scope.helpers.collect(RH::CREATE_ELEMENT_VNODE);
+        let option = CodeGenerateOption {
+            mode: ScriptMode::Module {
+                runtime_module_name: "vue".into(),
+            },
+            indent_style,
+            indent_size,
+            ..Default::default()
+        };
+        let mut writer = CodeWriter::new(vec![], Rc::new(option), &info);
+        writer.generate_root(ir).unwrap();
+        String::from_utf8(writer.writer.inner).unwrap()
+    }
+
+    #[test]
+    fn test_indent_style() {
+        let s = indent_gen("<p :foo='a'/>", IndentStyle::Spaces, 4);
+        assert!(s.contains("{\n    return "), "{}", s);
+        assert!(s.contains("{\n        foo: a,\n    }"), "{}", s);
+        let s = indent_gen("<p :foo='a'/>", IndentStyle::Tabs, 4);
+        assert!(s.contains("import {\n\tcreateElementVNode"), "{}", s);
+        assert!(s.contains("{\n\t\tfoo: a,\n\t}"), "{}", s);
+        assert!(!s.contains("  "), "{}", s);
+    }
+
+    #[test]
+    fn test_single_line_output() {
+        let s = indent_gen("<p :foo='a'/>", IndentStyle::None, 2);
+        assert!(!s.contains('\n'), "{}", s);
+        assert!(!s.starts_with(' ') && !s.ends_with(' '), "{}", s);
+        let import = r#"import { createElementVNode as _createElementVNode, } from "vue";"#;
+        assert!(s.starts_with(import), "{}", s);
+        let render = "\"vue\"; export function render(_ctx, _cache) { return ";
+        assert!(s.contains(render), "{}", s);
+        assert!(s.contains("{ foo: a, }"), "{}", s);
+    }
+
+    fn ssr_gen(s: &str, mode: ScriptMode) -> String {
+        let info = SFCInfo::default();
+        let ir = base_convert(s);
+        let option = CodeGenerateOption {
+            mode,
+            render_mode: RenderMode::Ssr,
+            ..Default::default()
+        };
+        let mut writer = CodeWriter::new(vec![], Rc::new(option), &info);
+        writer.generate_root(ir).unwrap();
+        String::from_utf8(writer.writer.inner).unwrap()
+    }
+
+    fn function_mode() -> ScriptMode {
+        ScriptMode::Function {
+            prefix_identifier: false,
+            runtime_global_name: "Vue".into(),
+        }
+    }
+
+    #[test]
+    fn test_ssr_render() {
+        let module = ScriptMode::Module {
+            runtime_module_name: "vue".into(),
+        };
+        let s = ssr_gen("hello {{world}}", module);
+        assert!(s.contains("ssrInterpolate as _ssrInterpolate"), "{}", s);
+        assert!(s.contains(r#"from "vue/server-renderer""#), "{}", s);
+        assert!(!s.contains(r#"from "vue""#), "{}", s);
+        assert!(
+            s.contains("export function ssrRender(_ctx, _push, _parent, _attrs) {"),
+            "{}",
+            s
+        );
+        assert!(s.contains("_push(`hello `)"), "{}", s);
+        assert!(s.contains("_push(`${_ssrInterpolate(world)}`)"), "{}", s);
+        assert!(!s.contains("return"), "{}", s);
+    }
+
+    #[test]
+    fn test_ssr_function_mode() {
+        let s = ssr_gen("{{ a }}", function_mode());
+        assert!(s.contains("ssrInterpolate: _ssrInterpolate"), "{}", s);
+        assert!(s.contains(r#"} = require("vue/server-renderer")"#), "{}", s);
+        assert!(s.contains("return function ssrRender("), "{}", s);
+        assert!(s.contains("with (_ctx) {"), "{}", s);
+    }
+
+    #[test]
+    fn test_ssr_escape() {
+        let s = ssr_gen("a > b && `${c}` 'd'", function_mode());
+        let escaped = r"_push(`a &gt; b &amp;&amp; \`\${c}\` &#39;d&#39;`)";
+        assert!(s.contains(escaped), "{}", s);
+        let s = ssr_gen("<!-- x -->", function_mode());
+        assert!(s.contains("_push(`<!-- x -->`)"), "{}", s);
     }
 }
@@ -0,0 +1,327 @@
+//! Source map v3 generation. Positions are recovered from VStr raw slices:
+//! every slice borrowed from template source knows its own offset.
+use crate::util::VStr;
+use std::fmt::Write;
+use std::ops::Range;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+struct Mapping {
+    /// 0-based generated line
+    gen_line: usize,
+    /// 0-based generated column in UTF-16 code units
+    gen_column: usize,
+    /// 0-based source line
+    src_line: usize,
+    /// 0-based source column in UTF-16 code units
+    src_column: usize,
+    /// index into names
+    name: Option<usize>,
+}
+
+pub struct SourceMapBuilder<'a> {
+    source: &'a str,
+    /// byte offsets of each line start in source
+    line_starts: Vec<usize>,
+    mappings: Vec<Mapping>,
+    names: Vec<&'a str>,
+}
+
+impl<'a> SourceMapBuilder<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let newlines = source.match_indices('\n').map(|(i, _)| i + 1);
+        let line_starts = std::iter::once(0).chain(newlines).collect();
+        Self {
+            source,
+            line_starts,
+            mappings: vec![],
+            names: vec![],
+        }
+    }
+
+    /// Map current output position to `src` if it is a slice of template source.
+    /// gen_line is 1-based like WriteAdaptor's line.
+    pub fn add(&mut self, src: &str, gen_line: usize, gen_column: usize) {
+        if let Some(offset) = self.source_offset(src) {
+            self.add_offset(offset, None, gen_line, gen_column);
+        }
+    }
+
+    /// Map current output position to the attribute at `range` of source,
+    /// recording its name like `v-show` or `@click` in names.
+    /// NB: range is counted in char like SourceLocation, not u8.
+    pub fn add_attr(&mut self, range: Range<usize>, gen_line: usize, gen_column: usize) {
+        let start = byte_offset(self.source, range.start);
+        let end = byte_offset(self.source, range.end);
+        let attr = &self.source[start..end];
+        if attr.is_empty() {
+            return;
+        }
+        let name = attr.split('=').next().unwrap_or(attr).trim_end();
+        let index = match self.names.iter().position(|n| *n == name) {
+            Some(i) => i,
+            None => {
+                self.names.push(name);
+                self.names.len() - 1
+            }
+        };
+        self.add_offset(start, Some(index), gen_line, gen_column);
+    }
+
+    fn add_offset(
+        &mut self,
+        offset: usize,
+        name: Option<usize>,
+        gen_line: usize,
+        gen_column: usize,
+    ) {
+        let src_line = match self.line_starts.binary_search(&offset) {
+            Ok(i) => i,
+            Err(i) => i - 1,
+        };
+        let line_start = self.line_starts[src_line];
+        let src_column = utf16_len(&self.source[line_start..offset]);
+        self.mappings.push(Mapping {
+            gen_line: gen_line - 1,
+            gen_column,
+            src_line,
+            src_column,
+            name,
+        });
+    }
+
+    fn source_offset(&self, src: &str) -> Option<usize> {
+        let base = self.source.as_ptr() as usize;
+        let ptr = src.as_ptr() as usize;
+        if src.is_empty() || ptr < base || ptr + src.len() > base + self.source.len() {
+            return None;
+        }
+        Some(ptr - base)
+    }
+
+    /// Serialize to source map v3 JSON.
+    pub fn into_json(self, filename: &str) -> String {
+        let mut ret = String::from(r#"{"version":3,"sources":["#);
+        write_json_str(&mut ret, filename);
+        ret.push_str(r#"],"sourcesContent":["#);
+        write_json_str(&mut ret, self.source);
+        ret.push_str(r#"],"names":["#);
+        for (i, name) in self.names.iter().enumerate() {
+            if i > 0 {
+                ret.push(',');
+            }
+            write_json_str(&mut ret, name);
+        }
+        ret.push_str(r#"],"mappings":""#);
+        self.write_mappings(&mut ret);
+        ret.push_str("\"}");
+        ret
+    }
+
+    fn write_mappings(&self, out: &mut String) {
+        let mut line = 0;
+        let mut prev_gen_column = 0;
+        let mut prev_src_line = 0;
+        let mut prev_src_column = 0;
+        let mut prev_name = 0;
+        for (i, m) in self.mappings.iter().enumerate() {
+            if m.gen_line > line {
+                for _ in line..m.gen_line {
+                    out.push(';');
+                }
+                line = m.gen_line;
+                prev_gen_column = 0;
+            } else if i > 0 {
+                out.push(',');
+            }
+            encode_vlq(out, m.gen_column as i64 - prev_gen_column as i64);
+            // only one source, index delta is always 0
+            encode_vlq(out, 0);
+            encode_vlq(out, m.src_line as i64 - prev_src_line as i64);
+            encode_vlq(out, m.src_column as i64 - prev_src_column as i64);
+            if let Some(name) = m.name {
+                encode_vlq(out, name as i64 - prev_name as i64);
+                prev_name = name;
+            }
+            prev_gen_column = m.gen_column;
+            prev_src_line = m.src_line;
+            prev_src_column = m.src_column;
+        }
+    }
+}
+
+/// Drops mappings of the first `lines` generated lines from a source map
+/// made by [SourceMapBuilder], e.g. after the code on them is moved out.
+pub fn skip_generated_lines(map: &str, lines: usize) -> String {
+    const KEY: &str = r#""mappings":""#;
+    let start = match map.find(KEY) {
+        Some(i) => i + KEY.len(),
+        None => return map.into(),
+    };
+    let end = map[start..].find('"').map_or(map.len(), |i| start + i);
+    let mut groups = map[start..end].splitn(lines + 1, ';');
+    // source, line, column and name are relative to previous segment even across lines
+    let mut skipped = [0; 4];
+    for group in groups.by_ref().take(lines) {
+        for segment in group.split(',').filter(|s| !s.is_empty()) {
+            let fields = decode_vlq(segment);
+            for (sum, delta) in skipped.iter_mut().zip(&fields[1..]) {
+                *sum += delta;
+            }
+        }
+    }
+    let rest = groups.next().unwrap_or("");
+    let mut ret = String::from(&map[..start]);
+    let trimmed = rest.trim_start_matches(';');
+    ret.push_str(&rest[..rest.len() - trimmed.len()]);
+    // only the first remaining segment is relative to skipped ones
+    let first_end = trimmed
+        .find(|c| c == ',' || c == ';')
+        .unwrap_or(trimmed.len());
+    let (first, tail) = trimmed.split_at(first_end);
+    let fields = decode_vlq(first);
+    for (i, field) in fields.iter().enumerate() {
+        let base = if i == 0 { 0 } else { skipped[i - 1] };
+        encode_vlq(&mut ret, field + base);
+    }
+    ret.push_str(tail);
+    ret.push_str(&map[end..]);
+    ret
+}
+
+fn write_json_str(out: &mut String, s: &str) {
+    let mut v = VStr::raw(s);
+    v.be_js_str()
+        .write_to(out)
+        .expect("string should never fail");
+}
+
+fn byte_offset(s: &str, char_offset: usize) -> usize {
+    s.char_indices()
+        .nth(char_offset)
+        .map_or(s.len(), |(i, _)| i)
+}
+
+pub fn utf16_len(s: &str) -> usize {
+    s.chars().map(char::len_utf16).sum()
+}
+
+fn encode_vlq(out: &mut String, value: i64) {
+    let mut vlq = if value < 0 {
+        ((-value) << 1) | 1
+    } else {
+        value << 1
+    };
+    loop {
+        let mut digit = vlq & 0b11111;
+        vlq >>= 5;
+        if vlq > 0 {
+            digit |= 0b100000;
+        }
+        out.write_char(BASE64[digit as usize] as char)
+            .expect("string should never fail");
+        if vlq == 0 {
+            break;
+        }
+    }
+}
+
+fn decode_vlq(segment: &str) -> Vec<i64> {
+    let mut values = vec![];
+    let mut vlq = 0;
+    let mut shift = 0;
+    for b in segment.bytes() {
+        let digit = BASE64
+            .iter()
+            .position(|&c| c == b)
+            .expect("mappings should be base64") as i64;
+        vlq |= (digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+        values.push(if vlq & 1 == 1 { -(vlq >> 1) } else { vlq >> 1 });
+        vlq = 0;
+        shift = 0;
+    }
+    values
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn vlq(n: i64) -> String {
+        let mut s = String::new();
+        encode_vlq(&mut s, n);
+        s
+    }
+
+    #[test]
+    fn test_vlq() {
+        assert_eq!(vlq(0), "A");
+        assert_eq!(vlq(1), "C");
+        assert_eq!(vlq(-1), "D");
+        assert_eq!(vlq(15), "e");
+        assert_eq!(vlq(16), "gB");
+        assert_eq!(vlq(-17), "jB");
+    }
+
+    #[test]
+    fn test_mappings() {
+        let source = "<p>\n  {{ a }}</p>";
+        let mut builder = SourceMapBuilder::new(source);
+        let a = &source[9..10];
+        assert_eq!(a, "a");
+        builder.add(a, 1, 4);
+        builder.add(a, 3, 2);
+        // not a slice of source
+        builder.add("a", 3, 8);
+        let json = builder.into_json("App.vue");
+        assert!(json.contains(r#""sources":["App.vue"]"#), "{}", json);
+        assert!(
+            json.contains(r#""sourcesContent":["<p>\n  {{ a }}</p>"]"#),
+            "{}",
+            json
+        );
+        assert!(json.contains(r#""mappings":"IACK;;EAAA""#), "{}", json);
+    }
+
+    #[test]
+    fn test_skip_generated_lines() {
+        let source = "<p>\n  {{ a }}</p>";
+        let a = &source[9..10];
+        let mut builder = SourceMapBuilder::new(source);
+        builder.add(a, 1, 4);
+        builder.add(&source[0..1], 2, 0);
+        builder.add(a, 4, 2);
+        let json = skip_generated_lines(&builder.into_json("App.vue"), 2);
+        let mut expected = SourceMapBuilder::new(source);
+        expected.add(a, 2, 2);
+        assert_eq!(json, expected.into_json("App.vue"));
+        assert_eq!(decode_vlq("jBgBA"), [-17, 16, 0]);
+    }
+
+    #[test]
+    fn test_attr_names() {
+        let source = "<p v-show='a' @click='b'/>";
+        let mut builder = SourceMapBuilder::new(source);
+        builder.add_attr(3..13, 1, 0);
+        builder.add_attr(14..24, 1, 4);
+        builder.add_attr(3..13, 1, 8);
+        let json = builder.into_json("App.vue");
+        assert!(json.contains(r#""names":["v-show","@click"]"#), "{}", json);
+        // 5th field is name index delta
+        assert!(
+            json.contains(r#""mappings":"AAAGA,IAAWC,IAAXD""#),
+            "{}",
+            json
+        );
+        // char offsets are converted to byte offsets
+        let source = "<p title='é' v-show='a'/>";
+        let mut builder = SourceMapBuilder::new(source);
+        builder.add_attr(13..23, 1, 0);
+        let json = builder.into_json("App.vue");
+        assert!(json.contains(r#""names":["v-show"]"#), "{}", json);
+    }
+}
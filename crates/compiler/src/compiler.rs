@@ -1,24 +1,34 @@
 use super::{
     SFCInfo,
-    codegen::{CodeGenerateOption, CodeGenerator, CodeGen, ScriptMode, CodeGenInfo},
+    codegen::{
+        CodeGenerateOption, CodeGenerator, CodeGen, IndentStyle, PrefixStyle, RenderMode,
+        ScriptMode, CodeGenInfo,
+    },
     converter::{
         no_op_directive_convert, BaseConvertInfo as BaseInfo, BaseConverter, BaseRoot,
         ConvertOption, Converter, DirConvertFn, V_BIND, V_MODEL,
     },
-    error::{NoopErrorHandler, RcErrHandle},
+    error::{CompileError, NoopErrorHandler, RcErrHandle},
     flags::RuntimeHelper,
-    parser::{Element, ParseOption, Parser, WhitespaceStrategy, AstRoot},
+    hmr::HmrInfo,
+    manifest::{hash_hex, Manifest},
+    project::CompileCache,
+    parser::{Element, IsCustomElement, ParseOption, Parser, WhitespaceStrategy, AstRoot},
     scanner::{ScanOption, Scanner, TextMode, Tokens},
     transformer::{BaseTransformer, CorePass, TransformOption, Transformer},
-    util::{no, yes},
+    util::{no, CTX_IDENT},
     Namespace,
     transformer::{
         collect_entities::EntityCollector,
+        collect_refs::RefCollector,
+        hoist_static::{HoistBailout, HoistStatic, StaticRootMarker, StaticSerializer},
         mark_patch_flag::PatchFlagMarker,
         mark_slot_flag::SlotFlagMarker,
         optimize_text::TextOptimizer,
         pass::{Scope, SharedInfoPasses},
         process_expression::ExpressionProcessor,
+        v_once::VOnceProcessor,
+        warn_self_ref::SelfRefWarner,
     },
 };
 
@@ -26,8 +36,10 @@ use rustc_hash::FxHashMap;
 use std::{io, rc::Rc, marker::PhantomData};
 
 pub struct CompileOption {
-    /// e.g. platform native elements, e.g. `<div>` for browsers
-    pub is_native_tag: fn(&str) -> bool,
+    /// e.g. platform native elements, e.g. `<div>` for browsers.
+    /// Namespace is the one computed by `get_namespace` for the tag,
+    /// e.g. `<text>` is native only in SVG.
+    pub is_native_tag: fn(&str, Namespace) -> bool,
 
     /// e.g. native elements that can self-close, e.g. `<img>`, `<br>`, `<hr>`
     pub is_void_tag: fn(&str) -> bool,
@@ -42,7 +54,7 @@ pub struct CompileOption {
     pub get_builtin_component: fn(&str) -> Option<RuntimeHelper>,
 
     /// Separate option for end users to extend the native elements list
-    pub is_custom_element: fn(&str) -> bool,
+    pub is_custom_element: IsCustomElement,
 
     /// Get tag namespace
     pub get_namespace: fn(&str, Option<&Element<'_>>) -> Namespace,
@@ -56,10 +68,26 @@ pub struct CompileOption {
     /// Whitespace handling strategy
     pub whitespace: WhitespaceStrategy,
 
+    /// Compile `<script>` and `<style>` in template as plain elements,
+    /// e.g. for email templates. By default they are dropped with an error.
+    /// @default false
+    pub allow_side_effect_tags: bool,
+
+    /// Warn self-closing non-void native elements, e.g. `<div/>`,
+    /// which browsers do not close when the template is in-DOM.
+    /// @default false
+    pub strict_html: bool,
+
+    /// Warn components rendering themselves unconditionally, i.e. a self reference
+    /// (see `SFCInfo::self_name`) without v-if/v-for/slot ancestor, which never
+    /// terminates at runtime.
+    /// @default false
+    pub warn_self_reference: bool,
+
     /// platform speicific helper
     pub helper_strs: &'static [&'static str],
 
-    /// Whether to keep comments in the templates AST.
+    /// Whether to keep template comments in the render output.
     /// This defaults to `true` in development and `false` in production builds.
     pub preserve_comments: Option<bool>,
     /// Whether the output is dev build which includes v-if comment and dev patch flags.
@@ -71,6 +99,17 @@ pub struct CompileOption {
     /// Hoist static VNodes and props objects to `_hoisted_x` constants
     /// @default false
     pub hoist_static: bool,
+    /// Record why elements are not hoisted, see [CompileOutput::hoist_bailouts].
+    /// Only takes effect with `hoist_static`. Code output is unchanged.
+    /// @default false
+    pub explain_hoist: bool,
+    /// Build [Manifest] for build systems, see [CompileOutput::manifest].
+    /// Code output is unchanged.
+    /// @default false
+    pub manifest: bool,
+    /// Collect [HmrInfo], see [CompileOutput::hmr]. Code output is unchanged.
+    /// @default false
+    pub hmr: bool,
     /// Cache v-on handlers to avoid creating new inline functions on each render,
     /// also avoids the need for dynamically patching the handlers by wrapping it.
     /// e.g `@click="foo"` by default is compiled to `{ onClick: foo }`. With this
@@ -91,15 +130,62 @@ pub struct CompileOption {
     /// used with `new Function(code)()` to generate a render function at runtime.
     /// @default 'function'
     pub mode: ScriptMode,
+    /// Generate `ssrRender` for server side rendering instead of `render`.
+    /// Server renderer helpers are imported from `vue/server-renderer`.
+    /// @default RenderMode::Client
+    pub render_mode: RenderMode,
+    /// Object of CSS variables bound in `<style>`, e.g. `{ "--hash-color": (_ctx.color) }`.
+    /// SSR renders it as `style` of root elements, since there is no
+    /// client runtime to inject the variables. Ignored in client render.
+    /// @default None
+    pub ssr_css_vars: Option<String>,
     /// Generate source map?
     /// @default false
     pub source_map: bool,
+    /// Template file name, used as source map `sources` entry.
+    /// @default ''
+    pub filename: String,
+    /// Generate TypeScript. Render function parameters are annotated,
+    /// and identifiers are always prefixed since TS forbids `with`.
+    /// @default false
+    pub is_ts: bool,
+    /// TS type of slot props keyed by slot name, e.g. `{ item: Item }` for
+    /// `default`, usually from `defineSlots` in script setup. Slot functions
+    /// of inline TS render are annotated with it, others use `any`.
+    /// @default {}
+    pub slots_types: FxHashMap<String, String>,
+    /// Insert newlines so that each element's vnode call is generated
+    /// on the same line as the element in template, where feasible.
+    /// Useful for coverage/debugging tools that map by line.
+    /// @default false
+    pub preserve_lines: bool,
+    /// Annotate render function and hoisted vnodes with JSDoc types,
+    /// for JS projects using `checkJs`. Has no effect if `is_ts` is set.
+    /// @default false
+    pub emit_jsdoc: bool,
+    /// Name of the render function parameter for the component instance.
+    /// @default '_ctx'
+    pub ctx_identifier: String,
+    /// Prefix identifiers as members of `ctx_identifier` or of `this`.
+    /// The latter is for builds rendering with Options API only.
+    /// @default PrefixStyle::CtxMember
+    pub prefix_style: PrefixStyle,
+    /// Indent generated code with spaces or tabs, or output a single line.
+    /// @default IndentStyle::Spaces
+    pub indent_style: IndentStyle,
+    /// Spaces per indent level when `indent_style` is `Spaces`.
+    /// @default 2
+    pub indent_size: u8,
     /// Whether the output JS needs re-rendering when Vue runtime data change.
     /// e.g. SSR can set it to false since SSR is executed only once per request.
     /// @default true
     pub need_reactivity: bool,
     /// Custom error reporter. Default is noop.
     pub error_handler: RcErrHandle,
+    /// Serialize large hoisted static subtrees into custom code instead of
+    /// `createStaticVNode` HTML. Used by platforms stringifying static nodes, e.g. DOM.
+    /// @default None
+    pub static_serializer: Option<StaticSerializer>,
     // deleted options
     // nodeTransforms?: NodeTransform[]
     // transformHoist?: HoistTransform | null
@@ -114,10 +200,9 @@ pub struct CompileOption {
     // scopeId?: string | null
     // slotted?: boolean
 
-    // moved to SSR or need_reactivity
+    // moved to render_mode or need_reactivity
     // ssr: bool // will be false in fallback node
     // inSSR?: bool // always true in ssr build
-    // ssrCssVars?: string
     // ssrRuntimeModuleName?: string
 }
 
@@ -128,28 +213,46 @@ impl Default for CompileOption {
         directive_converters.insert(V_MODEL.0, V_MODEL.1);
         directive_converters.insert("on", no_op_directive_convert);
         Self {
-            is_native_tag: yes,
+            is_native_tag: |_, _| true,
             is_void_tag: no,
             is_pre_tag: no,
             get_builtin_component: |_| None,
-            is_custom_element: no,
+            is_custom_element: Rc::new(no),
             get_namespace: |_, _| Namespace::Html,
             get_text_mode: |_| TextMode::Data,
             delimiters: ("{{".into(), "}}".into()),
             whitespace: WhitespaceStrategy::Preserve,
+            allow_side_effect_tags: false,
+            strict_html: false,
+            warn_self_reference: false,
             helper_strs: &[],
             preserve_comments: None,
             is_dev: true,
             directive_converters,
             hoist_static: false,
+            explain_hoist: false,
+            manifest: false,
+            hmr: false,
             cache_handlers: false,
             mode: ScriptMode::Function {
                 prefix_identifier: false,
                 runtime_global_name: "Vue".into(),
             },
+            render_mode: RenderMode::Client,
+            ssr_css_vars: None,
             source_map: false,
+            filename: String::new(),
+            is_ts: false,
+            slots_types: FxHashMap::default(),
+            preserve_lines: false,
+            emit_jsdoc: false,
+            ctx_identifier: CTX_IDENT.into(),
+            prefix_style: PrefixStyle::CtxMember,
+            indent_style: IndentStyle::Spaces,
+            indent_size: 2,
             need_reactivity: true,
             error_handler: Rc::new(NoopErrorHandler),
+            static_serializer: None,
         }
     }
 }
@@ -164,42 +267,128 @@ impl CompileOption {
     pub fn parsing(&self) -> ParseOption {
         ParseOption {
             whitespace: self.whitespace.clone(),
-            preserve_comment: self.preserve_comments.unwrap_or(self.is_dev),
+            // converter decides, a v-if branch may need a comment as placeholder
+            preserve_comment: true,
             get_namespace: self.get_namespace,
             get_text_mode: self.get_text_mode,
             is_native_element: self.is_native_tag,
             is_void_tag: self.is_void_tag,
             is_pre_tag: self.is_pre_tag,
             get_builtin_component: self.get_builtin_component,
-            is_custom_element: self.is_custom_element,
+            is_custom_element: self.is_custom_element.clone(),
+            strict_html: self.strict_html,
         }
     }
     pub fn converting(&self) -> ConvertOption {
         ConvertOption {
             get_builtin_component: self.get_builtin_component,
+            is_custom_element: self.is_custom_element.clone(),
             is_dev: self.is_dev,
             directive_converters: self.directive_converters.clone(),
             need_reactivity: self.need_reactivity,
+            allow_side_effect_tags: self.allow_side_effect_tags,
+            comments: self.preserve_comments.unwrap_or(self.is_dev),
         }
     }
-    pub fn transforming(&self) -> TransformOption {
+    pub fn transforming(&self, sfc_info: &SFCInfo) -> TransformOption {
         let prefix = match self.mode {
             ScriptMode::Function {
                 prefix_identifier, ..
-            } => prefix_identifier,
+            } => prefix_identifier || self.is_ts,
             ScriptMode::Module { .. } => true,
         };
+        // inline render accesses setup bindings directly without `with`
+        let prefix = prefix || sfc_info.inline;
         TransformOption {
             prefix_identifier: prefix,
             is_dev: self.is_dev,
+            cache_handlers: self.cache_handlers && prefix,
         }
     }
+    /// Hash of options affecting output, used as part of build cache keys.
+    /// Function pointer options, e.g. `is_native_tag`, cannot be hashed and
+    /// are left out, so callers changing them should bust cache themselves.
+    pub fn fingerprint(&self) -> String {
+        let mode = match &self.mode {
+            ScriptMode::Function {
+                prefix_identifier,
+                runtime_global_name,
+            } => format!("function:{}:{}", prefix_identifier, runtime_global_name),
+            ScriptMode::Module {
+                runtime_module_name,
+            } => format!("module:{}", runtime_module_name),
+        };
+        let render_mode = match self.render_mode {
+            RenderMode::Client => "client",
+            RenderMode::Ssr => "ssr",
+        };
+        let whitespace = match self.whitespace {
+            WhitespaceStrategy::Preserve => "preserve",
+            WhitespaceStrategy::Condense => "condense",
+        };
+        let ctx = match self.prefix_style {
+            PrefixStyle::CtxMember => "ctx",
+            PrefixStyle::ThisMember => "this",
+        };
+        let mut dirs: Vec<_> = self.directive_converters.keys().collect();
+        dirs.sort();
+        let mut slots: Vec<_> = self.slots_types.iter().collect();
+        slots.sort();
+        // a custom serializer is keyed by identity, so keys persisted
+        // across processes never match for it
+        let serializer = self
+            .static_serializer
+            .as_ref()
+            .map(|s| Rc::as_ptr(s) as *const u8 as usize);
+        let desc = format!(
+            "{}|{}|{:?}|{}|{}{}|{}|{}|{:?}|{}|{}|{}|{}|{}|{}|{}|{}|{}:{}|{:?}:{}|{}|{:?}|{:?}|{:?}|{:?}",
+            mode,
+            render_mode,
+            self.ssr_css_vars,
+            whitespace,
+            self.delimiters.0,
+            self.delimiters.1,
+            self.allow_side_effect_tags,
+            self.strict_html,
+            self.preserve_comments,
+            self.is_dev,
+            self.hoist_static,
+            self.cache_handlers,
+            self.source_map,
+            self.filename,
+            self.is_ts,
+            self.preserve_lines,
+            self.emit_jsdoc,
+            ctx,
+            self.ctx_identifier,
+            self.indent_style,
+            self.indent_size,
+            self.need_reactivity,
+            self.helper_strs,
+            dirs,
+            slots,
+            serializer,
+        );
+        hash_hex(desc.as_bytes())
+    }
     pub fn codegen(&self) -> CodeGenerateOption {
         CodeGenerateOption {
             is_dev: self.is_dev,
             mode: self.mode.clone(),
+            render_mode: self.render_mode,
+            ssr_css_vars: self.ssr_css_vars.clone(),
             source_map: self.source_map,
+            filename: self.filename.clone(),
+            is_ts: self.is_ts,
+            slots_types: self.slots_types.clone(),
             helper_strs: self.helper_strs,
+            preserve_lines: self.preserve_lines,
+            emit_jsdoc: self.emit_jsdoc,
+            ctx_identifier: self.ctx_identifier.clone(),
+            prefix_style: self.prefix_style,
+            indent_style: self.indent_style,
+            indent_size: self.indent_size,
+            is_void_tag: self.is_void_tag,
         }
     }
 }
@@ -226,6 +415,63 @@ pub trait TemplateCompiler<'a> {
     }
 }
 
+/// Creates a custom transform pass for each compilation.
+pub type PassFactory<'a> =
+    Box<dyn Fn(&'a SFCInfo<'a>, &CompileOption) -> Box<dyn CorePass<BaseInfo<'a>> + 'a> + 'a>;
+
+/// Custom transform passes, e.g. compile-time directives or i18n key extraction.
+/// Pre passes traverse the converted IR before built-in passes and post passes
+/// traverse the fully transformed IR before codegen. Passes in one group run
+/// in registration order, like `chain!`.
+/// NB: custom passes are not part of `CompileOption::fingerprint`.
+#[derive(Default)]
+pub struct CompilerPassRegistry<'a> {
+    pre_passes: Vec<PassFactory<'a>>,
+    post_passes: Vec<PassFactory<'a>>,
+}
+
+impl<'a> CompilerPassRegistry<'a> {
+    pub fn add_pre_pass<F, P>(&mut self, factory: F)
+    where
+        F: Fn(&'a SFCInfo<'a>, &CompileOption) -> P + 'a,
+        P: CorePass<BaseInfo<'a>> + 'a,
+    {
+        self.pre_passes.push(box_factory(factory));
+    }
+    pub fn add_post_pass<F, P>(&mut self, factory: F)
+    where
+        F: Fn(&'a SFCInfo<'a>, &CompileOption) -> P + 'a,
+        P: CorePass<BaseInfo<'a>> + 'a,
+    {
+        self.post_passes.push(box_factory(factory));
+    }
+    pub fn is_empty(&self) -> bool {
+        self.pre_passes.is_empty() && self.post_passes.is_empty()
+    }
+}
+
+fn box_factory<'a, F, P>(factory: F) -> PassFactory<'a>
+where
+    F: Fn(&'a SFCInfo<'a>, &CompileOption) -> P + 'a,
+    P: CorePass<BaseInfo<'a>> + 'a,
+{
+    Box::new(move |info: &'a SFCInfo<'a>, opt: &CompileOption| {
+        let pass: Box<dyn CorePass<BaseInfo<'a>> + 'a> = Box::new(factory(info, opt));
+        pass
+    })
+}
+
+fn create_passes<'a>(
+    factories: &[PassFactory<'a>],
+    info: &'a SFCInfo<'a>,
+    opt: &CompileOption,
+) -> Vec<Box<dyn CorePass<BaseInfo<'a>> + 'a>> {
+    factories.iter().map(|f| f(info, opt)).collect()
+}
+
+/// BaseCompiler keeps no global mutable state, so compiling on many threads
+/// gives identical output. It is not Send because options share Rc handles,
+/// e.g. error handler, so build one compiler per thread.
 pub struct BaseCompiler<'a, P, W>
 where
     W: io::Write,
@@ -233,6 +479,9 @@ where
 {
     writer: fn() -> W,
     passes: fn(&'a SFCInfo<'a>, &CompileOption) -> P,
+    registry: CompilerPassRegistry<'a>,
+    /// cache and the option fingerprint in its keys
+    cache: Option<(CompileCache, String)>,
     option: CompileOption,
     scanner: Scanner,
     parser: Parser,
@@ -252,17 +501,224 @@ where
         Self {
             writer,
             passes,
+            registry: CompilerPassRegistry::default(),
+            cache: None,
             scanner: Scanner::new(option.scanning()),
             parser: Parser::new(option.parsing()),
             option,
             pd: PhantomData,
         }
     }
+    /// Reuse output of templates compiled before, see [CompileCache].
+    /// Only `compile` consults the cache, `compile_with_source_map` always runs.
+    pub fn with_cache(mut self, cache: CompileCache) -> Self {
+        // option is fixed after construction, fingerprint it once
+        self.cache = Some((cache, self.option.fingerprint()));
+        self
+    }
+    fn compile_cached(
+        &self,
+        source: &'a str,
+        sfc_info: &'a SFCInfo<'a>,
+        (cache, fingerprint): &(CompileCache, String),
+    ) -> Result<W, CompileError> {
+        let key = CompileCache::key(source, fingerprint, sfc_info);
+        let mut writer = (self.writer)();
+        if let Some(code) = cache.get(&key) {
+            writer.write_all(code.as_bytes())?;
+            return Ok(writer);
+        }
+        let eh = self.get_error_handler();
+        let reported = (eh.error_count(), eh.warning_count());
+        // generate to buffer to keep a copy, W may not be readable
+        let mut code = vec![];
+        self.compile_into(source, sfc_info, &mut code)?;
+        writer.write_all(&code)?;
+        // diagnostics are reported once, do not hide them on next compilation
+        if (eh.error_count(), eh.warning_count()) == reported {
+            let code = String::from_utf8(code).expect("generated code must be utf8");
+            cache.insert(key, code);
+        }
+        Ok(writer)
+    }
+    /// Pipeline of `compile`, shared by cached and uncached compilation.
+    fn compile_into<V: io::Write>(
+        &self,
+        source: &'a str,
+        sfc_info: &'a SFCInfo<'a>,
+        writer: &mut V,
+    ) -> Result<(), CompileError> {
+        let ir = self.compile_to_ir(source, sfc_info);
+        let gen_info = CodeGenInfo {
+            writer,
+            sfc_info,
+            // compile returns no source map
+            source: "",
+        };
+        CodeGen::new(self.option.codegen()).generate(ir, gen_info)?;
+        Ok(())
+    }
     fn get_converter(&self) -> BaseConverter {
         let eh = self.get_error_handler();
         let option = self.option.converting();
         BaseConverter::new(eh, option)
     }
+    /// Like `compile`, but also returns source map if `source_map` option is on.
+    pub fn compile_with_source_map(
+        &self,
+        source: &'a str,
+        sfc_info: &'a SFCInfo<'a>,
+    ) -> Result<CompileOutput<W>, CompileError> {
+        let ir = self.compile_to_ir(source, sfc_info);
+        self.generate_output(ir, sfc_info, source)
+    }
+    /// Run all passes and return the IR right before codegen, for tools
+    /// inspecting templates, e.g. lint rules or i18n key scanners.
+    /// IR is serializable with the `serde` feature.
+    pub fn compile_to_ir(&self, source: &'a str, sfc_info: &'a SFCInfo<'a>) -> BaseRoot<'a> {
+        let tokens = self.scan(source);
+        let ast = self.parse(tokens);
+        let mut ir = self.convert(ast, sfc_info);
+        self.transform(&mut ir, sfc_info);
+        ir
+    }
+    /// Generate code from transformed IR. `source` is used for
+    /// source map and manifest input hash.
+    pub fn generate_output(
+        &self,
+        ir: BaseRoot<'a>,
+        sfc_info: &'a SFCInfo<'a>,
+        source: &'a str,
+    ) -> Result<CompileOutput<W>, CompileError> {
+        let writer = (self.writer)();
+        self.generate_output_into(ir, sfc_info, source, writer)
+    }
+    /// Same as `generate_output` but writes to the given writer,
+    /// e.g. a buffer preallocated for the expected output size.
+    pub fn generate_output_into(
+        &self,
+        mut ir: BaseRoot<'a>,
+        sfc_info: &'a SFCInfo<'a>,
+        source: &'a str,
+        mut writer: W,
+    ) -> Result<CompileOutput<W>, CompileError> {
+        let manifest = self.option.manifest.then(|| {
+            Manifest::new(
+                source,
+                self.option.fingerprint(),
+                sfc_info.binding_metadata.fingerprint(),
+                &ir.top_scope,
+                self.option.helper_strs,
+            )
+        });
+        let hmr = self
+            .option
+            .hmr
+            .then(|| HmrInfo::new(source, sfc_info, &ir.top_scope));
+        let hoist_bailouts = std::mem::take(&mut ir.top_scope.hoist_bailouts);
+        let is_static = ir.top_scope.is_static;
+        let option = self.option.codegen();
+        let generator = CodeGen::new(option);
+        let gen_info = CodeGenInfo {
+            writer: &mut writer,
+            sfc_info,
+            source,
+        };
+        let output = generator.generate(ir, gen_info)?;
+        let manifest = manifest.map(|mut manifest| {
+            manifest.hoist_count = output.hoist_count;
+            manifest.cache_slot_count = output.cache_count;
+            manifest.output_bytes = output.output_bytes;
+            manifest.error_count = self.get_error_handler().error_count();
+            manifest
+        });
+        Ok(CompileOutput {
+            code: writer,
+            map: output.map,
+            preamble_bytes: output.preamble_bytes,
+            is_static,
+            manifest,
+            hmr,
+            hoist_bailouts,
+        })
+    }
+}
+
+/// Builds a BaseCompiler with custom passes around the built-in ones.
+/// A pass is any `CorePass<BaseConvertInfo>` returned by a factory that is
+/// called once per compilation with the SFC info and compile option.
+/// `add_pre_pass` sees raw expressions before prefixing and static hoisting,
+/// `add_post_pass` sees the IR exactly as codegen will print it.
+pub struct CompilerBuilder<'a, P, W>
+where
+    W: io::Write,
+    P: CorePass<BaseInfo<'a>>,
+{
+    compiler: BaseCompiler<'a, P, W>,
+}
+
+impl<'a, P, W> CompilerBuilder<'a, P, W>
+where
+    W: io::Write,
+    P: CorePass<BaseInfo<'a>>,
+{
+    /// `passes` are the built-in passes, e.g. `get_base_passes`.
+    pub fn new(
+        writer: fn() -> W,
+        passes: fn(&'a SFCInfo<'a>, &CompileOption) -> P,
+        option: CompileOption,
+    ) -> Self {
+        Self {
+            compiler: BaseCompiler::new(writer, passes, option),
+        }
+    }
+    pub fn add_pre_pass<F, Q>(mut self, factory: F) -> Self
+    where
+        F: Fn(&'a SFCInfo<'a>, &CompileOption) -> Q + 'a,
+        Q: CorePass<BaseInfo<'a>> + 'a,
+    {
+        self.compiler.registry.add_pre_pass(factory);
+        self
+    }
+    pub fn add_post_pass<F, Q>(mut self, factory: F) -> Self
+    where
+        F: Fn(&'a SFCInfo<'a>, &CompileOption) -> Q + 'a,
+        Q: CorePass<BaseInfo<'a>> + 'a,
+    {
+        self.compiler.registry.add_post_pass(factory);
+        self
+    }
+    /// Use passes registered elsewhere, e.g. shared by a bundler plugin.
+    pub fn with_registry(mut self, registry: CompilerPassRegistry<'a>) -> Self {
+        self.compiler.registry = registry;
+        self
+    }
+    pub fn with_cache(mut self, cache: CompileCache) -> Self {
+        self.compiler = self.compiler.with_cache(cache);
+        self
+    }
+    pub fn build(self) -> BaseCompiler<'a, P, W> {
+        self.compiler
+    }
+}
+
+/// Generated code and its source map JSON, if requested.
+pub struct CompileOutput<W> {
+    pub code: W,
+    pub map: Option<String>,
+    /// code before this offset is helper imports and hoists, the rest
+    /// is the render function. Used to split inline render from preamble.
+    pub preamble_bytes: usize,
+    /// template has no binding, tooling can skip reactivity wiring
+    pub is_static: bool,
+    /// summary for build system caching, see [Manifest::to_json].
+    /// Present if `manifest` option is on.
+    pub manifest: Option<Manifest>,
+    /// metadata to decide hot reload strategy, see [crate::hmr::compare_renders].
+    /// Present if `hmr` option is on.
+    pub hmr: Option<HmrInfo>,
+    /// elements not hoisted and why, empty unless `explain_hoist` is on
+    pub hoist_bailouts: Vec<HoistBailout>,
 }
 
 impl<'a, P, W> TemplateCompiler<'a> for BaseCompiler<'a, P, W>
@@ -272,7 +728,16 @@ where
 {
     type IR = BaseRoot<'a>;
     type Info = &'a SFCInfo<'a>;
-    type Output = io::Result<W>;
+    type Output = Result<W, CompileError>;
+
+    fn compile(&self, source: &'a str, info: Self::Info) -> Self::Output {
+        if let Some(cache) = &self.cache {
+            return self.compile_cached(source, info, cache);
+        }
+        let mut writer = (self.writer)();
+        self.compile_into(source, info, &mut writer)?;
+        Ok(writer)
+    }
 
     fn scan(&self, source: &'a str) -> Tokens<'a> {
         self.scanner.scan(source, self.get_error_handler())
@@ -285,19 +750,22 @@ where
         self.get_converter().convert_ir(ast, info)
     }
     fn transform(&self, ir: &mut Self::IR, info: Self::Info) {
+        let registry = &self.registry;
+        if !registry.pre_passes.is_empty() {
+            let pre = create_passes(&registry.pre_passes, info, &self.option);
+            BaseTransformer::transform(ir, pre);
+        }
         let pass = (self.passes)(info, &self.option);
-        BaseTransformer::transform(ir, pass)
+        BaseTransformer::transform(ir, pass);
+        if !registry.post_passes.is_empty() {
+            let post = create_passes(&registry.post_passes, info, &self.option);
+            BaseTransformer::transform(ir, post);
+        }
     }
     fn generate(&self, ir: Self::IR, sfc_info: Self::Info) -> Self::Output {
-        let mut writer = (self.writer)();
-        let option = self.option.codegen();
-        let generator = CodeGen::new(option);
-        let gen_info = CodeGenInfo {
-            writer: &mut writer,
-            sfc_info,
-        };
-        generator.generate(ir, gen_info)?;
-        Ok(writer)
+        // source is unavailable here, use compile_with_source_map for source map
+        let output = self.generate_output(ir, sfc_info, "")?;
+        Ok(output.code)
     }
     fn get_error_handler(&self) -> RcErrHandle {
         self.option.error_handler.clone()
@@ -309,23 +777,233 @@ pub fn get_base_passes<'a>(
     opt: &CompileOption,
 ) -> impl CorePass<BaseInfo<'a>> {
     use crate::chain;
-    let prefix_identifier = opt.transforming().prefix_identifier;
+    let transform = opt.transforming(sfc_info);
+    // SSR output has no vnode to hoist
+    let hoist_static = opt.hoist_static && opt.render_mode == RenderMode::Client;
     let shared = chain![
         SlotFlagMarker,
+        HoistStatic::new(transform.cache_handlers, hoist_static, opt.explain_hoist),
         ExpressionProcessor {
-            prefix_identifier,
+            prefix_identifier: transform.prefix_identifier,
             sfc_info,
             err_handle: opt.error_handler.clone(),
         },
     ];
     chain![
+        SelfRefWarner::new(opt.error_handler.clone(), opt.warn_self_reference),
         TextOptimizer,
         EntityCollector::default(),
+        RefCollector::default(),
         PatchFlagMarker,
+        StaticRootMarker,
         SharedInfoPasses {
             passes: shared,
             shared_info: Scope::default(),
             pd: PhantomData,
         },
+        // last pass exits first, after patch flags are final but before hoisting
+        VOnceProcessor { hoist_static },
     ]
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::ir::JsExpr as Js;
+    use crate::transformer::{BaseFor, BaseVNode};
+    use std::cell::{Cell, RefCell};
+
+    // replaces <b> with <strong>
+    struct StrongPass;
+    impl<'a> CorePass<BaseInfo<'a>> for StrongPass {
+        fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+            if matches!(&vn.tag, Js::StrLit(t) if t.raw == "b") {
+                vn.tag = Js::str_lit("strong");
+            }
+        }
+    }
+
+    struct TagRecorder(Rc<RefCell<Vec<String>>>);
+    impl<'a> CorePass<BaseInfo<'a>> for TagRecorder {
+        fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+            if let Js::StrLit(t) = &vn.tag {
+                self.0.borrow_mut().push(t.into_string());
+            }
+        }
+        // v-for is still a ForNodeIR after built-in passes
+        fn enter_for(&mut self, _: &mut BaseFor<'a>) {
+            self.0.borrow_mut().push("for".into());
+        }
+    }
+
+    #[test]
+    fn test_custom_passes() {
+        let sfc_info = Default::default();
+        let tags = Rc::new(RefCell::new(vec![]));
+        let recorded = tags.clone();
+        let compiler = CompilerBuilder::new(Vec::new, get_base_passes, CompileOption::default())
+            .add_pre_pass(|_, _| StrongPass)
+            .add_post_pass(move |_, _| TagRecorder(recorded.clone()))
+            .build();
+        let code = compiler.compile("<div><b v-for='i in a'>{{ i }}</b></div>", &sfc_info);
+        let code = String::from_utf8(code.unwrap()).unwrap();
+        assert!(code.contains(r#"_createElementVNode("strong""#), "{}", code);
+        assert!(!code.contains(r#""b""#), "{}", code);
+        // post passes see the output of pre passes
+        assert_eq!(*tags.borrow(), ["div", "for", "strong"]);
+        // factories are called for every compilation
+        compiler.compile("<p/>", &sfc_info).unwrap();
+        assert_eq!(tags.borrow().last().unwrap(), "p");
+    }
+
+    #[test]
+    fn test_pass_order_in_group() {
+        let sfc_info = Default::default();
+        let tags = Rc::new(RefCell::new(vec![]));
+        let mut registry = CompilerPassRegistry::default();
+        assert!(registry.is_empty());
+        let recorded = tags.clone();
+        registry.add_pre_pass(move |_, _| TagRecorder(recorded.clone()));
+        registry.add_pre_pass(|_, _| StrongPass);
+        let compiler = CompilerBuilder::new(Vec::new, get_base_passes, CompileOption::default())
+            .with_registry(registry)
+            .build();
+        let code = compiler.compile("<b/>", &sfc_info).unwrap();
+        let code = String::from_utf8(code).unwrap();
+        assert!(code.contains(r#""strong""#), "{}", code);
+        // recorder registered first sees the tag before it is replaced
+        assert_eq!(*tags.borrow(), ["b"]);
+    }
+
+    #[test]
+    fn test_compile_cache() {
+        let sfc_info = Default::default();
+        let other = SFCInfo {
+            self_name: "I".into(),
+            ..Default::default()
+        };
+        let count = Rc::new(Cell::new(0));
+        let counter = count.clone();
+        let cache = CompileCache::default();
+        let compiler = CompilerBuilder::new(Vec::new, get_base_passes, CompileOption::default())
+            .add_pre_pass(move |_, _| {
+                counter.set(counter.get() + 1);
+                StrongPass
+            })
+            .with_cache(cache.clone())
+            .build();
+        let first = compiler.compile("<b>{{ a }}</b>", &sfc_info).unwrap();
+        let second = compiler.compile("<b>{{ a }}</b>", &sfc_info).unwrap();
+        assert_eq!(first, second);
+        assert!(String::from_utf8(second).unwrap().contains(r#""strong""#));
+        // cache hit skips the whole pipeline including transform
+        assert_eq!(count.get(), 1);
+        assert_eq!(cache.len(), 1);
+        compiler.compile("<i/>", &sfc_info).unwrap();
+        assert_eq!(count.get(), 2);
+        compiler.compile("<i/>", &other).unwrap();
+        assert_eq!(count.get(), 3);
+        assert_eq!(cache.len(), 3);
+    }
+
+    fn compile_with(option: CompileOption, source: &str) -> String {
+        let sfc_info = Default::default();
+        let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+        String::from_utf8(compiler.compile(source, &sfc_info).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_comments_option() {
+        let option = |preserve_comments| CompileOption {
+            preserve_comments,
+            ..Default::default()
+        };
+        let s = "<div><!-- a --><p/></div>";
+        let code = compile_with(option(Some(true)), s);
+        assert!(code.contains(r#"_createCommentVNode(" a ")"#), "{}", code);
+        // comments are kept in dev mode by default
+        let code = compile_with(option(None), s);
+        assert!(code.contains(r#"_createCommentVNode(" a ")"#), "{}", code);
+        let code = compile_with(option(Some(false)), s);
+        assert!(!code.contains("_createCommentVNode"), "{}", code);
+        // parser keeps comments so a comment only branch has its placeholder
+        let tpl = "<template v-if='a'><!-- x --></template>";
+        let code = compile_with(option(Some(false)), tpl);
+        assert!(code.contains(r#"_createCommentVNode(" x ")"#), "{}", code);
+    }
+
+    fn prefixed_function() -> ScriptMode {
+        ScriptMode::Function {
+            prefix_identifier: true,
+            runtime_global_name: "Vue".into(),
+        }
+    }
+
+    #[test]
+    fn test_custom_ctx_identifier() {
+        let option = || CompileOption {
+            ctx_identifier: "$ctx".into(),
+            ..Default::default()
+        };
+        let code = compile_with(option(), "<p :id='a'>{{ b }}</p>");
+        assert!(code.contains("function render($ctx, _cache) {"), "{}", code);
+        assert!(code.contains("with ($ctx) {"), "{}", code);
+        assert!(!code.contains("_ctx"), "{}", code);
+        let option = CompileOption {
+            mode: prefixed_function(),
+            ..option()
+        };
+        let code = compile_with(option, "<p :id='a' :title='c'>{{ b }}</p>");
+        assert!(code.contains("function render($ctx, _cache) {"), "{}", code);
+        assert!(code.contains("$ctx.a"), "{}", code);
+        assert!(code.contains("$ctx.b"), "{}", code);
+        assert!(code.contains("$ctx.c"), "{}", code);
+        assert!(!code.contains("_ctx"), "{}", code);
+    }
+
+    #[test]
+    fn test_this_member_prefix() {
+        let option = CompileOption {
+            mode: ScriptMode::Module {
+                runtime_module_name: "vue".into(),
+            },
+            prefix_style: PrefixStyle::ThisMember,
+            ..Default::default()
+        };
+        let source = "<p :id='a' :title='c(i)' v-for='i in list'>{{ i + b }}</p>";
+        let code = compile_with(option, source);
+        let signature = "export function render(_ctx, _cache) {";
+        assert!(code.contains(signature), "{}", code);
+        assert!(code.contains("this.a"), "{}", code);
+        assert!(code.contains("this.c(i)"), "{}", code);
+        assert!(code.contains("this.list"), "{}", code);
+        assert!(code.contains("i + this.b"), "{}", code);
+        assert!(!code.contains("_ctx."), "{}", code);
+        let parsed = rslint_parser::parse_module(&code, 0);
+        assert!(parsed.errors().is_empty(), "{}", code);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_compile_to_ir_json() {
+        let sfc_info = Default::default();
+        let option = CompileOption {
+            mode: prefixed_function(),
+            ..Default::default()
+        };
+        let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+        let source = "<p v-if='ok' :id='a'>{{ msg }}</p><i v-for='i in list'/>";
+        let ir = compiler.compile_to_ir(source, &sfc_info);
+        let json = serde_json::to_value(&ir).unwrap();
+        let body = json["body"].as_array().unwrap();
+        assert_eq!(body.len(), 2);
+        let branch = &body[0]["If"]["branches"][0];
+        assert!(branch["condition"].is_object(), "{}", json);
+        let vnode = &branch["child"]["VNodeCall"];
+        assert_eq!(vnode["is_block"], true);
+        assert!(!vnode["patch_flag"].is_null(), "{}", json);
+        assert_eq!(vnode["dynamic_props"].as_array().unwrap().len(), 1);
+        assert!(body[1]["For"]["source"].is_object(), "{}", json);
+        assert!(json["top_scope"]["helpers"].is_u64(), "{}", json);
+    }
+}
@@ -1,12 +1,15 @@
 use super::{BaseConversion as BC, CoreConversion, Element, VStr};
 use crate::{
+    error::{CompilationError, CompilationErrorKind as ErrorKind},
     flags::{self, PatchFlag, RuntimeHelper},
     ir::{JsExpr as Js, Prop},
-    parser::{Directive, ElemProp},
+    parser::{Directive, DirectiveArg, ElemProp},
     scanner::Attribute,
     util::{self, is_bind_key, is_component_tag, is_reserved_prop},
+    SourceLocation,
 };
 use rustc_hash::{FxHashMap, FxHashSet};
+use std::fmt;
 use std::iter::IntoIterator;
 use std::mem;
 
@@ -44,6 +47,74 @@ struct CollectProps<'a> {
     runtime_dirs: Dirs<'a>,
     dynamic_props: FxHashSet<VStr<'a>>,
     prop_flags: PropFlags,
+    prop_sources: PropSources<'a>,
+}
+
+/// Attribute or directive that generates props.
+enum PropSource<'a> {
+    Attr(&'a str),
+    /// directive name and its static argument, e.g. v-bind:id
+    Dir(&'a str, Option<&'a str>),
+}
+
+impl<'a> fmt::Display for PropSource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PropSource::Attr(name) => write!(f, "`{}`", name),
+            PropSource::Dir(name, Some(arg)) => write!(f, "`v-{}:{}`", name, arg),
+            PropSource::Dir(name, None) => write!(f, "`v-{}`", name),
+        }
+    }
+}
+
+#[derive(Default)]
+/// records where static prop keys come from. A key generated by two
+/// different sources is reported unless it can be merged, e.g.
+/// `<input id="a" :id="b">` or `<comp v-model="a" :modelValue="b">`.
+struct PropSources<'a> {
+    sources: Vec<(PropSource<'a>, SourceLocation)>,
+    first_source: FxHashMap<VStr<'a>, usize>,
+}
+
+impl<'a> PropSources<'a> {
+    fn record(
+        &mut self,
+        bc: &BC<'a>,
+        props: &[Prop<'a>],
+        src: PropSource<'a>,
+        loc: &SourceLocation,
+    ) {
+        if !bc.is_dev() {
+            return;
+        }
+        let idx = self.sources.len();
+        for (key, _) in props {
+            let key = match key {
+                Js::StrLit(key) => *key,
+                _ => continue,
+            };
+            let i = *self.first_source.entry(key).or_insert(idx);
+            // compare rendered key, e.g. v-on:click is keyed as onClick
+            if i == idx || util::is_mergeable_prop(&key.into_string()) {
+                continue;
+            }
+            let (prev, prev_loc) = &self.sources[i];
+            let context = format!(
+                "{} at {}:{} and {} at {}:{}",
+                prev,
+                prev_loc.start.line,
+                prev_loc.start.column,
+                src,
+                loc.start.line,
+                loc.start.column,
+            );
+            let error = CompilationError::new(ErrorKind::DuplicateProp)
+                .with_location(loc.clone())
+                .with_context(context);
+            bc.emit_warning(error);
+        }
+        self.sources.push((src, loc.clone()));
+    }
 }
 
 impl<'a> CollectProps<'a> {
@@ -64,10 +135,16 @@ where
     T: IntoIterator<Item = ElemProp<'a>>,
 {
     let mut cp = CollectProps::new(e);
-    elm_props.into_iter().for_each(|prop| match prop {
-        ElemProp::Dir(dir) => collect_dir(bc, e, dir, &mut cp),
-        ElemProp::Attr(attr) => collect_attr(bc, e, attr, &mut cp),
-    });
+    // props stay on the element while converting since directive
+    // converters may inspect siblings, e.g. DOM v-model reads `type`
+    let rest = mem::replace(&mut e.properties, elm_props.into_iter().collect());
+    for i in 0..e.properties.len() {
+        match &e.properties[i] {
+            ElemProp::Dir(_) => collect_dir(bc, e, i, &mut cp),
+            ElemProp::Attr(attr) => collect_attr(bc, e, attr, &mut cp),
+        }
+    }
+    e.properties = rest;
     let prop_expr = compute_prop_expr(cp.prop_args);
     let CollectProps {
         runtime_dirs,
@@ -83,8 +160,14 @@ where
     }
 }
 
-fn collect_attr<'a>(bc: &BC, e: &Element<'a>, attr: Attribute<'a>, cp: &mut CollectProps<'a>) {
-    let Attribute { name, value, .. } = attr;
+fn collect_attr<'a>(bc: &BC<'a>, e: &Element<'a>, attr: &Attribute<'a>, cp: &mut CollectProps<'a>) {
+    let Attribute {
+        name,
+        value,
+        location,
+        ..
+    } = attr;
+    let name = *name;
     let val = match value {
         Some(v) => v.content,
         None => VStr::raw(""),
@@ -93,16 +176,15 @@ fn collect_attr<'a>(bc: &BC, e: &Element<'a>, attr: Attribute<'a>, cp: &mut Coll
     if name == "is" && (is_component_tag(e.tag_name) || val.starts_with("vue:")) {
         return;
     }
-    let mut value_expr = Js::StrLit(val);
     if name == "ref" {
+        // inline setup ref is resolved in ExpressionProcessor
         cp.prop_flags.has_ref = true;
-        if bc.sfc_info.inline && !val.is_empty() {
-            value_expr = process_inline_ref(val);
-        }
     }
-    cp.prop_args
-        .pending_props
-        .push((Js::str_lit(name), value_expr));
+    let prop = (Js::str_lit(name), Js::StrLit(val));
+    let source = PropSource::Attr(name);
+    cp.prop_sources
+        .record(bc, std::slice::from_ref(&prop), source, location);
+    cp.prop_args.pending_props.push(prop);
 }
 
 #[inline]
@@ -115,14 +197,15 @@ fn is_pre_convert_dir(s: &str) -> bool {
 }
 
 // by abstracting DirConvRet we can fully extract out v-on/v-bind!
-fn collect_dir<'a>(
-    bc: &BC<'a>,
-    e: &mut Element<'a>,
-    mut dir: Directive<'a>,
-    cp: &mut CollectProps<'a>,
-) {
+/// Converts the directive at `i` of element props. The directive is put
+/// back unless it is kept as runtime directive.
+fn collect_dir<'a>(bc: &BC<'a>, e: &mut Element<'a>, i: usize, cp: &mut CollectProps<'a>) {
     use super::DirectiveConvertResult as DirConv;
-    let Directive { name, argument, .. } = &dir;
+    let dir = match &e.properties[i] {
+        ElemProp::Dir(dir) => dir,
+        ElemProp::Attr(_) => return,
+    };
+    let Directive { name, argument, .. } = dir;
     let name = *name;
     if is_pre_convert_dir(name) {
         return;
@@ -130,18 +213,29 @@ fn collect_dir<'a>(
     if is_bind_key(argument, "is") && is_component_tag(e.tag_name) {
         return; // skip <component :is="c"/>
     }
+    let arg = match argument {
+        Some(DirectiveArg::Static(arg)) => Some(*arg),
+        _ => None,
+    };
+    let source = PropSource::Dir(name, arg);
+    let location = dir.location.clone();
+    let mut dir = match &mut e.properties[i] {
+        ElemProp::Dir(dir) => mem::take(dir),
+        ElemProp::Attr(_) => return,
+    };
     let (value, runtime) = match bc.convert_directive(&mut dir, e) {
         DirConv::Converted { value, runtime } => (value, runtime),
         DirConv::Preserve => return cp.runtime_dirs.push((dir, None)),
-        DirConv::Dropped => return,
+        DirConv::Dropped => return e.properties[i] = ElemProp::Dir(dir),
     };
     match runtime {
         Ok(helper) => cp.runtime_dirs.push((dir, Some(helper))),
         Err(true) => cp.runtime_dirs.push((dir, None)),
-        Err(false) => (),
+        Err(false) => e.properties[i] = ElemProp::Dir(dir),
     }
     if let Js::Props(props) = value {
         props.iter().for_each(|p| analyze_patch_flag(p, cp));
+        cp.prop_sources.record(bc, &props, source, &location);
         cp.prop_args.pending_props.extend(props);
         return;
     }
@@ -161,10 +255,6 @@ fn flush_pending_props(prop_args: &mut PropArgs) {
     prop_args.merge_args.push(Js::Props(arg));
 }
 
-fn process_inline_ref(_val: VStr) -> Js {
-    todo!("setup binding is pending")
-}
-
 fn dedupe_properties(props: Props) -> Props {
     let mut known_props = FxHashMap::default();
     let mut ret = vec![];
@@ -180,7 +270,7 @@ fn dedupe_properties(props: Props) -> Props {
             if util::is_mergeable_prop(name) {
                 merge_as_array(&mut ret[i], val);
             }
-            // TODO: should remove by parser
+            // otherwise the first one wins, conflict is reported by PropSources
         } else {
             known_props.insert(*name, ret.len());
             ret.push((key, val));
@@ -194,9 +284,8 @@ fn merge_as_array<'a>(existing: &mut Prop<'a>, incoming: Js<'a>) {
     if let Js::Array(arr) = val {
         arr.push(incoming);
     } else {
-        let v = mem::replace(val, Js::Src(""));
-        let mut arr = Js::Array(vec![v]);
-        mem::swap(val, &mut arr);
+        let v = mem::take(val);
+        *val = Js::Array(vec![v, incoming]);
     }
 }
 
@@ -238,6 +327,11 @@ fn analyze_patch_flag<'a>(p: &Prop<'a>, cp: &mut CollectProps<'a>) {
     if val.static_level() > flags::StaticLevel::NotStatic {
         return;
     }
+    // e.g. .prop/^attr keys are not class/style, list the key as written in props
+    if VStr::has_affix(name) {
+        cp.dynamic_props.insert(*name);
+        return;
+    }
     match name.raw {
         "ref" => flags.has_ref = true,
         "class" => flags.has_class_binding = true,
@@ -281,3 +375,145 @@ fn build_patch_flag<'a>(
     }
     patch_flag
 }
+
+pub fn find_key_on_js(e: &Js) -> bool {
+    match e {
+        Js::Call(RuntimeHelper::MERGE_PROPS, args) => args.iter().any(find_key_on_js),
+        Js::Props(ps) => ps.iter().any(|(k, _)| match k {
+            Js::StrLit(s) => s.raw == "key",
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+// 1. mergeProps(...)
+// 2. toHandlers(...)
+pub fn inject_prop<'a>(props: &mut Js<'a>, key: Prop<'a>) {
+    debug_assert!(!find_key_on_js(props));
+    match props {
+        Js::Call(RuntimeHelper::MERGE_PROPS, args) => {
+            for arg in args.iter_mut() {
+                if let Js::Props(ps) = arg {
+                    ps.push(key);
+                    return;
+                }
+            }
+            args.push(Js::Props(vec![key]));
+        }
+        Js::Props(ps) => ps.push(key),
+        // should not inject props to any other expression
+        obj => {
+            return {
+                let mut temp = Js::Src("");
+                std::mem::swap(obj, &mut temp);
+                let p = Js::Props(vec![key]);
+                *obj = Js::Call(RuntimeHelper::MERGE_PROPS, vec![temp, p]);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{test::base_convert, BaseConvertInfo, BaseConverter, BaseIR};
+    use super::super::{v_bind, v_on, ConvertOption, Converter};
+    use super::*;
+    use crate::{
+        cast,
+        error::VecErrorHandler,
+        ir::{IRNode, VNodeIR},
+        parser::test::base_parse,
+    };
+    use std::rc::Rc;
+
+    fn prop_names<'a>(vn: &VNodeIR<BaseConvertInfo<'a>>) -> Vec<&'a str> {
+        let props = cast!(vn.props.as_ref().unwrap(), Js::Props);
+        props
+            .iter()
+            .map(|(k, _)| cast!(k, Js::StrLit).raw)
+            .collect()
+    }
+    fn first_vnode(s: &str) -> BaseIR {
+        base_convert(s).body.remove(0)
+    }
+
+    #[test]
+    fn test_key_not_dynamic_prop() {
+        let vn = cast!(first_vnode("<p :key='k' :foo='a'/>"), IRNode::VNodeCall);
+        // key stays in props but is not patched as a prop
+        assert_eq!(prop_names(&vn), ["key", "foo"]);
+        assert_eq!(vn.dynamic_props.len(), 1);
+        assert!(vn.dynamic_props.iter().all(|p| p.raw == "foo"));
+        assert_eq!(vn.patch_flag, PatchFlag::PROPS);
+    }
+
+    #[test]
+    fn test_ref_need_patch() {
+        let vn = cast!(first_vnode("<p :ref='r'/>"), IRNode::VNodeCall);
+        assert_eq!(prop_names(&vn), ["ref"]);
+        assert!(vn.dynamic_props.is_empty());
+        assert_eq!(vn.patch_flag, PatchFlag::NEED_PATCH);
+    }
+
+    #[test]
+    fn test_is_consumed_on_component() {
+        let vn = cast!(
+            first_vnode("<component :is='c' :foo='a'/>"),
+            IRNode::VNodeCall
+        );
+        assert!(matches!(
+            vn.tag,
+            Js::Call(RuntimeHelper::RESOLVE_DYNAMIC_COMPONENT, _)
+        ));
+        assert_eq!(prop_names(&vn), ["foo"]);
+        assert_eq!(vn.patch_flag, PatchFlag::PROPS);
+    }
+
+    #[test]
+    fn test_key_on_component() {
+        let vn = cast!(first_vnode("<comp :key='k'/>"), IRNode::VNodeCall);
+        assert!(vn.is_component);
+        assert_eq!(prop_names(&vn), ["key"]);
+        assert!(vn.dynamic_props.is_empty());
+        assert_eq!(vn.patch_flag, PatchFlag::empty());
+    }
+
+    fn duplicate_contexts(s: &str) -> Vec<String> {
+        let eh = Rc::new(VecErrorHandler::default());
+        let option = ConvertOption {
+            directive_converters: vec![v_bind::V_BIND, v_on::V_ON].into_iter().collect(),
+            ..Default::default()
+        };
+        let bc = BaseConverter {
+            err_handle: eh.clone(),
+            option: Rc::new(option),
+        };
+        bc.convert_ir(base_parse(s), &Default::default());
+        assert!(eh.errors().is_empty());
+        let warnings = eh.warnings();
+        assert!(warnings.iter().all(|e| e.slug() == Some("duplicate-prop")));
+        warnings.iter().filter_map(|e| e.context.clone()).collect()
+    }
+
+    #[test]
+    fn test_duplicate_prop_sources() {
+        let ctx = duplicate_contexts("<p id='a' :id='b'/>");
+        assert_eq!(ctx, ["`id` at 1:4 and `v-bind:id` at 1:11"]);
+        let ctx = duplicate_contexts("<p\n  :title='a'\n  v-bind:title='b'/>");
+        assert_eq!(ctx, ["`v-bind:title` at 2:3 and `v-bind:title` at 3:3"]);
+        // first one wins
+        let vn = cast!(first_vnode("<p id='a' :id='b'/>"), IRNode::VNodeCall);
+        let props = cast!(vn.props.as_ref().unwrap(), Js::Props);
+        assert_eq!(props.len(), 1);
+        assert_eq!(cast!(&props[0].1, Js::StrLit).raw, "a");
+    }
+
+    #[test]
+    fn test_mergeable_prop_sources() {
+        assert!(duplicate_contexts("<p class='a' :class='b' style='c' :style='d'/>").is_empty());
+        assert!(duplicate_contexts("<p @click='a' v-on:click.once='b' :onClick='c'/>").is_empty());
+        // keys from v-bind object are only known at runtime
+        assert!(duplicate_contexts("<p id='a' v-bind='obj'/>").is_empty());
+    }
+}
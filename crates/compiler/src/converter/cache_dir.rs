@@ -1,7 +1,7 @@
 // v-once / v-memo
 use super::{BaseConversion, BaseIR, CoreConversion, Directive, Element};
-use crate::ir::{IRNode, CacheIR, CacheKind, JsExpr as Js};
-use crate::error::CompilationErrorKind as ErrorKind;
+use crate::ir::{ConvertInfo, IRNode, CacheIR, CacheKind, JsExpr as Js};
+use crate::error::{CompilationError, CompilationErrorKind as ErrorKind};
 use crate::util::find_dir_empty;
 
 pub fn pre_convert_memo<'a>(elem: &mut Element<'a>) -> Option<Directive<'a>> {
@@ -21,6 +21,24 @@ pub fn pre_convert_once<'a>(elem: &mut Element<'a>) -> Option<Directive<'a>> {
     Some(b)
 }
 
+/// v-once inside v-for would share one cache slot among all items.
+/// Drop it with a warning so the subtree renders as usual.
+pub fn remove_once_in_for<'a, T, C>(bc: &C, elem: &mut Element<'a>)
+where
+    T: ConvertInfo,
+    C: CoreConversion<'a, T> + ?Sized,
+{
+    let children = elem.children.iter_mut().filter_map(|c| c.get_element_mut());
+    for child in children {
+        if let Some(dir) = find_dir_empty(&mut *child, "once") {
+            let dir = dir.take();
+            let error = CompilationError::new(ErrorKind::VOnceInVFor).with_location(dir.location);
+            bc.emit_error(error);
+        }
+        remove_once_in_for(bc, child);
+    }
+}
+
 pub fn convert_memo<'a>(bc: &BaseConversion, d: Directive<'a>, n: BaseIR<'a>) -> BaseIR<'a> {
     if let Some(error) = d.check_empty_expr(ErrorKind::VMemoNoExpression) {
         bc.emit_error(error);
@@ -50,7 +68,7 @@ pub fn convert_once<'a>(_bc: &BaseConversion, _: Directive<'a>, n: BaseIR<'a>) -
 
 #[cfg(test)]
 mod test {
-    use super::super::test::base_convert;
+    use super::super::test::{base_convert, convert_with_handler};
     use super::*;
     use crate::cast;
 
@@ -82,6 +100,26 @@ mod test {
         assert!(matches!(cn.kind, CacheKind::MemoInVFor { .. }));
     }
     #[test]
+    fn test_memo_key_in_v_for() {
+        let case = "<p v-for='(a, i) in b' :key='a.id' v-memo='a'/>";
+        let mut body = base_convert(case).body;
+        let f = cast!(body.remove(0), IRNode::For);
+        let key = memo_key(*f.child).unwrap();
+        let key = cast!(key, Js::Simple);
+        assert_eq!(key.into_string(), "a.id");
+        // without key prop, v-for alias should not be used as key
+        let mut body = base_convert("<p v-for='(a, i) in b' v-memo='a'/>").body;
+        let f = cast!(body.remove(0), IRNode::For);
+        assert!(memo_key(*f.child).is_none());
+    }
+    fn memo_key(n: BaseIR) -> Option<Js> {
+        let cn = cast!(n, IRNode::CacheNode);
+        match cn.kind {
+            CacheKind::MemoInVFor { v_for_key, .. } => v_for_key,
+            _ => panic!("memo in v-for expected"),
+        }
+    }
+    #[test]
     fn test_memo_in_template_for() {
         let case = "<template v-for='a in b'><p v-memo='a'/></template>";
         let mut body = base_convert(case).body;
@@ -90,12 +128,47 @@ mod test {
         let cn = cast!(vn.children.remove(0), IRNode::CacheNode);
         cast!(cn.kind, CacheKind::Memo);
     }
-    // fn test_once() {
-    //     let cases = [
-    //         "<template v-for='a in b'><p v-once/></template>",
-    //         "<p v-for='a in b' v-once/>",
-    //         "<p v-if='a' v-once/>",
-    //         "<p v-once/>",
-    //     ];
-    // }
+    #[test]
+    fn test_once() {
+        let mut body = base_convert("<p v-once/>").body;
+        let cn = cast!(body.remove(0), IRNode::CacheNode);
+        assert!(matches!(cn.kind, CacheKind::Once));
+        cast!(*cn.child, IRNode::VNodeCall);
+    }
+    #[test]
+    fn test_nested_once() {
+        let mut body = base_convert("<p v-once><b v-once/></p>").body;
+        let cn = cast!(body.remove(0), IRNode::CacheNode);
+        let mut vn = cast!(*cn.child, IRNode::VNodeCall);
+        cast!(vn.children.remove(0), IRNode::VNodeCall);
+    }
+    #[test]
+    fn test_once_on_v_for() {
+        let mut body = base_convert("<p v-for='a in b' v-once/>").body;
+        let cn = cast!(body.remove(0), IRNode::CacheNode);
+        cast!(*cn.child, IRNode::For);
+    }
+    #[test]
+    fn test_once_on_v_if() {
+        let mut body = base_convert("<p v-if='a' v-once/><p v-else v-once/>").body;
+        let cn = cast!(body.remove(0), IRNode::CacheNode);
+        let v_if = cast!(*cn.child, IRNode::If);
+        assert_eq!(v_if.branches.len(), 2);
+        for branch in v_if.branches {
+            cast!(*branch.child, IRNode::VNodeCall);
+        }
+    }
+    #[test]
+    fn test_once_in_v_for() {
+        use crate::error::VecErrorHandler;
+        use std::rc::Rc;
+        let case = "<template v-for='a in b'><p v-once/></template>";
+        let handler = Rc::new(VecErrorHandler::default());
+        let mut body = convert_with_handler(case, handler.clone()).body;
+        let f = cast!(body.remove(0), IRNode::For);
+        cast!(*f.child, IRNode::VNodeCall);
+        let errors = handler.errors();
+        assert_eq!(errors.len(), 1);
+        assert!(matches!(errors[0].kind, ErrorKind::VOnceInVFor));
+    }
 }
@@ -9,7 +9,9 @@ use crate::{
     ir::{IRNode, JsExpr as Js, RuntimeDir, VNodeIR},
     parser::{AstNode, Directive, ElemProp, ElementType},
     scanner::Attribute,
-    util::{find_dir, get_core_component, is_builtin_symbol, is_component_tag, prop_finder},
+    util::{
+        dir_finder, find_dir, get_core_component, is_builtin_symbol, is_component_tag, prop_finder,
+    },
     BindingMetadata, BindingTypes, SourceLocation,
 };
 use std::{iter, mem};
@@ -43,6 +45,7 @@ pub fn convert_element<'a>(bc: &BC<'a>, mut e: Element<'a>) -> BaseIR<'a> {
         is_block,
         disable_tracking: false,
         is_component: e.is_component(),
+        location: e.location,
     };
     IRNode::VNodeCall(vnode)
 }
@@ -51,8 +54,8 @@ pub fn convert_element<'a>(bc: &BC<'a>, mut e: Element<'a>) -> BaseIR<'a> {
 pub fn convert_template<'a>(bc: &BC<'a>, mut e: Element<'a>) -> BaseIR<'a> {
     debug_assert!(e.tag_type == ElementType::Template);
     check_wrong_slot(bc, &e, ErrorKind::VSlotTemplateMisplaced);
-    // TODO: optimize away template if it has one stable element child
-    // TODO: pass key property to the direct element child
+    // TODO: optimize away template if it has one stable element child in v-if
+    // v-for case is collapsed in convert_for
     // template here is purely a fragment that groups element.
     let mut patch_flag = PatchFlag::STABLE_FRAGMENT;
     let child_count = e
@@ -76,6 +79,7 @@ pub fn convert_template<'a>(bc: &BC<'a>, mut e: Element<'a>) -> BaseIR<'a> {
         patch_flag,
         props,
         is_block: true, // only v-if/v-for(always block) or v-slot(as wrapper)
+        location: e.location,
         ..VNodeIR::default()
     })
 }
@@ -90,10 +94,15 @@ pub fn resolve_element_tag<'a>(bc: &BC<'a>, e: &Element<'a>) -> Js<'a> {
     }
     let is_explicit_dynamic = is_component_tag(e.tag_name);
     // 1. resolve dynamic component
-    let tag = match resolve_dynamic_component(e, is_explicit_dynamic) {
+    let tag = match resolve_dynamic_component(bc, e, is_explicit_dynamic) {
         Ok(call_expr) => return call_expr,
         Err(tag_name) => tag_name,
     };
+    if is_explicit_dynamic && prop_finder(e, "is").find().is_none() {
+        let error =
+            CompilationError::new(ErrorKind::ComponentMissingIs).with_location(e.location.clone());
+        bc.emit_error(error);
+    }
     // 1.5 v-is (deprecated)
     if let Some(call_expr) = resolve_v_is_component(e, is_explicit_dynamic) {
         return call_expr;
@@ -129,6 +138,7 @@ pub fn resolve_element_tag<'a>(bc: &BC<'a>, e: &Element<'a>) -> Js<'a> {
 const MUST_NON_EMPTY: &str = "find_prop must return prop with non-empty value";
 /// Returns Ok if resolved as dynamic component call, Err if resolved as static string tag
 fn resolve_dynamic_component<'a>(
+    bc: &BC<'a>,
     e: &Element<'a>,
     is_explicit_dynamic: bool,
 ) -> Result<Js<'a>, &'a str> {
@@ -139,13 +149,24 @@ fn resolve_dynamic_component<'a>(
     };
     if is_explicit_dynamic {
         let exp = match prop.get_ref() {
+            // static is resolves like other component assets
             ElemProp::Attr(Attribute {
                 value: Some(val), ..
-            }) => Js::StrLit(val.content), // TODO: return Err(val.content) ?
+            }) => return Err(val.content.raw),
             ElemProp::Dir(Directive {
                 expression: Some(exp),
                 ..
-            }) => Js::simple(exp.content),
+            }) => {
+                // component object needs no runtime resolution
+                let bindings = &bc.sfc_info.binding_metadata;
+                let comp = Some(exp.content.raw.trim())
+                    .filter(|name| bindings.is_component(name))
+                    .and_then(|name| resolve_setup_reference(bc, name));
+                if let Some(comp) = comp {
+                    return Ok(comp);
+                }
+                Js::simple(exp.content)
+            }
             _ => panic!("{}", MUST_NON_EMPTY),
         };
         return Ok(Js::Call(
@@ -247,7 +268,8 @@ fn build_directive_arg<'a>(
     let mods = if dir.modifiers.is_empty() {
         None
     } else {
-        let mapper = |v| (Js::simple(v), Js::Src("true"));
+        // modifier names are object keys, not identifiers to prefix
+        let mapper = |v| (Js::str_lit(v), Js::Src("true"));
         let props = dir.modifiers.into_iter().map(mapper);
         Some(Js::Props(props.collect()))
     };
@@ -256,6 +278,7 @@ fn build_directive_arg<'a>(
         expr,
         arg,
         mods,
+        location: dir.location,
     }
 }
 
@@ -269,7 +292,7 @@ fn build_children<'a>(
         v_slot::check_wrong_slot(bc, e, ErrorKind::VSlotMisplaced);
     }
     let mut more_flag = PatchFlag::empty();
-    if e.children.is_empty() {
+    if e.children.is_empty() || is_content_overridden(bc, e) {
         return (vec![], more_flag);
     }
     let should_build_as_slot = v_slot::check_build_as_slot(e, tag);
@@ -295,6 +318,14 @@ fn build_children<'a>(
     (children, more_flag)
 }
 
+/// v-html and v-text replace element content. Children are kept
+/// unconverted so their directive converters can report them.
+fn is_content_overridden(bc: &BC, e: &Element) -> bool {
+    let convs = &bc.option.directive_converters;
+    let overrides = |name: &str| (name == "html" || name == "text") && convs.contains_key(name);
+    dir_finder(e, overrides).allow_empty().find().is_some()
+}
+
 fn resolve_setup_component<'a>(bc: &BC<'a>, tag: &'a str) -> Option<Js<'a>> {
     if let Some(from_setup) = resolve_setup_reference(bc, tag) {
         return Some(from_setup);
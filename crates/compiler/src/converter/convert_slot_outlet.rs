@@ -87,8 +87,10 @@ fn process_slot_outlet<'a>(bc: &BC<'a>, e: &mut Element<'a>) -> NameAndProps<'a>
         props, directives, ..
     } = build_props(bc, e, non_name_props);
     if !directives.is_empty() {
+        let dir = &directives[0].0;
         let error = CompilationError::new(VSlotUnexpectedDirectiveOnSlotOutlet)
-            .with_location(directives[0].0.location.clone());
+            .with_location(dir.location.clone())
+            .with_context(dir.name);
         bc.emit_error(error)
     }
     (slot_name, props)
@@ -26,7 +26,7 @@ mod build_props;
 mod cache_dir;
 mod convert_element;
 mod convert_slot_outlet;
-mod v_bind;
+pub mod v_bind;
 mod v_for;
 mod v_if;
 pub mod v_model;
@@ -34,12 +34,15 @@ pub mod v_on;
 mod v_slot;
 
 use crate::{
+    error::CompilationErrorKind as ErrorKind,
     flags::{HelperCollector, RuntimeHelper},
     ir::{ConvertInfo, IRNode, IRRoot, JsExpr, TextIR},
-    parser::{SourceNode, TextNode},
-    util::{get_core_component, VStr},
+    parser::{ElementType, IsCustomElement, SourceNode, TextNode},
+    transformer::hoist_static::HoistBailout,
+    util::{get_core_component, no, VStr},
     SFCInfo,
 };
+pub use build_props::{find_key_on_js, inject_prop};
 pub use v_bind::V_BIND;
 pub use v_model::V_MODEL;
 
@@ -77,22 +80,57 @@ pub trait CoreConversion<'a, T: ConvertInfo> {
             top_scope: T::TopType::default(),
         }
     }
-    fn convert_children(&self, children: Vec<AstNode<'a>>) -> Vec<IRNode<T>> {
+    fn convert_children(&self, mut children: Vec<AstNode<'a>>) -> Vec<IRNode<T>> {
         let mut key = 0;
+        children.retain(|n| !self.is_ignored_side_effect_tag(n));
+        if !self.keep_comments() {
+            strip_comments(&mut children);
+        }
+        // end offset of previous text or interpolation, if it is the last sibling
+        let mut text_end = None;
         // pre group adjacent v-if here to avoid access siblings
         pre_group_v_if(children)
             .map(|pre| match pre {
                 PreGroup::VIfGroup(to_convert) => {
+                    text_end = None;
                     let len = to_convert.len();
                     let converted = self.convert_if(to_convert, key);
                     key += len;
                     converted
                 }
-                PreGroup::StandAlone(n) => self.dispatch_ast(n),
+                PreGroup::StandAlone(n) => {
+                    let is_text = matches!(n, AstNode::Text(_) | AstNode::Interpolation(_));
+                    let loc = n.get_location();
+                    // texts are contiguous in source unless a node is removed between
+                    let split = is_text && text_end.map_or(false, |e| e != loc.start.offset);
+                    text_end = is_text.then(|| loc.end.offset);
+                    let mut converted = self.dispatch_ast(n);
+                    if let IRNode::TextCall(t) = &mut converted {
+                        t.split_from_prev = split;
+                    }
+                    converted
+                }
             })
             .collect()
     }
 
+    /// <script> and <style> in template are dropped with an error unless allowed.
+    fn is_ignored_side_effect_tag(&self, n: &AstNode<'a>) -> bool {
+        let e = match n {
+            AstNode::Element(e) => e,
+            _ => return false,
+        };
+        let is_side_effect = matches!(e.tag_name, "script" | "style");
+        if e.tag_type != ElementType::Plain || !is_side_effect || self.allow_side_effect_tags() {
+            return false;
+        }
+        let error = CompilationError::new(ErrorKind::IgnoredSideEffectTag)
+            .with_location(e.location.clone())
+            .with_context(e.tag_name);
+        self.emit_error(error);
+        true
+    }
+
     fn dispatch_ast(&self, n: AstNode<'a>) -> IRNode<T> {
         match n {
             AstNode::Text(t) => self.convert_text(t),
@@ -157,10 +195,47 @@ pub trait CoreConversion<'a, T: ConvertInfo> {
 
     // emit error
     fn emit_error(&self, error: CompilationError);
+    // emit issue that does not make the output wrong
+    fn emit_warning(&self, warning: CompilationError);
     // platform specific options
     fn get_builtin_component(&self, tag: &str) -> Option<RuntimeHelper>;
+    fn is_custom_element(&self, tag: &str) -> bool;
+    // emit dev only warnings
+    fn is_dev(&self) -> bool;
     // is reactive
     fn is_reactive_build(&self) -> bool;
+    // keep <script>/<style> in template
+    fn allow_side_effect_tags(&self) -> bool;
+    // emit comments as createCommentVNode
+    fn keep_comments(&self) -> bool;
+}
+
+/// Removes comments and the whitespace they leave dangling at either end
+/// or next to other whitespace, so that a stripped comment does not add
+/// a text node to siblings, e.g. turning a single root into a Fragment.
+fn strip_comments(children: &mut Vec<AstNode>) {
+    let is_comment = |n: Option<&AstNode>| matches!(n, Some(AstNode::Comment(_)));
+    if !children.iter().any(|n| is_comment(Some(n))) {
+        return;
+    }
+    let is_blank = |n: &AstNode| matches!(n, AstNode::Text(t) if t.is_all_whitespace());
+    let dangling: Vec<_> = (0..children.len())
+        .map(|i| {
+            let prev = i.checked_sub(1).and_then(|j| children.get(j));
+            is_blank(&children[i]) && (is_comment(prev) || is_comment(children.get(i + 1)))
+        })
+        .collect();
+    let mut nodes: Vec<_> = std::mem::take(children)
+        .into_iter()
+        .zip(dangling)
+        .filter(|(n, _)| !is_comment(Some(n)))
+        .collect();
+    nodes.dedup_by(|(_, dangling), (prev, _)| *dangling && is_blank(prev));
+    while matches!(nodes.last(), Some((_, true))) {
+        nodes.pop();
+    }
+    let leading = nodes.iter().take_while(|(_, dangling)| *dangling).count();
+    children.extend(nodes.into_iter().skip(leading).map(|(n, _)| n));
 }
 
 /// Directive's prop argument passed to VNodeCall after conversion.
@@ -197,6 +272,19 @@ pub struct ImportItem<'a> {
     pub path: &'a str,
 }
 
+/// Constant lifted out of render function, declared as `_hoisted_x`.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum Hoist<'a> {
+    /// static vnode subtree, text or comment
+    Node(BaseIR<'a>),
+    /// static props object of an element that still needs patching
+    Props(JsExpr<'a>),
+    /// constant expression shared by props, e.g. modifier-only handler
+    Expr(JsExpr<'a>),
+    /// static subtree serialized to code, e.g. `createStaticVNode` call
+    Static(String),
+}
+
 #[derive(Default)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TopScope<'a> {
@@ -207,11 +295,21 @@ pub struct TopScope<'a> {
     /// directives that requires resolveDirecitve call
     pub directives: FxHashSet<VStr<'a>>,
     /// hoisted vnode/text/js object
-    pub hoists: Vec<BaseIR<'a>>,
+    pub hoists: Vec<Hoist<'a>>,
     /// assets need to be imported for template, e.g. image
     pub imports: Vec<ImportItem<'a>>,
     /// counters for temporary variables created in template
     pub temps: usize,
+    /// whether the template renders the same vnode tree on every call.
+    /// Tooling can skip reactivity wiring for such templates.
+    pub is_static: bool,
+    /// template expressions reference `$emit`
+    pub uses_emit: bool,
+    /// elements not hoisted and why, filled if `explain_hoist` is on
+    pub hoist_bailouts: Vec<HoistBailout>,
+    /// template source, used to locate errors found after conversion
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source: &'a str,
 }
 
 impl<'a> ConvertInfo for BaseConvertInfo<'a> {
@@ -238,16 +336,25 @@ pub struct ConvertOption {
     /// e.g. transition, transition-group. Components that require code in Vue runtime.
     pub get_builtin_component: fn(&str) -> Option<RuntimeHelper>,
     pub directive_converters: FxHashMap<&'static str, DirConvertFn>,
+    /// Custom elements are plain elements but keep state like components.
+    pub is_custom_element: IsCustomElement,
     pub is_dev: bool,
     pub need_reactivity: bool,
+    pub allow_side_effect_tags: bool,
+    /// Keep template comments as createCommentVNode calls. If false they
+    /// are stripped, except as placeholder of a comment-only v-if branch.
+    pub comments: bool,
 }
 
 impl Default for ConvertOption {
     fn default() -> Self {
         Self {
             get_builtin_component: get_core_component,
+            is_custom_element: Rc::new(no),
             is_dev: true,
             need_reactivity: true,
+            allow_side_effect_tags: false,
+            comments: true,
             directive_converters: FxHashMap::default(),
         }
     }
@@ -274,7 +381,10 @@ impl Converter for BaseConverter {
             sfc_info: info,
             option: self.option.clone(),
         };
-        conversion.convert_core_ir(ast)
+        let source = ast.source;
+        let mut ir = conversion.convert_core_ir(ast);
+        ir.top_scope.source = source;
+        ir
     }
 }
 
@@ -289,14 +399,29 @@ impl<'a> CoreConversion<'a, BaseConvertInfo<'a>> for BaseConversion<'a> {
     fn emit_error(&self, error: CompilationError) {
         self.err_handle.on_error(error)
     }
+    fn emit_warning(&self, warning: CompilationError) {
+        self.err_handle.on_warn(warning)
+    }
     fn is_reactive_build(&self) -> bool {
         self.option.need_reactivity
     }
+    fn is_dev(&self) -> bool {
+        self.option.is_dev
+    }
+    fn allow_side_effect_tags(&self) -> bool {
+        self.option.allow_side_effect_tags
+    }
+    fn keep_comments(&self) -> bool {
+        self.option.comments
+    }
 
     // platform specific methods
     fn get_builtin_component(&self, tag: &str) -> Option<RuntimeHelper> {
         (self.option.get_builtin_component)(tag)
     }
+    fn is_custom_element(&self, tag: &str) -> bool {
+        (self.option.is_custom_element)(tag)
+    }
 
     // core template syntax conversion
     fn convert_directive(
@@ -343,6 +468,7 @@ impl<'a> CoreConversion<'a, BaseConvertInfo<'a>> for BaseConversion<'a> {
         IRNode::TextCall(TextIR {
             fast_path: false,
             need_patch: false,
+            split_from_prev: false,
             texts,
         })
     }
@@ -352,6 +478,7 @@ impl<'a> CoreConversion<'a, BaseConvertInfo<'a>> for BaseConversion<'a> {
         IRNode::TextCall(TextIR {
             fast_path: false,
             need_patch: false,
+            split_from_prev: false,
             texts: smallvec![call],
         })
     }
@@ -372,7 +499,7 @@ impl<'a> BaseConversion<'a> {
 #[cfg(test)]
 pub mod test {
     use super::*;
-    use crate::{cast, error::test::TestErrorHandler, ir::VNodeIR, parser::test::base_parse};
+    use crate::{cast, error::test::TestErrorHandler, parser::test::base_parse};
     use std::rc::Rc;
     use BaseConverter as BC;
     use JsExpr as Js;
@@ -398,6 +525,13 @@ pub mod test {
         let ast = base_parse(s);
         bc.convert_ir(ast, &SFC_INFO)
     }
+    pub fn convert_with_handler(s: &str, err_handle: RcErrHandle) -> BaseRoot {
+        let bc = BC {
+            err_handle,
+            option: Default::default(),
+        };
+        bc.convert_ir(base_parse(s), &SFC_INFO)
+    }
     pub fn handler_convert(s: &str) -> BaseRoot {
         let convs = vec![
             v_bind::V_BIND,
@@ -418,6 +552,18 @@ pub mod test {
         bc.convert_ir(ast, &SFC_INFO)
     }
 
+    pub fn comment_convert(s: &str, comments: bool) -> BaseRoot {
+        let option = ConvertOption {
+            comments,
+            ..Default::default()
+        };
+        let bc = BC {
+            err_handle: Rc::new(TestErrorHandler),
+            option: Rc::new(option),
+        };
+        bc.convert_ir(base_parse(s), &SFC_INFO)
+    }
+
     pub fn assert_str_lit(expr: &Js, s: &str) {
         let v = cast!(expr, Js::StrLit);
         assert_eq!(v.raw, s);
@@ -427,11 +573,8 @@ pub mod test {
     fn test_simplest() {
         let body = base_convert("<p/>").body;
         assert_eq!(body.len(), 1);
-        if let IRNode::VNodeCall(VNodeIR { tag, .. }) = &body[0] {
-            assert_str_lit(tag, "p");
-        } else {
-            panic!("wrong parsing");
-        }
+        let vn = cast!(&body[0], IRNode::VNodeCall);
+        assert_str_lit(&vn.tag, "p");
         let body = base_convert("hello world").body;
         let t = cast!(&body[0], IRNode::TextCall);
         assert_str_lit(&t.texts[0], "hello world");
@@ -441,4 +584,74 @@ pub mod test {
     fn test_abort() {
         base_convert("hello <p/> {{world}}");
     }
+
+    fn convert_side_effect(s: &str, allow: bool) -> (BaseRoot, usize) {
+        use crate::error::VecErrorHandler;
+        let option = ConvertOption {
+            allow_side_effect_tags: allow,
+            ..Default::default()
+        };
+        let eh = Rc::new(VecErrorHandler::default());
+        let bc = BC {
+            err_handle: eh.clone(),
+            option: Rc::new(option),
+        };
+        let ir = bc.convert_ir(base_parse(s), &SFC_INFO);
+        let errors = eh.errors();
+        for e in errors.iter() {
+            assert_eq!(e.slug(), Some("ignored-side-effect-tag"));
+        }
+        (ir, errors.len())
+    }
+
+    #[test]
+    fn test_side_effect_tags() {
+        let (ir, errors) = convert_side_effect("<div><style>p {}</style><p/></div>", false);
+        assert_eq!(errors, 1);
+        let div = cast!(&ir.body[0], IRNode::VNodeCall);
+        assert_eq!(div.children.len(), 1);
+        let p = cast!(&div.children[0], IRNode::VNodeCall);
+        assert_str_lit(&p.tag, "p");
+        let (ir, errors) = convert_side_effect("<script>alert(1)</script>", false);
+        assert_eq!(errors, 1);
+        assert!(ir.body.is_empty());
+    }
+
+    #[test]
+    fn test_allow_side_effect_tags() {
+        let (ir, errors) = convert_side_effect("<style>p {}</style>", true);
+        assert_eq!(errors, 0);
+        let style = cast!(&ir.body[0], IRNode::VNodeCall);
+        assert_str_lit(&style.tag, "style");
+        let text = cast!(&style.children[0], IRNode::TextCall);
+        // scanner may split text at delimiter-like chars
+        let texts = text.texts.iter();
+        let css: String = texts.map(|t| cast!(t, Js::StrLit).raw).collect();
+        assert_eq!(css, "p {}");
+    }
+
+    #[test]
+    fn test_comments_option() {
+        let s = "<div><!-- a --><p/>\n<!-- b --></div>";
+        let body = comment_convert(s, true).body;
+        let div = cast!(&body[0], IRNode::VNodeCall);
+        assert_eq!(div.children.len(), 4);
+        assert_eq!(*cast!(&div.children[0], IRNode::CommentCall), " a ");
+        let body = comment_convert(s, false).body;
+        let div = cast!(&body[0], IRNode::VNodeCall);
+        assert_eq!(div.children.len(), 1);
+        assert_str_lit(&cast!(&div.children[0], IRNode::VNodeCall).tag, "p");
+    }
+
+    #[test]
+    fn test_stripped_comment_keeps_single_root() {
+        let s = "<!-- a -->\n<div/>\n<!-- b -->";
+        assert_eq!(comment_convert(s, true).body.len(), 5);
+        let body = comment_convert(s, false).body;
+        assert_eq!(body.len(), 1);
+        assert_str_lit(&cast!(&body[0], IRNode::VNodeCall).tag, "div");
+        // whitespace between elements is unaffected
+        let body = comment_convert("<b/> <!-- a --> <i/>", false).body;
+        assert_eq!(body.len(), 3);
+    }
 }
@@ -8,6 +8,8 @@ use super::{
 use crate::parser::DirectiveArg;
 
 // this module process v-bind without arg and with arg.
+// Argument is kept as written unless `.camel` is present: SVG/MathML attributes
+// like stroke-width are set as attributes and runtime normalizes component props.
 pub fn convert_v_bind<'a>(
     dir: &mut Directive<'a>,
     _: &Element<'a>,
@@ -41,7 +43,6 @@ pub fn convert_v_bind<'a>(
                 Js::Compound(vec![Js::Src("("), e, Js::Src(") || ''")])
             }
         };
-        // TODO: handle .attr, .prop, modifiers in DOM
         if modifiers.contains(&"camel") {
             arg = match arg {
                 Js::StrLit(ref mut s) => {
@@ -51,6 +52,11 @@ pub fn convert_v_bind<'a>(
                 a => Js::Call(RuntimeHelper::CAMELIZE, vec![a]),
             }
         }
+        if modifiers.contains(&"prop") {
+            arg = inject_prefix(arg, true);
+        } else if modifiers.contains(&"attr") {
+            arg = inject_prefix(arg, false);
+        }
         Js::Props(vec![(arg, expr)])
     } else {
         expr
@@ -61,7 +67,76 @@ pub fn convert_v_bind<'a>(
     }
 }
 
+/// prefix of dynamic .prop key, followed by the key expression and `)`
+pub const DYNAMIC_PROP_PREFIX: &str = "'.' + (";
+/// prefix of dynamic .attr key, followed by the key expression and `)`
+pub const DYNAMIC_ATTR_PREFIX: &str = "'^' + (";
+
+/// .prop and .attr are encoded as `.`/`^` key prefixes for runtime-dom.
+fn inject_prefix(arg: Js, is_prop: bool) -> Js {
+    match arg {
+        Js::StrLit(mut s) => {
+            if is_prop {
+                s.prefix_prop();
+            } else {
+                s.prefix_attr();
+            }
+            Js::StrLit(s)
+        }
+        dynamic => {
+            let prefix = if is_prop {
+                DYNAMIC_PROP_PREFIX
+            } else {
+                DYNAMIC_ATTR_PREFIX
+            };
+            Js::Compound(vec![Js::Src(prefix), dynamic, Js::Src(")")])
+        }
+    }
+}
+
 pub const V_BIND: DirectiveConverter = ("bind", convert_v_bind);
 
 #[cfg(test)]
-mod test {}
+mod test {
+    use crate::converter::test::base_convert;
+    use crate::{cast, ir::IRNode, ir::JsExpr as Js};
+
+    fn first_child_keys(s: &str) -> (Vec<String>, Vec<String>) {
+        let mut ir = base_convert(s);
+        let vn = cast!(ir.body.remove(0), IRNode::VNodeCall);
+        let vn = cast!(&vn.children[0], IRNode::VNodeCall);
+        let props = cast!(vn.props.as_ref().unwrap(), Js::Props);
+        let keys = props
+            .iter()
+            .map(|(k, _)| cast!(k, Js::StrLit).into_string())
+            .collect();
+        let mut dynamic: Vec<_> = vn.dynamic_props.iter().map(|p| p.into_string()).collect();
+        dynamic.sort();
+        (keys, dynamic)
+    }
+
+    #[test]
+    fn test_svg_attr_not_camelized() {
+        let (keys, dynamic) =
+            first_child_keys("<svg><path :stroke-width='w' text-anchor='a'/></svg>");
+        assert_eq!(keys, ["stroke-width", "text-anchor"]);
+        assert_eq!(dynamic, ["stroke-width"]);
+        let (keys, dynamic) = first_child_keys("<math><mi :math-variant='v'/></math>");
+        assert_eq!(keys, ["math-variant"]);
+        assert_eq!(dynamic, ["math-variant"]);
+    }
+
+    #[test]
+    fn test_component_prop_as_written() {
+        let (keys, dynamic) = first_child_keys("<div><comp :stroke-width='w'/></div>");
+        assert_eq!(keys, ["stroke-width"]);
+        assert_eq!(dynamic, ["stroke-width"]);
+    }
+
+    #[test]
+    fn test_explicit_camel_on_svg() {
+        let (keys, dynamic) = first_child_keys("<svg><path :view-box.camel='v'/></svg>");
+        assert_eq!(keys, ["viewBox"]);
+        assert_eq!(dynamic, ["viewBox"]);
+    }
+}
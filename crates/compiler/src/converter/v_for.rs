@@ -1,10 +1,11 @@
+use super::cache_dir::remove_once_in_for;
 use super::{
-    BaseConvertInfo, BaseConversion, BaseIR, CompilationError, ConvertInfo, CoreConversion,
-    Directive, Element,
+    find_key_on_js, inject_prop, BaseConvertInfo, BaseConversion, BaseIR, CompilationError,
+    ConvertInfo, CoreConversion, Directive, Element,
 };
 use crate::{
     error::CompilationErrorKind as ErrorKind,
-    flags::PatchFlag,
+    flags::{PatchFlag, RuntimeHelper as RH},
     ir::{ForNodeIR, ForParseResult, IRNode, JsExpr as Js},
     parser::ElementType,
     util::{find_dir_empty, find_prop, VStr},
@@ -21,6 +22,12 @@ pub fn pre_convert_for<'a, T: ConvertInfo, C: CoreConversion<'a, T> + ?Sized>(
     let b = dir.take();
     debug_assert!(find_dir_empty(&mut *elem, "for").is_none());
     check_template_v_for_key(bc, elem);
+    if bc.is_dev() {
+        check_v_for_component_key(bc, elem, &b);
+    }
+    if bc.is_reactive_build() {
+        remove_once_in_for(bc, elem);
+    }
     Some(b)
 }
 
@@ -35,12 +42,14 @@ pub fn convert_for<'a>(bc: &BaseConversion, d: Directive<'a>, n: BaseIR<'a>) ->
         Some(parsed) => parsed,
         None => {
             let error = CompilationError::new(ErrorKind::VForMalformedExpression)
-                .with_location(expr.location.clone());
+                .with_location(expr.location.clone())
+                .with_context(expr.content.raw);
             bc.emit_error(error);
             return n;
         }
     };
-    let n = convert_memo_in_v_for(n, || parse_result.key.clone());
+    let n = collapse_template_child(n);
+    let n = convert_memo_in_v_for(n);
     IRNode::For(ForNodeIR {
         source,
         parse_result,
@@ -51,6 +60,34 @@ pub fn convert_for<'a>(bc: &BaseConversion, d: Directive<'a>, n: BaseIR<'a>) ->
     })
 }
 
+/// `<template v-for>` with exactly one element child needs no Fragment.
+/// The template's key is moved to the child. Other children like v-if,
+/// slot outlet or multiple nodes keep the keyed Fragment wrapper.
+fn collapse_template_child(n: BaseIR<'_>) -> BaseIR<'_> {
+    let mut fragment = match n {
+        IRNode::VNodeCall(v) if matches!(v.tag, Js::Symbol(RH::FRAGMENT)) => v,
+        _ => return n,
+    };
+    if fragment.children.len() != 1 || !matches!(fragment.children[0], IRNode::VNodeCall(_)) {
+        return IRNode::VNodeCall(fragment);
+    }
+    let mut child = match fragment.children.pop() {
+        Some(IRNode::VNodeCall(child)) => child,
+        _ => unreachable!(),
+    };
+    let key_prop = match fragment.props {
+        Some(Js::Props(mut ps)) if ps.len() == 1 => ps.pop().unwrap(),
+        _ => return IRNode::VNodeCall(child),
+    };
+    match &mut child.props {
+        // key on child is already reported and kept
+        Some(props) if find_key_on_js(props) => {}
+        Some(props) => inject_prop(props, key_prop),
+        None => child.props = Some(Js::Props(vec![key_prop])),
+    }
+    IRNode::VNodeCall(child)
+}
+
 type ParsedFor<'a> = (Js<'a>, ForParseResult<BaseConvertInfo<'a>>);
 
 const PARENS: &[char] = &['(', ')'];
@@ -95,10 +132,7 @@ fn split_v_for_iter(mut lhs: &str) -> (&str, Option<&str>, Option<&str>) {
     }
 }
 
-fn convert_memo_in_v_for<'a, F>(n: BaseIR<'a>, get_key: F) -> BaseIR<'a>
-where
-    F: Fn() -> Option<Js<'a>>,
-{
+fn convert_memo_in_v_for(n: BaseIR) -> BaseIR {
     use crate::ir::{
         CacheIR,
         CacheKind::{Memo, MemoInVFor},
@@ -112,12 +146,30 @@ where
         _ => return n,
     };
     let kind = MemoInVFor {
-        v_for_key: get_key(),
+        v_for_key: find_key_value(&child),
         expr,
     };
     CacheNode(CacheIR { kind, child })
 }
 
+/// `key` prop value of the memoized item, compared against cached item's key
+fn find_key_value<'a>(n: &BaseIR<'a>) -> Option<Js<'a>> {
+    match n {
+        IRNode::VNodeCall(v) => key_value_on_js(v.props.as_ref()?),
+        _ => None,
+    }
+}
+fn key_value_on_js<'a>(e: &Js<'a>) -> Option<Js<'a>> {
+    match e {
+        Js::Call(RH::MERGE_PROPS, args) => args.iter().find_map(key_value_on_js),
+        Js::Props(ps) => ps.iter().find_map(|(k, v)| match k {
+            Js::StrLit(s) if s.raw == "key" => Some(v.clone()),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
 // check <template v-for> key placement
 fn check_template_v_for_key<'a, T: ConvertInfo, C: CoreConversion<'a, T> + ?Sized>(
     bc: &C,
@@ -139,6 +191,32 @@ fn check_template_v_for_key<'a, T: ConvertInfo, C: CoreConversion<'a, T> + ?Size
     }
 }
 
+// unkeyed components in v-for are reused in place and keep stale state.
+// plain elements are exempt since in-place patch is mostly harmless there
+fn check_v_for_component_key<'a, T: ConvertInfo, C: CoreConversion<'a, T> + ?Sized>(
+    bc: &C,
+    elem: &Element,
+    dir: &Directive,
+) {
+    let is_stateful = |e: &Element| e.is_component() || bc.is_custom_element(e.tag_name);
+    if find_prop(elem, "key").is_some() {
+        return;
+    }
+    let need_key = if elem.tag_type == ElementType::Template {
+        let mut children = elem.children.iter().filter_map(|c| c.get_element());
+        // keyed children are reported by check_template_v_for_key
+        children.clone().all(|c| find_prop(c, "key").is_none()) && children.any(is_stateful)
+    } else {
+        is_stateful(elem)
+    };
+    if need_key {
+        let error = CompilationError::new(ErrorKind::VForComponentNoKey)
+            .with_location(dir.location.clone())
+            .with_context(elem.tag_name);
+        bc.emit_warning(error);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -181,4 +259,52 @@ mod test {
             assert!(parse_for_expr(VStr::raw(src)).is_none());
         }
     }
+
+    fn key_warnings(s: &str) -> usize {
+        use super::super::{BaseConverter, ConvertOption, Converter};
+        use crate::error::VecErrorHandler;
+        use crate::parser::test::base_parse;
+        use std::rc::Rc;
+        let eh = Rc::new(VecErrorHandler::default());
+        let option = ConvertOption {
+            is_custom_element: Rc::new(|t: &str| t.starts_with("my-")),
+            ..Default::default()
+        };
+        let bc = BaseConverter::new(eh.clone(), option);
+        bc.convert_ir(base_parse(s), &Default::default());
+        assert!(eh
+            .errors()
+            .iter()
+            .all(|e| !matches!(e.kind, ErrorKind::VForComponentNoKey)));
+        let warnings = eh.warnings();
+        warnings
+            .iter()
+            .filter(|e| matches!(e.kind, ErrorKind::VForComponentNoKey))
+            .count()
+    }
+
+    #[test]
+    fn test_component_key_warning() {
+        assert_eq!(key_warnings("<comp v-for='a in b'/>"), 1);
+        assert_eq!(key_warnings("<my-el v-for='a in b'/>"), 1);
+        assert_eq!(key_warnings("<comp v-for='a in b' :key='a'/>"), 0);
+        assert_eq!(key_warnings("<my-el v-for='a in b' key='a'/>"), 0);
+        assert_eq!(key_warnings("<div v-for='a in b'/>"), 0);
+        assert_eq!(key_warnings("<div v-for='a in b'><comp/></div>"), 0);
+    }
+
+    #[test]
+    fn test_template_key_warning() {
+        let mixed = "<template v-for='a in b'><p/><comp/></template>";
+        assert_eq!(key_warnings(mixed), 1);
+        let keyed = "<template v-for='a in b' :key='a'><p/><comp/></template>";
+        assert_eq!(key_warnings(keyed), 0);
+        // misplaced key is reported as VForTemplateKeyPlacement instead
+        let misplaced = "<template v-for='a in b'><p/><comp :key='a'/></template>";
+        assert_eq!(key_warnings(misplaced), 0);
+        assert_eq!(
+            key_warnings("<template v-for='a in b'><p/>text</template>"),
+            0
+        );
+    }
 }
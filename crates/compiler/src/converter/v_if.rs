@@ -1,11 +1,12 @@
 use super::{
-    super::parser::ElemProp, AstNode, BaseConvertInfo, BaseConversion as BC, BaseIR,
-    CompilationError, Directive, Element, IRNode,
+    super::parser::ElemProp, cache_dir::pre_convert_once, AstNode, BaseConvertInfo,
+    BaseConversion as BC, BaseIR, CompilationError, Directive, Element, IRNode,
 };
 use crate::{
     converter::{CoreConversion, JsExpr as Js},
     error::CompilationErrorKind as ErrorKind,
     ir::{IfBranch, IfNodeIR},
+    parser::ElementType,
     scanner::Attribute,
     util::{find_dir_empty, find_prop, VStr},
 };
@@ -102,25 +103,41 @@ pub fn pre_group_v_if(children: Vec<AstNode>) -> impl Iterator<Item = PreGroup>
 }
 
 /// key is Vue-generated default key based on the number of sibling v-if.
-pub fn convert_if<'a>(c: &BC<'a>, elems: Vec<Element<'a>>, key: usize) -> BaseIR<'a> {
+pub fn convert_if<'a>(c: &BC<'a>, mut elems: Vec<Element<'a>>, key: usize) -> BaseIR<'a> {
     debug_assert!(!elems.is_empty());
+    // parser closes every branch before its next sibling starts,
+    // so branches never leak nodes into each other.
+    debug_assert!(elems.windows(2).all(|w| {
+        let (prev, next) = (&w[0].location, &w[1].location);
+        prev.start.offset < prev.end.offset && prev.end.offset <= next.start.offset
+    }));
     check_v_if_group(c, &elems);
+    // v-once on v-if caches the whole conditional, not only one branch
+    let once = pre_convert_once(&mut elems[0]);
+    if once.is_some() {
+        for elem in elems.iter_mut().skip(1) {
+            pre_convert_once(elem);
+        }
+    }
     let branches: Vec<_> = elems
         .into_iter()
         .enumerate()
         .map(|(i, n)| convert_if_branch(c, n, key + i))
         .collect();
-    IRNode::If(IfNodeIR { branches })
+    let n = IRNode::If(IfNodeIR { branches });
+    match once {
+        Some(d) => c.convert_once(d, n),
+        None => n,
+    }
 }
 
 pub fn report_dangling_else<'a>(c: &BC<'a>, elem: &Element<'a>) {
     debug_assert!(find_dir_empty(elem, "if").is_none());
-    let loc = find_dir_empty(elem, ["else-if", "else"])
-        .expect("must have other v-if dir")
-        .get_ref()
-        .location
-        .clone();
-    let error = CompilationError::new(ErrorKind::VElseNoAdjacentIf).with_location(loc);
+    let found = find_dir_empty(elem, ["else-if", "else"]).expect("must have other v-if dir");
+    let dir = found.get_ref();
+    let error = CompilationError::new(ErrorKind::VElseNoAdjacentIf)
+        .with_location(dir.location.clone())
+        .with_context(dir.name);
     c.emit_error(error);
 }
 
@@ -136,8 +153,9 @@ fn check_duplicate_key<'a>(
             ..
         }) => {
             if dirs.contains(&v.content) {
-                let error =
-                    CompilationError::new(ErrorKind::VIfSameKey).with_location(v.location.clone());
+                let error = CompilationError::new(ErrorKind::VIfSameKey)
+                    .with_location(v.location.clone())
+                    .with_context(v.content.raw);
                 c.emit_error(error);
             } else {
                 dirs.insert(v.content);
@@ -145,8 +163,9 @@ fn check_duplicate_key<'a>(
         }
         ElemProp::Attr(Attribute { value: Some(v), .. }) => {
             if attrs.contains(&v.content) {
-                let error =
-                    CompilationError::new(ErrorKind::VIfSameKey).with_location(v.location.clone());
+                let error = CompilationError::new(ErrorKind::VIfSameKey)
+                    .with_location(v.location.clone())
+                    .with_context(v.content.raw);
                 c.emit_error(error);
             } else {
                 attrs.insert(v.content);
@@ -191,12 +210,34 @@ fn convert_if_branch<'a>(
         .take();
     report_duplicate_v_if(c, &mut e);
     let condition = convert_if_condition(c, dir);
+    let child = match comment_placeholder(c, &e) {
+        Some(comment) => IRNode::CommentCall(comment),
+        None => c.pre_convert_element(e),
+    };
     IfBranch {
-        child: Box::new(c.pre_convert_element(e)),
+        child: Box::new(child),
         condition,
         info: key,
     }
 }
+/// A `<template>` branch with only comments would render nothing once
+/// comments are stripped, so its first comment is kept as placeholder.
+fn comment_placeholder<'a>(c: &BC<'a>, e: &Element<'a>) -> Option<&'a str> {
+    if c.option.comments || e.tag_type != ElementType::Template || !e.properties.is_empty() {
+        return None;
+    }
+    let mut placeholder = None;
+    for child in e.children.iter() {
+        match child {
+            AstNode::Comment(comment) => {
+                placeholder.get_or_insert(comment.source);
+            }
+            AstNode::Text(t) if t.is_all_whitespace() => (),
+            _ => return None,
+        }
+    }
+    placeholder
+}
 fn convert_if_condition<'a>(c: &BC<'a>, dir: Directive<'a>) -> Option<Js<'a>> {
     if dir.name != "else" {
         if let Some(err) = dir.check_empty_expr(ErrorKind::VIfNoExpression) {
@@ -204,8 +245,9 @@ fn convert_if_condition<'a>(c: &BC<'a>, dir: Directive<'a>) -> Option<Js<'a>> {
             return Some(Js::Src("true"));
         }
     } else if let Some(expr) = dir.expression {
-        let error =
-            CompilationError::new(ErrorKind::UnexpectedDirExpression).with_location(expr.location);
+        let error = CompilationError::new(ErrorKind::UnexpectedDirExpression)
+            .with_location(expr.location)
+            .with_context(dir.name);
         c.emit_error(error);
         return None;
     }
@@ -215,7 +257,9 @@ fn report_duplicate_v_if<'a>(c: &BC<'a>, e: &mut Element<'a>) {
     // https://stackoverflow.com/a/48144226/2198656
     while let Some(found) = find_dir_empty(&mut *e, ["if", "else-if", "else"]) {
         let dir = found.take();
-        let error = CompilationError::new(ErrorKind::VIfDuplicateDir).with_location(dir.location);
+        let error = CompilationError::new(ErrorKind::VIfDuplicateDir)
+            .with_location(dir.location)
+            .with_context(dir.name);
         c.emit_error(error);
     }
 }
@@ -251,4 +295,51 @@ mod test {
         let cond = cast!(condition, Js::Simple);
         assert_eq!(cond.into_string(), "true");
     }
+
+    fn branch_children(ir: &BaseIR, i: usize) -> usize {
+        let v_if = cast!(ir, IRNode::If);
+        let vn = cast!(&*v_if.branches[i].child, IRNode::VNodeCall);
+        vn.children.len()
+    }
+
+    #[test]
+    fn test_stray_close_in_v_if() {
+        let body = base_convert("<div v-if='a'><b/></span><i/></div><p v-else/>").body;
+        assert_eq!(body.len(), 1);
+        assert_eq!(cast!(&body[0], IRNode::If).branches.len(), 2);
+        assert_eq!(branch_children(&body[0], 0), 2);
+    }
+
+    #[test]
+    fn test_stray_close_in_v_else() {
+        let body = base_convert("<p v-if='a'/><div v-else><b/></span><i/></div><p/>").body;
+        assert_eq!(body.len(), 2);
+        assert_eq!(cast!(&body[0], IRNode::If).branches.len(), 2);
+        assert_eq!(branch_children(&body[0], 1), 2);
+    }
+
+    #[test]
+    fn test_missing_close_at_branch_end() {
+        let body = base_convert("<div v-if='a'><b>x</div><div v-else>y</div>").body;
+        assert_eq!(body.len(), 1);
+        assert_eq!(cast!(&body[0], IRNode::If).branches.len(), 2);
+        // <b> is auto closed inside the v-if branch
+        assert_eq!(branch_children(&body[0], 0), 1);
+        assert_eq!(branch_children(&body[0], 1), 1);
+    }
+
+    #[test]
+    fn test_comment_only_branch() {
+        let s = "<template v-if='a'><!-- empty --></template><p v-else/>";
+        let body = comment_convert(s, true).body;
+        assert_eq!(branch_children(&body[0], 0), 1);
+        let body = comment_convert(s, false).body;
+        let v_if = cast!(&body[0], IRNode::If);
+        let placeholder = cast!(&*v_if.branches[0].child, IRNode::CommentCall);
+        assert_eq!(*placeholder, " empty ");
+        // branches with other content drop comments as usual
+        let s = "<template v-if='a'><!-- c --><p/></template>";
+        let body = comment_convert(s, false).body;
+        assert_eq!(branch_children(&body[0], 0), 1);
+    }
 }
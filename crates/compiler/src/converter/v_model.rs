@@ -26,12 +26,14 @@ pub fn convert_v_model_core<'a>(
         argument,
         ..
     } = dir;
-    let attr_value = expression.take().expect("empty dir should be dropped");
+    // expression is kept as the value of native v-model runtime directive
+    let attr_value = expression.as_ref().expect("empty dir should be dropped");
     let val = attr_value.content;
     // TODO: looks like pattern can also work?
     if !is_member_expression(val) {
-        let error =
-            Error::new(ErrorKind::VModelMalformedExpression).with_location(attr_value.location);
+        let error = Error::new(ErrorKind::VModelMalformedExpression)
+            .with_location(attr_value.location.clone())
+            .with_context(val.raw);
         eh.on_error(error);
         return DirectiveConvertResult::Dropped;
     }
@@ -56,14 +56,32 @@ pub fn convert_v_on<'a>(
     }
 }
 
+/// Handler of v-on with only modifiers, e.g. `@submit.prevent`.
+pub const NOOP_HANDLER: &str = "() => {}";
+
 pub fn convert_v_on_expr<'a>(expr: Option<&AttributeValue<'a>>) -> Js<'a> {
     let val = match expr {
-        Some(val) => val.content,
-        None => return Js::Src("() => {}"),
+        Some(val) if !val.content.trim().is_empty() => val.content,
+        _ => return Js::Src(NOOP_HANDLER),
     };
     Js::func(val)
 }
 
+/// Whether the handler is the noop, possibly wrapped by modifier guards like
+/// `withModifiers(() => {}, ["prevent"])`. It is constant and can be hoisted.
+pub fn is_noop_handler(exp: &Js) -> bool {
+    match exp {
+        Js::Src(s) => *s == NOOP_HANDLER,
+        Js::Call(_, args) => match args.as_slice() {
+            [handler, Js::Array(mods)] => {
+                is_noop_handler(handler) && mods.iter().all(|m| matches!(m, Js::StrLit(_)))
+            }
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
 fn is_js_identifier(c: char) -> bool {
     !not_js_identifier(c)
 }
@@ -114,6 +132,12 @@ pub fn is_member_expression(expr: VStr) -> bool {
 }
 
 pub fn get_handler_type(val: VStr) -> HandlerType {
+    // `_ctx.` prefix added by expression processing keeps the handler kind
+    let val = if VStr::is_ctx_prefixed(&val) && !VStr::is_event_assign(&val) {
+        VStr::raw(val.raw)
+    } else {
+        val
+    };
     if is_member_expression(val) {
         HandlerType::MemberExpr
     } else if is_fn_exp(val.raw) {
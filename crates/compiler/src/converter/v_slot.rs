@@ -17,7 +17,9 @@ use crate::{
 pub fn check_wrong_slot(bc: &BC, e: &Element, kind: ErrorKind) -> bool {
     if let Some(found) = dir_finder(e, "slot").allow_empty().find() {
         let dir = found.get_ref();
-        let error = CompilationError::new(kind).with_location(dir.location.clone());
+        let error = CompilationError::new(kind)
+            .with_location(dir.location.clone())
+            .with_context(e.tag_name);
         bc.emit_error(error);
         true
     } else {
@@ -28,9 +30,10 @@ pub fn check_wrong_slot(bc: &BC, e: &Element, kind: ErrorKind) -> bool {
 pub fn check_build_as_slot(e: &Element, tag: &Js) -> bool {
     debug_assert!(e.tag_type != ElementType::Template);
     use RuntimeHelper as RH;
+    // KeepAlive and Teleport take raw children instead of slot functions
     match tag {
-        Js::Symbol(RH::KEEP_ALIVE) => true,
-        Js::Symbol(RH::TELEPORT) => true,
+        Js::Symbol(RH::KEEP_ALIVE) => false,
+        Js::Symbol(RH::TELEPORT) => false,
         _ => e.is_component(),
     }
 }
@@ -162,8 +165,9 @@ fn build_stable_slot<'a>(
     let name = get_slot_name(&argument);
     if let Js::StrLit(n) = &name {
         if seen.contains(n.raw) {
-            let error =
-                CompilationError::new(ErrorKind::VSlotDuplicateSlotNames).with_location(loc);
+            let error = CompilationError::new(ErrorKind::VSlotDuplicateSlotNames)
+                .with_location(loc)
+                .with_context(n.raw);
             bc.emit_error(error);
             return None;
         }
@@ -182,7 +186,7 @@ fn build_alterable_slots<'a>(bc: &BC<'a>, mut templates: Vec<Element<'a>>) -> Ve
     let templates = templates.into_iter().map(AstNode::Element);
     let mut ir_nodes = bc.convert_children(templates.collect());
     // re-assign name to slot
-    assign_slot_names(ir_nodes.iter_mut(), &mut dirs);
+    assign_slot_names(bc, ir_nodes.iter_mut(), &mut dirs);
     debug_assert!(dirs.is_empty(), "all v-slot should be consumed");
     ir_nodes
 }
@@ -193,7 +197,7 @@ fn get_child<'a, 'b>(b: &'b mut IfBranch<BaseConvertInfo<'a>>) -> &'b mut BaseIR
     &mut *b.child
 }
 
-fn assign_slot_names<'a, 'b, I>(ir_nodes: I, dirs: &'b mut VecDeque<Directive<'a>>)
+fn assign_slot_names<'a, 'b, I>(bc: &BC<'a>, ir_nodes: I, dirs: &'b mut VecDeque<Directive<'a>>)
 where
     I: Iterator<Item = &'b mut BaseIR<'a>>,
 {
@@ -201,11 +205,20 @@ where
         match ir {
             IRNode::If(i) => {
                 let branches = i.branches.iter_mut().map(get_child);
-                assign_slot_names(branches, dirs);
+                assign_slot_names(bc, branches, dirs);
             }
             IRNode::For(f) => {
                 let child = std::iter::once(&mut *f.child);
-                assign_slot_names(child, dirs);
+                assign_slot_names(bc, child, dirs);
+            }
+            IRNode::CacheNode(c) => {
+                let dir = dirs.front().expect("should be non empty");
+                let error = CompilationError::new(ErrorKind::VSlotTemplateCached)
+                    .with_location(dir.location.clone());
+                bc.emit_error(error);
+                // slot function cannot be cached, render the template as usual
+                *ir = mem::replace(&mut *c.child, IRNode::CommentCall(""));
+                assign_slot_names(bc, std::iter::once(ir), dirs);
             }
             IRNode::VNodeCall(vnode) => {
                 let body = mem::take(&mut vnode.children);
@@ -214,7 +227,7 @@ where
                 let param = dir.expression.map(|v| Js::Param(v.content.raw));
                 *ir = IRNode::AlterableSlot(Slot { name, param, body });
             }
-            _ => panic!("alterable slot only contains if/for/vnode call"),
+            _ => unreachable!("alterable slot only contains if/for/cache/vnode call"),
         };
     }
 }
@@ -261,6 +274,16 @@ mod test {
         assert_str_lit(&text.texts[0], "hello");
     }
     #[test]
+    fn test_cached_alterable_slot() {
+        let case = "<comp><template #a v-if='x' v-once>hi</template></comp>";
+        let mut body = base_convert(case).body;
+        let mut vn = cast!(body.remove(0), IRNode::VNodeCall);
+        let mut v_slot = cast!(vn.children.remove(0), IRNode::VSlotUse);
+        let mut i = cast!(v_slot.alterable_slots.remove(0), IRNode::If);
+        let slot = cast!(*i.branches.remove(0).child, IRNode::AlterableSlot);
+        assert_str_lit(&slot.name, "a");
+    }
+    #[test]
     fn test_implicit_named_slot() {}
     #[test]
     fn test_template_slot() {}
@@ -1,10 +1,23 @@
 use super::SourceLocation;
 use std::cell::{Ref, RefMut, RefCell};
 use std::fmt;
+use std::io;
 use std::rc::Rc;
 
 pub trait ErrorKind {
     fn msg(&self) -> &'static str;
+    /// stable kebab-case identifier for documentation lookup
+    fn slug(&self) -> Option<&'static str> {
+        None
+    }
+    /// actionable suggestion, interpolated with the offending name if any
+    fn help(&self, _context: Option<&str>) -> Option<String> {
+        None
+    }
+    /// numeric code of vue-next `ErrorCodes`, None for errors vue-next does not have
+    fn code(&self) -> Option<u32> {
+        None
+    }
 }
 
 pub enum CompilationErrorKind {
@@ -42,6 +55,7 @@ pub enum CompilationErrorKind {
     MissingDirectiveArg,
     MissingDirectiveMod,
     InvalidVSlotModifier,
+    SelfClosingNonVoidElement,
 
     // transform errors
     VIfNoExpression,
@@ -51,6 +65,7 @@ pub enum CompilationErrorKind {
     VForNoExpression,
     VForMalformedExpression,
     VForTemplateKeyPlacement,
+    VForComponentNoKey,
     VBindNoExpression,
     VOnNoExpression,
     VSlotUnexpectedDirectiveOnSlotOutlet,
@@ -59,7 +74,9 @@ pub enum CompilationErrorKind {
     VSlotDuplicateSlotNames,
     VSlotExtraneousDefaultSlotChildren,
     VSlotMisplaced,
+    VSlotTemplateCached,
     VMemoNoExpression,
+    VOnceInVFor,
     VModelNoExpression,
     VModelMalformedExpression,
     VModelOnScopeVariable,
@@ -67,6 +84,10 @@ pub enum CompilationErrorKind {
 
     UnexpectedDirExpression,
     KeepAliveInvalidChildren,
+    ComponentMissingIs,
+    DuplicateProp,
+    IgnoredSideEffectTag,
+    UnguardedSelfReference,
 
     // generic errors
     PrefixIdNotSupported,
@@ -89,6 +110,9 @@ pub struct CompilationError {
     pub kind: CompilationErrorKind,
     pub additional_message: Option<&'static str>,
     pub location: SourceLocation,
+    /// The offending name, e.g. tag, directive or slot name.
+    /// Used to interpolate help text.
+    pub context: Option<String>,
 }
 
 impl CompilationError {
@@ -97,6 +121,7 @@ impl CompilationError {
             kind,
             additional_message: None,
             location: Default::default(),
+            context: None,
         }
     }
     pub fn with_location(mut self, loc: SourceLocation) -> Self {
@@ -107,17 +132,29 @@ impl CompilationError {
         self.additional_message = Some(msg);
         self
     }
+    pub fn with_context<S: Into<String>>(mut self, context: S) -> Self {
+        self.context = Some(context.into());
+        self
+    }
     pub fn extended<K: ErrorKind + 'static>(kind: K) -> Self {
-        Self {
-            kind: CompilationErrorKind::ExtendPoint(Box::new(kind)),
-            additional_message: None,
-            location: Default::default(),
-        }
+        Self::new(CompilationErrorKind::ExtendPoint(Box::new(kind)))
     }
 
     fn msg(&self) -> &'static str {
         msg(&self.kind)
     }
+    /// Stable documentation slug, e.g. `v-slot-misplaced`.
+    pub fn slug(&self) -> Option<&'static str> {
+        self.kind.slug()
+    }
+    /// Short suggestion on how to fix the error, distinct from the message.
+    pub fn help(&self) -> Option<String> {
+        self.kind.help(self.context.as_deref())
+    }
+    /// Same number as vue-next, e.g. 30 for `X_V_ELSE_NO_ADJACENT_IF`.
+    pub fn code(&self) -> Option<u32> {
+        self.kind.code()
+    }
 }
 
 #[cold]
@@ -163,6 +200,8 @@ fn msg(kind: &CompilationErrorKind) -> &'static str {
         MissingDirectiveArg => "Directive argument was expected.",
         MissingDirectiveMod => "Directive modifier was expected.",
         InvalidVSlotModifier => "v-slot does not take modifier.",
+        SelfClosingNonVoidElement =>
+            "Self-closing non-void element is treated as an open tag in in-DOM templates. Use an explicit end tag.",
 
         // transform errors
         VIfNoExpression => "v-if/v-else-if is missing expression.",
@@ -172,6 +211,7 @@ fn msg(kind: &CompilationErrorKind) -> &'static str {
         VForNoExpression => "v-for is missing expression.",
         VForMalformedExpression => "v-for has invalid expression.",
         VForTemplateKeyPlacement => "<template v-for> key should be placed on the <template> tag.",
+        VForComponentNoKey => "Component in v-for should have a key to preserve its state.",
         VBindNoExpression => "v-bind is missing expression.",
         VOnNoExpression => "v-on is missing expression.",
         VSlotUnexpectedDirectiveOnSlotOutlet => "Unexpected custom directive on <slot> outlet.",
@@ -181,8 +221,10 @@ fn msg(kind: &CompilationErrorKind) -> &'static str {
         VSlotExtraneousDefaultSlotChildren =>
             r#"Extraneous children found when component already has explicitly named "default slot. These children will be ignored."#,
         VSlotMisplaced => "v-slot can only be used on components or <template> tags.",
+        VSlotTemplateCached => "v-once and v-memo are ignored on <template v-slot>.",
         VSlotTemplateMisplaced => "<template v-slot> can only be used as a component's direct child.",
         VMemoNoExpression => "v-memo is missing expression.",
+        VOnceInVFor => "v-once inside v-for is ignored since all items would share one cache.",
         VModelNoExpression => "v-model is missing expression.",
         VModelMalformedExpression => "v-model value must be a valid JavaScript member expression.",
         VModelOnScopeVariable =>
@@ -190,6 +232,11 @@ fn msg(kind: &CompilationErrorKind) -> &'static str {
         InvalidExpression => "Error parsing JavaScript expression: ",
         UnexpectedDirExpression => "This directive does not accept any epxression.",
         KeepAliveInvalidChildren => "<KeepAlive> expects exactly one child component.",
+        ComponentMissingIs => "<component> is missing `is` binding.",
+        DuplicateProp => "Prop is set by more than one attribute or directive.",
+        IgnoredSideEffectTag => "Tags with side effect (<script> and <style>) are ignored in client component templates.",
+        UnguardedSelfReference =>
+            "Component renders itself unconditionally, which recurses infinitely at runtime.",
 
         // generic errors
         PrefixIdNotSupported =>
@@ -202,10 +249,309 @@ fn msg(kind: &CompilationErrorKind) -> &'static str {
     }
 }
 
+#[cold]
+#[inline(never)]
+fn slug(kind: &CompilationErrorKind) -> Option<&'static str> {
+    use CompilationErrorKind::*;
+    let s = match *kind {
+        AbruptClosingOfEmptyComment => "abrupt-closing-of-empty-comment",
+        CDataInHtmlContent => "cdata-in-html-content",
+        DuplicateAttribute => "duplicate-attribute",
+        EndTagWithAttributes => "end-tag-with-attributes",
+        EndTagWithTrailingSolidus => "end-tag-with-trailing-solidus",
+        EofBeforeTagName => "eof-before-tag-name",
+        EofInCdata => "eof-in-cdata",
+        EofInComment => "eof-in-comment",
+        EofInScriptHtmlCommentLikeText => "eof-in-script-html-comment-like-text",
+        EofInTag => "eof-in-tag",
+        IncorrectlyClosedComment => "incorrectly-closed-comment",
+        IncorrectlyOpenedComment => "incorrectly-opened-comment",
+        InvalidFirstCharacterOfTagName => "invalid-first-character-of-tag-name",
+        MissingAttributeValue => "missing-attribute-value",
+        MissingEndTagName => "missing-end-tag-name",
+        MissingWhitespaceBetweenAttributes => "missing-whitespace-between-attributes",
+        NestedComment => "nested-comment",
+        UnexpectedEqualsSignBeforeAttributeName => "unexpected-equals-sign-before-attribute-name",
+        UnexpectedCharacterInAttributeName => "unexpected-character-in-attribute-name",
+        UnexpectedCharacterInUnquotedAttributeValue => {
+            "unexpected-character-in-unquoted-attribute-value"
+        }
+        UnexpectedNullCharacter => "unexpected-null-character",
+        UnexpectedQuestionMarkInsteadOfTagName => "unexpected-question-mark-instead-of-tag-name",
+        UnexpectedSolidusInTag => "unexpected-solidus-in-tag",
+
+        InvalidEndTag => "invalid-end-tag",
+        MissingEndTag => "missing-end-tag",
+        MissingInterpolationEnd => "missing-interpolation-end",
+        MissingDynamicDirectiveArgumentEnd => "missing-dynamic-directive-argument-end",
+        UnexpectedContentAfterDynamicDirective => "unexpected-content-after-dynamic-directive",
+        MissingDirectiveName => "missing-directive-name",
+        MissingDirectiveArg => "missing-directive-arg",
+        MissingDirectiveMod => "missing-directive-mod",
+        InvalidVSlotModifier => "invalid-v-slot-modifier",
+        SelfClosingNonVoidElement => "self-closing-non-void-element",
+
+        VIfNoExpression => "v-if-no-expression",
+        VIfSameKey => "v-if-same-key",
+        VIfDuplicateDir => "v-if-duplicate-dir",
+        VElseNoAdjacentIf => "v-else-no-adjacent-if",
+        VForNoExpression => "v-for-no-expression",
+        VForMalformedExpression => "v-for-malformed-expression",
+        VForTemplateKeyPlacement => "v-for-template-key-placement",
+        VForComponentNoKey => "v-for-component-no-key",
+        VBindNoExpression => "v-bind-no-expression",
+        VOnNoExpression => "v-on-no-expression",
+        VSlotUnexpectedDirectiveOnSlotOutlet => "v-slot-unexpected-directive-on-slot-outlet",
+        VSlotMixedSlotUsage => "v-slot-mixed-slot-usage",
+        VSlotTemplateMisplaced => "v-slot-template-misplaced",
+        VSlotDuplicateSlotNames => "v-slot-duplicate-slot-names",
+        VSlotExtraneousDefaultSlotChildren => "v-slot-extraneous-default-slot-children",
+        VSlotMisplaced => "v-slot-misplaced",
+        VSlotTemplateCached => "v-slot-template-cached",
+        VMemoNoExpression => "v-memo-no-expression",
+        VOnceInVFor => "v-once-in-v-for",
+        VModelNoExpression => "v-model-no-expression",
+        VModelMalformedExpression => "v-model-malformed-expression",
+        VModelOnScopeVariable => "v-model-on-scope-variable",
+        InvalidExpression => "invalid-expression",
+        UnexpectedDirExpression => "unexpected-dir-expression",
+        KeepAliveInvalidChildren => "keep-alive-invalid-children",
+        ComponentMissingIs => "component-missing-is",
+        DuplicateProp => "duplicate-prop",
+        IgnoredSideEffectTag => "ignored-side-effect-tag",
+        UnguardedSelfReference => "unguarded-self-reference",
+
+        PrefixIdNotSupported => "prefix-id-not-supported",
+        ModuleModeNotSupported => "module-mode-not-supported",
+        CacheHandlerNotSupported => "cache-handler-not-supported",
+        ScopeIdNotSupported => "scope-id-not-supported",
+        ExtendPoint(ref err) => return err.slug(),
+    };
+    Some(s)
+}
+
+#[cold]
+#[inline(never)]
+fn help(kind: &CompilationErrorKind, context: Option<&str>) -> Option<String> {
+    use CompilationErrorKind::*;
+    let name = |default: &'static str| context.unwrap_or(default);
+    let h = match *kind {
+        DuplicateAttribute => {
+            format!("Remove one of the duplicate `{}` attributes.", name("same"))
+        }
+        EofInTag => "Close the tag with `>` before the end of template.".into(),
+        EofInComment => "Close the comment with `-->`.".into(),
+        MissingAttributeValue => "Quote the value after `=`, or remove the `=`.".into(),
+        InvalidFirstCharacterOfTagName => "Use `&lt;` to print a literal `<`.".into(),
+        InvalidEndTag => {
+            let tag = name("tag");
+            format!("Remove `</{0}>`, or add a matching `<{0}>` before it.", tag)
+        }
+        MissingEndTag => {
+            let tag = name("tag");
+            format!("Add `</{0}>`, or write `<{0}/>` if it has no children.", tag)
+        }
+        MissingInterpolationEnd => "Close the interpolation with `}}`.".into(),
+        MissingDynamicDirectiveArgumentEnd => format!(
+            "Close the dynamic argument in `{}` with `]` and remove spaces inside it.",
+            name("directive"),
+        ),
+        UnexpectedContentAfterDynamicDirective => format!(
+            "Separate modifiers in `{}` with `.`, e.g. `v-bind:[key].prop`.",
+            name("directive"),
+        ),
+        MissingDirectiveName => format!(
+            "Write a directive name after `v-` in `{}`, e.g. `v-show`.",
+            name("v-"),
+        ),
+        MissingDirectiveArg => format!(
+            "Write an argument after the prefix in `{}`, e.g. `:id` or `@click`.",
+            name("directive"),
+        ),
+        MissingDirectiveMod => format!(
+            "Remove the trailing or duplicate `.` in `{}`.",
+            name("directive")
+        ),
+        InvalidVSlotModifier => format!(
+            "Remove modifiers from `{}`, v-slot only takes an argument, e.g. `#header`.",
+            name("v-slot")
+        ),
+        SelfClosingNonVoidElement => {
+            let tag = name("tag");
+            format!("Write `<{0}></{0}>` instead of `<{0}/>`.", tag)
+        }
+        VIfNoExpression => format!(
+            "Add a condition to `{}`, e.g. `v-if=\"ok\"`.",
+            name("v-if")
+        ),
+        VIfSameKey => format!(
+            "Give each branch a different key, `{}` is used more than once.",
+            name("key")
+        ),
+        VIfDuplicateDir => format!(
+            "Keep only one of v-if/v-else-if/v-else, `v-{}` is redundant. Chain conditions on sibling elements.",
+            name("if")
+        ),
+        VElseNoAdjacentIf => format!(
+            "Place `v-{}` right after an element with v-if or v-else-if, only comments and whitespace may sit between.",
+            name("else")
+        ),
+        VForNoExpression => "Add an expression to v-for, e.g. `v-for=\"item in items\"`.".into(),
+        VForMalformedExpression => format!(
+            "Rewrite `{}` as `item in items` or `(item, index) in items`.",
+            name("expression")
+        ),
+        VForTemplateKeyPlacement => "Move `key` from the child to the `<template v-for>` tag.".into(),
+        VForComponentNoKey => format!(
+            "Add a unique `:key` to `<{}>`, e.g. `:key=\"item.id\"`.",
+            name("component")
+        ),
+        VBindNoExpression => format!(
+            "Add a value to `{}`, e.g. `:id=\"id\"`, or remove the directive.",
+            name("v-bind")
+        ),
+        VOnNoExpression => format!(
+            "Add a handler to `{}`, e.g. `@click=\"onClick\"`.",
+            name("v-on")
+        ),
+        VSlotUnexpectedDirectiveOnSlotOutlet => format!(
+            "Remove `v-{}` from `<slot>`, or move it to a wrapping element.",
+            name("directive")
+        ),
+        VSlotMixedSlotUsage => format!(
+            "Move v-slot from `<{}>` to a `<template #default>` child.",
+            name("component")
+        ),
+        VSlotTemplateMisplaced => {
+            "Move `<template v-slot>` to be a direct child of the component.".into()
+        }
+        VSlotDuplicateSlotNames => format!(
+            "Merge the `{}` slot templates into one, or rename one of them.",
+            name("same")
+        ),
+        VSlotExtraneousDefaultSlotChildren => {
+            "Move loose children into the `<template #default>` tag.".into()
+        }
+        VSlotMisplaced => format!(
+            "Move v-slot from `<{}>` to a component or a `<template>` inside a component.",
+            name("element")
+        ),
+        VSlotTemplateCached => {
+            "Move v-once or v-memo onto an element inside the slot template.".into()
+        }
+        VMemoNoExpression => "Add a dependency array to v-memo, e.g. `v-memo=\"[a, b]\"`.".into(),
+        VOnceInVFor => {
+            "Move v-once onto the element with v-for to render the list once, or use v-memo.".into()
+        }
+        VModelNoExpression => "Bind v-model to a property, e.g. `v-model=\"text\"`.".into(),
+        VModelMalformedExpression => format!(
+            "Use a writable property instead of `{}`, e.g. `v-model=\"form.name\"`.",
+            name("expression")
+        ),
+        VModelOnScopeVariable => format!(
+            "`{}` is a scope variable. Bind v-model to a property of it or of the component state.",
+            name("variable")
+        ),
+        UnexpectedDirExpression => format!("Remove the value of `v-{}`.", name("directive")),
+        KeepAliveInvalidChildren => {
+            "Wrap multiple children with a single component, or use v-if/v-else.".into()
+        }
+        ComponentMissingIs => {
+            "Add the component to render, e.g. `<component :is=\"view\"/>`.".into()
+        }
+        DuplicateProp => format!("Keep only one of {}.", name("the bindings")),
+        IgnoredSideEffectTag => format!(
+            "Move `<{}>` out of <template> into its own SFC block.",
+            name("tag")
+        ),
+        UnguardedSelfReference => format!(
+            "Render `<{}>` under v-if, v-for or a slot so that recursion can stop.",
+            name("component")
+        ),
+        ExtendPoint(ref err) => return err.help(context),
+        _ => return None,
+    };
+    Some(h)
+}
+
 impl ErrorKind for CompilationErrorKind {
     fn msg(&self) -> &'static str {
         msg(self)
     }
+    fn slug(&self) -> Option<&'static str> {
+        slug(self)
+    }
+    fn help(&self, context: Option<&str>) -> Option<String> {
+        help(self, context)
+    }
+    fn code(&self) -> Option<u32> {
+        code(self)
+    }
+}
+
+/// Keep in sync with `ErrorCodes` in @vue/compiler-core.
+fn code(kind: &CompilationErrorKind) -> Option<u32> {
+    use CompilationErrorKind::*;
+    let c = match kind {
+        AbruptClosingOfEmptyComment => 0,
+        CDataInHtmlContent => 1,
+        DuplicateAttribute => 2,
+        EndTagWithAttributes => 3,
+        EndTagWithTrailingSolidus => 4,
+        EofBeforeTagName => 5,
+        EofInCdata => 6,
+        EofInComment => 7,
+        EofInScriptHtmlCommentLikeText => 8,
+        EofInTag => 9,
+        IncorrectlyClosedComment => 10,
+        IncorrectlyOpenedComment => 11,
+        InvalidFirstCharacterOfTagName => 12,
+        MissingAttributeValue => 13,
+        MissingEndTagName => 14,
+        MissingWhitespaceBetweenAttributes => 15,
+        NestedComment => 16,
+        UnexpectedCharacterInAttributeName => 17,
+        UnexpectedCharacterInUnquotedAttributeValue => 18,
+        UnexpectedEqualsSignBeforeAttributeName => 19,
+        UnexpectedNullCharacter => 20,
+        UnexpectedQuestionMarkInsteadOfTagName => 21,
+        UnexpectedSolidusInTag => 22,
+
+        InvalidEndTag => 23,
+        MissingEndTag => 24,
+        MissingInterpolationEnd => 25,
+        MissingDirectiveName => 26,
+        MissingDynamicDirectiveArgumentEnd => 27,
+
+        VIfNoExpression => 28,
+        VIfSameKey => 29,
+        VElseNoAdjacentIf => 30,
+        VForNoExpression => 31,
+        VForMalformedExpression => 32,
+        VForTemplateKeyPlacement => 33,
+        VBindNoExpression => 34,
+        VOnNoExpression => 35,
+        VSlotUnexpectedDirectiveOnSlotOutlet => 36,
+        VSlotMixedSlotUsage => 37,
+        VSlotDuplicateSlotNames => 38,
+        VSlotExtraneousDefaultSlotChildren => 39,
+        VSlotMisplaced => 40,
+        VModelNoExpression => 41,
+        VModelMalformedExpression => 42,
+        VModelOnScopeVariable => 43,
+        InvalidExpression => 44,
+        KeepAliveInvalidChildren => 45,
+
+        PrefixIdNotSupported => 46,
+        ModuleModeNotSupported => 47,
+        CacheHandlerNotSupported => 48,
+        ScopeIdNotSupported => 49,
+        // X_IGNORED_SIDE_EFFECT_TAG is a DOMErrorCodes in vue-next
+        IgnoredSideEffectTag => 60,
+        ExtendPoint(err) => return err.code(),
+        _ => return None,
+    };
+    Some(c)
 }
 
 impl fmt::Display for CompilationError {
@@ -218,6 +564,43 @@ impl fmt::Display for CompilationError {
     }
 }
 
+/// Unrecoverable failure that aborts the compilation. Recoverable template
+/// errors are reported to [ErrorHandler] and compilation goes on.
+#[derive(Debug)]
+pub enum CompileError {
+    /// valid template that the compiler cannot generate code for yet
+    UnsupportedSyntax {
+        description: &'static str,
+    },
+    Io(io::Error),
+}
+
+impl fmt::Display for CompileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnsupportedSyntax { description } => {
+                write!(f, "unsupported syntax: {}", description)
+            }
+            Self::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for CompileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CompileError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
 /// This trait handles error occured in the compilation.
 /// NB: clone bound is needed since scan/parse/ir/code gen
 /// all requires ownership of a error report.
@@ -227,6 +610,17 @@ pub trait ErrorHandler {
     // cannot use mut ref due to borrow semantics
     // use RefCell as implementation
     fn on_error(&self, _: CompilationError) {}
+    /// Issues that do not make the output wrong, e.g. a self-closing `<div/>`.
+    /// Same as vue-next's `onWarn`, warnings are not counted in `error_count`.
+    fn on_warn(&self, _: CompilationError) {}
+    /// Number of errors received so far. Handlers dropping errors return 0.
+    fn error_count(&self) -> usize {
+        0
+    }
+    /// Number of warnings received so far. Handlers dropping warnings return 0.
+    fn warning_count(&self) -> usize {
+        0
+    }
 }
 
 pub type RcErrHandle = Rc<dyn ErrorHandler>;
@@ -236,6 +630,7 @@ impl ErrorHandler for NoopErrorHandler {}
 
 pub struct VecErrorHandler {
     errors: RefCell<Vec<CompilationError>>,
+    warnings: RefCell<Vec<CompilationError>>,
 }
 impl VecErrorHandler {
     pub fn errors(&self) -> Ref<Vec<CompilationError>> {
@@ -244,11 +639,15 @@ impl VecErrorHandler {
     pub fn error_mut(&self) -> RefMut<Vec<CompilationError>> {
         self.errors.borrow_mut()
     }
+    pub fn warnings(&self) -> Ref<Vec<CompilationError>> {
+        self.warnings.borrow()
+    }
 }
 impl Default for VecErrorHandler {
     fn default() -> Self {
         Self {
             errors: RefCell::new(vec![]),
+            warnings: RefCell::new(vec![]),
         }
     }
 }
@@ -257,12 +656,156 @@ impl ErrorHandler for VecErrorHandler {
     fn on_error(&self, e: CompilationError) {
         self.errors.borrow_mut().push(e);
     }
+    fn on_warn(&self, e: CompilationError) {
+        self.warnings.borrow_mut().push(e);
+    }
+    fn error_count(&self) -> usize {
+        self.errors.borrow().len()
+    }
+    fn warning_count(&self) -> usize {
+        self.warnings.borrow().len()
+    }
 }
 
 #[cfg(test)]
 pub mod test {
-    use super::ErrorHandler;
+    use super::*;
+    use crate::codegen::RenderMode;
+    use crate::compiler::{get_base_passes, BaseCompiler, CompileOption, TemplateCompiler};
     #[derive(Clone)]
     pub struct TestErrorHandler;
     impl ErrorHandler for TestErrorHandler {}
+
+    fn compile_reports(s: &str) -> Rc<VecErrorHandler> {
+        let eh = Rc::new(VecErrorHandler::default());
+        let option = CompileOption {
+            is_native_tag: |t, _| t != "comp",
+            error_handler: eh.clone(),
+            ..Default::default()
+        };
+        let sfc_info = Default::default();
+        let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+        compiler.compile(s, &sfc_info).unwrap();
+        eh
+    }
+
+    fn first_error(s: &str) -> CompilationError {
+        let eh = compile_reports(s);
+        let mut errors = eh.error_mut();
+        assert!(!errors.is_empty(), "{} should report error", s);
+        errors.remove(0)
+    }
+
+    fn first_warning(s: &str) -> CompilationError {
+        let eh = compile_reports(s);
+        let mut warnings = eh.warnings.borrow_mut();
+        assert!(!warnings.is_empty(), "{} should report warning", s);
+        warnings.remove(0)
+    }
+
+    fn assert_help(s: &str, slug: &str, expected: &str) {
+        check_help(first_error(s), slug, expected);
+    }
+
+    fn assert_warning_help(s: &str, slug: &str, expected: &str) {
+        check_help(first_warning(s), slug, expected);
+    }
+
+    fn check_help(error: CompilationError, slug: &str, expected: &str) {
+        assert_eq!(error.slug(), Some(slug));
+        let help = error.help().expect("should have help");
+        assert!(help.contains(expected), "{}", help);
+        assert_ne!(help, error.to_string());
+    }
+
+    #[test]
+    fn test_error_help() {
+        assert_help("<div><span></div>", "missing-end-tag", "</span>");
+        assert_help("<p :id></p>", "v-bind-no-expression", "`v-bind:id`");
+        assert_help(
+            "<p v-for='item'></p>",
+            "v-for-malformed-expression",
+            "`item`",
+        );
+        assert_help("<p v-slot:header></p>", "v-slot-misplaced", "`<p>`");
+        assert_help("<component/>", "component-missing-is", "`<component :is");
+        let dup = "<comp><template #top>a</template><template #top>b</template></comp>";
+        assert_help(dup, "v-slot-duplicate-slot-names", "`top`");
+    }
+
+    #[test]
+    fn test_warning_help() {
+        assert_warning_help(
+            "<comp v-for='a in b'/>",
+            "v-for-component-no-key",
+            "`<comp>`",
+        );
+        let dup = "<p id='a' :id='b'/>";
+        assert_warning_help(dup, "duplicate-prop", "`id` at 1:4 and `v-bind:id` at 1:11");
+    }
+
+    #[test]
+    fn test_help_without_context() {
+        let error = CompilationError::new(CompilationErrorKind::MissingEndTag);
+        assert!(error.help().unwrap().contains("</tag>"));
+        let error = CompilationError::new(CompilationErrorKind::PrefixIdNotSupported);
+        assert!(error.help().is_none());
+        assert_eq!(error.slug(), Some("prefix-id-not-supported"));
+    }
+
+    #[test]
+    fn test_recoverable_expression_error() {
+        use crate::codegen::ScriptMode;
+        for source in ["<p>{{ a + }}</p>", "<p v-for='in list'/>"] {
+            let eh = Rc::new(VecErrorHandler::default());
+            let option = CompileOption {
+                error_handler: eh.clone(),
+                mode: ScriptMode::Function {
+                    prefix_identifier: true,
+                    runtime_global_name: "Vue".into(),
+                },
+                ..Default::default()
+            };
+            let sfc_info = Default::default();
+            let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+            let ret = compiler.compile(source, &sfc_info);
+            let code = ret.expect("expression error should not abort compilation");
+            assert!(!code.is_empty());
+            assert!(!eh.errors().is_empty(), "{} should report error", source);
+        }
+    }
+
+    #[test]
+    fn test_unsupported_syntax() {
+        let option = CompileOption {
+            render_mode: RenderMode::Ssr,
+            ..Default::default()
+        };
+        let sfc_info = Default::default();
+        let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+        assert!(compiler.compile("<div>text</div>", &sfc_info).is_ok());
+        let ret = compiler.compile("<div v-if='a'>text</div>", &sfc_info);
+        let error = ret.expect_err("SSR v-if should be unsupported");
+        assert!(matches!(error, CompileError::UnsupportedSyntax { .. }));
+        assert!(error.to_string().starts_with("unsupported syntax"));
+    }
+
+    fn assert_span(s: &str, code: u32, span: (usize, usize)) {
+        let error = first_error(s);
+        assert_eq!(error.code(), Some(code), "{}", error);
+        let loc = &error.location;
+        assert_eq!((loc.start.offset, loc.end.offset), span, "{}", s);
+    }
+
+    #[test]
+    fn test_error_code_and_span() {
+        // spans the v-else attribute
+        assert_span("<div v-else>a</div>", 30, (5, 11));
+        // spans the dropped attribute
+        assert_span(r#"<div id="a" id="b"></div>"#, 2, (12, 18));
+        // spans from `{{` to EOF
+        assert_span("<div>{{ a </div>", 25, (5, 16));
+        let error = CompilationError::new(CompilationErrorKind::VOnceInVFor);
+        assert_eq!(error.code(), None);
+    }
 }
@@ -126,7 +126,7 @@ pub enum SlotFlag {
 /// e.g. v-for needs a list looping helper to make vdom
 /// preamble helper needs collect helper when traversing template ast
 /// and generates corresponding JavaScript imports in compilation output
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
 pub struct RuntimeHelper(pub u8);
 use RuntimeHelper as RH;
 
@@ -170,8 +170,15 @@ impl RuntimeHelper {
     pub const IS_REF: RH = RH(37);
     pub const WITH_MEMO: RH = RH(38);
     pub const IS_MEMO_SAME: RH = RH(39);
+    // server renderer helpers
+    pub const SSR_INTERPOLATE: RH = RH(40);
+    pub const SSR_RENDER_ATTR: RH = RH(41);
+    pub const SSR_RENDER_ATTRS: RH = RH(42);
+    pub const SSR_RENDER_DYNAMIC_ATTR: RH = RH(43);
+    pub const SSR_RENDER_CLASS: RH = RH(44);
+    pub const SSR_RENDER_STYLE: RH = RH(45);
 
-    pub const INTERNAL_MAX: u8 = 40;
+    pub const INTERNAL_MAX: u8 = 46;
 
     pub fn helper_str(&self, map: &[&'static str]) -> &'static str {
         match *self {
@@ -214,6 +221,12 @@ impl RuntimeHelper {
             RH::IS_REF => "isRef",
             RH::WITH_MEMO => "withMemo",
             RH::IS_MEMO_SAME => "isMemoSame",
+            RH::SSR_INTERPOLATE => "ssrInterpolate",
+            RH::SSR_RENDER_ATTR => "ssrRenderAttr",
+            RH::SSR_RENDER_ATTRS => "ssrRenderAttrs",
+            RH::SSR_RENDER_DYNAMIC_ATTR => "ssrRenderDynamicAttr",
+            RH::SSR_RENDER_CLASS => "ssrRenderClass",
+            RH::SSR_RENDER_STYLE => "ssrRenderStyle",
             RH(s) => map[(s - RH::INTERNAL_MAX) as usize],
         }
     }
@@ -229,6 +242,7 @@ impl Serialize for RuntimeHelper {
 }
 
 pub const HELPERS_IN_HOISTED: &[RH] = &[
+    RH::FRAGMENT, // static multi-root template
     RH::CREATE_COMMENT,
     RH::CREATE_ELEMENT_VNODE,
     RH::CREATE_STATIC,
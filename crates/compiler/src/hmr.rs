@@ -0,0 +1,232 @@
+//! Hot module replacement support. Dev servers compare two compilations
+//! of the same template to decide if re-rendering the component is enough.
+use crate::compiler::CompileOutput;
+use crate::converter::TopScope;
+use crate::flags::RuntimeHelper as RH;
+use crate::manifest::hash_hex;
+use crate::SFCInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HmrAction {
+    /// only render function changed, swap it on live instances
+    RerenderOnly,
+    /// component shape changed, instances must be recreated
+    Reload,
+}
+
+/// Template metadata that a re-render cannot patch in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HmrInfo {
+    /// see [template_hash]
+    pub template_hash: String,
+    /// template renders `<slot>` outlets
+    pub has_slots: bool,
+    /// template references `$emit`
+    pub uses_emit: bool,
+    pub scope_id: Option<String>,
+}
+
+impl HmrInfo {
+    pub fn new(source: &str, sfc_info: &SFCInfo, top: &TopScope) -> Self {
+        Self {
+            template_hash: template_hash(source),
+            has_slots: top.helpers.contains(RH::RENDER_SLOT),
+            uses_emit: top.uses_emit,
+            scope_id: sfc_info.scope_id.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum HashState {
+    Text,
+    Tag,
+    Interpolation,
+}
+
+/// Hash of template source that ignores line breaks between tags and
+/// collapses other whitespace, so reformatting does not trigger recompile.
+/// Like whitespace `condense`, whitespace-only text between tags is dropped
+/// only if it contains a newline, and text keeps its leading/trailing space.
+/// Whitespace in quoted attribute values, strings of interpolations,
+/// comments and `<pre>`/`<textarea>` content is kept as is.
+pub fn template_hash(source: &str) -> String {
+    use HashState as S;
+    let src = source.trim().as_bytes();
+    let mut normalized = Vec::with_capacity(src.len());
+    let mut state = S::Text;
+    let mut pending_space = false;
+    let mut pending_newline = false;
+    let mut in_raw_text = false;
+    let mut i = 0;
+    while i < src.len() {
+        let rest = &src[i..];
+        let b = src[i];
+        if b.is_ascii_whitespace() && !(state == S::Text && in_raw_text) {
+            pending_space = true;
+            pending_newline |= b == b'\n';
+            i += 1;
+            continue;
+        }
+        let verbatim = match state {
+            S::Text if rest.starts_with(b"<!--") => Some(find_end(rest, b"-->")),
+            S::Tag if b == b'"' || b == b'\'' => Some(find_quote(rest, false)),
+            S::Interpolation if matches!(b, b'"' | b'\'' | b'`') => Some(find_quote(rest, true)),
+            _ => None,
+        };
+        let closing = match state {
+            S::Tag => b == b'>' || rest.starts_with(b"/>"),
+            S::Interpolation => rest.starts_with(b"}}"),
+            S::Text => false,
+        };
+        let after_tag = normalized.last() == Some(&b'>');
+        let keep_space = match state {
+            // `<p> a</p>` renders a space but `<p>\n<b/>` does not
+            S::Text => !(after_tag && b == b'<' && pending_newline),
+            // `{{ a }}` and `{{a}}` render the same
+            S::Interpolation => !closing && !normalized.ends_with(b"{{"),
+            S::Tag => !closing,
+        };
+        if pending_space && keep_space {
+            normalized.push(b' ');
+        }
+        pending_space = false;
+        pending_newline = false;
+        if let Some(len) = verbatim {
+            normalized.extend_from_slice(&rest[..len]);
+            i += len;
+            continue;
+        }
+        let len = match state {
+            S::Text if rest.starts_with(b"{{") => {
+                state = S::Interpolation;
+                2
+            }
+            S::Text if b == b'<' => {
+                state = S::Tag;
+                let name_len = rest[1..]
+                    .iter()
+                    .take_while(|c| c.is_ascii_alphanumeric() || **c == b'/')
+                    .count();
+                let name = rest[1..=name_len].to_ascii_lowercase();
+                if name == b"pre" || name == b"textarea" {
+                    in_raw_text = true;
+                } else if name == b"/pre" || name == b"/textarea" {
+                    in_raw_text = false;
+                }
+                1 + name_len
+            }
+            S::Interpolation if closing => {
+                state = S::Text;
+                2
+            }
+            S::Tag if b == b'>' => {
+                state = S::Text;
+                1
+            }
+            _ => 1,
+        };
+        normalized.extend_from_slice(&rest[..len]);
+        i += len;
+    }
+    hash_hex(&normalized)
+}
+
+/// Length of `s` up to and including `end`, or all of `s` if unterminated.
+fn find_end(s: &[u8], end: &[u8]) -> usize {
+    s.windows(end.len())
+        .position(|w| w == end)
+        .map_or(s.len(), |p| p + end.len())
+}
+
+/// Length of the string literal `s` starts with, including both quotes.
+fn find_quote(s: &[u8], escapable: bool) -> usize {
+    let quote = s[0];
+    let mut i = 1;
+    while i < s.len() {
+        if escapable && s[i] == b'\\' {
+            i += 2;
+            continue;
+        }
+        if s[i] == quote {
+            return i + 1;
+        }
+        i += 1;
+    }
+    s.len()
+}
+
+/// Compare outputs of the old and new template. Identical templates can be
+/// detected by [template_hash] before compiling at all.
+/// Outputs compiled without the `hmr` option always need reload.
+pub fn compare_renders<W>(old: &CompileOutput<W>, new: &CompileOutput<W>) -> HmrAction {
+    match (&old.hmr, &new.hmr) {
+        (Some(old), Some(new)) => compare_hmr_info(old, new),
+        _ => HmrAction::Reload,
+    }
+}
+
+pub fn compare_hmr_info(old: &HmrInfo, new: &HmrInfo) -> HmrAction {
+    let same_shape = old.has_slots == new.has_slots
+        && old.uses_emit == new.uses_emit
+        && old.scope_id == new.scope_id;
+    if same_shape {
+        HmrAction::RerenderOnly
+    } else {
+        HmrAction::Reload
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_template_hash() {
+        let a = template_hash("<div>\n  <p>{{ a }}  b</p>\n</div>\n");
+        assert_eq!(a, template_hash("<div><p>{{ a }} b</p></div>"));
+        assert_ne!(a, template_hash("<div><p>{{ a }}b</p></div>"));
+        assert_ne!(a, template_hash("<div><p>{{ a }} c</p></div>"));
+        assert_eq!(a, template_hash("<div><p>{{a}} b</p></div>"));
+    }
+
+    #[test]
+    fn test_template_hash_keeps_significant_space() {
+        let same = |a: &str, b: &str| template_hash(a) == template_hash(b);
+        assert!(same("<p\n  id='a'\n  class='b'/>", "<p id='a' class='b'/>"));
+        assert!(!same("<p title='a  b'/>", "<p title='a b'/>"));
+        assert!(!same("<p>{{ 'a  b' }}</p>", "<p>{{ 'a b' }}</p>"));
+        assert!(!same("<p>{{ `a\\`  b` }}</p>", "<p>{{ `a\\` b` }}</p>"));
+        assert!(!same("<!--a  b-->", "<!--a b-->"));
+        assert!(!same("<pre>a\n  b</pre>", "<pre>a b</pre>"));
+        assert!(same("<pre>a</pre>\n<p>a  b</p>", "<pre>a</pre><p>a b</p>"));
+        // space without newline between elements is condensed, not removed
+        assert!(!same("<b>a</b> <b>b</b>", "<b>a</b><b>b</b>"));
+        assert!(same("<b>a</b>  <b>b</b>", "<b>a</b> <b>b</b>"));
+    }
+
+    #[test]
+    fn test_compare_hmr_info() {
+        let info = HmrInfo {
+            template_hash: template_hash("<p/>"),
+            has_slots: false,
+            uses_emit: false,
+            scope_id: None,
+        };
+        let changed = HmrInfo {
+            template_hash: template_hash("<p>{{ a }}</p>"),
+            ..info.clone()
+        };
+        assert_eq!(compare_hmr_info(&info, &changed), HmrAction::RerenderOnly);
+        let scoped = HmrInfo {
+            scope_id: Some("data-v-1".into()),
+            ..info.clone()
+        };
+        assert_eq!(compare_hmr_info(&info, &scoped), HmrAction::Reload);
+        let emit = HmrInfo {
+            uses_emit: true,
+            ..info.clone()
+        };
+        assert_eq!(compare_hmr_info(&info, &emit), HmrAction::Reload);
+    }
+}
@@ -1,7 +1,7 @@
 use crate::{
     flags::{PatchFlag, RuntimeHelper, SlotFlag, StaticLevel},
     util::VStr,
-    Name,
+    Name, SourceLocation,
 };
 use rustc_hash::FxHashSet;
 use std::hash::Hash;
@@ -50,12 +50,17 @@ pub enum IRNode<T: ConvertInfo> {
     CacheNode(CacheIR<T>),
     /// comment
     CommentCall(T::CommentType),
+    /// reference to a static node lifted out of render as `_hoisted_x`
+    Hoisted(usize),
 }
 
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct TextIR<T: ConvertInfo> {
     pub fast_path: bool,  // without createTextCall
     pub need_patch: bool, // PatchFlag::TEXT
+    /// a removed node, e.g. comment in production, separates this
+    /// text from the previous one. They must stay two DOM text nodes.
+    pub split_from_prev: bool,
     pub texts: T::TextType,
 }
 #[cfg_attr(feature = "serde", derive(Serialize))]
@@ -99,6 +104,8 @@ pub struct RuntimeDir<T: ConvertInfo> {
     pub expr: Option<T::JsExpression>,
     pub arg: Option<T::JsExpression>,
     pub mods: Option<T::JsExpression>,
+    /// directive attribute span, for source map
+    pub location: SourceLocation,
 }
 #[derive(Default)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
@@ -112,6 +119,8 @@ pub struct VNodeIR<T: ConvertInfo> {
     pub is_block: bool,
     pub disable_tracking: bool,
     pub is_component: bool,
+    /// element span in template, default for synthesized vnodes
+    pub location: SourceLocation,
 }
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct Slot<T: ConvertInfo> {
@@ -10,14 +10,20 @@ pub mod compiler;
 pub mod converter;
 pub mod error;
 pub mod flags;
+pub mod hmr;
 pub mod ir;
+pub mod manifest;
 pub mod parser;
+pub mod printer;
+pub mod project;
 pub mod scanner;
 pub mod transformer;
 
 use flags::StaticLevel;
 use ir::JsExpr as Js;
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
+use manifest::hash_hex;
+use std::fmt::Write;
 use std::ops::Deref;
 use std::ops::Range;
 pub use transformer::pass::Chain;
@@ -30,7 +36,7 @@ use serde::Serialize;
 // may change to tendril
 pub type Name<'a> = &'a str;
 
-#[derive(PartialEq, Eq, Clone)]
+#[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Position {
     /// the 0-indexed offset in the source str modulo newline
     pub offset: usize,
@@ -67,7 +73,7 @@ impl Default for Position {
     }
 }
 
-#[derive(Default, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
 #[cfg_attr(feature = "serde", derive(Serialize))]
 pub struct SourceLocation {
     pub start: Position,
@@ -91,7 +97,7 @@ pub enum Namespace {
     UserDefined(&'static str),
 }
 
-#[derive(PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum BindingTypes {
     /// returned from data()
     Data,
@@ -127,15 +133,40 @@ impl BindingTypes {
 
 /// stores binding variables exposed by data/prop/setup script.
 /// also stores if the binding is from setup script.
-#[derive(Default)]
-pub struct BindingMetadata<'a>(FxHashMap<&'a str, BindingTypes>, bool);
+/// Setup consts known to hold a component, e.g. imported from `.vue` file
+/// or assigned by `defineAsyncComponent`, are listed in the last field.
+#[derive(Default, Clone)]
+pub struct BindingMetadata<'a>(FxHashMap<&'a str, BindingTypes>, bool, FxHashSet<&'a str>);
 impl<'a> BindingMetadata<'a> {
     pub fn new(map: FxHashMap<&'a str, BindingTypes>, from_setup: bool) -> Self {
-        Self(map, from_setup)
+        Self(map, from_setup, FxHashSet::default())
+    }
+    /// Marks setup bindings as component objects found by script analysis.
+    pub fn with_components<I: IntoIterator<Item = &'a str>>(mut self, names: I) -> Self {
+        self.2.extend(names);
+        self
     }
     pub fn is_setup(&self) -> bool {
         self.1
     }
+    /// Component object binding that `<component :is>` can render directly.
+    pub fn is_component(&self, name: &str) -> bool {
+        self.2.contains(name) && self.0.get(name) == Some(&BindingTypes::SetupConst)
+    }
+    /// Hash of bindings independent of map iteration order.
+    /// Bindings change prefixing, so it is part of build cache keys.
+    pub fn fingerprint(&self) -> String {
+        let mut entries: Vec<_> = self.0.iter().collect();
+        entries.sort_by_key(|(name, _)| **name);
+        let mut desc = self.1.to_string();
+        for (name, ty) in entries {
+            write!(desc, "|{}:{:?}", name, ty).unwrap();
+            if self.2.contains(name) {
+                desc.push_str(":component");
+            }
+        }
+        hash_hex(desc.as_bytes())
+    }
 }
 impl<'a> Deref for BindingMetadata<'a> {
     type Target = FxHashMap<&'a str, BindingTypes>;
@@ -183,4 +214,35 @@ mod test {
     fn test_source_size() {
         assert_eq!(std::mem::size_of::<Position>(), 16);
     }
+
+    #[test]
+    fn test_bindings_fingerprint() {
+        let bindings = |entries: &[(&'static str, BindingTypes)], setup| {
+            BindingMetadata::new(entries.iter().copied().collect(), setup).fingerprint()
+        };
+        let a = bindings(
+            &[("a", BindingTypes::Props), ("b", BindingTypes::Data)],
+            true,
+        );
+        let b = bindings(
+            &[("b", BindingTypes::Data), ("a", BindingTypes::Props)],
+            true,
+        );
+        assert_eq!(a, b);
+        assert_ne!(a, bindings(&[("a", BindingTypes::Props)], true));
+        assert_ne!(
+            a,
+            bindings(
+                &[("a", BindingTypes::Data), ("b", BindingTypes::Data)],
+                true
+            )
+        );
+        assert_ne!(
+            a,
+            bindings(
+                &[("a", BindingTypes::Props), ("b", BindingTypes::Data)],
+                false
+            )
+        );
+    }
 }
@@ -0,0 +1,204 @@
+//! Machine-readable summary of one compilation for build systems,
+//! e.g. bazel/buck like tools that cache outputs and track dependencies.
+//! The JSON layout is stable: any breaking change bumps `schemaVersion`.
+use crate::converter::TopScope;
+use crate::flags::{HelperCollector, RuntimeHelper as RH};
+use crate::util::VStr;
+use rustc_hash::FxHashSet;
+use std::fmt::Write;
+
+/// Bump when a field is renamed, removed or changes meaning.
+pub const MANIFEST_SCHEMA_VERSION: u32 = 1;
+
+/// template features that require runtime support, keyed by their helper
+const FEATURES: &[(RH, &str)] = &[
+    (RH::RENDER_LIST, "v-for"),
+    (RH::WITH_DIRECTIVES, "directives"),
+    (RH::RENDER_SLOT, "slot-outlet"),
+    (RH::WITH_CTX, "slots"),
+    (RH::SET_BLOCK_TRACKING, "v-once"),
+    (RH::WITH_MEMO, "v-memo"),
+    (RH::IS_MEMO_SAME, "v-memo"),
+    (RH::RESOLVE_DYNAMIC_COMPONENT, "dynamic-component"),
+    (RH::TELEPORT, "teleport"),
+    (RH::SUSPENSE, "suspense"),
+    (RH::KEEP_ALIVE, "keep-alive"),
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Manifest {
+    /// FNV-1a hash of template source in hex
+    pub input_hash: String,
+    /// hash of options affecting output, see `CompileOption::fingerprint`
+    pub options_fingerprint: String,
+    /// hash of binding metadata, see `BindingMetadata::fingerprint`
+    pub bindings_fingerprint: String,
+    /// runtime helpers used by render function, in helper id order
+    pub helpers: Vec<&'static str>,
+    /// components resolved at runtime, sorted
+    pub components: Vec<String>,
+    /// custom directives resolved at runtime, sorted
+    pub directives: Vec<String>,
+    pub features: Vec<&'static str>,
+    /// template has no binding at all, render output never changes
+    pub is_static: bool,
+    pub hoist_count: usize,
+    pub cache_slot_count: usize,
+    pub error_count: usize,
+    pub output_bytes: usize,
+}
+
+impl Manifest {
+    /// Collect template entities. Counters are filled after codegen.
+    pub fn new(
+        source: &str,
+        options_fingerprint: String,
+        bindings_fingerprint: String,
+        top: &TopScope,
+        helper_strs: &[&'static str],
+    ) -> Self {
+        Self {
+            input_hash: hash_hex(source.as_bytes()),
+            options_fingerprint,
+            bindings_fingerprint,
+            helpers: helper_names(&top.helpers, helper_strs),
+            components: sorted_assets(&top.components),
+            directives: sorted_assets(&top.directives),
+            features: features(&top.helpers),
+            is_static: top.is_static,
+            hoist_count: 0,
+            cache_slot_count: 0,
+            error_count: 0,
+            output_bytes: 0,
+        }
+    }
+
+    /// Serialize with a fixed key order.
+    pub fn to_json(&self) -> String {
+        let mut ret = String::from("{");
+        write!(ret, r#""schemaVersion":{},"#, MANIFEST_SCHEMA_VERSION).unwrap();
+        ret.push_str(r#""inputHash":"#);
+        write_json_str(&mut ret, &self.input_hash);
+        ret.push_str(r#","optionsFingerprint":"#);
+        write_json_str(&mut ret, &self.options_fingerprint);
+        ret.push_str(r#","bindingsFingerprint":"#);
+        write_json_str(&mut ret, &self.bindings_fingerprint);
+        ret.push_str(r#","helpers":"#);
+        write_json_list(&mut ret, &self.helpers);
+        ret.push_str(r#","components":"#);
+        write_json_list(&mut ret, &self.components);
+        ret.push_str(r#","directives":"#);
+        write_json_list(&mut ret, &self.directives);
+        ret.push_str(r#","features":"#);
+        write_json_list(&mut ret, &self.features);
+        write!(ret, r#","isStatic":{}"#, self.is_static).unwrap();
+        write!(
+            ret,
+            r#","hoistCount":{},"cacheSlotCount":{},"errorCount":{},"outputBytes":{}}}"#,
+            self.hoist_count, self.cache_slot_count, self.error_count, self.output_bytes,
+        )
+        .unwrap();
+        ret
+    }
+
+    /// Key of build cache entry. Output is the same if all inputs,
+    /// i.e. source, options and bindings, are the same.
+    pub fn cache_key(&self) -> String {
+        join_key(
+            &self.input_hash,
+            &self.options_fingerprint,
+            &self.bindings_fingerprint,
+        )
+    }
+}
+
+/// Same as [Manifest::cache_key] without building the manifest.
+pub fn cache_key(source: &str, options_fingerprint: &str, bindings_fingerprint: &str) -> String {
+    let input_hash = hash_hex(source.as_bytes());
+    join_key(&input_hash, options_fingerprint, bindings_fingerprint)
+}
+
+fn join_key(input_hash: &str, options_fingerprint: &str, bindings_fingerprint: &str) -> String {
+    let key = format!(
+        "{}|{}|{}",
+        input_hash, options_fingerprint, bindings_fingerprint
+    );
+    hash_hex(key.as_bytes())
+}
+
+/// 64-bit FNV-1a. Stable across platforms and releases unlike std's hasher.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+/// [fnv1a] in 16 hex digits.
+pub fn hash_hex(bytes: &[u8]) -> String {
+    format!("{:016x}", fnv1a(bytes))
+}
+
+fn helper_names(helpers: &HelperCollector, helper_strs: &[&'static str]) -> Vec<&'static str> {
+    helpers
+        .clone()
+        .into_iter()
+        .map(|h| h.helper_str(helper_strs))
+        .collect()
+}
+
+fn sorted_assets(assets: &FxHashSet<VStr>) -> Vec<String> {
+    let mut names: Vec<_> = assets.iter().map(|v| v.raw.to_string()).collect();
+    names.sort();
+    names
+}
+
+fn features(helpers: &HelperCollector) -> Vec<&'static str> {
+    let mut ret: Vec<&'static str> = vec![];
+    for &(helper, name) in FEATURES {
+        if helpers.contains(helper) && !ret.contains(&name) {
+            ret.push(name);
+        }
+    }
+    ret
+}
+
+fn write_json_str(out: &mut String, s: &str) {
+    VStr::raw(s)
+        .be_js_str()
+        .write_to(out)
+        .expect("string should never fail");
+}
+
+fn write_json_list<S: AsRef<str>>(out: &mut String, list: &[S]) {
+    out.push('[');
+    for (i, s) in list.iter().enumerate() {
+        if i > 0 {
+            out.push(',');
+        }
+        write_json_str(out, s.as_ref());
+    }
+    out.push(']');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_hash_hex() {
+        assert_eq!(hash_hex(b""), "cbf29ce484222325");
+        assert_eq!(hash_hex(b"a"), "af63dc4c8601ec8c");
+    }
+
+    #[test]
+    fn test_features() {
+        let mut helpers = HelperCollector::new();
+        helpers.collect(RH::WITH_MEMO);
+        helpers.collect(RH::IS_MEMO_SAME);
+        helpers.collect(RH::RENDER_LIST);
+        assert_eq!(features(&helpers), vec!["v-for", "v-memo"]);
+    }
+}
@@ -18,11 +18,12 @@ use super::{
     error::{CompilationError, CompilationErrorKind as ErrorKind, RcErrHandle},
     flags::RuntimeHelper,
     scanner::{Attribute, AttributeValue, Tag, TextMode, Token, TokenSource},
-    util::{find_dir, is_core_component, no, non_whitespace, yes, VStr},
+    util::{find_dir, find_dir_empty, is_core_component, no, non_whitespace, VStr},
     Name, Namespace, SourceLocation,
 };
 use smallvec::{smallvec, SmallVec};
 use std::ops::Deref;
+use std::rc::Rc;
 
 #[cfg(feature = "serde")]
 use serde::Serialize;
@@ -164,6 +165,12 @@ pub struct Element<'a> {
     pub properties: Vec<ElemProp<'a>>,
     pub children: Vec<AstNode<'a>>,
     pub location: SourceLocation,
+    /// written as `<tag/>`
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub self_closing: bool,
+    /// closed by `</tag>`, false if void, self-closing or unclosed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub has_end_tag: bool,
 }
 
 impl<'a> Element<'a> {
@@ -210,7 +217,14 @@ impl<'a> Directive<'a> {
             .expression
             .as_ref()
             .map_or(self.head_loc.clone(), |v| v.location.clone());
-        Some(CompilationError::new(kind).with_location(loc))
+        let context = match &self.argument {
+            Some(DirectiveArg::Static(arg)) => format!("v-{}:{}", self.name, arg),
+            _ => format!("v-{}", self.name),
+        };
+        let error = CompilationError::new(kind)
+            .with_location(loc)
+            .with_context(context);
+        Some(error)
     }
 }
 
@@ -218,6 +232,9 @@ impl<'a> Directive<'a> {
 pub struct AstRoot<'a> {
     pub children: Vec<AstNode<'a>>,
     pub location: SourceLocation,
+    /// template source, expression slices borrow from it
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub source: &'a str,
 }
 
 #[derive(Clone)]
@@ -231,6 +248,9 @@ impl Default for WhitespaceStrategy {
     }
 }
 
+/// Tag predicate capturing user data, e.g. a list of custom element names.
+pub type IsCustomElement = Rc<dyn Fn(&str) -> bool>;
+
 // `is_xxx` methods in ParseOption targets different audience.
 // Please refer to project README for more details.
 #[derive(Clone)]
@@ -245,12 +265,16 @@ pub struct ParseOption {
     // in original Vue this is only used for parsing SFC.
     pub is_pre_tag: fn(&str) -> bool,
     /// Exposed to end user for customization like importing web-component from React.
-    pub is_custom_element: fn(&str) -> bool,
+    pub is_custom_element: IsCustomElement,
     /// For platform developers. Registers platform specific components written in JS.
     /// e.g. transition, transition-group. Components that require code in Vue runtime.
     pub get_builtin_component: fn(&str) -> Option<RuntimeHelper>,
     /// For platform developer. Registers platform components written in host language like C++.
-    pub is_native_element: fn(&str) -> bool,
+    /// Called with the element namespace, e.g. SVG and HTML have different elements.
+    pub is_native_element: fn(&str, Namespace) -> bool,
+    /// Warn self-closing non-void HTML elements like `<div/>`.
+    /// They are fine in templates compiled ahead of time but not in in-DOM templates.
+    pub strict_html: bool,
 }
 
 impl Default for ParseOption {
@@ -262,9 +286,10 @@ impl Default for ParseOption {
             get_text_mode: |_| TextMode::Data,
             is_void_tag: no,
             is_pre_tag: |s| s == "pre",
-            is_custom_element: no,
+            is_custom_element: Rc::new(no),
             get_builtin_component: |_| None,
-            is_native_element: yes,
+            is_native_element: |_, _| true,
+            strict_html: false,
         }
     }
 }
@@ -331,8 +356,10 @@ where
         }
     }
 
-    fn emit_error(&self, kind: ErrorKind, loc: SourceLocation) {
-        let error = CompilationError::new(kind).with_location(loc);
+    fn emit_error(&self, kind: ErrorKind, loc: SourceLocation, tag: &str) {
+        let error = CompilationError::new(kind)
+            .with_location(loc)
+            .with_context(tag);
         self.err_handle.on_error(error)
     }
 }
@@ -344,6 +371,7 @@ where
 {
     fn build_ast(mut self) -> AstRoot<'a> {
         let start = self.tokens.current_position();
+        let source = self.tokens.rest_source();
         while let Some(token) = self.tokens.next() {
             self.parse_token(token);
         }
@@ -359,6 +387,7 @@ where
         AstRoot {
             children: self.root_nodes,
             location,
+            source,
         }
     }
 
@@ -390,8 +419,15 @@ where
                 start: self.tokens.last_position(),
                 end: self.tokens.current_position(),
             },
+            self_closing,
+            has_end_tag: false,
         };
-        if self_closing || (self.option.is_void_tag)(name) {
+        // void elements are HTML only, e.g. <source> in SVG needs closing
+        let is_void = ns == Namespace::Html && (self.option.is_void_tag)(name);
+        if self_closing && !is_void && self.option.strict_html {
+            self.check_self_closing_html(&elem);
+        }
+        if self_closing || is_void {
             let node = self.parse_element(elem);
             self.insert_node(node);
         } else {
@@ -402,21 +438,21 @@ where
             self.set_scanner_flag();
         }
     }
-    fn parse_attributes(&mut self, mut attrs: Vec<Attribute<'a>>) -> Vec<ElemProp<'a>> {
+    fn parse_attributes(&mut self, attrs: Vec<Attribute<'a>>) -> Vec<ElemProp<'a>> {
         // in v-pre, parse no directive
         if self.v_pre_index.is_some() {
             return attrs.into_iter().map(ElemProp::Attr).collect();
         }
         let mut dir_parser = DirectiveParser::new(&self.err_handle);
-        // v-pre precedes any other directives
-        for i in 0..attrs.len() {
-            if attrs[i].name != "v-pre" {
-                continue;
-            }
-            let dir = dir_parser.parse(attrs.remove(i));
-            let mut ret = vec![ElemProp::Dir(dir)];
-            ret.extend(attrs.into_iter().map(ElemProp::attr));
-            return ret;
+        // v-pre precedes any other directives, attributes keep source order
+        if attrs.iter().any(|a| a.name == "v-pre") {
+            return attrs
+                .into_iter()
+                .map(|attr| match attr.name {
+                    "v-pre" => ElemProp::Dir(dir_parser.parse(attr)),
+                    _ => ElemProp::attr(attr),
+                })
+                .collect();
         }
         attrs
             .into_iter()
@@ -431,6 +467,17 @@ where
             .collect()
     }
 
+    fn check_self_closing_html(&self, elem: &Element) {
+        // components and foreign elements can self-close legally
+        let is_html = elem.namespace == Namespace::Html;
+        if is_html && (self.option.is_native_element)(elem.tag_name, elem.namespace) {
+            let error = CompilationError::new(ErrorKind::SelfClosingNonVoidElement)
+                .with_location(elem.location.clone())
+                .with_context(elem.tag_name);
+            self.err_handle.on_warn(error);
+        }
+    }
+
     fn handle_pre_like(&mut self, elem: &Element) {
         debug_assert!(
             self.open_elems
@@ -466,7 +513,7 @@ where
         } else {
             let start = self.tokens.last_position();
             let loc = self.tokens.get_location_from(start);
-            self.emit_error(ErrorKind::InvalidEndTag, loc);
+            self.emit_error(ErrorKind::InvalidEndTag, loc, end_tag);
         }
     }
     fn close_element(&mut self, has_matched_end: bool) {
@@ -479,10 +526,11 @@ where
                 start: start.clone(),
                 end: start.clone(),
             };
-            self.emit_error(ErrorKind::MissingEndTag, err_location);
+            self.emit_error(ErrorKind::MissingEndTag, err_location, elem.tag_name);
         }
         let location = self.tokens.get_location_from(start);
         elem.location = location;
+        elem.has_end_tag = has_matched_end;
         if self.pre_count > 0 {
             self.decrement_pre(&mut elem)
         } else if (self.option.get_text_mode)(elem.tag_name) == TextMode::Data {
@@ -595,7 +643,11 @@ where
                 start: self.tokens.last_position(),
                 end: self.tokens.last_position(),
             };
-            self.emit_error(ErrorKind::EofInScriptHtmlCommentLikeText, loc);
+            self.emit_error(
+                ErrorKind::EofInScriptHtmlCommentLikeText,
+                loc,
+                elem.tag_name,
+            );
         }
     }
 
@@ -623,7 +675,7 @@ where
             || tag_name.starts_with(|c| matches!(c, 'A'..='Z'))
             || is_core_component(tag_name)
             || (opt.get_builtin_component)(tag_name).is_some()
-            || !(opt.is_native_element)(tag_name)
+            || !(opt.is_native_element)(tag_name, e.namespace)
         {
             return true;
         }
@@ -654,6 +706,7 @@ const DIR_MARK: &str = "v-";
 type StrPair<'a> = (&'a str, &'a str);
 struct DirectiveParser<'a, 'b> {
     eh: &'b RcErrHandle,
+    attr_name: &'a str,
     name_loc: SourceLocation,
     location: SourceLocation,
     cached: Option<StrPair<'a>>,
@@ -662,13 +715,16 @@ impl<'a, 'b> DirectiveParser<'a, 'b> {
     fn new(eh: &'b RcErrHandle) -> Self {
         Self {
             eh,
+            attr_name: "",
             name_loc: Default::default(),
             location: Default::default(),
             cached: None,
         }
     }
     fn attr_name_err(&self, kind: ErrorKind) {
-        let error = CompilationError::new(kind).with_location(self.name_loc.clone());
+        let error = CompilationError::new(kind)
+            .with_location(self.name_loc.clone())
+            .with_context(self.attr_name);
         self.eh.on_error(error);
     }
     fn detect_directive(&mut self, attr: &Attribute<'a>) -> bool {
@@ -679,6 +735,7 @@ impl<'a, 'b> DirectiveParser<'a, 'b> {
     fn set_location(&mut self, attr: &Attribute<'a>) {
         self.location = attr.location.clone();
         self.name_loc = attr.name_loc.clone();
+        self.attr_name = attr.name;
     }
 
     fn parse(&mut self, attr: Attribute<'a>) -> Directive<'a> {
@@ -833,21 +890,12 @@ impl<'a, 'b> DirectiveParser<'a, 'b> {
 }
 
 fn compress_whitespaces(nodes: &mut Vec<AstNode>, need_condense: bool) {
-    // no two consecutive Text node, ensured by parse_text
-    debug_assert!({
-        let no_consecutive_text = |last_is_text, is_text| {
-            if last_is_text && is_text {
-                None
-            } else {
-                Some(is_text)
-            }
-        };
-        nodes
-            .iter()
-            .map(|n| matches!(n, AstNode::Text(_)))
-            .try_fold(false, no_consecutive_text)
-            .is_some()
-    });
+    // no two contiguous Text node, ensured by parse_text.
+    // Text nodes around a removed comment are consecutive but not contiguous.
+    debug_assert!(nodes.windows(2).all(|w| match w {
+        [AstNode::Text(a), AstNode::Text(b)] => a.location.end != b.location.start,
+        _ => true,
+    }));
     let mut i = 0;
     while i < nodes.len() {
         let should_remove = if let AstNode::Text(child) = &nodes[i] {
@@ -911,7 +959,7 @@ fn is_special_template_directive(n: &str) -> bool {
 }
 
 fn is_template_element(e: &Element) -> bool {
-    e.tag_name == "template" && find_dir(e, is_special_template_directive).is_some()
+    e.tag_name == "template" && find_dir_empty(e, is_special_template_directive).is_some()
 }
 
 fn element_matches_end_tag(e: &Element, tag: &str) -> bool {
@@ -955,10 +1003,67 @@ pub mod test {
         assert_eq!(val.into_string(), "&");
     }
 
+    #[test]
+    fn test_self_closing_non_void() {
+        let ast = base_parse("<div/><span>x</span>");
+        assert_eq!(ast.children.len(), 2);
+        let div = cast!(&ast.children[0], AstNode::Element);
+        assert_eq!(div.tag_name, "div");
+        assert!(div.children.is_empty());
+        let span = cast!(&ast.children[1], AstNode::Element);
+        assert_eq!(span.children.len(), 1);
+    }
+    #[test]
+    fn test_strict_html() {
+        use crate::error::VecErrorHandler;
+        let count_warnings = |s: &str, strict_html| {
+            let eh = std::rc::Rc::new(VecErrorHandler::default());
+            let parser = Parser::new(ParseOption {
+                is_native_element: |s, _| s != "comp",
+                is_void_tag: |s| s == "br",
+                strict_html,
+                ..Default::default()
+            });
+            parser.parse(base_scan(s), eh.clone());
+            assert!(eh.errors().is_empty());
+            let warnings = eh.warnings();
+            warnings
+                .iter()
+                .filter(|e| matches!(e.kind, ErrorKind::SelfClosingNonVoidElement))
+                .count()
+        };
+        assert_eq!(count_warnings("<div/><span>x</span>", true), 1);
+        assert_eq!(count_warnings("<div/><span>x</span>", false), 0);
+        assert_eq!(count_warnings("<br/><br>", true), 0);
+        assert_eq!(count_warnings("<comp/>", true), 0);
+    }
+
+    #[test]
+    fn test_void_tag_namespace() {
+        let parser = Parser::new(ParseOption {
+            is_void_tag: |s| s == "source",
+            get_namespace: |tag, parent| match parent {
+                Some(p) if p.namespace == Namespace::Svg => Namespace::Svg,
+                _ if tag == "svg" => Namespace::Svg,
+                _ => Namespace::Html,
+            },
+            ..Default::default()
+        });
+        let eh = std::rc::Rc::new(TestErrorHandler);
+        let case = "<svg><source><g/></source></svg><source><p/>";
+        let ast = parser.parse(base_scan(case), eh);
+        // void elements are HTML only
+        assert_eq!(ast.children.len(), 3);
+        let svg = cast!(&ast.children[0], AstNode::Element);
+        let source = cast!(&svg.children[0], AstNode::Element);
+        assert!(source.namespace == Namespace::Svg);
+        assert_eq!(source.children.len(), 1);
+    }
+
     pub fn base_parse(s: &str) -> AstRoot {
         let tokens = base_scan(s);
         let parser = Parser::new(ParseOption {
-            is_native_element: |s| s != "comp",
+            is_native_element: |s, _| s != "comp",
             ..Default::default()
         });
         let eh = std::rc::Rc::new(TestErrorHandler);
@@ -0,0 +1,387 @@
+//! Prints template AST back to source, e.g. for codemods that parse,
+//! mutate and print a template. Nodes left untouched are printed as
+//! written by slicing the source with their locations, so quotes and
+//! whitespace survive. Nodes changed or created after parsing are printed
+//! in canonical form, which keeps the diff against the original minimal.
+//! Parse with `WhitespaceStrategy::Preserve` to reproduce text as is.
+
+use crate::{
+    parser::{AstNode, AstRoot, Directive, DirectiveArg, ElemProp, Element, SourceNode, TextNode},
+    scanner::{Attribute, AttributeValue},
+    Position, SourceLocation,
+};
+
+pub fn print_template(ast: &AstRoot) -> String {
+    let mut printer = Printer::new(ast.source);
+    printer.print_children(&ast.children, Some(0), Some(ast.source.len()));
+    printer.out
+}
+
+struct Printer<'a> {
+    source: &'a str,
+    /// byte index of each char since Position counts chars, None if ASCII
+    char_bytes: Option<Vec<usize>>,
+    out: String,
+}
+
+impl<'a> Printer<'a> {
+    fn new(source: &'a str) -> Self {
+        let char_bytes = if source.is_ascii() {
+            None
+        } else {
+            let mut bytes: Vec<_> = source.char_indices().map(|(i, _)| i).collect();
+            bytes.push(source.len());
+            Some(bytes)
+        };
+        Self {
+            source,
+            char_bytes,
+            out: String::with_capacity(source.len()),
+        }
+    }
+
+    fn byte_offset(&self, pos: &Position) -> Option<usize> {
+        match &self.char_bytes {
+            Some(bytes) => bytes.get(pos.offset).copied(),
+            None => Some(pos.offset).filter(|&i| i <= self.source.len()),
+        }
+    }
+    /// Byte range of a location. Nodes created after parsing have none.
+    fn range(&self, loc: &SourceLocation) -> Option<(usize, usize)> {
+        let start = self.byte_offset(&loc.start)?;
+        let end = self.byte_offset(&loc.end)?;
+        Some((start, end)).filter(|_| start < end)
+    }
+    fn slice(&self, loc: &SourceLocation) -> Option<&'a str> {
+        self.range(loc).map(|(start, end)| &self.source[start..end])
+    }
+
+    /// `start` and `end` bound the whitespace before the first child and
+    /// after the last child, which parser drops as whitespace-only text.
+    fn print_children(&mut self, children: &[AstNode], start: Option<usize>, end: Option<usize>) {
+        let mut prev_end = start;
+        for child in children {
+            let range = self.range(child.get_location());
+            if let (Some(prev), Some((start, _))) = (prev_end, range) {
+                self.push_gap(prev, start);
+            }
+            self.print_node(child);
+            prev_end = range.map(|r| r.1);
+        }
+        if let (Some(prev), Some(end)) = (prev_end, end) {
+            self.push_gap(prev, end);
+        }
+    }
+    fn push_gap(&mut self, start: usize, end: usize) {
+        if start >= end {
+            return;
+        }
+        let gap = &self.source[start..end];
+        if gap.chars().all(is_space) {
+            self.out.push_str(gap);
+        }
+    }
+
+    fn print_node(&mut self, node: &AstNode) {
+        match node {
+            AstNode::Element(e) => self.print_element(e),
+            AstNode::Text(t) => self.print_text(t),
+            AstNode::Interpolation(i) => self.print_source_node(i, "{{", "}}"),
+            AstNode::Comment(c) => self.print_source_node(c, "<!--", "-->"),
+        }
+    }
+
+    fn print_element(&mut self, e: &Element) {
+        let source = self.source;
+        let range = self.range(&e.location);
+        let start_tag = range.and_then(|(start, end)| {
+            let len = start_tag_len(&source[start..end])?;
+            Some(&source[start..start + len])
+        });
+        self.out.push('<');
+        self.out.push_str(e.tag_name);
+        for prop in &e.properties {
+            self.print_prop(prop);
+        }
+        if let Some(tag) = start_tag {
+            // whitespace before `>` or `/>`
+            let tag = tag.strip_suffix('>').unwrap_or(tag);
+            let tag = tag.strip_suffix('/').unwrap_or(tag);
+            let space = &tag[tag.trim_end_matches(is_space).len()..];
+            self.out.push_str(space);
+        }
+        self.out.push_str(if e.self_closing { "/>" } else { ">" });
+        let end_tag = match range {
+            Some((start, end)) if e.has_end_tag => {
+                source[start..end].rfind("</").map(|i| (start + i, end))
+            }
+            _ => None,
+        };
+        let inner_start = start_tag.and_then(|tag| range.map(|r| r.0 + tag.len()));
+        let inner_end = end_tag.map(|t| t.0);
+        self.print_children(&e.children, inner_start, inner_end);
+        if !e.has_end_tag {
+            return;
+        }
+        match end_tag.map(|(start, end)| &source[start..end]) {
+            Some(tag) if is_end_tag_of(tag, e.tag_name) => self.out.push_str(tag),
+            _ => {
+                self.out.push_str("</");
+                self.out.push_str(e.tag_name);
+                self.out.push('>');
+            }
+        }
+    }
+
+    fn print_prop(&mut self, prop: &ElemProp) {
+        let source = self.source;
+        let loc = prop.get_location();
+        let space = match self.range(loc) {
+            Some((start, _)) => {
+                let before = &source[..start];
+                &before[before.trim_end_matches(is_space).len()..]
+            }
+            None => "",
+        };
+        let space = if space.is_empty() { " " } else { space };
+        self.out.push_str(space);
+        // attribute location may include whitespace after it
+        let written = self.slice(loc).map(|s| s.trim_end_matches(is_space));
+        let untouched = written.filter(|&w| match prop {
+            ElemProp::Attr(a) => self.is_attr_untouched(a, w),
+            ElemProp::Dir(d) => self.is_dir_untouched(d, w),
+        });
+        if let Some(written) = untouched {
+            self.out.push_str(written);
+            return;
+        }
+        match prop {
+            ElemProp::Attr(a) => {
+                self.out.push_str(a.name);
+                if let Some(v) = &a.value {
+                    self.push_value(v);
+                }
+            }
+            ElemProp::Dir(d) => {
+                let head = self.slice(&d.head_loc);
+                self.out.push_str(&canonical_head(d, head));
+                if let Some(v) = &d.expression {
+                    self.push_value(v);
+                }
+            }
+        }
+    }
+    fn is_attr_untouched(&self, a: &Attribute, written: &str) -> bool {
+        if self.slice(&a.name_loc) != Some(a.name) {
+            return false;
+        }
+        match &a.value {
+            Some(v) => self.value_inner(v) == Some(v.content.raw),
+            None => written == a.name,
+        }
+    }
+    fn is_dir_untouched(&self, d: &Directive, written: &str) -> bool {
+        let head = match self.slice(&d.head_loc) {
+            Some(head) => head,
+            None => return false,
+        };
+        let same_head = HEAD_STYLES
+            .iter()
+            .any(|&style| dir_head(d, style).as_deref() == Some(head));
+        if !same_head {
+            return false;
+        }
+        // parser trims directive expression
+        match &d.expression {
+            Some(v) => self.value_inner(v).map(str::trim) == Some(v.content.raw),
+            None => written == head,
+        }
+    }
+    /// Attribute value as written without quotes.
+    fn value_inner(&self, v: &AttributeValue) -> Option<&'a str> {
+        let written = self.slice(&v.location)?.trim_end_matches(is_space);
+        Some(match v.quote {
+            Some(q) => {
+                let s = written.strip_prefix(q).unwrap_or(written);
+                s.strip_suffix(q).unwrap_or(s)
+            }
+            None => written,
+        })
+    }
+    fn push_value(&mut self, v: &AttributeValue) {
+        let raw = v.content.raw;
+        let unquoted =
+            !raw.is_empty() && !raw.contains(|c: char| is_space(c) || "\"'=<>`".contains(c));
+        let quote = match v.quote {
+            None if unquoted => None,
+            Some('\'') if !raw.contains('\'') => Some('\''),
+            _ if raw.contains('"') => Some('\''),
+            _ => Some('"'),
+        };
+        self.out.push('=');
+        self.out.extend(quote);
+        self.out.push_str(raw);
+        self.out.extend(quote);
+    }
+
+    fn print_text(&mut self, t: &TextNode) {
+        let text: String = t.text.iter().map(|s| s.raw).collect();
+        // <pre> drops the leading newline
+        let untouched = self.slice(&t.location).filter(|&w| {
+            let text = Some(text.as_str());
+            Some(w) == text || w.strip_prefix('\n') == text || w.strip_prefix("\r\n") == text
+        });
+        self.out.push_str(untouched.unwrap_or(&text));
+    }
+
+    fn print_source_node(&mut self, n: &SourceNode, open: &str, close: &str) {
+        let written = self.slice(&n.location);
+        // source is borrowed from the written text if untouched
+        match written.filter(|w| is_sub_slice(w, n.source)) {
+            Some(written) => self.out.push_str(written),
+            None => {
+                self.out.push_str(open);
+                self.out.push_str(n.source);
+                self.out.push_str(close);
+            }
+        }
+    }
+}
+
+/// `v` for long form, other styles are directive shorthands.
+const HEAD_STYLES: &[char] = &['v', ':', '.', '@', '#'];
+
+/// Directive head in the style, e.g. `v-bind:a`, `:a`, `.a` or `@a`.
+fn dir_head(d: &Directive, style: char) -> Option<String> {
+    let (arg, is_static) = match &d.argument {
+        Some(DirectiveArg::Static(arg)) => (Some(arg.to_string()), true),
+        Some(DirectiveArg::Dynamic(arg)) => (Some(format!("[{}]", arg)), false),
+        None => (None, false),
+    };
+    let mut mods = &d.modifiers[..];
+    let mut head = match (style, d.name, arg) {
+        ('v', name, Some(arg)) => format!("v-{}:{}", name, arg),
+        ('v', name, None) => format!("v-{}", name),
+        (':', "bind", Some(arg)) | ('@', "on", Some(arg)) | ('#', "slot", Some(arg)) => {
+            format!("{}{}", style, arg)
+        }
+        // parser appends `prop` modifier for `.arg`
+        ('.', "bind", Some(arg)) if is_static && mods.last() == Some(&"prop") => {
+            mods = &mods[..mods.len() - 1];
+            format!(".{}", arg)
+        }
+        _ => return None,
+    };
+    for m in mods {
+        head.push('.');
+        head.push_str(m);
+    }
+    Some(head)
+}
+
+/// Keeps the style of the written head, new directives use shorthand.
+fn canonical_head(d: &Directive, written: Option<&str>) -> String {
+    let head = match written.and_then(|h| h.chars().next()) {
+        Some(c) if HEAD_STYLES.contains(&c) => dir_head(d, c),
+        Some(_) => None,
+        None => [':', '@', '#'].iter().find_map(|&style| dir_head(d, style)),
+    };
+    head.or_else(|| dir_head(d, 'v'))
+        .expect("long form always exists")
+}
+
+/// Byte length of start tag, e.g. 9 for `<p a=">">`.
+fn start_tag_len(s: &str) -> Option<usize> {
+    let mut quote = None;
+    let mut after_eq = false;
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => continue,
+            None if c == '>' => return Some(i + 1),
+            None if after_eq && (c == '"' || c == '\'') => quote = Some(c),
+            None => (),
+        }
+        if !is_space(c) {
+            after_eq = c == '=';
+        }
+    }
+    None
+}
+
+fn is_end_tag_of(tag: &str, name: &str) -> bool {
+    let tag = tag.strip_prefix("</").unwrap_or(tag);
+    let tag = tag.strip_suffix('>').unwrap_or(tag);
+    tag.trim_end_matches(is_space).eq_ignore_ascii_case(name)
+}
+
+fn is_sub_slice(outer: &str, inner: &str) -> bool {
+    let base = outer.as_ptr() as usize;
+    let ptr = inner.as_ptr() as usize;
+    ptr >= base && ptr + inner.len() <= base + outer.len()
+}
+
+fn is_space(c: char) -> bool {
+    c.is_ascii_whitespace()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        cast,
+        error::test::TestErrorHandler,
+        parser::{ParseOption, Parser, WhitespaceStrategy},
+        scanner::test::base_scan,
+        util::VStr,
+    };
+    use std::rc::Rc;
+
+    fn parse(s: &str) -> AstRoot {
+        let parser = Parser::new(ParseOption {
+            whitespace: WhitespaceStrategy::Preserve,
+            is_void_tag: |t| t == "br" || t == "input",
+            ..Default::default()
+        });
+        parser.parse(base_scan(s), Rc::new(TestErrorHandler))
+    }
+
+    const FIXTURES: &[&str] = &[
+        "<div id=\"app\" class='a'>\n  <p v-if=\"ok\" :title = \"t\">{{ msg }}</p>\n  <p v-else>no</p>\n</div>\n",
+        "<ul>\n  <li v-for=\"(item, i) in items\" :key=\"item.id\" @click.stop=\"select(i)\">\n    {{ item.name }} &amp; more\n  </li>\n</ul>",
+        "<comp #default=\"{ a }\" .foo=\"bar\" v-bind:[key]=\" v \" >\n<!-- note -->\n<br>\n<input disabled value=plain />\n</comp>",
+        "<pre>\nline\n</pre>\n<textarea>\n x</textarea>\n<DIV></div >",
+        "  <div :a='x' v-pre>{{ raw }}</div>\n<p title=\"héllo\">ünï {{ ü }}</p><svg><path d=\"M0 0\"/></svg>",
+    ];
+
+    #[test]
+    fn test_print_identity() {
+        for &fixture in FIXTURES {
+            let ast = parse(fixture);
+            assert_eq!(print_template(&ast), fixture);
+        }
+    }
+
+    #[test]
+    fn test_print_renamed_tag() {
+        let source = FIXTURES[0];
+        let mut ast = parse(source);
+        let div = ast.children[0].get_element_mut().unwrap();
+        div.tag_name = "section";
+        assert_eq!(print_template(&ast), source.replace("div", "section"));
+    }
+
+    #[test]
+    fn test_print_mutated_props() {
+        let source = "<p id='a' :title=\"t\" @click=\"go\">x</p>";
+        let mut ast = parse(source);
+        let p = ast.children[0].get_element_mut().unwrap();
+        let id = cast!(&mut p.properties[0], ElemProp::Attr);
+        id.value.as_mut().unwrap().content = VStr::raw("b");
+        let title = cast!(&mut p.properties[1], ElemProp::Dir);
+        title.argument = Some(DirectiveArg::Static("label"));
+        p.properties.remove(2);
+        let expected = "<p id='b' :label=\"t\">x</p>";
+        assert_eq!(print_template(&ast), expected);
+    }
+}
@@ -0,0 +1,277 @@
+//! Support for compiling many templates of one project, e.g. a monorepo
+//! whose binding metadata is computed centrally by vue-tsc.
+use crate::manifest::fnv1a;
+use crate::{BindingMetadata, SFCInfo};
+use rustc_hash::FxHashMap;
+use std::sync::{Arc, Mutex};
+
+/// Looks up binding metadata by filename and caches the result, so
+/// a provider backed by expensive analysis is consulted once per file.
+pub struct BindingsCache<'a, F>
+where
+    F: Fn(&str) -> Option<BindingMetadata<'a>>,
+{
+    provider: F,
+    cache: FxHashMap<String, Option<BindingMetadata<'a>>>,
+}
+
+impl<'a, F> BindingsCache<'a, F>
+where
+    F: Fn(&str) -> Option<BindingMetadata<'a>>,
+{
+    pub fn new(provider: F) -> Self {
+        Self {
+            provider,
+            cache: FxHashMap::default(),
+        }
+    }
+    pub fn get(&mut self, filename: &str) -> Option<&BindingMetadata<'a>> {
+        if !self.cache.contains_key(filename) {
+            let bindings = (self.provider)(filename);
+            self.cache.insert(filename.to_string(), bindings);
+        }
+        self.cache[filename].as_ref()
+    }
+    /// Drop cached metadata after the file's script changes.
+    pub fn invalidate(&mut self, filename: &str) {
+        self.cache.remove(filename);
+    }
+    /// SFCInfo for compiling the file, without bindings if provider has none.
+    pub fn sfc_info(&mut self, filename: &str) -> SFCInfo<'a> {
+        SFCInfo {
+            binding_metadata: self.get(filename).cloned().unwrap_or_default(),
+            self_name: filename.to_string(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Generated code of unchanged templates, keyed by the source together with
+/// options and SFC info, see [CacheKey]. Clones share the same storage, so one
+/// cache can serve compilers on many threads. Bindings may store richer
+/// results than code, e.g. napi caches whole compile results.
+/// NB: like `CompileOption::fingerprint`, function pointer options and custom
+/// passes are not in the key. Share a cache only among compilers agreeing on them.
+pub struct CompileCache<V = String>(Arc<Mutex<CacheEntries<V>>>);
+
+/// Cache entry address. Entries are found by `hash` and the full `input`
+/// is compared on lookup, so a hash collision is a miss, not another
+/// template's code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CacheKey {
+    hash: u64,
+    input: String,
+}
+
+struct CacheEntry<V> {
+    input: String,
+    value: V,
+    /// the tick it was last used
+    used: u64,
+}
+
+struct CacheEntries<V> {
+    map: FxHashMap<u64, CacheEntry<V>>,
+    tick: u64,
+    capacity: usize,
+}
+
+impl<V> CacheEntries<V> {
+    fn insert(&mut self, key: CacheKey, value: V) {
+        self.tick += 1;
+        let CacheKey { hash, input } = key;
+        if self.map.len() >= self.capacity && !self.map.contains_key(&hash) {
+            let lru = self.map.iter().min_by_key(|(_, e)| e.used);
+            if let Some(lru) = lru.map(|(k, _)| *k) {
+                self.map.remove(&lru);
+            }
+        }
+        let entry = CacheEntry {
+            input,
+            value,
+            used: self.tick,
+        };
+        // a colliding entry is replaced, the latest input wins
+        self.map.insert(hash, entry);
+    }
+}
+
+impl<V> Clone for CompileCache<V> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Unbounded cache, for one-off builds that drop the cache afterwards.
+impl<V: Clone> Default for CompileCache<V> {
+    fn default() -> Self {
+        Self::bounded(usize::MAX)
+    }
+}
+
+impl CompileCache {
+    pub fn key(source: &str, options_fingerprint: &str, sfc_info: &SFCInfo) -> CacheKey {
+        let input = format!(
+            "{}|{}|{}|{}|{}|{:?}|{}",
+            options_fingerprint,
+            sfc_info.binding_metadata.fingerprint(),
+            sfc_info.inline,
+            sfc_info.slotted,
+            sfc_info.self_name,
+            sfc_info.scope_id,
+            source,
+        );
+        CacheKey {
+            hash: fnv1a(input.as_bytes()),
+            input,
+        }
+    }
+}
+
+impl<V: Clone> CompileCache<V> {
+    /// Cache holding at most `capacity` entries. The least recently used
+    /// entry is evicted to make room, so long running servers stay bounded.
+    pub fn bounded(capacity: usize) -> Self {
+        let entries = CacheEntries {
+            map: FxHashMap::default(),
+            tick: 0,
+            capacity,
+        };
+        Self(Arc::new(Mutex::new(entries)))
+    }
+    pub fn get(&self, key: &CacheKey) -> Option<V> {
+        let mut entries = self.0.lock().ok()?;
+        entries.tick += 1;
+        let tick = entries.tick;
+        let entry = entries.map.get_mut(&key.hash)?;
+        if entry.input != key.input {
+            return None;
+        }
+        entry.used = tick;
+        Some(entry.value.clone())
+    }
+    pub fn insert(&self, key: CacheKey, value: V) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.insert(key, value);
+        }
+    }
+    pub fn len(&self) -> usize {
+        self.0.lock().map_or(0, |entries| entries.map.len())
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.0.lock() {
+            entries.map.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::codegen::ScriptMode;
+    use crate::compiler::{get_base_passes, BaseCompiler, CompileOption, CompileOutput};
+    use crate::BindingTypes;
+    use std::cell::{Cell, RefCell};
+
+    fn compile(source: &str, sfc_info: &SFCInfo) -> CompileOutput<Vec<u8>> {
+        let option = CompileOption {
+            mode: ScriptMode::Module {
+                runtime_module_name: "vue".into(),
+            },
+            manifest: true,
+            ..Default::default()
+        };
+        let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+        compiler.compile_with_source_map(source, sfc_info).unwrap()
+    }
+
+    #[test]
+    fn test_bindings_per_file() {
+        let provider = |filename: &str| {
+            let ty = match filename {
+                "A.vue" => BindingTypes::SetupConst,
+                "B.vue" => BindingTypes::Props,
+                _ => return None,
+            };
+            let bindings = std::iter::once(("a", ty)).collect();
+            Some(BindingMetadata::new(bindings, true))
+        };
+        let mut cache = BindingsCache::new(provider);
+        let source = "<p>{{ a }}</p>";
+        let mut keys = vec![];
+        for (file, expected) in [
+            ("A.vue", "$setup.a"),
+            ("B.vue", "$props.a"),
+            ("C.vue", "_ctx.a"),
+        ] {
+            let sfc_info = cache.sfc_info(file);
+            let output = compile(source, &sfc_info);
+            let code = String::from_utf8(output.code).unwrap();
+            assert!(code.contains(expected), "{}: {}", file, code);
+            keys.push(output.manifest.unwrap().cache_key());
+        }
+        assert_ne!(keys[0], keys[1]);
+        assert_ne!(keys[1], keys[2]);
+    }
+
+    #[test]
+    fn test_bindings_invalidation() {
+        let ty = RefCell::new(BindingTypes::SetupConst);
+        let calls = Cell::new(0);
+        let provider = |_: &str| {
+            calls.set(calls.get() + 1);
+            let bindings = std::iter::once(("a", *ty.borrow())).collect();
+            Some(BindingMetadata::new(bindings, true))
+        };
+        let mut cache = BindingsCache::new(provider);
+        let source = "<p>{{ a }}</p>";
+        let old = compile(source, &cache.sfc_info("A.vue")).manifest.unwrap();
+        *ty.borrow_mut() = BindingTypes::Data;
+        // provider is not consulted again until invalidated
+        let cached = compile(source, &cache.sfc_info("A.vue")).manifest.unwrap();
+        assert_eq!(calls.get(), 1);
+        assert_eq!(old.cache_key(), cached.cache_key());
+        cache.invalidate("A.vue");
+        let new = compile(source, &cache.sfc_info("A.vue")).manifest.unwrap();
+        assert_eq!(calls.get(), 2);
+        assert_eq!(old.input_hash, new.input_hash);
+        assert_ne!(old.cache_key(), new.cache_key());
+    }
+
+    fn key(source: &str) -> CacheKey {
+        CompileCache::key(source, "", &SFCInfo::default())
+    }
+
+    #[test]
+    fn test_bounded_cache() {
+        let cache = CompileCache::bounded(2);
+        cache.insert(key("1"), "a".to_string());
+        cache.insert(key("2"), "b".to_string());
+        // 1 is now used more recently than 2
+        assert!(cache.get(&key("1")).is_some());
+        cache.insert(key("3"), "c".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("2")), None);
+        assert_eq!(cache.get(&key("1")).as_deref(), Some("a"));
+        // replacing an entry does not evict others
+        cache.insert(key("3"), "d".to_string());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("3")).as_deref(), Some("d"));
+    }
+
+    #[test]
+    fn test_cache_hash_collision() {
+        let cache = CompileCache::default();
+        let a = key("<a/>");
+        let b = CacheKey {
+            hash: a.hash,
+            input: key("<b/>").input,
+        };
+        cache.insert(a.clone(), "a".to_string());
+        assert_eq!(cache.get(&b), None);
+        assert_eq!(cache.get(&a).as_deref(), Some("a"));
+    }
+}
@@ -33,6 +33,9 @@ impl<'a> Attribute<'a> {
 pub struct AttributeValue<'a> {
     pub content: VStr<'a>,
     pub location: SourceLocation,
+    /// quote char around the value, None if unquoted
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub quote: Option<char>,
 }
 
 /// Tag is used only for start tag since end tag is bare
@@ -215,8 +218,12 @@ impl<'a> Tokens<'a> {
         debug_assert!(self.source.starts_with(&delimiters.0));
         let index = self.source.find(&delimiters.1);
         if index.is_none() {
+            let start = self.current_position();
             let src = self.move_by(self.source.len());
-            self.emit_error(ErrorKind::MissingInterpolationEnd);
+            // span the unclosed interpolation instead of the EOF
+            let err = CompilationError::new(ErrorKind::MissingInterpolationEnd)
+                .with_location(self.get_location_from(start));
+            self.err_handle.on_error(err);
             return Token::Interpolation(&src[2..]);
         }
         let src = &self.move_by(index.unwrap())[2..];
@@ -256,6 +263,10 @@ impl<'a> Tokens<'a> {
         debug_assert!(self.source.starts_with('<'));
         self.move_by(1);
         let tag = self.scan_tag_name();
+        // self closing tag has no content to switch text mode for
+        if tag.self_closing {
+            return Token::StartTag(tag);
+        }
         // https://html.spec.whatwg.org/multipage/parsing.html#parsing-elements-that-contain-only-text
         // Parsing algorithms are always invoked in response to a start tag token.
         let parsing_algorithm = self.option.get_text_mode;
@@ -302,7 +313,10 @@ impl<'a> Tokens<'a> {
             if set.contains(attr.name) {
                 // new attribute must be removed from the token.
                 // NB: original vue compiler does not remove it.
-                self.emit_error(ErrorKind::DuplicateAttribute);
+                let err = CompilationError::new(ErrorKind::DuplicateAttribute)
+                    .with_location(attr.location)
+                    .with_context(attr.name);
+                self.err_handle.on_error(err);
                 continue;
             }
             set.insert(attr.name);
@@ -382,8 +396,12 @@ impl<'a> Tokens<'a> {
             return None;
         }
         let start = self.current_position();
-        let content = if self.source.starts_with(&['"', '\''][..]) {
-            let c = self.source.chars().next().unwrap();
+        let quote = self
+            .source
+            .chars()
+            .next()
+            .filter(|c| ['"', '\''].contains(c));
+        let content = if let Some(c) = quote {
             self.scan_quoted_attr_value(c)?
         } else {
             self.scan_unquoted_attr_value()?
@@ -391,6 +409,7 @@ impl<'a> Tokens<'a> {
         Some(AttributeValue {
             content: VStr::raw(content),
             location: self.get_location_from(start),
+            quote,
         })
     }
     // https://html.spec.whatwg.org/multipage/parsing.html#attribute-value-(double-quoted)-state
@@ -807,8 +826,15 @@ impl<'a> Locatable for Tokens<'a> {
     }
 }
 
-pub trait TokenSource<'a>: FusedIterator<Item = Token<'a>> + FlagCDataNs + Locatable {}
-impl<'a> TokenSource<'a> for Tokens<'a> {}
+pub trait TokenSource<'a>: FusedIterator<Item = Token<'a>> + FlagCDataNs + Locatable {
+    /// source text not scanned yet, the whole template before scanning
+    fn rest_source(&self) -> &'a str;
+}
+impl<'a> TokenSource<'a> for Tokens<'a> {
+    fn rest_source(&self) -> &'a str {
+        self.source
+    }
+}
 
 #[cfg(test)]
 pub mod test {
@@ -835,6 +861,22 @@ pub mod test {
         assert_eq!(val.content.into_string(), "&amp;");
     }
 
+    #[test]
+    fn test_invalid_attr_name() {
+        use crate::error::{CompilationErrorKind as Kind, VecErrorHandler};
+        // static attr names cannot smuggle quote or tag open into output
+        for case in [r#"<p a"b=1/>"#, "<p a'b=1/>", "<p a<b=1/>"] {
+            let eh = std::rc::Rc::new(VecErrorHandler::default());
+            let scanner = Scanner::new(ScanOption::default());
+            let _: Vec<_> = scanner.scan(case, eh.clone()).collect();
+            let errors = eh.errors();
+            let found = errors
+                .iter()
+                .any(|e| matches!(e.kind, Kind::UnexpectedCharacterInAttributeName));
+            assert!(found, "{}", case);
+        }
+    }
+
     fn scan_with_opt(s: &str, opt: ScanOption) -> impl TokenSource {
         let scanner = Scanner::new(opt);
         let ctx = std::rc::Rc::new(TestErrorHandler);
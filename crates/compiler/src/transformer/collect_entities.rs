@@ -4,7 +4,7 @@
 use super::{
     BaseFor, BaseIf, BaseInfo, BaseRenderSlot, BaseText, BaseVNode, BaseVSlot, BaseCache, CorePass,
 };
-use crate::converter::BaseRoot;
+use crate::converter::{BaseIR, BaseRoot};
 use crate::flags::{HelperCollector, RuntimeHelper as RH};
 use crate::ir::{IRNode as IR, JsExpr as Js};
 use crate::util::{get_vnode_call_helper, VStr};
@@ -16,23 +16,41 @@ pub struct EntityCollector<'a> {
     helpers: HelperCollector,
     components: FxHashSet<VStr<'a>>,
     directives: FxHashSet<VStr<'a>>,
+    uses_emit: bool,
+    has_once: bool,
 }
 
 impl<'a> CorePass<BaseInfo<'a>> for EntityCollector<'a> {
-    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+    // check root fragment before static hoisting merges root nodes
+    fn enter_root(&mut self, r: &mut BaseRoot<'a>) {
+        // codegen wraps multiple roots in a plain Fragment vnode call
         if r.body.len() > 1 {
             self.helpers.collect(RH::FRAGMENT);
+            self.helpers.collect(RH::CREATE_ELEMENT_VNODE);
+        }
+    }
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+        // VOnceProcessor may have unwrapped all v-once caches
+        if self.has_once && r.body.iter().any(has_once_cache) {
+            self.helpers.collect(RH::SET_BLOCK_TRACKING);
         }
         let scope = &mut r.top_scope;
         swap(&mut scope.helpers, &mut self.helpers);
         swap(&mut scope.components, &mut self.components);
         swap(&mut scope.directives, &mut self.directives);
+        scope.uses_emit = self.uses_emit;
     }
     fn exit_js_expr(&mut self, e: &mut Js) {
         match e {
             Js::Call(h, ..) | Js::Symbol(h) => {
                 self.helpers.collect(*h);
             }
+            Js::Compound(v) | Js::FuncCompound { body: v, .. } => {
+                collect_rewritten_helpers(&mut self.helpers, v);
+            }
+            Js::Simple(v, _) | Js::FuncSimple { src: v, .. } => {
+                self.uses_emit = self.uses_emit || references_emit(v.raw);
+            }
             _ => {}
         }
     }
@@ -54,12 +72,21 @@ impl<'a> CorePass<BaseInfo<'a>> for EntityCollector<'a> {
     fn exit_vnode(&mut self, v: &mut BaseVNode<'a>) {
         if !v.directives.is_empty() {
             self.helpers.collect(RH::WITH_DIRECTIVES);
-            // dir with Js::Symbol is collected in js_expr
-            for dir in v.directives.iter() {
-                if let Js::StrLit(d) = dir.name {
-                    self.directives.insert(d);
-                }
-            }
+        }
+        // directives can be used on both elements and components.
+        // only hoisted asset needs handling, Js::Symbol is collected in js_expr
+        // see [build_directive_arg] in convert_element
+        let mut hoisted_dir_names = v
+            .directives
+            .iter()
+            .map(|dir| &dir.name)
+            .filter_map(is_hoisted_asset)
+            .peekable();
+        if hoisted_dir_names.peek().is_some() {
+            self.helpers.collect(RH::RESOLVE_DIRECTIVE);
+        }
+        for dir_name in hoisted_dir_names {
+            self.directives.insert(*dir_name);
         }
         if v.is_block {
             self.helpers.collect(RH::OPEN_BLOCK);
@@ -75,19 +102,6 @@ impl<'a> CorePass<BaseInfo<'a>> for EntityCollector<'a> {
             self.helpers.collect(RH::RESOLVE_COMPONENT);
             self.components.insert(*tag);
         }
-        // only StrLit needs handling, see [build_directive_arg] in convert_element
-        let mut hoisted_dir_names = v
-            .directives
-            .iter()
-            .map(|dir| &dir.name)
-            .filter_map(is_hoisted_asset)
-            .peekable();
-        if hoisted_dir_names.peek().is_some() {
-            self.helpers.collect(RH::RESOLVE_DIRECTIVE);
-        }
-        for dir_name in hoisted_dir_names {
-            self.directives.insert(*dir_name);
-        }
     }
     fn exit_slot_outlet(&mut self, _: &mut BaseRenderSlot<'a>) {
         self.helpers.collect(RH::RENDER_SLOT);
@@ -110,7 +124,7 @@ impl<'a> CorePass<BaseInfo<'a>> for EntityCollector<'a> {
     fn enter_cache(&mut self, r: &mut BaseCache<'a>) {
         use crate::ir::CacheKind::{Once, Memo, MemoInVFor};
         match r.kind {
-            Once => self.helpers.collect(RH::SET_BLOCK_TRACKING),
+            Once => self.has_once = true,
             Memo(_) => self.helpers.collect(RH::WITH_MEMO),
             MemoInVFor { .. } => {
                 self.helpers.collect(RH::IS_MEMO_SAME);
@@ -119,6 +133,23 @@ impl<'a> CorePass<BaseInfo<'a>> for EntityCollector<'a> {
     }
 }
 
+fn has_once_cache(ir: &BaseIR) -> bool {
+    use crate::ir::CacheKind::Once;
+    match ir {
+        IR::CacheNode(c) => matches!(c.kind, Once) || has_once_cache(&c.child),
+        IR::VNodeCall(v) => v.children.iter().any(has_once_cache),
+        IR::If(i) => i.branches.iter().any(|b| has_once_cache(&b.child)),
+        IR::For(f) => has_once_cache(&f.child),
+        IR::RenderSlotCall(r) => r.fallbacks.iter().any(has_once_cache),
+        IR::VSlotUse(s) => {
+            let mut bodies = s.stable_slots.iter().flat_map(|slot| slot.body.iter());
+            bodies.any(has_once_cache) || s.alterable_slots.iter().any(has_once_cache)
+        }
+        IR::AlterableSlot(s) => s.body.iter().any(has_once_cache),
+        IR::TextCall(_) | IR::CommentCall(_) | IR::Hoisted(_) => false,
+    }
+}
+
 pub fn is_hoisted_asset<'a, 'b>(expr: &'b Js<'a>) -> Option<&'b VStr<'a>> {
     match expr {
         Js::Simple(n, _) if VStr::is_asset(n) => Some(n),
@@ -126,6 +157,33 @@ pub fn is_hoisted_asset<'a, 'b>(expr: &'b Js<'a>) -> Option<&'b VStr<'a>> {
     }
 }
 
+/// Expression processor rewrites identifiers into calls like `unref(a)`
+/// after sub expressions are visited, so the calls are collected here.
+fn collect_rewritten_helpers(helpers: &mut HelperCollector, exprs: &[Js]) {
+    for e in exprs {
+        match e {
+            Js::Call(h, args) => {
+                helpers.collect(*h);
+                collect_rewritten_helpers(helpers, args);
+            }
+            Js::Compound(v) | Js::FuncCompound { body: v, .. } => {
+                collect_rewritten_helpers(helpers, v)
+            }
+            _ => {}
+        }
+    }
+}
+
+/// `$emit` used as identifier, not as member like `a.$emit` or `$emitter`.
+fn references_emit(exp: &str) -> bool {
+    let is_id = |c: char| c.is_alphanumeric() || c == '_' || c == '$';
+    exp.match_indices("$emit").any(|(i, m)| {
+        let before = exp[..i].chars().next_back();
+        let after = exp[i + m.len()..].chars().next();
+        !matches!(before, Some(c) if is_id(c) || c == '.') && !after.map_or(false, is_id)
+    })
+}
+
 #[cfg(test)]
 mod test {
     use super::super::test::base_convert;
@@ -142,6 +200,14 @@ mod test {
         let helpers = ir.top_scope.helpers;
         assert!(helpers.contains(RH::CREATE_COMMENT));
     }
+    #[test]
+    fn test_v_once_helper() {
+        let ir = transform("<div><p v-if='a' v-once/></div>");
+        assert!(ir.top_scope.helpers.contains(RH::SET_BLOCK_TRACKING));
+        let ir = transform("<div><p v-memo='[a]'/></div>");
+        assert!(!ir.top_scope.helpers.contains(RH::SET_BLOCK_TRACKING));
+    }
+
     #[test]
     fn test_v_for_helper() {
         let ir = transform("<p v-for='a in b'/>");
@@ -165,4 +231,21 @@ mod test {
         assert!(helpers.contains(RH::RENDER_LIST));
         assert!(helpers.contains(RH::WITH_CTX));
     }
+    #[test]
+    fn test_element_directive() {
+        let ir = transform("<p v-foo/>");
+        let top = ir.top_scope;
+        assert!(top.helpers.contains(RH::WITH_DIRECTIVES));
+        assert!(top.helpers.contains(RH::RESOLVE_DIRECTIVE));
+        assert_eq!(top.directives.len(), 1);
+        assert!(top.components.is_empty());
+    }
+    #[test]
+    fn test_uses_emit() {
+        let uses_emit = |s| transform(s).top_scope.uses_emit;
+        assert!(uses_emit("<p>{{ $emit('a') }}</p>"));
+        assert!(uses_emit("<p>{{ a && $emit }}</p>"));
+        assert!(!uses_emit("<p title='$emit'>$emit</p>"));
+        assert!(!uses_emit("<p>{{ a.$emit }}{{ $emitter }}</p>"));
+    }
 }
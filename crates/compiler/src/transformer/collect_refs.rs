@@ -0,0 +1,96 @@
+// Template refs are plain props, e.g. `{ ref: "r" }`, which the runtime uses
+// to populate `$refs`. Inside v-for, the same ref name is shared by every
+// item, so `ref_for: true` tells the runtime to collect them into an array.
+use super::{BaseFor, BaseInfo, BaseVNode, CorePass};
+use crate::ir::JsExpr as Js;
+
+#[derive(Default)]
+pub struct RefCollector {
+    v_for_depth: usize,
+}
+
+impl<'a> CorePass<BaseInfo<'a>> for RefCollector {
+    fn enter_for(&mut self, _: &mut BaseFor<'a>) {
+        self.v_for_depth += 1;
+    }
+    fn exit_for(&mut self, _: &mut BaseFor<'a>) {
+        self.v_for_depth -= 1;
+    }
+    fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+        if self.v_for_depth == 0 {
+            return;
+        }
+        if let Some(props) = vn.props.as_mut() {
+            mark_ref_for(props);
+        }
+    }
+}
+
+fn mark_ref_for(props: &mut Js) {
+    let props = match props {
+        Js::Props(ps) => ps,
+        // v-bind object merged with other props
+        Js::Call(_, args) => {
+            args.iter_mut().for_each(mark_ref_for);
+            return;
+        }
+        _ => return,
+    };
+    let found = props.iter().position(|(k, _)| match k {
+        Js::StrLit(k) => k.raw == "ref",
+        _ => false,
+    });
+    if let Some(i) = found {
+        props.insert(i, (Js::str_lit("ref_for"), Js::Src("true")));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::base_convert;
+    use super::super::{BaseTransformer, Transformer};
+    use super::*;
+    use crate::cast;
+    use crate::converter::{BaseIR, BaseRoot};
+    use crate::ir::IRNode as IR;
+
+    fn transform(s: &str) -> BaseRoot {
+        let mut ir = base_convert(s);
+        BaseTransformer::transform(&mut ir, RefCollector::default());
+        ir
+    }
+    fn prop_keys<'a>(ir: &BaseIR<'a>) -> Vec<&'a str> {
+        let vn = cast!(ir, IR::VNodeCall);
+        let props = cast!(vn.props.as_ref().unwrap(), Js::Props);
+        props
+            .iter()
+            .map(|(k, _)| cast!(k, Js::StrLit).raw)
+            .collect()
+    }
+
+    #[test]
+    fn test_ref_outside_for() {
+        let ir = transform("<p ref='r'/>");
+        assert_eq!(prop_keys(&ir.body[0]), ["ref"]);
+        let ir = transform("<p :ref='r'/>");
+        assert_eq!(prop_keys(&ir.body[0]), ["ref"]);
+    }
+
+    #[test]
+    fn test_ref_in_for() {
+        let ir = transform("<p v-for='i in a' ref='r' id='i'/>");
+        let f = cast!(&ir.body[0], IR::For);
+        assert_eq!(prop_keys(&f.child), ["ref_for", "ref", "id"]);
+        let ir = transform("<div v-for='i in a'><p :ref='r'/></div>");
+        let f = cast!(&ir.body[0], IR::For);
+        let div = cast!(&*f.child, IR::VNodeCall);
+        assert_eq!(prop_keys(&div.children[0]), ["ref_for", "ref"]);
+    }
+
+    #[test]
+    fn test_ref_after_for() {
+        let ir = transform("<div><p v-for='i in a'/><p ref='r'/></div>");
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert_eq!(prop_keys(&div.children[1]), ["ref"]);
+    }
+}
@@ -1,12 +1,126 @@
 /// cache handlers so that it's always the same handler being passed down.
 /// this avoids unnecessary re-renders when users use inline handlers on
 /// components. NB. requires prefix_identifiers
-use super::{BaseInfo, BaseVNode, CorePassExt, BaseCache, Scope};
+use super::{BaseInfo, BaseVNode, CorePass, CorePassExt, BaseCache, Scope};
 use crate::{
-    converter::v_on::get_handler_type,
-    flags::StaticLevel,
-    ir::{JsExpr as Js, CacheKind, HandlerType},
+    converter::{
+        v_on::{get_handler_type, is_noop_handler},
+        BaseIR, BaseRoot, Hoist,
+    },
+    flags::{PatchFlag, RuntimeHelper as RH, StaticLevel},
+    ir::{JsExpr as Js, CacheKind, HandlerType, IRNode as IR, VNodeIR},
+    SourceLocation,
 };
+use rustc_hash::{FxHashMap, FxHasher};
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    mem,
+    rc::Rc,
+};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+/// Read-only view of a hoisted static subtree, given to a custom
+/// [StaticSerializer]. Attribute values and texts are already decoded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StaticSubtree {
+    Element {
+        tag: String,
+        props: Vec<(String, String)>,
+        children: Vec<StaticSubtree>,
+    },
+    Text(String),
+    Comment(String),
+}
+
+/// Returns JS code that creates the subtree, e.g. `_createStaticVNode("<p></p>", 1)`.
+pub type StaticSerializer = Rc<dyn Fn(&StaticSubtree) -> String>;
+
+/// The first check of [static_check] a node fails, or why a hoisted
+/// subtree is not stringified (the last three).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub enum BailReason {
+    Component,
+    /// e.g. `<svg>`, which needs to track its dynamic descendants
+    Block,
+    /// span of the first runtime directive, e.g. `v-focus`
+    RuntimeDirective(SourceLocation),
+    /// alphabetically first prop patched at runtime
+    DynamicProp(String),
+    /// patched for other reasons, e.g. class, style or text
+    PatchFlag(PatchFlag),
+    /// e.g. `<component :is>`
+    DynamicTag,
+    /// props object is not constant, e.g. `v-bind="obj"`
+    DynamicProps,
+    /// a child is not static, reported separately if it is an element
+    DynamicChild,
+    /// interpolation, v-if, v-for, slot or cached node
+    Dynamic,
+    /// runtime may set props of custom elements as DOM properties
+    CustomElement(String),
+    /// e.g. a prop set as DOM property
+    Unstringifiable(String),
+    /// innerHTML is not parsed as SVG or MathML
+    ForeignContent,
+}
+
+impl fmt::Display for BailReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Component => write!(f, "is a component"),
+            Self::Block => write!(f, "is a block"),
+            Self::RuntimeDirective(loc) => {
+                let pos = &loc.start;
+                write!(f, "has runtime directive at {}:{}", pos.line, pos.column)
+            }
+            Self::DynamicProp(prop) => write!(f, "has dynamic prop `{}`", prop),
+            Self::PatchFlag(flag) => write!(f, "needs patching ({:?})", flag),
+            Self::DynamicTag => write!(f, "has dynamic tag"),
+            Self::DynamicProps => write!(f, "has non-constant props"),
+            Self::DynamicChild => write!(f, "has dynamic children"),
+            Self::Dynamic => write!(f, "is dynamic"),
+            Self::CustomElement(tag) => write!(f, "contains custom element <{}>", tag),
+            Self::Unstringifiable(what) => write!(f, "has {} that cannot be stringified", what),
+            Self::ForeignContent => write!(f, "has SVG or MathML content"),
+        }
+    }
+}
+
+/// An element that could have been hoisted or stringified but was not,
+/// reported if `explain_hoist` is on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct HoistBailout {
+    pub tag: String,
+    pub location: SourceLocation,
+    pub reason: BailReason,
+}
+
+impl HoistBailout {
+    pub fn new(vn: &BaseVNode, reason: BailReason) -> Self {
+        let tag = match &vn.tag {
+            Js::StrLit(t) | Js::Simple(t, _) => t.raw.to_string(),
+            _ => String::new(),
+        };
+        Self {
+            tag,
+            location: vn.location.clone(),
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for HoistBailout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pos = &self.location.start;
+        let (tag, reason) = (&self.tag, &self.reason);
+        write!(f, "{}:{}: <{}> {}", pos.line, pos.column, tag, reason)
+    }
+}
 
 // 1. cache handler
 // 2. hoist static
@@ -14,18 +128,28 @@ pub struct HoistStatic {
     in_v_once: bool,
     is_component: bool,
     cache_handlers: bool,
+    hoist_static: bool,
+    explain_hoist: bool,
 }
 impl HoistStatic {
-    pub fn new(cache_handlers: bool) -> Self {
+    pub fn new(cache_handlers: bool, hoist_static: bool, explain_hoist: bool) -> Self {
         Self {
             in_v_once: false,
             is_component: false,
             cache_handlers,
+            hoist_static,
+            explain_hoist,
         }
     }
 }
 
 impl<'a> CorePassExt<BaseInfo<'a>, Scope<'a>> for HoistStatic {
+    // static levels and patch flags are final only after all nodes exit
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>, _: &mut Scope<'a>) {
+        if self.hoist_static {
+            hoist_root(r, self.explain_hoist);
+        }
+    }
     fn enter_cache(&mut self, cn: &mut BaseCache<'a>, _: &mut Scope<'a>) {
         if matches!(cn.kind, CacheKind::Once) {
             self.in_v_once = true;
@@ -39,14 +163,19 @@ impl<'a> CorePassExt<BaseInfo<'a>, Scope<'a>> for HoistStatic {
     fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>, _: &mut Scope<'a>) {
         self.is_component = vn.is_component;
     }
-    fn enter_js_expr(&mut self, exp: &mut Js<'a>, scope: &mut Scope<'a>) {
+    // handlers are inspected on exit since ExpressionProcessor prefixes
+    // them and computes their static level on exit
+    fn exit_js_expr(&mut self, exp: &mut Js<'a>, scope: &mut Scope<'a>) {
         // unnecessary to cache inside v-once
         if !self.cache_handlers || self.in_v_once {
             return;
         }
-        let ty = match exp {
-            Js::FuncSimple { src, .. } => get_handler_type(*src),
-            Js::FuncCompound { ty, .. } => ty.clone(),
+        let (ty, local_ref) = match exp {
+            Js::FuncSimple { src, .. } => (get_handler_type(*src), scope.has_identifier(src.raw)),
+            Js::FuncCompound { ty, body, .. } => {
+                let local_ref = body.iter_mut().any(|e| scope.has_ref_in_expr(e));
+                (ty.clone(), local_ref)
+            }
             _ => return,
         };
         let is_member_exp = matches!(ty, HandlerType::MemberExpr);
@@ -59,10 +188,10 @@ impl<'a> CorePassExt<BaseInfo<'a>, Scope<'a>> for HoistStatic {
             !(is_member_exp && self.is_component) &&
             // bail if the function references closure variables (v-for, v-slot)
             // it must be passed fresh to avoid stale values.
-            !scope.has_ref_in_expr(exp) &&
+            !local_ref &&
             // runtime constants don't need to be cached
             // (this is analyzed by compileScript in SFC <script setup>)
-            exp.static_level() > StaticLevel::NotStatic;
+            exp.static_level() == StaticLevel::NotStatic;
         let cache = match exp {
             Js::FuncSimple { cache, .. } | Js::FuncCompound { cache, .. } => cache,
             _ => return,
@@ -70,3 +199,485 @@ impl<'a> CorePassExt<BaseInfo<'a>, Scope<'a>> for HoistStatic {
         *cache = should_cache;
     }
 }
+
+/// Reference to the `index`th hoisted constant, i.e. `_hoisted_x`.
+/// Names are 1-based like vue-next, so index 0 is `_hoisted_1`.
+pub fn hoisted_ref<'a>(index: usize) -> Js<'a> {
+    Js::Compound(vec![Js::Src("_hoisted_"), Js::Num(index + 1)])
+}
+
+/// Collects why elements are not hoisted if enabled.
+struct Explainer {
+    enabled: bool,
+    bailouts: Vec<HoistBailout>,
+}
+
+impl Explainer {
+    fn record(&mut self, vn: &BaseVNode, reason: BailReason) {
+        if self.enabled {
+            self.bailouts.push(HoistBailout::new(vn, reason));
+        }
+    }
+}
+
+struct Hoister<'a, 'h> {
+    hoists: &'h mut Vec<Hoist<'a>>,
+    /// indices of hoisted nodes keyed by [hash_node], so that identical
+    /// subtrees are found without comparing against every hoist
+    nodes: FxHashMap<u64, Vec<usize>>,
+    ex: Explainer,
+}
+
+/// Lifts static subtrees and static props objects to `top_scope.hoists`
+/// so they are created once outside of render function.
+fn hoist_root(r: &mut BaseRoot, explain: bool) {
+    let (body, top) = (&mut r.body, &mut r.top_scope);
+    let mut h = Hoister {
+        hoists: &mut top.hoists,
+        nodes: FxHashMap::default(),
+        ex: Explainer {
+            enabled: explain,
+            bailouts: vec![],
+        },
+    };
+    if !body.is_empty() && body.iter().all(is_static_ir) {
+        // entirely static template returns the same tree on every render
+        let mut children = std::mem::take(body);
+        let ir = if children.len() == 1 {
+            children.pop().unwrap()
+        } else {
+            IR::VNodeCall(VNodeIR {
+                tag: Js::Symbol(RH::FRAGMENT),
+                children,
+                ..VNodeIR::default()
+            })
+        };
+        body.push(h.hoist_node(ir));
+    } else if body.len() == 1 {
+        // sole root node is returned directly, so only its descendants can be hoisted
+        h.hoist_in_ir(&mut body[0]);
+    } else {
+        h.hoist_children(body);
+    }
+    top.hoist_bailouts = h.ex.bailouts;
+}
+
+impl<'a, 'h> Hoister<'a, 'h> {
+    fn hoist_node(&mut self, mut ir: BaseIR<'a>) -> BaseIR<'a> {
+        if let IR::VNodeCall(vn) = &mut ir {
+            vn.patch_flag = PatchFlag::HOISTED;
+        }
+        // identical subtrees share one hoisted vnode, runtime clones it
+        // if it is already mounted elsewhere
+        let mut hasher = FxHasher::default();
+        hash_node(&ir, &mut hasher);
+        let hoists = &mut *self.hoists;
+        let same = self.nodes.entry(hasher.finish()).or_default();
+        let existing = same.iter().copied().find(|&i| match &hoists[i] {
+            Hoist::Node(n) => is_same_node(n, &ir),
+            _ => false,
+        });
+        if let Some(index) = existing {
+            return IR::Hoisted(index);
+        }
+        hoists.push(Hoist::Node(ir));
+        same.push(hoists.len() - 1);
+        IR::Hoisted(hoists.len() - 1)
+    }
+
+    fn hoist_children(&mut self, children: &mut [BaseIR<'a>]) {
+        for child in children.iter_mut() {
+            let vn = match &*child {
+                IR::VNodeCall(vn) => vn,
+                _ => {
+                    self.hoist_in_ir(child);
+                    continue;
+                }
+            };
+            if let Err(reason) = check_vnode(vn) {
+                self.ex.record(vn, reason);
+                self.hoist_in_ir(child);
+                continue;
+            }
+            let ir = std::mem::replace(child, IR::Hoisted(0));
+            *child = self.hoist_node(ir);
+        }
+    }
+
+    // v-if branch and v-for child are never hoisted themselves since
+    // they need their own block and key, but their descendants can be.
+    fn hoist_in_ir(&mut self, ir: &mut BaseIR<'a>) {
+        match ir {
+            IR::VNodeCall(vn) => {
+                self.hoist_children(&mut vn.children);
+                hoist_props(vn, self.hoists);
+                if let Some(props) = vn.props.as_mut() {
+                    hoist_noop_handlers(props, self.hoists);
+                }
+            }
+            IR::If(i) => {
+                for branch in i.branches.iter_mut() {
+                    self.hoist_in_ir(&mut branch.child);
+                }
+            }
+            IR::For(f) => self.hoist_in_ir(&mut f.child),
+            IR::RenderSlotCall(r) => self.hoist_children(&mut r.fallbacks),
+            IR::VSlotUse(s) => {
+                for slot in s.stable_slots.iter_mut() {
+                    self.hoist_children(&mut slot.body);
+                }
+                for alterable in s.alterable_slots.iter_mut() {
+                    self.hoist_in_ir(alterable);
+                }
+            }
+            IR::AlterableSlot(s) => self.hoist_children(&mut s.body),
+            // v-once and v-memo are cached by runtime already
+            IR::CacheNode(_) | IR::TextCall(_) | IR::CommentCall(_) | IR::Hoisted(_) => {}
+        }
+    }
+}
+
+/// dynamic element can still share its props object if no prop is patched
+fn hoist_props<'a>(vn: &mut BaseVNode<'a>, hoists: &mut Vec<Hoist<'a>>) {
+    let patchable = PatchFlag::NEED_PATCH | PatchFlag::TEXT;
+    if vn.is_component || !patchable.contains(vn.patch_flag) {
+        return;
+    }
+    let props = match vn.props.take() {
+        Some(p) if matches!(&p, Js::Props(ps) if !ps.is_empty()) && is_hoistable(&p) => p,
+        other => {
+            vn.props = other;
+            return;
+        }
+    };
+    hoists.push(Hoist::Props(props));
+    vn.props = Some(hoisted_ref(hoists.len() - 1));
+}
+
+/// modifier-only handlers like `@submit.prevent` are constant so identical
+/// ones share one hoisted function instead of being recreated every render
+fn hoist_noop_handlers<'a>(props: &mut Js<'a>, hoists: &mut Vec<Hoist<'a>>) {
+    let props = match props {
+        Js::Props(ps) => ps,
+        // v-bind object merged with other props
+        Js::Call(_, args) => {
+            for arg in args {
+                hoist_noop_handlers(arg, hoists);
+            }
+            return;
+        }
+        _ => return,
+    };
+    for (_, value) in props.iter_mut() {
+        if !is_noop_handler(value) {
+            continue;
+        }
+        let existing = hoists.iter().position(|h| match h {
+            Hoist::Expr(e) => is_same_const(e, value),
+            _ => false,
+        });
+        let index = existing.unwrap_or_else(|| {
+            hoists.push(Hoist::Expr(std::mem::take(value)));
+            hoists.len() - 1
+        });
+        *value = hoisted_ref(index);
+    }
+}
+
+fn is_same_const(a: &Js, b: &Js) -> bool {
+    match (a, b) {
+        (Js::Src(a), Js::Src(b)) => a == b,
+        (Js::StrLit(a), Js::StrLit(b)) => a == b,
+        (Js::Num(a), Js::Num(b)) => a == b,
+        (Js::Symbol(a), Js::Symbol(b)) => a == b,
+        // only hoistable expressions reach here, same source means same value
+        (Js::Simple(a, _), Js::Simple(b, _)) => a == b,
+        (Js::Array(a), Js::Array(b)) => is_same_list(a, b),
+        (Js::Call(h1, a), Js::Call(h2, b)) => h1 == h2 && is_same_list(a, b),
+        (Js::Props(a), Js::Props(b)) => {
+            let same_prop = |((k1, v1), (k2, v2)): (&(Js, Js), &(Js, Js))| {
+                is_same_const(k1, k2) && is_same_const(v1, v2)
+            };
+            a.len() == b.len() && a.iter().zip(b).all(same_prop)
+        }
+        _ => false,
+    }
+}
+
+fn is_same_list(a: &[Js], b: &[Js]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_same_const(a, b))
+}
+
+/// Structural equality of static subtrees, ignoring template locations.
+fn is_same_node(a: &BaseIR, b: &BaseIR) -> bool {
+    let same_children = |a: &[BaseIR], b: &[BaseIR]| {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| is_same_node(a, b))
+    };
+    match (a, b) {
+        (IR::VNodeCall(a), IR::VNodeCall(b)) => {
+            let same_props = match (&a.props, &b.props) {
+                (Some(a), Some(b)) => is_same_const(a, b),
+                (None, None) => true,
+                _ => false,
+            };
+            a.patch_flag == b.patch_flag
+                && is_same_const(&a.tag, &b.tag)
+                && same_props
+                && same_children(&a.children, &b.children)
+        }
+        (IR::TextCall(a), IR::TextCall(b)) => {
+            a.fast_path == b.fast_path
+                && a.split_from_prev == b.split_from_prev
+                && is_same_list(&a.texts, &b.texts)
+        }
+        (IR::CommentCall(a), IR::CommentCall(b)) => a == b,
+        (IR::Hoisted(a), IR::Hoisted(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Hash consistent with [is_same_node]: equal subtrees hash the same.
+fn hash_node<H: Hasher>(ir: &BaseIR, h: &mut H) {
+    mem::discriminant(ir).hash(h);
+    match ir {
+        IR::VNodeCall(v) => {
+            v.patch_flag.bits().hash(h);
+            hash_const(&v.tag, h);
+            if let Some(props) = &v.props {
+                hash_const(props, h);
+            }
+            v.children.len().hash(h);
+            v.children.iter().for_each(|c| hash_node(c, h));
+        }
+        IR::TextCall(t) => {
+            (t.fast_path, t.split_from_prev).hash(h);
+            t.texts.iter().for_each(|e| hash_const(e, h));
+        }
+        IR::CommentCall(c) => c.hash(h),
+        IR::Hoisted(i) => i.hash(h),
+        _ => {}
+    }
+}
+
+/// Hash consistent with [is_same_const].
+fn hash_const<H: Hasher>(exp: &Js, h: &mut H) {
+    mem::discriminant(exp).hash(h);
+    match exp {
+        Js::Src(s) => s.hash(h),
+        Js::StrLit(v) | Js::Simple(v, _) => v.hash(h),
+        Js::Num(n) => n.hash(h),
+        Js::Symbol(rh) => rh.hash(h),
+        Js::Array(a) | Js::Call(_, a) => a.iter().for_each(|e| hash_const(e, h)),
+        Js::Props(ps) => {
+            for (k, v) in ps {
+                hash_const(k, h);
+                hash_const(v, h);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Marks the whole template as static if every root node can be hoisted.
+/// It must run after expression processing and patch flag marking,
+/// which is always true since it only inspects the tree in exit_root.
+pub struct StaticRootMarker;
+
+impl<'a> CorePass<BaseInfo<'a>> for StaticRootMarker {
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+        r.top_scope.is_static = r.body.iter().all(is_static_ir);
+    }
+}
+
+fn is_hoistable(exp: &Js) -> bool {
+    exp.static_level() >= StaticLevel::CanHoist
+}
+
+/// Whether the node renders the same vnode tree on every render.
+pub fn is_static_ir(ir: &BaseIR) -> bool {
+    static_check(ir).is_ok()
+}
+
+/// Checks [is_static_ir], failing with the first reason the node is not static.
+pub fn static_check(ir: &BaseIR) -> Result<(), BailReason> {
+    match ir {
+        IR::TextCall(t) if t.texts.iter().all(is_hoistable) => Ok(()),
+        IR::CommentCall(_) | IR::Hoisted(_) => Ok(()),
+        IR::VNodeCall(v) => check_vnode(v),
+        // structural directives, slots and cache depend on render time state
+        _ => Err(BailReason::Dynamic),
+    }
+}
+
+fn check_vnode(v: &BaseVNode) -> Result<(), BailReason> {
+    // components have their own states, blocks need tracking
+    if v.is_component {
+        return Err(BailReason::Component);
+    }
+    if v.is_block {
+        return Err(BailReason::Block);
+    }
+    if let Some(dir) = v.directives.first() {
+        return Err(BailReason::RuntimeDirective(dir.location.clone()));
+    }
+    if let Some(prop) = v.dynamic_props.iter().map(|p| p.raw).min() {
+        return Err(BailReason::DynamicProp(prop.to_string()));
+    }
+    if !v.patch_flag.is_empty() {
+        return Err(BailReason::PatchFlag(v.patch_flag));
+    }
+    if !is_hoistable(&v.tag) {
+        return Err(BailReason::DynamicTag);
+    }
+    if !v.props.as_ref().map_or(true, is_hoistable) {
+        return Err(BailReason::DynamicProps);
+    }
+    if !v.children.iter().all(is_static_ir) {
+        return Err(BailReason::DynamicChild);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::base_convert;
+    use crate::converter::test::handler_convert;
+    use super::super::{BaseTransformer, Transformer};
+    use super::*;
+    use crate::cast;
+
+    fn hoist(s: &str) -> BaseRoot {
+        hoist_ir(base_convert(s))
+    }
+
+    fn hoist_ir(ir: BaseRoot) -> BaseRoot {
+        hoist_explained(ir, false)
+    }
+
+    fn hoist_explained(mut ir: BaseRoot, explain: bool) -> BaseRoot {
+        use super::super::pass::SharedInfoPasses;
+        use std::marker::PhantomData;
+        let pass = SharedInfoPasses {
+            passes: HoistStatic::new(false, true, explain),
+            shared_info: Scope::default(),
+            pd: PhantomData,
+        };
+        BaseTransformer::transform(&mut ir, pass);
+        ir
+    }
+
+    fn is_static(s: &str) -> bool {
+        let mut ir = base_convert(s);
+        BaseTransformer::transform(&mut ir, StaticRootMarker);
+        ir.top_scope.is_static
+    }
+
+    #[test]
+    fn test_static_root() {
+        assert!(is_static(
+            "<p class='a'>hello</p><!--c--><div><span>world</span></div>"
+        ));
+        assert!(is_static("hello world"));
+    }
+
+    #[test]
+    fn test_non_static_root() {
+        assert!(!is_static("<p>hello</p><p>{{world}}</p>"));
+        assert!(!is_static("<p :class='a'/>"));
+        assert!(!is_static("<comp/>"));
+        assert!(!is_static("<p v-if='a'/>"));
+        assert!(!is_static("<p v-for='a in b'/>"));
+    }
+
+    #[test]
+    fn test_hoist_static_node() {
+        let ir = hoist("<div><p>a</p>{{b}}</div>");
+        assert_eq!(ir.top_scope.hoists.len(), 1);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert!(matches!(div.children[0], IR::Hoisted(0)));
+        let p = cast!(&ir.top_scope.hoists[0], Hoist::Node);
+        let p = cast!(p, IR::VNodeCall);
+        assert_eq!(p.patch_flag, PatchFlag::HOISTED);
+    }
+
+    #[test]
+    fn test_hoist_static_root() {
+        let ir = hoist("<p>a</p><p>b</p>");
+        assert_eq!(ir.top_scope.hoists.len(), 1);
+        assert!(matches!(ir.body.as_slice(), [IR::Hoisted(0)]));
+        let ir = hoist("<p>{{a}}</p>");
+        assert!(ir.top_scope.hoists.is_empty());
+    }
+
+    #[test]
+    fn test_hoist_dedupe() {
+        let ir = hoist("<div><p>a</p>{{b}}<p>a</p><p>c</p><p>a</p></div>");
+        assert_eq!(ir.top_scope.hoists.len(), 2);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        let refs: Vec<_> = div
+            .children
+            .iter()
+            .filter_map(|c| match c {
+                IR::Hoisted(i) => Some(*i),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(refs, vec![0, 0, 1, 0]);
+    }
+
+    #[test]
+    fn test_hoist_noop_handler() {
+        // dynamic id keeps <a> from being hoisted as a whole
+        let ir = hoist_ir(handler_convert(
+            "<form @submit.prevent><a @click.stop :id='x'/></form>",
+        ));
+        // modifier-only handlers share the same noop function
+        assert_eq!(ir.top_scope.hoists.len(), 1);
+        let noop = cast!(&ir.top_scope.hoists[0], Hoist::Expr);
+        assert!(is_noop_handler(noop));
+        let form = cast!(&ir.body[0], IR::VNodeCall);
+        let props = cast!(form.props.as_ref().unwrap(), Js::Props);
+        assert!(matches!(&props[0].1, Js::Compound(v) if matches!(v[1], Js::Num(1))));
+        let ir = hoist_ir(handler_convert("<a @click.stop='a'/>"));
+        assert!(ir.top_scope.hoists.is_empty());
+    }
+
+    fn bailouts_of(s: &str, explain: bool) -> Vec<HoistBailout> {
+        hoist_explained(base_convert(s), explain)
+            .top_scope
+            .hoist_bailouts
+    }
+
+    #[test]
+    fn test_hoist_bailouts() {
+        let source = r#"<div>
+  <p :id="a">x</p>
+  <comp/>
+  <span v-focus>y</span>
+  <i>static</i>
+</div>"#;
+        let bailouts = bailouts_of(source, true);
+        let reasons: Vec<_> = bailouts
+            .iter()
+            .map(|b| (b.location.start.line, &b.reason))
+            .collect();
+        assert_eq!(reasons.len(), 3);
+        assert_eq!(reasons[0], (2, &BailReason::DynamicProp("id".into())));
+        assert_eq!(reasons[1], (3, &BailReason::Component));
+        let dir = cast!(reasons[2].1, BailReason::RuntimeDirective);
+        assert_eq!(reasons[2].0, 4);
+        assert_eq!(&source[dir.start.offset..dir.end.offset], "v-focus");
+        assert_eq!(bailouts[0].to_string(), "2:3: <p> has dynamic prop `id`");
+        assert!(bailouts_of(source, false).is_empty());
+    }
+
+    #[test]
+    fn test_static_check_reason() {
+        let ir = base_convert("<p><i v-if='a'/></p>");
+        assert_eq!(static_check(&ir.body[0]), Err(BailReason::DynamicChild));
+        let ir = base_convert("<p v-if='a'/>");
+        assert_eq!(static_check(&ir.body[0]), Err(BailReason::Dynamic));
+        let ir = base_convert("<p>a</p>");
+        assert_eq!(static_check(&ir.body[0]), Ok(()));
+    }
+}
@@ -1,9 +1,9 @@
 // mark patch flag and is_block for runtime
 // it should happen after process_expression
 use super::{BaseFor, BaseIf, BaseInfo, BaseText, BaseVNode, CorePass};
-use crate::converter::BaseIR;
+use crate::converter::{find_key_on_js, inject_prop, BaseIR};
 use crate::flags::{PatchFlag, RuntimeHelper as RH, StaticLevel};
-use crate::ir::{IRNode as IR, JsExpr as Js, Prop};
+use crate::ir::{IRNode as IR, JsExpr as Js};
 use crate::util::is_builtin_symbol;
 
 pub struct PatchFlagMarker;
@@ -20,12 +20,15 @@ impl<'a> CorePass<BaseInfo<'a>> for PatchFlagMarker {
             let props = match &mut *branch.child {
                 IR::VNodeCall(v) => &mut v.props,
                 IR::RenderSlotCall(r) => &mut r.slot_props,
-                IR::For(f) => return f.key = Some(Js::Num(branch.info)),
-                _ => return,
+                IR::For(f) => {
+                    f.key = Some(Js::Num(branch.info));
+                    continue;
+                }
+                _ => continue,
             };
             // already has key
             if props.as_ref().map_or(false, find_key_on_js) {
-                return;
+                continue;
             }
             // inject default key
             let key = Js::str_lit("key");
@@ -85,10 +88,11 @@ fn find_prop<'a, 'b>(t: &'b BaseIR<'a>) -> Option<&'b Js<'a>> {
         IR::RenderSlotCall(r) => r.slot_props.as_ref(),
         IR::CacheNode(c) => find_prop(&c.child),
         IR::AlterableSlot(..) => None, // why this compile??
+        // TODO: convert to CompileError
         IR::VSlotUse(_) => {
             panic!("v-slot with v-for must be alterable slots")
         }
-        IR::TextCall(_) | IR::For(_) | IR::If(_) | IR::CommentCall(_) => {
+        IR::TextCall(_) | IR::For(_) | IR::If(_) | IR::CommentCall(_) | IR::Hoisted(_) => {
             panic!("v-for child must be vnode/renderSlot/slotfn")
         }
     }
@@ -103,40 +107,41 @@ fn find_key(t: &BaseIR) -> bool {
     }
 }
 
-fn find_key_on_js(e: &Js) -> bool {
-    match e {
-        Js::Call(RH::MERGE_PROPS, args) => args.iter().any(find_key_on_js),
-        Js::Props(ps) => ps.iter().any(|(k, _)| match k {
-            Js::StrLit(s) => s.raw == "key",
-            _ => false,
-        }),
-        _ => false,
+#[cfg(test)]
+mod test {
+    use super::super::test::base_convert;
+    use super::super::{BaseTransformer, Transformer};
+    use super::*;
+    use crate::cast;
+
+    fn branch_key<'a, 'b>(ir: &'b BaseIR<'a>) -> &'b Js<'a> {
+        let vn = cast!(ir, IR::VNodeCall);
+        let props = cast!(vn.props.as_ref().unwrap(), Js::Props);
+        let (_, val) = props
+            .iter()
+            .find(|(k, _)| matches!(k, Js::StrLit(s) if s.raw == "key"))
+            .expect("branch should have key");
+        val
     }
-}
 
-// 1. mergeProps(...)
-// 2. toHandlers(...)
-fn inject_prop<'a>(props: &mut Js<'a>, key: Prop<'a>) {
-    debug_assert!(!find_key_on_js(props));
-    match props {
-        Js::Call(RH::MERGE_PROPS, args) => {
-            for arg in args.iter_mut() {
-                if let Js::Props(ps) = arg {
-                    ps.push(key);
-                    return;
-                }
-            }
-            args.push(Js::Props(vec![key]));
-        }
-        Js::Props(ps) => ps.push(key),
-        // should not inject props to any other expression
-        obj => {
-            return {
-                let mut temp = Js::Src("");
-                std::mem::swap(obj, &mut temp);
-                let p = Js::Props(vec![key]);
-                *obj = Js::Call(RH::MERGE_PROPS, vec![temp, p]);
-            }
+    #[test]
+    fn test_inject_branch_key() {
+        let mut ir = base_convert("<p v-if='a'/><p v-else-if='b'/><p v-else/>");
+        BaseTransformer::transform(&mut ir, PatchFlagMarker);
+        let i = cast!(&ir.body[0], IR::If);
+        assert_eq!(i.branches.len(), 3);
+        for (n, branch) in i.branches.iter().enumerate() {
+            assert!(matches!(branch_key(&branch.child), Js::Num(k) if *k == n));
         }
     }
+
+    #[test]
+    fn test_keep_user_key() {
+        let mut ir = base_convert("<p v-if='a' key='x'/><p v-else/>");
+        BaseTransformer::transform(&mut ir, PatchFlagMarker);
+        let i = cast!(&ir.body[0], IR::If);
+        assert!(matches!(branch_key(&i.branches[0].child), Js::StrLit(s) if s.raw == "x"));
+        // user key on one branch should not stop injection on the others
+        assert!(matches!(branch_key(&i.branches[1].child), Js::Num(1)));
+    }
 }
@@ -6,10 +6,10 @@ pub struct SlotFlagMarker;
 
 impl<'a> CorePassExt<BaseInfo<'a>, Scope<'a>> for SlotFlagMarker {
     fn exit_vnode(&mut self, v: &mut BaseVNode<'a>, scope: &mut Scope<'a>) {
-        if !v.is_component || v.children.is_empty() {
+        // KeepAlive and Teleport have raw children instead of slots
+        if !v.is_component || !matches!(v.children.as_slice(), [IRNode::VSlotUse(_)]) {
             return;
         }
-        debug_assert_eq!(v.children.len(), 1);
         let has_dynamic_slots = scope.has_ref_in_vnode(v);
         // has dynamic stable slot key
         let v_slot = match &mut v.children[0] {
@@ -56,6 +56,7 @@ fn has_forward_one(ir: &BaseIR) -> bool {
         IR::AlterableSlot(s) => has_forward_list(&s.body),
         IR::TextCall(_) => false,
         IR::CommentCall(_) => false,
+        IR::Hoisted(_) => false,
     }
 }
 
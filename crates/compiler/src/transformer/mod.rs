@@ -8,20 +8,28 @@ the original ones for the parity of features not implemented in Convert.
 * transformExpression
 * ~~vOnce (moved to convert)~~
 * ~~vMemo (moved to convert)~~
+* ~~vModel (moved to convert)~~:
+`converter::v_model` expands props/events and dom `converter::v_model` picks
+the runtime directive, see its tests and `test_inline_v_model` in sfc for setup refs.
 * trackScopes
 
 ## Original
 * collect_entities:
 track all helpers/components/directives used in AST.
 Vue track it by helper/helperString.
+* collect_refs:
+mark template refs in v-for with `ref_for: true`.
 * optimize_text:
 1. merge consecutive text call
 2. wrap text in createTextVNode
 * patch_flag:
 seems patch flag can be extracted out
+* warn_self_ref:
+opt-in warning for self referencing component without v-if/v-for/slot ancestor.
  */
 
 pub mod collect_entities;
+pub mod collect_refs;
 pub mod hoist_static;
 pub mod mark_patch_flag;
 pub mod mark_slot_flag;
@@ -29,6 +37,8 @@ pub mod normalize_props;
 pub mod optimize_text;
 pub mod pass;
 pub mod process_expression;
+pub mod v_once;
+pub mod warn_self_ref;
 
 use std::marker::PhantomData;
 
@@ -49,6 +59,9 @@ pub trait Transformer<P> {
 pub struct TransformOption {
     pub prefix_identifier: bool,
     pub is_dev: bool,
+    /// wrap v-on handlers in `_cache[n] || (_cache[n] = ...)`.
+    /// Only effective with prefix_identifier since it needs scope analysis.
+    pub cache_handlers: bool,
 }
 
 pub type BaseText<'a> = C::TextIR<BaseInfo<'a>>;
@@ -76,6 +89,8 @@ trait CoreTransformer<T: ConvertInfo, P: CorePass<T>>: Transformer<P> {
             I::VSlotUse(s) => Self::transform_v_slot(s, ps),
             I::CacheNode(c) => Self::transform_cache(c, ps),
             I::AlterableSlot(a) => Self::transform_slot_fn(a, ps),
+            // hoisted nodes are already transformed
+            I::Hoisted(_) => {}
         }
     }
     fn transform_children(children: &mut Vec<IRNode<T>>, ps: &mut P) {
@@ -287,6 +302,7 @@ mod test {
             passes,
             shared_info: Scope {
                 identifiers: FxHashMap::default(),
+                source: "",
             },
             pd: PhantomData,
         }
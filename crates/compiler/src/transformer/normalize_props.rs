@@ -59,8 +59,8 @@ fn pre_normalize_prop<'a>(mut props: Vec<(Js<'a>, Js<'a>)>) -> Js<'a> {
         }
     }
     if let Some(stl) = style_val {
-        // Props is parsed from literal style string
-        if !matches!(stl, Js::Props(..)) {
+        // Props is parsed from literal style string, unparsed literal is kept as is
+        if !matches!(stl, Js::Props(..) | Js::StrLit(..)) {
             let val = mem::take(stl);
             *stl = Js::Call(RH::NORMALIZE_STYLE, vec![val]);
         }
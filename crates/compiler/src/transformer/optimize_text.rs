@@ -31,6 +31,11 @@ impl<'a> CorePass<BaseInfo<'a>> for TextOptimizer {
     }
 }
 
+/// Each text call becomes one DOM text node that hydration matches against
+/// SSR output, so merging must follow upstream exactly:
+/// 1. a run of adjacent texts and interpolations merges into one call
+/// 2. any other node, e.g. element or comment, ends the run
+/// 3. a node removed from output, e.g. comment in production, also ends the run
 fn merge_consecutive_calls(cs: &mut Vec<BaseIR>) {
     let mut i = 0;
     while i < cs.len() {
@@ -42,8 +47,9 @@ fn merge_consecutive_calls(cs: &mut Vec<BaseIR>) {
         let dest = must_text(&mut left[i]);
         let mut j = 0;
         while j < right.len() {
-            if !matches!(&right[j], IR::TextCall(_)) {
-                break;
+            match &right[j] {
+                IR::TextCall(t) if !t.split_from_prev => {}
+                _ => break,
             }
             let src = must_text(&mut right[j]);
             dest.extend(src.drain(..));
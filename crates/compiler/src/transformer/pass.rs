@@ -95,6 +95,53 @@ where
     // }
 }
 
+macro_rules! boxed_pass {
+    ($method: ident, $ty: ident) => {
+        #[inline]
+        fn $method(&mut self, r: &mut C::$ty<T>) {
+            (**self).$method(r);
+        }
+    };
+}
+
+impl<T, P> CorePass<T> for Box<P>
+where
+    T: ConvertInfo,
+    P: CorePass<T> + ?Sized,
+{
+    impl_enter!(boxed_pass);
+    impl_exit!(boxed_pass);
+}
+
+macro_rules! vec_enter {
+    ($method: ident, $ty: ident) => {
+        fn $method(&mut self, r: &mut C::$ty<T>) {
+            for pass in self.iter_mut() {
+                pass.$method(r);
+            }
+        }
+    };
+}
+macro_rules! vec_exit {
+    ($method: ident, $ty: ident) => {
+        fn $method(&mut self, r: &mut C::$ty<T>) {
+            for pass in self.iter_mut().rev() {
+                pass.$method(r);
+            }
+        }
+    };
+}
+
+/// Passes only known at runtime, ordered like `chain!`.
+impl<T, P> CorePass<T> for Vec<P>
+where
+    T: ConvertInfo,
+    P: CorePass<T>,
+{
+    impl_enter!(vec_enter);
+    impl_exit!(vec_exit);
+}
+
 /// Chains multiple transform pass.
 #[macro_export]
 macro_rules! chain {
@@ -121,10 +168,13 @@ macro_rules! chain {
     }};
 }
 
-type Identifiers<'a> = FxHashMap<Name<'a>, usize>;
+/// count of each identifier in scope, nested v-for/v-slot can bind one name twice
+type Identifiers<'a> = FxHashMap<Name<'a>, u32>;
 #[derive(Default)]
 pub struct Scope<'a> {
     pub identifiers: Identifiers<'a>,
+    /// template source to locate errors in expressions
+    pub source: &'a str,
 }
 
 /// Check if an IR contains expressions that reference current context scope ids
@@ -141,7 +191,12 @@ impl<'a> Scope<'a> {
         *self.identifiers.entry(id).or_default() += 1;
     }
     pub fn remove_identifier(&mut self, id: Name<'a>) {
-        *self.identifiers.entry(id).or_default() -= 1;
+        if let Some(count) = self.identifiers.get_mut(id) {
+            *count -= 1;
+            if *count == 0 {
+                self.identifiers.remove(id);
+            }
+        }
     }
     pub fn has_ref_in_vnode(&self, node: &mut BaseVNode<'a>) -> bool {
         if self.identifiers.is_empty() {
@@ -2,13 +2,15 @@
 // currently only v-for and v-slot
 // 2. prefix expression
 use super::collect_entities::is_hoisted_asset;
-use super::{BaseInfo, CorePassExt, Scope};
+use super::{BaseInfo, BaseRoot, BaseVNode, CorePassExt, Scope};
 use crate::converter::v_on::get_handler_type;
 use crate::error::{CompilationError, CompilationErrorKind as ErrorKind, RcErrHandle};
 use crate::flags::{RuntimeHelper as RH, StaticLevel};
 use crate::ir::JsExpr as Js;
-use crate::util::{is_global_allow_listed, is_simple_identifier, rslint, VStr};
-use crate::{cast, BindingTypes, SFCInfo, SourceLocation};
+use crate::util::rslint::{self, FreeVar, VarWrite};
+use crate::util::{get_source_location, is_global_allow_listed, is_simple_identifier, VStr};
+use crate::{BindingTypes, SFCInfo};
+use std::ops::Range;
 
 pub struct ExpressionProcessor<'a, 'b> {
     pub prefix_identifier: bool,
@@ -17,8 +19,11 @@ pub struct ExpressionProcessor<'a, 'b> {
 }
 
 impl<'a, 'b> CorePassExt<BaseInfo<'a>, Scope<'a>> for ExpressionProcessor<'a, 'b> {
+    fn enter_root(&mut self, r: &mut BaseRoot<'a>, shared: &mut Scope<'a>) {
+        shared.source = r.top_scope.source;
+    }
     fn enter_fn_param(&mut self, p: &mut Js<'a>, shared: &mut Scope<'a>) {
-        self.process_fn_param(p);
+        self.process_fn_param(p, shared);
         match p {
             Js::Param(id) => shared.add_identifier(id),
             Js::Compound(ids) => {
@@ -26,7 +31,8 @@ impl<'a, 'b> CorePassExt<BaseInfo<'a>, Scope<'a>> for ExpressionProcessor<'a, 'b
                     shared.add_identifier(id);
                 }
             }
-            _ => panic!("only Js::Param is legal"),
+            // only Js::Param is legal, report instead of aborting compilation
+            _ => self.report_wrong_identifier("", shared),
         }
     }
     fn exit_fn_param(&mut self, p: &mut Js<'a>, shared: &mut Scope<'a>) {
@@ -37,7 +43,8 @@ impl<'a, 'b> CorePassExt<BaseInfo<'a>, Scope<'a>> for ExpressionProcessor<'a, 'b
                     shared.remove_identifier(id);
                 }
             }
-            _ => panic!("only Js::Param is legal"),
+            // already reported on enter
+            _ => {}
         };
     }
     // only transform expression after its' sub-expression is transformed
@@ -45,15 +52,27 @@ impl<'a, 'b> CorePassExt<BaseInfo<'a>, Scope<'a>> for ExpressionProcessor<'a, 'b
     fn exit_js_expr(&mut self, e: &mut Js<'a>, shared: &mut Scope<'a>) {
         self.process_expression(e, shared);
     }
+    // props are already processed on exit, ref binding won't become `r.value`
+    fn exit_vnode(&mut self, vn: &mut BaseVNode<'a>, _: &mut Scope<'a>) {
+        if !self.sfc_info.inline {
+            return;
+        }
+        if let Some(props) = vn.props.as_mut() {
+            self.process_inline_ref(props);
+        }
+    }
 }
 
 impl<'a, 'b> ExpressionProcessor<'a, 'b> {
     // parse expr as function params:
-    fn process_fn_param(&self, p: &mut Js) {
+    fn process_fn_param(&self, p: &mut Js<'a>, scope: &Scope<'a>) {
         if !self.prefix_identifier {
             return;
         }
-        let raw = *cast!(p, Js::Param);
+        let raw = match p {
+            Js::Param(raw) => *raw,
+            _ => return,
+        };
         if is_simple_identifier(VStr::raw(raw)) {
             return;
         }
@@ -62,10 +81,14 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
         let broken_atoms = if let Some(atoms) = self.break_down_fn_params(raw) {
             atoms
         } else {
-            // TODO: add identifier location
-            self.report_wrong_identifier(SourceLocation::default());
+            self.report_wrong_identifier(raw, scope);
             return;
         };
+        // empty pattern like v-slot="{}" binds nothing
+        if broken_atoms.is_empty() {
+            *p = Js::Compound(vec![Js::Src(raw)]);
+            return;
+        }
         // 3. reunite these 1 and 2 to a compound expression
         *p = reunite_atoms(raw, broken_atoms, |atom| {
             let is_param = atom.property;
@@ -77,6 +100,36 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
             }
         })
     }
+    /// Inline render has no setupState to resolve string ref against.
+    /// `ref="r"` passes the binding itself: `ref_key: "r", ref: r`.
+    fn process_inline_ref(&self, props: &mut Js<'a>) {
+        let props = match props {
+            Js::Props(ps) => ps,
+            // v-bind object merged with other props
+            Js::Call(_, args) => {
+                for arg in args {
+                    self.process_inline_ref(arg);
+                }
+                return;
+            }
+            _ => return,
+        };
+        let found = props.iter().enumerate().find_map(|(i, prop)| match prop {
+            (Js::StrLit(k), Js::StrLit(v)) if k.raw == "ref" => Some((i, *v)),
+            _ => None,
+        });
+        let (i, val) = match found {
+            Some(found) => found,
+            None => return,
+        };
+        use BindingTypes as BT;
+        match self.sfc_info.binding_metadata.get(val.raw) {
+            Some(BT::SetupLet | BT::SetupRef | BT::SetupMaybeRef) => {}
+            _ => return,
+        }
+        props[i].1 = Js::Simple(VStr::raw(val.raw), StaticLevel::NotStatic);
+        props.insert(i, (Js::str_lit("ref_key"), Js::StrLit(val)));
+    }
     fn process_expression(&self, e: &mut Js<'a>, scope: &mut Scope) {
         if !self.prefix_identifier {
             return;
@@ -108,7 +161,12 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
                 ty: ty.clone(),
                 cache: false,
             },
-            _ => panic!("impossible"),
+            // maybe-ref setup binding, e.g. `unref(handler)`
+            call => Js::FuncCompound {
+                body: vec![call],
+                ty: ty.clone(),
+                cache: false,
+            },
         };
         if matches!(ty, InlineStmt) {
             scope.remove_identifier("$event");
@@ -128,7 +186,13 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
             Js::Simple(v, level) => (v, level),
             _ => panic!("impossible"),
         };
-        if !is_simple_identifier(*v) {
+        // v-model handler `(v) = $event` still assigns to a simple identifier
+        let id = if VStr::is_event_assign(v) {
+            VStr::raw(v.raw)
+        } else {
+            *v
+        };
+        if !is_simple_identifier(id) {
             return false;
         }
         let raw_exp = v.raw;
@@ -160,29 +224,35 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
             _ => panic!("impossible"),
         };
         let raw = v.raw;
+        let is_event_assign = VStr::is_event_assign(v);
         let broken = self.break_down_complex_expression(raw, scope);
-        let (broken_atoms, local_ref) = if let Some(pair) = broken {
-            pair
+        let broken_atoms = if let Some(atoms) = broken {
+            atoms
         } else {
-            // TODO: add identifier location
-            self.report_wrong_identifier(SourceLocation::default());
+            // raw source is emitted as is, like vue-next
+            self.report_wrong_identifier(raw, scope);
             return;
         };
-        // no prefixed identifier found
+        // no identifier found
         if broken_atoms.is_empty() {
             // if expr has no template var nor prefixed var, it can be hoisted as static
             // NOTE: func call and member access must be bailed for potential side-effect
             let side_effect = raw.contains('(') || raw.contains('.');
-            *level = if !local_ref && !side_effect {
+            *level = if !side_effect {
                 StaticLevel::CanStringify
             } else {
                 StaticLevel::NotStatic
             };
             return;
         }
-        *e = reunite_atoms(raw, broken_atoms, |atom| {
+        let reunited = reunite_atoms(raw, broken_atoms, |atom| {
             let prop = atom.property;
-            let id_str = VStr::raw(&raw[atom.range]);
+            let id_str = VStr::raw(prop.name);
+            // scope reference stays as is, but is kept as an atom
+            // so that ref analysis like handler caching can find it
+            if prop.is_local {
+                return Js::Simple(id_str, StaticLevel::NotStatic);
+            }
             let rewritten = self.rewrite_identifier(id_str, StaticLevel::NotStatic, prop.ctx_type);
             if prop.is_obj_shorthand {
                 Js::Compound(vec![Js::StrLit(id_str), Js::Src(": "), rewritten])
@@ -190,12 +260,21 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
                 rewritten
             }
         });
+        // rewritten atoms drop the ops of the whole expression
+        *e = if is_event_assign {
+            Js::Compound(vec![Js::Src("("), reunited, Js::Src(") = $event")])
+        } else {
+            reunited
+        };
     }
     fn rewrite_identifier(&self, raw: VStr<'a>, level: StaticLevel, ctx: CtxType<'a>) -> Js<'a> {
         let binding = self.sfc_info.binding_metadata.get(&raw.raw);
         if let Some(bind) = binding {
             if self.sfc_info.inline {
                 rewrite_inline_identifier(raw, level, bind, ctx)
+            } else if VStr::is_event_assign(&raw) {
+                let prop = bind.get_js_prop(VStr::raw(raw.raw), level);
+                Js::Compound(vec![Js::Src("("), prop, Js::Src(") = $event")])
             } else {
                 bind.get_js_prop(raw, level)
             }
@@ -204,8 +283,11 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
             Js::simple(*raw.clone().prefix_ctx())
         }
     }
-    fn report_wrong_identifier(&self, loc: SourceLocation) {
-        let error = CompilationError::new(ErrorKind::InvalidExpression).with_location(loc);
+    fn report_wrong_identifier(&self, raw: &str, scope: &Scope) {
+        let loc = get_source_location(scope.source, raw).unwrap_or_default();
+        let error = CompilationError::new(ErrorKind::InvalidExpression)
+            .with_location(loc)
+            .with_context(raw);
         self.err_handle.on_error(error);
     }
 
@@ -213,11 +295,10 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
         &self,
         raw: &'a str,
         scope: &Scope,
-    ) -> Option<(FreeVarAtoms<'a>, bool)> {
+    ) -> Option<FreeVarAtoms<'a>> {
         let expr = rslint::parse_js_expr(raw)?;
         let inline = self.sfc_info.inline;
         let mut atoms = vec![];
-        let mut has_local_ref = false;
         rslint::walk_free_variables(expr, |fv| {
             let id_text = fv.text();
             // skip global variable prefixing
@@ -225,22 +306,69 @@ impl<'a, 'b> ExpressionProcessor<'a, 'b> {
                 return;
             }
             let range = fv.range();
-            // skip id defined in the template scope
-            if scope.has_identifier(&raw[range.clone()]) {
-                has_local_ref = true;
+            let name = &raw[range.clone()];
+            // id defined in the template scope is not prefixed
+            if scope.has_identifier(name) {
+                atoms.push(Atom {
+                    range,
+                    property: FreeVarProp {
+                        name,
+                        ctx_type: CtxType::NoWrite,
+                        is_obj_shorthand: false,
+                        is_local: true,
+                    },
+                });
                 return;
             }
-            let ctx_type = if inline { todo!() } else { CtxType::NoWrite };
+            let (range, ctx_type) = if inline {
+                self.get_inline_ctx_type(&fv, raw, range, scope)
+            } else {
+                (range, CtxType::NoWrite)
+            };
             atoms.push(Atom {
                 range,
                 property: FreeVarProp {
+                    name,
                     ctx_type,
                     is_obj_shorthand: fv.is_shorthand(),
+                    is_local: false,
                 },
             })
         });
         atoms.sort_by_key(|r| r.range.start);
-        Some((atoms, has_local_ref))
+        Some(atoms)
+    }
+
+    /// Inline render accesses setup bindings directly, so writing to a binding
+    /// needs to know how it is written. Returns the range to rewrite with ctx type.
+    fn get_inline_ctx_type(
+        &self,
+        fv: &FreeVar,
+        raw: &'a str,
+        range: Range<usize>,
+        scope: &Scope,
+    ) -> (Range<usize>, CtxType<'a>) {
+        match fv.write_kind() {
+            Some(VarWrite::Assign(op, rhs)) => {
+                let mut rhs = Js::simple(VStr::raw(&raw[rhs]));
+                self.process_simple_expr(&mut rhs, scope);
+                let assign = Js::Compound(vec![Js::Src(&raw[op]), rhs]);
+                (range, CtxType::Assign(assign))
+            }
+            Some(VarWrite::Destructure) => (range, CtxType::Destructure),
+            Some(VarWrite::Update(is_pre, op, whole)) => {
+                // setup let rewrites the whole update expression since
+                // the operator is repeated in both ref and non-ref branches
+                let binding = self.sfc_info.binding_metadata.get(&raw[range.clone()]);
+                let range = if let Some(BindingTypes::SetupLet) = binding {
+                    whole
+                } else {
+                    range
+                };
+                (range, CtxType::Update(is_pre, Js::Src(op)))
+            }
+            None => (range, CtxType::NoWrite),
+        }
     }
 
     /// Atom's property records if it is param identifier
@@ -276,13 +404,17 @@ fn only_param_ids<'a, 'b>(ids: &'b [Js<'a>]) -> impl Iterator<Item = &'a str> +
 /// Atom is the atomic identifier text range in the expression.
 /// Property is the additional information for rewriting.
 struct Atom<T> {
-    range: std::ops::Range<usize>,
+    range: Range<usize>,
     property: T,
 }
 
 struct FreeVarProp<'a> {
+    /// identifier text, atom range can be larger for update expression
+    name: &'a str,
     is_obj_shorthand: bool,
     ctx_type: CtxType<'a>,
+    /// identifier declared in template scope, e.g. v-for alias
+    is_local: bool,
 }
 type FreeVarAtoms<'a> = Vec<Atom<FreeVarProp<'a>>>;
 
@@ -303,8 +435,6 @@ where
 {
     // expr without atoms have specific processing outside
     debug_assert!(!atoms.is_empty());
-    // the only one ident that spans the text should be handled in fast path
-    debug_assert!(atoms.len() > 1 || atoms[0].range.len() < raw.len());
     let mut inner = vec![];
     let mut last = 0;
     for atom in atoms {
@@ -331,11 +461,11 @@ fn rewrite_inline_identifier<'a>(
 ) -> Js<'a> {
     use BindingTypes as BT;
     debug_assert!(level == StaticLevel::NotStatic || bind == &BT::SetupConst);
-    let expr = move || Js::Simple(raw, level);
-    let dot_value = Js::Compound(vec![expr(), Js::Src(".value")]);
     if VStr::is_event_assign(&raw) {
-        todo!("handle event assign differently")
+        return rewrite_event_assign(raw, level, bind);
     }
+    let expr = move || Js::Simple(raw, level);
+    let dot_value = Js::Compound(vec![expr(), Js::Src(".value")]);
     match bind {
         BT::SetupConst => expr(),
         BT::SetupRef => dot_value,
@@ -352,23 +482,34 @@ fn rewrite_inline_identifier<'a>(
         }
         BT::SetupLet => rewrite_setup_let(ctx, expr, dot_value),
         BT::Props => Js::Compound(vec![Js::Src("__props."), expr()]),
-        BT::Data | BT::Options => Js::Compound(vec![Js::Src("_ctx."), expr()]),
+        BT::Data | BT::Options => Js::Simple(*raw.clone().prefix_ctx(), level),
     }
 }
 
+/// v-model handler `(raw) = $event` writes to the binding like `raw = $event`
+/// in template, e.g. `isRef(raw) ? raw.value = $event : raw = $event` for setup let.
+fn rewrite_event_assign<'a>(raw: VStr<'a>, level: StaticLevel, bind: &BindingTypes) -> Js<'a> {
+    // v-model value is a plain identifier, no other ops to keep
+    let id = VStr::raw(raw.raw);
+    let assign = Js::Src(" = $event");
+    let ctx = CtxType::Assign(assign.clone());
+    let lhs = rewrite_inline_identifier(id, level, bind, ctx);
+    Js::Compound(vec![lhs, assign])
+}
+
 fn rewrite_setup_let<'a, E>(ctx: CtxType<'a>, expr: E, dot_value: Js<'a>) -> Js<'a>
 where
     E: Fn() -> Js<'a>,
 {
     match ctx {
+        // the original ` = rhs` follows, completing the non-ref branch
         CtxType::Assign(assign) => Js::Compound(vec![
             Js::Call(RH::IS_REF, vec![expr()]),
             Js::Src("? "),
             dot_value,
-            assign.clone(),
+            assign,
             Js::Src(": "),
             expr(),
-            assign,
         ]),
         CtxType::Update(is_pre, op) => {
             let mut v = vec![Js::Call(RH::IS_REF, vec![expr()])];
@@ -403,7 +544,10 @@ mod test {
     };
     use super::*;
     use crate::cast;
+    use crate::converter::test::handler_convert;
     use crate::converter::BaseIR;
+    use crate::BindingMetadata;
+    use rustc_hash::FxHashMap;
     use crate::error::{NoopErrorHandler, RcErrHandle, VecErrorHandler};
     use crate::ir::IRNode;
     use std::rc::Rc;
@@ -537,6 +681,237 @@ mod test {
         assert_eq!(val.into_string(), "_ctx.c");
     }
 
+    fn slot_param(ir: BaseRoot) -> Js {
+        let vn = cast!(first_child(ir), IRNode::VNodeCall);
+        let slots = cast!(&vn.children[0], IRNode::VSlotUse);
+        slots.stable_slots[0].param.clone().unwrap()
+    }
+    fn param_ids<'a>(p: &Js<'a>) -> Vec<&'a str> {
+        let ids = cast!(p, Js::Compound);
+        only_param_ids(ids).collect()
+    }
+
+    #[test]
+    fn test_transform_nested_destruct() {
+        let ir = transform("<comp v-slot='{ user: { firstName }, list: [a, ...rest] }'>a</comp>");
+        let param = slot_param(ir);
+        assert_eq!(param_ids(&param), vec!["firstName", "a", "rest"]);
+    }
+
+    #[test]
+    fn test_transform_destruct_default() {
+        let ir = transform("<comp v-slot='{ x = 0, y = x + z }'>a</comp>");
+        let param = slot_param(ir);
+        assert_eq!(param_ids(&param), vec!["x", "y"]);
+        let parts = cast!(param, Js::Compound);
+        // default value references both the param and a free variable
+        let y_default = parts
+            .iter()
+            .find_map(|p| match p {
+                Js::Compound(c) => Some(c),
+                _ => None,
+            })
+            .unwrap();
+        // param reference is kept as an atom, but not prefixed
+        let x = cast!(y_default[0], Js::Simple);
+        let z = cast!(y_default[2], Js::Simple);
+        assert_eq!(x.into_string(), "x");
+        assert_eq!(z.into_string(), "_ctx.z");
+    }
+
+    #[test]
+    fn test_transform_v_for_array_destruct() {
+        let ir = transform("<p v-for='([key, value]) in entries' :k='key' :o='other'/>");
+        let v_for = cast!(first_child(ir), IRNode::For);
+        assert_eq!(param_ids(&v_for.parse_result.value), vec!["key", "value"]);
+        let p = cast!(*v_for.child, IRNode::VNodeCall);
+        let props = cast!(p.props.unwrap(), Js::Props);
+        let key = cast!(props[0].1, Js::Simple);
+        let other = cast!(props[1].1, Js::Simple);
+        assert_eq!(key.into_string(), "key");
+        assert_eq!(other.into_string(), "_ctx.other");
+    }
+
+    #[test]
+    fn test_transform_array_hole() {
+        let ir = transform("<p v-for='[, second, , ...others] in list'/>");
+        let v_for = cast!(first_child(ir), IRNode::For);
+        let value = &v_for.parse_result.value;
+        assert_eq!(param_ids(value), vec!["second", "others"]);
+        let parts = cast!(value, Js::Compound);
+        assert_eq!(cast!(parts[0], Js::Src), "[, ");
+    }
+
+    #[test]
+    fn test_slot_param_shadow() {
+        let ir = transform("<comp v-slot='{ item, index = 0 }'>{{ item + index + total }}</comp>");
+        let vn = cast!(first_child(ir), IRNode::VNodeCall);
+        let slots = cast!(&vn.children[0], IRNode::VSlotUse);
+        let slot = &slots.stable_slots[0];
+        assert_eq!(
+            param_ids(slot.param.as_ref().unwrap()),
+            vec!["item", "index"]
+        );
+        let text = cast!(&slot.body[0], IRNode::TextCall);
+        let text = match &text.texts[0] {
+            Js::Call(_, r) => &r[0],
+            _ => panic!("wrong interpolation"),
+        };
+        assert_eq!(stringify(text), " item + index + _ctx.total ");
+    }
+
+    fn interpolation<'a, 'b>(ir: &'b BaseIR<'a>) -> &'b Js<'a> {
+        let text = cast!(ir, IRNode::TextCall);
+        match &text.texts[0] {
+            Js::Call(_, r) => &r[0],
+            _ => panic!("wrong interpolation"),
+        }
+    }
+
+    #[test]
+    fn test_nested_v_for_shadow() {
+        let ir = transform(
+            "<div v-for='item in list'>\
+                <p v-for='item in item.children'>{{item}}</p>{{item}}\
+            </div>{{item}}",
+        );
+        let outer = cast!(&ir.body[0], IRNode::For);
+        let div = cast!(&*outer.child, IRNode::VNodeCall);
+        let inner = cast!(&div.children[0], IRNode::For);
+        assert_eq!(stringify(&inner.source), "item.children");
+        let p = cast!(&*inner.child, IRNode::VNodeCall);
+        assert_eq!(stringify(interpolation(&p.children[0])), "item");
+        // outer item is still in scope after inner loop
+        assert_eq!(stringify(interpolation(&div.children[1])), "item");
+        assert_eq!(stringify(interpolation(&ir.body[1])), "_ctx.item");
+    }
+
+    #[test]
+    fn test_transform_empty_pattern() {
+        let ir = transform("<comp v-slot='{}'>a</comp>");
+        let param = slot_param(ir);
+        assert!(param_ids(&param).is_empty());
+    }
+
+    fn transform_inline(s: &str) -> BaseRoot {
+        let mut ir = handler_convert(s);
+        let mut bindings = FxHashMap::default();
+        bindings.insert("count", BindingTypes::SetupLet);
+        bindings.insert("r", BindingTypes::SetupRef);
+        bindings.insert("m", BindingTypes::SetupMaybeRef);
+        let sfc_info = SFCInfo {
+            inline: true,
+            binding_metadata: BindingMetadata::new(bindings, true),
+            ..Default::default()
+        };
+        let exp = ExpressionProcessor {
+            prefix_identifier: true,
+            sfc_info: &sfc_info,
+            err_handle: Rc::new(NoopErrorHandler),
+        };
+        BaseTransformer::transform(&mut ir, transformer_ext(exp));
+        ir
+    }
+    fn stringify(e: &Js) -> String {
+        match e {
+            Js::Src(s) => s.to_string(),
+            Js::Simple(v, _) | Js::StrLit(v) => v.into_string(),
+            Js::Compound(v) | Js::FuncCompound { body: v, .. } => v.iter().map(stringify).collect(),
+            Js::Call(h, args) => {
+                let name = if *h == RH::IS_REF { "isRef" } else { "unref" };
+                let args: Vec<_> = args.iter().map(stringify).collect();
+                format!("{}({})", name, args.join(", "))
+            }
+            _ => panic!("unexpected expression in handler"),
+        }
+    }
+    fn inline_handler(s: &str) -> String {
+        let vn = cast!(first_child(transform_inline(s)), IRNode::VNodeCall);
+        let props = cast!(vn.props.unwrap(), Js::Props);
+        stringify(&props[0].1)
+    }
+
+    #[test]
+    fn test_inline_update() {
+        let s = inline_handler("<p v-on:click='count++'/>");
+        assert_eq!(s, "isRef(count)? count.value++: count++");
+        let s = inline_handler("<p @click='--count'/>");
+        assert_eq!(s, "isRef(count)? --count.value: --count");
+        // ref binding does not need runtime check
+        let s = inline_handler("<p @click='r++'/>");
+        assert_eq!(s, "r.value++");
+    }
+
+    #[test]
+    fn test_inline_assign() {
+        let s = inline_handler("<p @click='count = r + 1'/>");
+        assert_eq!(
+            s,
+            "isRef(count)? count.value = r.value + 1: count = r.value + 1"
+        );
+        let s = inline_handler("<p @click='r = count'/>");
+        assert_eq!(s, "r.value = unref(count)");
+    }
+
+    #[test]
+    fn test_inline_destructure() {
+        // let binding is assumed not to be a ref in destructuring
+        let s = inline_handler("<p @click='[r, count] = arr'/>");
+        assert_eq!(s, "[r.value, count] = _ctx.arr");
+        let s = inline_handler("<p @click='({r, a: count} = obj)'/>");
+        assert_eq!(s, "({r: r.value, a: count} = _ctx.obj)");
+        let s = inline_handler("<p @click='[count = r] = arr'/>");
+        assert_eq!(s, "[count = r.value] = _ctx.arr");
+    }
+
+    #[test]
+    fn test_inline_event_assign() {
+        let vn = cast!(
+            first_child(transform_inline("<comp v-model='r'/>")),
+            IRNode::VNodeCall
+        );
+        let props = cast!(vn.props.unwrap(), Js::Props);
+        assert_eq!(stringify(&props[1].1), "r.value = $event");
+        let vn = cast!(
+            first_child(transform_inline("<comp v-model='count'/>")),
+            IRNode::VNodeCall
+        );
+        let props = cast!(vn.props.unwrap(), Js::Props);
+        assert_eq!(
+            stringify(&props[1].1),
+            "isRef(count)? count.value = $event: count = $event"
+        );
+    }
+
+    #[test]
+    fn test_inline_maybe_ref_handler() {
+        let s = inline_handler("<p @click='m'/>");
+        assert_eq!(s, "unref(m)");
+    }
+
+    #[test]
+    fn test_inline_ref() {
+        let vn = cast!(
+            first_child(transform_inline("<p ref='r'/>")),
+            IRNode::VNodeCall
+        );
+        let props = cast!(vn.props.unwrap(), Js::Props);
+        assert_eq!(props.len(), 2);
+        assert_eq!(stringify(&props[0].0), "ref_key");
+        assert_eq!(stringify(&props[0].1), "r");
+        let (_, ref_val) = &props[1];
+        let r = cast!(ref_val, Js::Simple);
+        assert_eq!(r.into_string(), "r");
+        // ref not bound in setup is resolved by string key
+        let vn = cast!(
+            first_child(transform_inline("<p ref='a'/>")),
+            IRNode::VNodeCall
+        );
+        let props = cast!(vn.props.unwrap(), Js::Props);
+        assert_eq!(props.len(), 1);
+        assert!(matches!(props[0].1, Js::StrLit(_)));
+    }
+
     #[test]
     fn test_error_expression() {
         let error_handler = Rc::new(VecErrorHandler::default());
@@ -546,4 +921,23 @@ mod test {
         let kind = &errs[0].kind;
         assert!(matches!(kind, ErrorKind::InvalidExpression));
     }
+
+    #[test]
+    fn test_error_expression_location() {
+        let error_handler = Rc::new(VecErrorHandler::default());
+        let ir = transform_with_err("<p>\n{{ a + }}</p>", error_handler.clone());
+        let errs = error_handler.errors();
+        assert_eq!(errs.len(), 1);
+        assert_eq!(errs[0].context.as_deref(), Some(" a + "));
+        let start = &errs[0].location.start;
+        assert_eq!((start.offset, start.line, start.column), (6, 2, 3));
+        // raw expression is kept for output
+        let p = cast!(first_child(ir), IRNode::VNodeCall);
+        let text = cast!(&p.children[0], IRNode::TextCall);
+        let raw = text.texts.iter().find_map(|t| match t {
+            Js::Call(_, r) => Some(cast!(&r[0], Js::Simple).raw),
+            _ => None,
+        });
+        assert_eq!(raw, Some(" a + "));
+    }
 }
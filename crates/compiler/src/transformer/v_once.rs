@@ -0,0 +1,78 @@
+// v-once subtree without any binding renders the same vnode for every
+// component instance. Its cache is unwrapped so that HoistStatic lifts
+// the subtree to `_hoisted_x` once instead of caching it per instance.
+// Subtrees are checked on exit when their patch flags are final, so this
+// pass should be the last one in a chain to exit before HoistStatic.
+use super::{BaseInfo, BaseVNode, CorePass};
+use super::hoist_static::is_static_ir;
+use crate::converter::{BaseIR, BaseRoot};
+use crate::ir::{CacheKind, IRNode as IR};
+
+pub struct VOnceProcessor {
+    /// unwrapped subtree is re-created every render if not hoisted
+    pub hoist_static: bool,
+}
+
+impl<'a> CorePass<BaseInfo<'a>> for VOnceProcessor {
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+        if self.hoist_static {
+            unwrap_static_once(&mut r.body);
+        }
+    }
+    fn exit_vnode(&mut self, v: &mut BaseVNode<'a>) {
+        if self.hoist_static {
+            unwrap_static_once(&mut v.children);
+        }
+    }
+}
+
+fn unwrap_static_once(children: &mut [BaseIR]) {
+    for child in children.iter_mut() {
+        let cn = match child {
+            IR::CacheNode(cn) if matches!(cn.kind, CacheKind::Once) => cn,
+            _ => continue,
+        };
+        if !is_static_ir(&cn.child) {
+            continue;
+        }
+        let cached = std::mem::replace(&mut *cn.child, IR::Hoisted(0));
+        *child = cached;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::test::base_convert;
+    use super::super::{BaseTransformer, Transformer};
+    use super::*;
+    use crate::cast;
+
+    fn transform(s: &str, hoist_static: bool) -> BaseRoot {
+        let mut ir = base_convert(s);
+        BaseTransformer::transform(&mut ir, VOnceProcessor { hoist_static });
+        ir
+    }
+
+    #[test]
+    fn test_unwrap_static_once() {
+        let ir = transform("<div><p v-once class='a'>static</p></div>", true);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert!(matches!(div.children[0], IR::VNodeCall(_)));
+        let ir = transform("<p v-once>static</p><p/>", true);
+        assert!(matches!(ir.body[0], IR::VNodeCall(_)));
+    }
+
+    #[test]
+    fn test_keep_dynamic_once() {
+        let ir = transform("<div><p v-once>{{ a }}</p></div>", true);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert!(matches!(div.children[0], IR::CacheNode(_)));
+        let ir = transform("<div><comp v-once/></div>", true);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert!(matches!(div.children[0], IR::CacheNode(_)));
+        // without hoisting, cache is the only optimization
+        let ir = transform("<div><p v-once>static</p></div>", false);
+        let div = cast!(&ir.body[0], IR::VNodeCall);
+        assert!(matches!(div.children[0], IR::CacheNode(_)));
+    }
+}
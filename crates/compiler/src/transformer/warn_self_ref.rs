@@ -0,0 +1,125 @@
+use super::{BaseFor, BaseIf, BaseInfo, BaseRenderSlot, BaseSlotFn, BaseVNode, CorePass, Js};
+use crate::converter::RcErrHandle;
+use crate::error::{CompilationError, CompilationErrorKind as ErrorKind};
+use crate::util::VStr;
+
+/// Warns self referencing component rendered on every render path, e.g. Foo
+/// rendering `<Foo/>` outside of any v-if/v-for/slot, which never terminates.
+pub struct SelfRefWarner {
+    err_handle: RcErrHandle,
+    enabled: bool,
+    /// number of v-if/v-for/slot ancestors of the current node
+    guards: usize,
+}
+
+impl SelfRefWarner {
+    pub fn new(err_handle: RcErrHandle, enabled: bool) -> Self {
+        Self {
+            err_handle,
+            enabled,
+            guards: 0,
+        }
+    }
+}
+
+impl<'a> CorePass<BaseInfo<'a>> for SelfRefWarner {
+    fn enter_if(&mut self, _: &mut BaseIf<'a>) {
+        self.guards += 1;
+    }
+    fn exit_if(&mut self, _: &mut BaseIf<'a>) {
+        self.guards -= 1;
+    }
+    fn enter_for(&mut self, _: &mut BaseFor<'a>) {
+        self.guards += 1;
+    }
+    fn exit_for(&mut self, _: &mut BaseFor<'a>) {
+        self.guards -= 1;
+    }
+    // slot content is rendered only if the child component renders the slot
+    fn enter_slot_fn(&mut self, _: &mut BaseSlotFn<'a>) {
+        self.guards += 1;
+    }
+    fn exit_slot_fn(&mut self, _: &mut BaseSlotFn<'a>) {
+        self.guards -= 1;
+    }
+    // slot fallback is rendered only if parent passes no slot
+    fn enter_slot_outlet(&mut self, _: &mut BaseRenderSlot<'a>) {
+        self.guards += 1;
+    }
+    fn exit_slot_outlet(&mut self, _: &mut BaseRenderSlot<'a>) {
+        self.guards -= 1;
+    }
+    fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+        if !self.enabled || self.guards > 0 {
+            return;
+        }
+        let name = match &vn.tag {
+            Js::Simple(name, _) if VStr::is_self_suffixed(name) => name.raw,
+            _ => return,
+        };
+        let warning = CompilationError::new(ErrorKind::UnguardedSelfReference)
+            .with_location(vn.location.clone())
+            .with_context(name);
+        self.err_handle.on_warn(warning);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::{BaseTransformer, Transformer};
+    use super::*;
+    use crate::converter::{BaseConverter, Converter};
+    use crate::error::{ErrorHandler, VecErrorHandler};
+    use crate::parser::test::base_parse;
+    use crate::SFCInfo;
+    use std::rc::Rc;
+
+    fn collect_warnings(s: &str) -> Rc<VecErrorHandler> {
+        let eh = Rc::new(VecErrorHandler::default());
+        let info = SFCInfo {
+            self_name: "Foo".into(),
+            ..Default::default()
+        };
+        let bc = BaseConverter::new(eh.clone(), Default::default());
+        let mut ir = bc.convert_ir(base_parse(s), &info);
+        let pass = SelfRefWarner::new(eh.clone(), true);
+        BaseTransformer::transform(&mut ir, pass);
+        eh
+    }
+
+    fn count_warnings(s: &str) -> usize {
+        let eh = collect_warnings(s);
+        assert_eq!(eh.error_count(), 0);
+        let warnings = eh.warnings();
+        warnings.len()
+    }
+
+    #[test]
+    fn test_unguarded_self_reference() {
+        assert_eq!(count_warnings("<div><Foo/></div>"), 1);
+        assert_eq!(count_warnings("<Foo/><Foo/>"), 2);
+        assert_eq!(count_warnings("<div><Bar/></div>"), 0);
+    }
+
+    #[test]
+    fn test_self_reference_location() {
+        let eh = collect_warnings("<div>\n  <Foo/>\n</div>");
+        let warnings = eh.warnings();
+        let loc = &warnings[0].location;
+        assert_eq!((loc.start.line, loc.start.column), (2, 3));
+        assert_eq!((loc.end.line, loc.end.column), (2, 9));
+    }
+
+    #[test]
+    fn test_guarded_self_reference() {
+        assert_eq!(count_warnings("<div><Foo v-if='depth > 0'/></div>"), 0);
+        assert_eq!(count_warnings("<p v-if='a'/><div v-else><Foo/></div>"), 0);
+        assert_eq!(count_warnings("<Foo v-for='c in cs' :key='c'/>"), 0);
+    }
+
+    #[test]
+    fn test_self_reference_in_slot() {
+        assert_eq!(count_warnings("<Bar><Foo/></Bar>"), 0);
+        assert_eq!(count_warnings("<slot><Foo/></slot>"), 0);
+    }
+}
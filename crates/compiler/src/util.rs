@@ -4,6 +4,7 @@ use super::{
     ir::{JsExpr as Js, VNodeIR},
     parser::{Directive, DirectiveArg, ElemProp, Element},
     scanner::Attribute,
+    Position, SourceLocation,
 };
 use std::{
     borrow::{Borrow, BorrowMut},
@@ -23,12 +24,37 @@ macro_rules! cast {
     }};
 }
 
-mod decode_html;
+pub(crate) mod decode_html;
 mod json;
 mod named_chars;
 pub mod rslint;
 mod v_str;
-pub use v_str::VStr;
+pub use v_str::{VStr, CTX_IDENT};
+
+/// Location of `slice` if it is borrowed from `source`, e.g. raw text
+/// of an expression. Offset and column count chars as scanner does.
+pub fn get_source_location(source: &str, slice: &str) -> Option<SourceLocation> {
+    let base = source.as_ptr() as usize;
+    let ptr = slice.as_ptr() as usize;
+    if ptr < base || ptr + slice.len() > base + source.len() {
+        return None;
+    }
+    let start = advance_position(Position::default(), &source[..ptr - base]);
+    let end = advance_position(start.clone(), slice);
+    Some(SourceLocation { start, end })
+}
+
+fn advance_position(mut pos: Position, s: &str) -> Position {
+    let count = s.chars().count();
+    pos.offset += count;
+    if let Some(i) = s.rfind('\n') {
+        pos.line += s.matches('\n').count() as u32;
+        pos.column = s[i..].chars().count() as u32;
+    } else {
+        pos.column += count as u32;
+    }
+    pos
+}
 
 pub fn non_whitespace(c: char) -> bool {
     !c.is_ascii_whitespace()
@@ -52,7 +78,7 @@ pub fn is_core_component(tag: &str) -> bool {
 fn is_event_prop(prop: &str) -> bool {
     let bytes = prop.as_bytes();
     // equivalent to /^on[^a-z]/
-    bytes.len() > 2 && bytes.starts_with(b"on") && !bytes[3].is_ascii_lowercase()
+    bytes.len() > 2 && bytes.starts_with(b"on") && !bytes[2].is_ascii_lowercase()
 }
 
 pub fn is_mergeable_prop(prop: &str) -> bool {
@@ -469,6 +495,17 @@ mod test {
     use super::*;
     use crate::parser::test::mock_element;
 
+    #[test]
+    fn test_source_location() {
+        let source = "<p>\n  {{ é + }}</p>";
+        let loc = get_source_location(source, &source[9..13]).unwrap();
+        assert_eq!(loc.start.offset, 9);
+        assert_eq!((loc.start.line, loc.start.column), (2, 6));
+        assert_eq!(loc.end.offset, 12);
+        assert_eq!((loc.end.line, loc.end.column), (2, 9));
+        assert!(get_source_location(source, "é +").is_none());
+    }
+
     #[test]
     fn test_find_dir() {
         let e = mock_element("<p v-if=true/>");
@@ -12,16 +12,22 @@ pub fn decode_entities<W: Write>(s: &str, mut w: W, as_attr: bool) -> fmt::Resul
     while let Some(idx) = src.find('&') {
         let (decoded, next) = src.split_at(idx);
         w.write_str(decoded)?;
-        src = next;
-        if src.starts_with("&#") {
-            src = decode_numeric_ref(src, &mut w)?;
-        } else {
-            src = decode_named_ref(src, &mut w, as_attr)?;
-        }
+        src = decode_char_ref(next, &mut w, as_attr)?;
     }
     w.write_str(src)
 }
 
+/// Decode one character reference at the start of `s` and return the rest.
+/// Malformed reference is written as is.
+fn decode_char_ref<W: Write>(s: &str, w: W, as_attr: bool) -> DecodeResult {
+    debug_assert!(s.starts_with('&'));
+    if s.starts_with("&#") {
+        decode_numeric_ref(s, w)
+    } else {
+        decode_named_ref(s, w, as_attr)
+    }
+}
+
 fn decode_named_ref<W: Write>(s: &str, mut w: W, as_attr: bool) -> DecodeResult {
     debug_assert!(s.starts_with('&'));
     let mut src = &s[1..];
@@ -32,6 +38,7 @@ fn decode_named_ref<W: Write>(s: &str, mut w: W, as_attr: bool) -> DecodeResult
     let max_len = MAX_CR_NAME_LEN.min(src.len());
     let entry = (2..=max_len)
         .rev()
+        .filter(|&i| src.is_char_boundary(i))
         .map(|i| &src[..i])
         .find_map(|k| NAMED_CHAR_REF.get_entry(k));
     let (key, val) = match entry {
@@ -54,22 +61,19 @@ fn decode_named_ref<W: Write>(s: &str, mut w: W, as_attr: bool) -> DecodeResult
 }
 fn decode_numeric_ref<W: Write>(s: &str, mut w: W) -> DecodeResult {
     debug_assert!(s.starts_with("&#"));
-    let (num, next) = if let Some(src) = s.strip_prefix("&#x") {
-        // hex
-        let cnt = src.chars().take_while(|c| c.is_ascii_hexdigit()).count();
-        match u32::from_str_radix(&src[..cnt], 16) {
-            Ok(n) => (n, &src[cnt..]),
-            Err(_) => return Ok(src),
-        }
-    } else {
-        // num
-        let src = &s[2..];
-        let cnt = src.chars().take_while(|c| c.is_numeric()).count();
-        match src[..cnt].parse() {
-            Ok(n) => (n, &src[cnt..]),
-            Err(_) => return Ok(src),
-        }
-    };
+    let is_hex = s[2..].starts_with(|c: char| c == 'x' || c == 'X');
+    let (prefix, radix) = if is_hex { (&s[..3], 16) } else { (&s[..2], 10) };
+    let src = &s[prefix.len()..];
+    let cnt = src.chars().take_while(|c| c.is_digit(radix)).count();
+    // `&#` without digits is not a reference
+    if cnt == 0 {
+        w.write_str(prefix)?;
+        return Ok(src);
+    }
+    // overflowing number is outside of unicode range
+    let num = u32::from_str_radix(&src[..cnt], radix).unwrap_or(u32::MAX);
+    let next = &src[cnt..];
+    let next = next.strip_prefix(';').unwrap_or(next);
     let num = match num {
         0 => 0xfffd,
         n if n > 0x10ffff => 0xfffd,
@@ -81,10 +85,8 @@ fn decode_numeric_ref<W: Write>(s: &str, mut w: W) -> DecodeResult {
     };
     if let Some(c) = char::from_u32(num) {
         w.write_char(c)?;
-        Ok(next)
-    } else {
-        Ok(next)
     }
+    Ok(next)
 }
 
 // https://html.spec.whatwg.org/multipage/parsing.html#numeric-character-reference-end-state
@@ -122,3 +124,50 @@ const CCR_REPLACEMENTS: &[u32] = &[
     0x017e, // 0x9e
     0x0178, // 0x9f
 ];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn decode(s: &str) -> String {
+        let mut ret = String::new();
+        decode_entities(s, &mut ret, false).unwrap();
+        ret
+    }
+    fn decode_attr(s: &str) -> String {
+        let mut ret = String::new();
+        decode_entities(s, &mut ret, true).unwrap();
+        ret
+    }
+
+    #[test]
+    fn test_decode_text() {
+        assert_eq!(decode("a &amp; b &lt;p&gt;"), "a & b <p>");
+        assert_eq!(decode("&copy; 2021 &hearts;"), "© 2021 ♥");
+        assert_eq!(decode("&#39;&#x27;&#X27;"), "'''");
+        assert_eq!(
+            decode("&#128;&#0;&#xD800;&#x110000;"),
+            "€\u{fffd}\u{fffd}\u{fffd}"
+        );
+        assert_eq!(decode("&#99999999999;"), "\u{fffd}");
+        // legacy reference without semicolon
+        assert_eq!(decode("&copy=2"), "©=2");
+    }
+
+    #[test]
+    fn test_decode_attr() {
+        assert_eq!(decode_attr("?a=1&copy=2"), "?a=1&copy=2");
+        assert_eq!(decode_attr("?a=1&copyx"), "?a=1&copyx");
+        assert_eq!(decode_attr("&copy;=2"), "©=2");
+        assert_eq!(decode_attr("a &amp; b"), "a & b");
+    }
+
+    #[test]
+    fn test_decode_malformed() {
+        assert_eq!(decode("&notanentity;"), "¬anentity;");
+        assert_eq!(decode("&xyz;"), "&xyz;");
+        assert_eq!(decode("a & b &"), "a & b &");
+        assert_eq!(decode("&#;&#x;"), "&#;&#x;");
+        assert_eq!(decode("&中"), "&中");
+    }
+}
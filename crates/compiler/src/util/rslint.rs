@@ -72,6 +72,110 @@ impl FreeVar {
     pub fn range(&self) -> Range<usize> {
         self.syntax().trimmed_range().into()
     }
+    /// Returns how the variable is written if it is the target
+    /// of an assignment, update expression or destructuring assignment.
+    pub fn write_kind(&self) -> Option<VarWrite> {
+        if is_destructure_target(self.syntax()) {
+            return Some(VarWrite::Destructure);
+        }
+        let node = match self {
+            FreeVar::Ident(n) => n,
+            FreeVar::Shorthand(_) => return None,
+        };
+        // assignment target may be parsed as pattern
+        let target = match node.parent()? {
+            p if p.kind() == SyntaxKind::SINGLE_PATTERN => p,
+            _ => node.clone(),
+        };
+        let parent = target.parent()?;
+        match parent.kind() {
+            SyntaxKind::ASSIGN_EXPR => {
+                // only lhs is written, `a = b` does not write b
+                if parent.first_child()? != target {
+                    return None;
+                }
+                let rhs = parent.children().nth(1)?;
+                let lhs_end = Range::from(node.trimmed_range()).end;
+                let rhs = Range::from(rhs.trimmed_range());
+                Some(VarWrite::Assign(lhs_end..rhs.start, rhs))
+            }
+            SyntaxKind::UNARY_EXPR => {
+                let op = parent
+                    .children_with_tokens()
+                    .filter_map(|e| e.into_token())
+                    .find_map(|t| {
+                        let text: &str = t.text().as_ref();
+                        let op = match text {
+                            "++" => "++",
+                            "--" => "--",
+                            _ => return None,
+                        };
+                        Some((op, Range::from(t.text_range())))
+                    });
+                let (op, op_range) = op?;
+                let is_prefix = op_range.start < Range::from(node.trimmed_range()).start;
+                let whole = Range::from(parent.trimmed_range());
+                Some(VarWrite::Update(is_prefix, op, whole))
+            }
+            _ => None,
+        }
+    }
+}
+
+pub enum VarWrite {
+    /// `a op= b`: range of operator text between lhs and rhs, and range of rhs
+    Assign(Range<usize>, Range<usize>),
+    /// `++a` or `a--`: is prefix, operator and range of the whole expression
+    Update(bool, &'static str, Range<usize>),
+    /// `[a, {b}] = c`
+    Destructure,
+}
+
+/// containers an identifier can be nested in when destructured by assignment
+const DESTRUCTURE_KINDS: &[SyntaxKind] = &[
+    SyntaxKind::ARRAY_EXPR,
+    SyntaxKind::OBJECT_EXPR,
+    SyntaxKind::IDENT_PROP,
+    SyntaxKind::SPREAD_ELEMENT,
+    SyntaxKind::SPREAD_PROP,
+    SyntaxKind::OBJECT_PATTERN,
+    SyntaxKind::ARRAY_PATTERN,
+    SyntaxKind::REST_PATTERN,
+    SyntaxKind::SINGLE_PATTERN,
+];
+
+/// Whether the node is written by the assignment it is nested in,
+/// e.g. `[a, ...b] = c` or `({a: b} = c)`. Computed keys and defaults are read.
+fn is_destructure_target(node: &rl::SyntaxNode) -> bool {
+    use SyntaxKind as SK;
+    let mut child = node.clone();
+    let mut nested = false;
+    while let Some(parent) = child.parent() {
+        match parent.kind() {
+            SK::ASSIGN_EXPR => return nested && parent.first_child() == Some(child),
+            // only value is written in `{key: value}`
+            SK::LITERAL_PROP | SK::KEY_VALUE_PATTERN => {
+                if parent.last_child().as_ref() != Some(&child) {
+                    return false;
+                }
+            }
+            // only target is written in `[a = 1]`
+            SK::ASSIGN_PATTERN => {
+                if parent.first_child().as_ref() != Some(&child) {
+                    return false;
+                }
+            }
+            k if DESTRUCTURE_KINDS.contains(&k) => {}
+            _ => return false,
+        }
+        let k = parent.kind();
+        nested |= matches!(
+            k,
+            SK::ARRAY_EXPR | SK::OBJECT_EXPR | SK::ARRAY_PATTERN | SK::OBJECT_PATTERN
+        );
+        child = parent;
+    }
+    false
 }
 
 // just allocate if complex expressions are used
@@ -92,6 +196,9 @@ where
         if kind == SK::NAME_REF {
             self.emit_name_ref(node);
             0
+        } else if kind == SK::NAME {
+            self.emit_pattern_name(node);
+            0
         } else if kind == SK::IDENT_PROP {
             self.emit_object_shorthad(node);
             0
@@ -132,6 +239,38 @@ where
         }
         (self.func)(FreeVar::Shorthand(prop));
     }
+    /// names in assignment pattern are written, e.g. `[a, b] = c`.
+    /// names in declaration or parameter are bound instead.
+    fn emit_pattern_name(&mut self, name: &rl::SyntaxNode) {
+        use SyntaxKind as SK;
+        let pattern = match name.parent() {
+            Some(p) if p.kind() == SK::SINGLE_PATTERN => p,
+            _ => return,
+        };
+        let mut target = pattern.clone();
+        while let Some(p) = target.parent().filter(|p| PATTERNS.contains(&p.kind())) {
+            target = p;
+        }
+        let is_assign_target = target
+            .parent()
+            .filter(|p| p.kind() == SK::ASSIGN_EXPR)
+            .and_then(|p| p.first_child())
+            .map_or(false, |lhs| lhs == target);
+        if !is_assign_target || self.bound_vars.contains(&name.trimmed_text()) {
+            return;
+        }
+        // `{a} = b` or `{a = 1} = b` needs key when rewritten
+        let in_object =
+            |n: &rl::SyntaxNode| n.parent().map_or(false, |p| p.kind() == SK::OBJECT_PATTERN);
+        let parent = pattern.parent();
+        let is_shorthand = in_object(&pattern)
+            || parent.map_or(false, |p| p.kind() == SK::ASSIGN_PATTERN && in_object(&p));
+        if is_shorthand {
+            (self.func)(FreeVar::Shorthand(name.clone()));
+        } else {
+            (self.func)(FreeVar::Ident(name.clone()));
+        }
+    }
     #[inline(never)]
     fn track_block_var(&mut self, node: &ast::BlockStmt) -> usize {
         use ast::Decl;
@@ -404,6 +543,10 @@ mod test {
             ("{set test(a) {a; b}}", vec!["b"]),
             // keyword
             ("true, false, null, this", vec![]),
+            // destructuring assignment
+            ("[a, ...b] = c", vec!["a", "b", "c"]),
+            ("({a, b: c, [d]: e} = f)", vec!["a", "c", "d", "e", "f"]),
+            ("() => {let [a] = b; [a, c] = b}", vec!["b", "c", "b"]),
         ];
         for (src, expect) in cases {
             assert_eq!(walk_ident(src), expect);
@@ -455,4 +598,35 @@ mod test {
             assert_eq!(walk_param(src), expect);
         }
     }
+
+    fn write_kinds(s: &str) -> Vec<&'static str> {
+        let expr = parse_js_expr(s).unwrap();
+        let mut ret = vec![];
+        walk_free_variables(expr, |fv| {
+            ret.push(match fv.write_kind() {
+                Some(VarWrite::Assign(..)) => "assign",
+                Some(VarWrite::Update(..)) => "update",
+                Some(VarWrite::Destructure) => "destructure",
+                None => "read",
+            });
+        });
+        ret
+    }
+
+    #[test]
+    fn test_write_kind() {
+        let cases = [
+            ("a += b", vec!["assign", "read"]),
+            ("a++, --b", vec!["update", "update"]),
+            ("[a, {b}] = c", vec!["destructure", "destructure", "read"]),
+            (
+                "({[a]: b = c} = d)",
+                vec!["read", "destructure", "read", "read"],
+            ),
+            ("a.b = c", vec!["read", "read"]),
+        ];
+        for (src, expect) in cases {
+            assert_eq!(write_kinds(src), expect, "{}", src);
+        }
+    }
 }
@@ -1,6 +1,6 @@
 //! There is still a lot we can optimize VStr
 //! * instead of using &str, we can use intern to cache static attr name.
-//! * we can also cache camelize/capitalize result.
+//! * chained ops like camelize/capitalize and `_ctx` prefixing are cached with `intern` feature.
 //! * if VStr raw already satisfy StrOps, setting the ops flag is noop.
 //! * interning/cache can be optional, e.g. Text Token can skip it at all.
 use super::{
@@ -13,13 +13,16 @@ use std::{
     ops::Deref,
 };
 
+/// Render function parameter holding the component instance proxy.
+pub const CTX_IDENT: &str = "_ctx";
+
 bitflags! {
     /// Represents string manipulation. It has two categories:
     /// 1. IDEMPOTENT_OPS and 2. AFFINE_OPS,
     /// depending on whether the manipulation is idempotent or not
     /// NB strops is order sensitive when it is cast to string.
     #[derive(Default)]
-    pub struct StrOps: u16 {
+    pub struct StrOps: u32 {
         const HANDLER_KEY         = 1 << 0;
         const MODEL_HANDLER       = 1 << 1;
         const VALID_DIR           = 1 << 2;
@@ -29,13 +32,19 @@ bitflags! {
         const DECODE_ENTITY       = 1 << 6;
         const CAMEL_CASE          = 1 << 7;
         const CAPITALIZED         = 1 << 8;
-        const JS_STRING           = 1 << 9;
-        const CTX_PREFIX          = 1 << 11;
+        // event options and modifiers follow the handler key but precede quoting
+        const CAPTURE_SUFFIX      = 1 << 9;
+        const ONCE_SUFFIX         = 1 << 10;
+        const PASSIVE_SUFFIX      = 1 << 11;
         const MOD_SUFFIX          = 1 << 12;
-        const ASSIGN_EVT          = 1 << 13;
+        const PROP_PREFIX         = 1 << 13;
+        const ATTR_PREFIX         = 1 << 14;
+        const JS_STRING           = 1 << 15;
+        const CTX_PREFIX          = 1 << 16;
+        const ASSIGN_EVT          = 1 << 17;
         // marker op is placed at the end
-        const SELF_SUFFIX         = 1 << 14;
-        const DECODE_ATTR         = 1 << 15;
+        const SELF_SUFFIX         = 1 << 18;
+        const DECODE_ATTR         = 1 << 19;
         /// Ops that can be safely carried out multiple times
         const IDEMPOTENT_OPS =
             Self::COMPRESS_WHITESPACE.bits | Self::DECODE_ENTITY.bits |
@@ -45,7 +54,9 @@ bitflags! {
         const AFFINE_OPS =
             Self::HANDLER_KEY.bits | Self::MODEL_HANDLER.bits | Self::VALID_DIR.bits |
             Self::VALID_COMP.bits | Self::SELF_SUFFIX.bits | Self::V_DIR_PREFIX.bits |
-            Self::JS_STRING.bits | Self::CTX_PREFIX.bits;
+            Self::JS_STRING.bits | Self::CTX_PREFIX.bits |
+            Self::PROP_PREFIX.bits | Self::ATTR_PREFIX.bits |
+            Self::CAPTURE_SUFFIX.bits | Self::ONCE_SUFFIX.bits | Self::PASSIVE_SUFFIX.bits;
         /// Ops that mark the string is an hoisted asset
         const ASSET_OPS = Self::VALID_DIR.bits | Self::VALID_COMP.bits |
             Self::SELF_SUFFIX.bits;
@@ -152,29 +163,82 @@ fn write_valid_asset<W: Write>(mut s: &str, mut w: W, asset: &str) -> fmt::Resul
 impl StrOps {
     // ideally it should be str.satisfy(op) but adding a trait
     // to str is too much. Use passive voice.
-    fn is_satisfied_by(&self, _s: &str) -> bool {
-        todo!()
+    // only idempotent ops can be satisfied, affine ops always change the str.
+    fn is_satisfied_by(&self, s: &str) -> bool {
+        debug_assert!(self.bits().count_ones() == 1);
+        match *self {
+            StrOps::DECODE_ENTITY | StrOps::DECODE_ATTR => !s.contains('&'),
+            StrOps::CAMEL_CASE => !s.contains('-'),
+            StrOps::CAPITALIZED => s
+                .chars()
+                .next()
+                .map_or(true, |c| c.to_uppercase().eq(std::iter::once(c))),
+            StrOps::COMPRESS_WHITESPACE => {
+                let mut prev_space = false;
+                for c in s.chars() {
+                    if c.is_ascii_whitespace() && (c != ' ' || prev_space) {
+                        return false;
+                    }
+                    prev_space = c == ' ';
+                }
+                true
+            }
+            _ => false,
+        }
     }
-    fn write_ops<W: Write>(&self, s: &str, mut w: W) -> fmt::Result {
-        let flag_count = self.bits().count_ones();
+    /// `ctx` is the object that `CTX_PREFIX` accesses members on.
+    fn write_ops<W: Write>(&self, s: &str, ctx: &str, mut w: W) -> fmt::Result {
+        // skip leading noop ops. The src is unchanged until the first effective op,
+        // so checking against the original str is sound.
+        let mut ops = *self;
+        for op in self.iter() {
+            if !op.is_satisfied_by(s) {
+                break;
+            }
+            ops.remove(op);
+        }
+        let flag_count = ops.bits().count_ones();
         if flag_count == 0 {
             return w.write_str(s);
         }
-        if flag_count == 1 {
-            return Self::write_one_op(*self, s, w);
+        ops.write_cached(s, ctx, w)
+    }
+    #[cfg(not(feature = "intern"))]
+    fn write_cached<W: Write>(&self, s: &str, ctx: &str, w: W) -> fmt::Result {
+        self.write_uncached(s, ctx, w)
+    }
+    #[cfg(feature = "intern")]
+    fn write_cached<W: Write>(&self, s: &str, ctx: &str, w: W) -> fmt::Result {
+        // other single ops stream into the writer without allocation
+        let single = self.bits().count_ones() == 1 && !self.contains(StrOps::CTX_PREFIX);
+        // pool is keyed by the default ctx only
+        let custom_ctx = ctx != CTX_IDENT && self.contains(StrOps::CTX_PREFIX);
+        if single || custom_ctx || !intern::is_poolable(s) {
+            return self.write_uncached(s, ctx, w);
+        }
+        intern::write_interned(*self, s, w)
+    }
+    fn write_uncached<W: Write>(&self, s: &str, ctx: &str, mut w: W) -> fmt::Result {
+        if self.bits().count_ones() == 1 {
+            Self::write_one_op(*self, s, ctx, w)
+        } else {
+            w.write_str(&self.chain_ops(s, ctx)?)
         }
+    }
+    /// Applies multiple ops in order, which needs intermediate buffers.
+    fn chain_ops(&self, s: &str, ctx: &str) -> Result<String, fmt::Error> {
         let mut src = s;
-        let mut temp = String::new();
-        let mut dest = String::new();
+        let mut temp = String::with_capacity(s.len());
+        let mut dest = String::with_capacity(s.len());
         for op in self.iter() {
-            Self::write_one_op(op, src, &mut dest)?;
+            Self::write_one_op(op, src, ctx, &mut dest)?;
             std::mem::swap(&mut temp, &mut dest);
             dest.clear();
             src = &temp;
         }
-        w.write_str(src)
+        Ok(temp)
     }
-    fn write_one_op<W: Write>(op: Self, s: &str, mut w: W) -> fmt::Result {
+    fn write_one_op<W: Write>(op: Self, s: &str, ctx: &str, mut w: W) -> fmt::Result {
         debug_assert!(op.bits().count_ones() == 1);
         match op {
             StrOps::COMPRESS_WHITESPACE => write_compressed(s, w),
@@ -203,14 +267,35 @@ impl StrOps {
                 w.write_str("onUpdate:")?;
                 w.write_str(s)
             }
+            StrOps::PROP_PREFIX => {
+                w.write_str(".")?;
+                w.write_str(s)
+            }
+            StrOps::ATTR_PREFIX => {
+                w.write_str("^")?;
+                w.write_str(s)
+            }
             StrOps::CTX_PREFIX => {
-                w.write_str("_ctx.")?;
+                w.write_str(ctx)?;
+                w.write_str(".")?;
                 w.write_str(s)
             }
             StrOps::MOD_SUFFIX => {
                 w.write_str(s)?;
                 w.write_str("Modifiers")
             }
+            StrOps::CAPTURE_SUFFIX => {
+                w.write_str(s)?;
+                w.write_str("Capture")
+            }
+            StrOps::ONCE_SUFFIX => {
+                w.write_str(s)?;
+                w.write_str("Once")
+            }
+            StrOps::PASSIVE_SUFFIX => {
+                w.write_str(s)?;
+                w.write_str("Passive")
+            }
             StrOps::ASSIGN_EVT => {
                 w.write_str("(")?;
                 w.write_str(s)?;
@@ -246,6 +331,60 @@ impl Iterator for StrOpIter {
 
 impl ExactSizeIterator for StrOpIter {}
 
+/// Same raw str with same ops, e.g. prop names repeated in a large
+/// template, is computed once per thread and reused afterwards.
+/// Only short strs are pooled and the pool is dropped once full, so
+/// long-lived workers do not accumulate whole text contents.
+#[cfg(feature = "intern")]
+mod intern {
+    use super::StrOps;
+    use rustc_hash::FxHashMap;
+    use std::cell::RefCell;
+    use std::fmt::{self, Write};
+
+    const MAX_STR_LEN: usize = 64;
+    const MAX_POOL_SIZE: usize = 4096;
+
+    #[derive(Default)]
+    struct Pool {
+        // keyed by ops bits first so lookup only needs a borrowed str
+        map: FxHashMap<u32, FxHashMap<Box<str>, Box<str>>>,
+        size: usize,
+    }
+
+    thread_local! {
+        static POOL: RefCell<Pool> = RefCell::default();
+    }
+
+    pub fn is_poolable(s: &str) -> bool {
+        s.len() <= MAX_STR_LEN
+    }
+
+    pub fn write_interned<W: Write>(ops: StrOps, s: &str, mut w: W) -> fmt::Result {
+        POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if let Some(done) = pool.map.get(&ops.bits()).and_then(|m| m.get(s)) {
+                return w.write_str(done);
+            }
+            let done = ops.chain_ops(s, super::CTX_IDENT)?;
+            w.write_str(&done)?;
+            if pool.size >= MAX_POOL_SIZE {
+                pool.map.clear();
+                pool.size = 0;
+            }
+            pool.size += 1;
+            let interned = pool.map.entry(ops.bits()).or_default();
+            interned.insert(s.into(), done.into_boxed_str());
+            Ok(())
+        })
+    }
+
+    #[cfg(test)]
+    pub fn pool_size() -> usize {
+        POOL.with(|pool| pool.borrow().size)
+    }
+}
+
 /// A str for Vue compiler's internal modification.
 /// Instead of returning a Cow<str>, StrOp is recorded in the VStr
 /// and will be processed later in codegen phase.
@@ -283,6 +422,9 @@ impl<'a> VStr<'a> {
     pub fn is_event_assign(s: &VStr) -> bool {
         s.ops.contains(StrOps::ASSIGN_EVT)
     }
+    pub fn is_prop_prefixed(s: &VStr) -> bool {
+        s.ops.contains(StrOps::PROP_PREFIX)
+    }
     pub fn has_affix(s: &VStr) -> bool {
         s.ops.intersects(
             StrOps::MODEL_HANDLER
@@ -291,7 +433,9 @@ impl<'a> VStr<'a> {
                 | StrOps::V_DIR_PREFIX
                 | StrOps::CTX_PREFIX
                 | StrOps::MOD_SUFFIX
-                | StrOps::ASSIGN_EVT,
+                | StrOps::ASSIGN_EVT
+                | StrOps::PROP_PREFIX
+                | StrOps::ATTR_PREFIX,
         )
     }
 }
@@ -366,10 +510,35 @@ impl<'a> VStr<'a> {
         self.ops |= StrOps::CTX_PREFIX;
         self
     }
+    /// force binding as DOM prop: value -> .value
+    pub fn prefix_prop(&mut self) -> &mut Self {
+        self.ops |= StrOps::PROP_PREFIX;
+        self
+    }
+    /// force binding as attribute: value -> ^value
+    pub fn prefix_attr(&mut self) -> &mut Self {
+        self.ops |= StrOps::ATTR_PREFIX;
+        self
+    }
+    /// drop .prop/.attr prefix: .value -> value
+    pub fn unprefix(&mut self) -> &mut Self {
+        self.ops -= StrOps::PROP_PREFIX | StrOps::ATTR_PREFIX;
+        self
+    }
     pub fn suffix_mod(&mut self) -> &mut Self {
         self.ops |= StrOps::MOD_SUFFIX;
         self
     }
+    /// add event option modifier to handler key: onClick -> onClickOnce
+    pub fn suffix_event_option(&mut self, option: &str) -> &mut Self {
+        self.ops |= match option {
+            "capture" => StrOps::CAPTURE_SUFFIX,
+            "once" => StrOps::ONCE_SUFFIX,
+            "passive" => StrOps::PASSIVE_SUFFIX,
+            _ => panic!("{} is not an event option", option),
+        };
+        self
+    }
     pub fn assign_event(&mut self) -> &mut Self {
         self.ops |= StrOps::ASSIGN_EVT;
         self
@@ -381,7 +550,11 @@ impl<'a> VStr<'a> {
     }
 
     pub fn write_to<W: Write>(&self, w: W) -> fmt::Result {
-        self.ops.write_ops(self.raw, w)
+        self.ops.write_ops(self.raw, CTX_IDENT, w)
+    }
+    /// Same as `write_to` but prefixed identifiers access `ctx`, e.g. `this`.
+    pub fn write_with_ctx<W: Write>(&self, ctx: &str, w: W) -> fmt::Result {
+        self.ops.write_ops(self.raw, ctx, w)
     }
 }
 
@@ -435,9 +608,62 @@ mod test {
         assert_eq!(v.len(), 2);
     }
 
+    #[test]
+    fn test_str_ops_skip_noop() {
+        let cases = [
+            (
+                "a b",
+                StrOps::COMPRESS_WHITESPACE | StrOps::JS_STRING,
+                "\"a b\"",
+            ),
+            (
+                "a  b",
+                StrOps::COMPRESS_WHITESPACE | StrOps::JS_STRING,
+                "\"a b\"",
+            ),
+            (
+                "a\nb",
+                StrOps::COMPRESS_WHITESPACE | StrOps::JS_STRING,
+                "\"a b\"",
+            ),
+            (
+                "a&amp;",
+                StrOps::DECODE_ENTITY | StrOps::JS_STRING,
+                "\"a&\"",
+            ),
+            ("Ab", StrOps::CAPITALIZED, "Ab"),
+            ("a-b", StrOps::CAMEL_CASE | StrOps::CAPITALIZED, "AB"),
+        ];
+        for (src, ops, expect) in cases {
+            assert_eq!(write_string(ops, src), expect);
+        }
+        assert!(StrOps::DECODE_ENTITY.is_satisfied_by("text"));
+        assert!(!StrOps::COMPRESS_WHITESPACE.is_satisfied_by("a\tb"));
+        assert!(!StrOps::JS_STRING.is_satisfied_by("a"));
+    }
+
+    #[cfg(feature = "intern")]
+    #[test]
+    fn test_interned_ops() {
+        let ops = StrOps::CAMEL_CASE | StrOps::JS_STRING;
+        let before = intern::pool_size();
+        assert_eq!(write_string(ops, "item-name"), "\"itemName\"");
+        assert_eq!(intern::pool_size(), before + 1);
+        assert_eq!(write_string(ops, "item-name"), "\"itemName\"");
+        assert_eq!(intern::pool_size(), before + 1);
+        assert_eq!(write_string(StrOps::CTX_PREFIX, "name"), "_ctx.name");
+        assert_eq!(write_string(StrOps::CTX_PREFIX, "name"), "_ctx.name");
+        assert_eq!(intern::pool_size(), before + 2);
+        // other single ops and long strs write directly without the pool
+        assert_eq!(write_string(StrOps::CAMEL_CASE, "a-b"), "aB");
+        let long = "a".repeat(100);
+        assert_eq!(write_string(ops, &long), format!("\"{}\"", long));
+        assert_eq!(intern::pool_size(), before + 2);
+    }
+
     fn write_string(ops: StrOps, s: &str) -> String {
         let mut w = String::new();
-        ops.write_ops(s, &mut w).unwrap();
+        ops.write_ops(s, CTX_IDENT, &mut w).unwrap();
         w
     }
 
@@ -451,6 +677,16 @@ mod test {
             (StrOps::SELF_SUFFIX, "test"),
             (StrOps::JS_STRING, stringify!("test")),
             (StrOps::CAMEL_CASE | StrOps::V_DIR_PREFIX, "vTest"),
+            (StrOps::PROP_PREFIX, ".test"),
+            (StrOps::ATTR_PREFIX | StrOps::JS_STRING, stringify!("^test")),
+            (
+                StrOps::HANDLER_KEY | StrOps::ONCE_SUFFIX | StrOps::CAPTURE_SUFFIX,
+                "onTestCaptureOnce",
+            ),
+            (
+                StrOps::PASSIVE_SUFFIX | StrOps::JS_STRING,
+                stringify!("testPassive"),
+            ),
         ];
         for (ops, expect) in cases {
             let origin = ops;
@@ -0,0 +1,59 @@
+// Allocation counting needs its own global allocator,
+// so it lives in a separate test binary from integration_test.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use vue_compiler_core as compiler;
+use compiler::compiler::{get_base_passes, BaseCompiler, CompileOption, TemplateCompiler};
+use compiler::SFCInfo;
+
+struct CountingAlloc;
+static ALLOCS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn count_allocs(source: &str) -> usize {
+    let sfc_info = SFCInfo::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, CompileOption::default());
+    let before = ALLOCS.load(Ordering::Relaxed);
+    let output = compiler.compile(source, &sfc_info).unwrap();
+    let after = ALLOCS.load(Ordering::Relaxed);
+    assert!(!output.is_empty());
+    after - before
+}
+
+fn static_template(text: &str, count: usize) -> String {
+    (0..count).map(|_| format!("<p>{}</p>", text)).collect()
+}
+
+#[test]
+fn test_static_text_alloc() {
+    let short = static_template("a", 100);
+    let long = static_template(&"a".repeat(1000), 100);
+    let short_allocs = count_allocs(&short);
+    let long_allocs = count_allocs(&long);
+    // only the output buffer grows with byte count, and it grows geometrically.
+    assert!(
+        long_allocs <= short_allocs + 16,
+        "short: {}, long: {}",
+        short_allocs,
+        long_allocs
+    );
+    // allocations should scale with node count
+    let double_allocs = count_allocs(&static_template("a", 200));
+    assert!(double_allocs > short_allocs);
+}
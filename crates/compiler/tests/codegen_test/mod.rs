@@ -1,24 +1,88 @@
 use vue_compiler_core as compiler;
-use super::common::get_compiler;
-use compiler::compiler::TemplateCompiler;
+use super::common::{compile_with, get_compile_option, get_compiler};
+use compiler::codegen::ScriptMode;
+use compiler::compiler::{
+    get_base_passes, BaseCompiler, CompileOption, CompilerBuilder, TemplateCompiler,
+};
+use compiler::converter::{v_model::convert_v_model_event, v_on::V_ON, BaseConvertInfo};
+use compiler::ir::{IRNode, JsExpr as Js};
+use compiler::transformer::{BaseVNode, CorePass};
 use insta::assert_snapshot;
-use rslint_parser::parse_text;
+use rslint_parser::{parse_module, parse_text, parse_with_syntax, Syntax};
 
 fn test_codegen(case: &str) {
+    test_codegen_with(case, |_| {});
+}
+
+fn test_codegen_with(case: &str, f: fn(&mut CompileOption)) {
     let name = insta::_macro_support::AutoName;
-    let val = base_compile(case);
+    let val = compile_with(case, &Default::default(), f);
+    assert_valid_js(&val);
+    assert_clean_lines(&val);
+    assert_snapshot!(name, val, case);
+}
+
+/// generated code uses LF only and never ends a line with whitespace
+fn assert_clean_lines(val: &str) {
+    assert!(!val.contains('\r'), "{:?}", val);
+    for (i, line) in val.lines().enumerate() {
+        assert_eq!(
+            line,
+            line.trim_end(),
+            "line {} has trailing space:\n{}",
+            i + 1,
+            val
+        );
+    }
+    assert!(!val.contains("\n\n\n"), "double blank lines:\n{}", val);
+}
+
+#[test]
+fn test_snapshots_clean_lines() {
+    let root = std::path::Path::new(env!("CARGO_MANIFEST_DIR"));
+    let dir = root.join("tests/codegen_test/snapshots");
+    for entry in std::fs::read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        let snap = std::fs::read_to_string(&path).unwrap();
+        // skip insta header
+        let code = snap.splitn(3, "---\n").nth(2).unwrap_or_default();
+        assert_clean_lines(code);
+    }
+}
+
+#[test]
+fn test_clean_output_lines() {
+    let cases = [
+        "<div>\n  <p>{{ a }}</p>\n</div>",
+        "<comp v-for='i in list' :key='i'><template #foo='{ b }'>{{ b }}</template></comp>",
+        "<p :class='a\r\n  + b  \r\n'/>",
+        "<p v-if='a'/><p v-else-if='b'>{{ c }}</p><p v-else/>",
+    ];
+    for case in cases {
+        assert_clean_lines(&base_compile(case));
+        assert_clean_lines(&compile_with(case, &Default::default(), enable_hoist));
+    }
+}
+
+fn assert_valid_js(val: &str) {
     // `function target have return outside function
     let wrap_in_func = format!("function () {{ {} }}", val);
     let parsed = parse_text(&wrap_in_func, 0);
-    assert!(parsed.errors().is_empty());
-    assert_snapshot!(name, val, case);
+    assert!(parsed.errors().is_empty(), "{}", val);
 }
 
 pub fn base_compile(source: &str) -> String {
-    let sfc_info = Default::default();
-    let compiler = get_compiler();
-    let ret = compiler.compile(source, &sfc_info).unwrap();
-    String::from_utf8(ret).unwrap()
+    compile_with(source, &Default::default(), |_| {})
+}
+
+fn enable_hoist(option: &mut CompileOption) {
+    option.hoist_static = true;
+}
+
+fn enable_handlers(option: &mut CompileOption) {
+    let convs = &mut option.directive_converters;
+    convs.insert(V_ON.0, V_ON.1);
+    convs.insert("model", convert_v_model_event);
 }
 
 #[test]
@@ -33,3 +97,1340 @@ fn test_text_codegen() {
         test_codegen(case);
     }
 }
+
+#[test]
+fn test_component_merge_props() {
+    let val = base_compile("<comp foo='bar' v-bind='extra'/>");
+    assert_valid_js(&val);
+    assert!(val.contains("mergeProps: _mergeProps"), "{}", val);
+    assert!(val.contains("_mergeProps({"), "{}", val);
+    assert!(val.contains(r#"foo: "bar","#), "{}", val);
+    assert!(val.contains("}, extra)"), "{}", val);
+    // spread first keeps source order so later props win
+    let val = base_compile("<comp v-bind='extra' foo='bar'/>");
+    assert_valid_js(&val);
+    assert!(val.contains("_mergeProps(extra, {"), "{}", val);
+}
+
+#[test]
+fn test_v_for_codegen() {
+    let val = base_compile("<p v-for='(a, i) in b'>{{a}}</p>");
+    assert_valid_js(&val);
+    assert!(val.contains("renderList: _renderList"), "{}", val);
+    assert!(val.contains("Fragment: _Fragment"), "{}", val);
+    let list = "_createElementBlock(_Fragment, null, _renderList(b, (a, i) =>";
+    assert!(val.contains(list), "{}", val);
+    assert!(val.contains("UNKEYED_FRAGMENT"), "{}", val);
+}
+
+#[test]
+fn test_v_for_fragment_flag() {
+    let val = base_compile("<p v-for='a in list' :key='a.id'/>");
+    assert_valid_js(&val);
+    assert!(val.contains("_openBlock(true)"), "{}", val);
+    assert!(val.contains("KEYED_FRAGMENT"), "{}", val);
+    assert!(!val.contains("UNKEYED_FRAGMENT"), "{}", val);
+    let val = base_compile("<p v-for='({id, name}) in users'>{{name}}</p>");
+    assert_valid_js(&val);
+    assert!(val.contains("({id, name}) =>"), "{}", val);
+}
+
+#[test]
+fn test_nested_v_for() {
+    let val = base_compile("<div v-for='row in rows'><p v-for='(cell, i) in row'/></div>");
+    assert_valid_js(&val);
+    assert!(val.contains("_renderList(rows, (row) =>"), "{}", val);
+    assert!(val.contains("_renderList(row, (cell, i) =>"), "{}", val);
+    assert_eq!(val.matches("_openBlock(true)").count(), 2, "{}", val);
+}
+
+#[test]
+fn test_slot_outlet_codegen() {
+    let val = base_compile("<slot name='header' :msg='m'>fallback</slot>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"_renderSlot($slots, "header", {"#),
+        "{}",
+        val
+    );
+    assert!(val.contains("msg: m,"), "{}", val);
+    assert!(val.contains("() => ["), "{}", val);
+    assert!(val.contains(r#"_createTextVNode("fallback")"#), "{}", val);
+    let val = base_compile("<slot :name='n'/>");
+    assert_valid_js(&val);
+    assert!(val.contains("_renderSlot($slots, n)"), "{}", val);
+}
+
+#[test]
+fn test_render_slot_codegen() {
+    let val = base_compile(r#"<slot name="header" :user="user">default text</slot>"#);
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"_renderSlot($slots, "header", {"#),
+        "{}",
+        val
+    );
+    assert!(val.contains("user: user,"), "{}", val);
+    assert!(val.contains(r#"}, () => ["#), "{}", val);
+    // unnamed slot without props and fallback trims trailing args
+    let val = base_compile("<slot/>");
+    assert_valid_js(&val);
+    assert!(val.contains(r#"_renderSlot($slots, "default")"#), "{}", val);
+    // fallback without props still needs a props placeholder
+    let val = base_compile("<slot>fallback</slot>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"_renderSlot($slots, "default", {}, () => ["#),
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_custom_directive_codegen() {
+    let val = base_compile("<input v-focus v-my-dir:arg.mod='val'/>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"const _directive_focus = _resolveDirective("focus")"#),
+        "{}",
+        val
+    );
+    assert!(
+        val.contains(r#"const _directive_my_dir = _resolveDirective("my-dir")"#),
+        "{}",
+        val
+    );
+    assert!(
+        val.contains("_withDirectives(_createElementVNode(\"input\""),
+        "{}",
+        val
+    );
+    assert!(
+        val.contains(r#"[_directive_my_dir, val, "arg", {"#),
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_v_slot_codegen() {
+    let val = base_compile("<comp v-slot='{ msg }'>{{msg}}</comp>");
+    assert_valid_js(&val);
+    // slots are passed as object, not wrapped in children array
+    assert!(
+        val.contains("_createVNode(_component_comp, null, {"),
+        "{}",
+        val
+    );
+    assert!(val.contains("default: _withCtx(({ msg }) => ["), "{}", val);
+    assert!(val.contains("_: 1 /*Stable*/"), "{}", val);
+
+    let val =
+        base_compile("<comp><template #header>h</template><template #footer>f</template></comp>");
+    assert_valid_js(&val);
+    assert!(val.contains("header: _withCtx(() => ["), "{}", val);
+    assert!(val.contains("footer: _withCtx(() => ["), "{}", val);
+    assert!(!val.contains("_createSlots"), "{}", val);
+
+    let val = base_compile("<comp><template #[name]>dynamic</template></comp>");
+    assert_valid_js(&val);
+    assert!(val.contains("[name]: _withCtx(() => ["), "{}", val);
+    assert!(val.contains("_: 2 /*Dynamic*/"), "{}", val);
+    assert!(val.contains("DYNAMIC_SLOTS"), "{}", val);
+}
+
+#[test]
+fn test_alterable_slot_codegen() {
+    let val = base_compile("<comp><template v-for='n in names' #[n]>{{n}}</template></comp>");
+    assert_valid_js(&val);
+    assert!(val.contains("createSlots: _createSlots"), "{}", val);
+    assert!(val.contains("_createSlots({"), "{}", val);
+    assert!(val.contains("_renderList(names, (n) =>"), "{}", val);
+    assert!(val.contains("name: n,"), "{}", val);
+    assert!(val.contains("fn: _withCtx(() => ["), "{}", val);
+    assert!(val.contains("_: 2 /*Dynamic*/"), "{}", val);
+}
+
+#[test]
+fn test_template_ref_codegen() {
+    let val = base_compile("<div ref='root'><comp :ref='setRef'/></div>");
+    assert_valid_js(&val);
+    assert!(val.contains(r#"ref: "root""#), "{}", val);
+    assert!(val.contains("ref: setRef"), "{}", val);
+    assert!(!val.contains("ref_for"), "{}", val);
+    let val = base_compile("<p v-for='i in list' :key='i' ref='items'>{{ i }}</p>");
+    assert_valid_js(&val);
+    assert!(val.contains("ref_for: true,"), "{}", val);
+    assert!(val.contains(r#"ref: "items""#), "{}", val);
+}
+
+#[test]
+fn test_component_assets_codegen() {
+    // only div is native so that my-comp resolves as component
+    let sfc_info = Default::default();
+    let source = "<div><MyComponent/><MyComponent/><my-comp/></div>";
+    let val = compile_with(source, &sfc_info, |o| o.is_native_tag = |s, _| s == "div");
+    assert_valid_js(&val);
+    let my_component = r#"const _component_MyComponent = _resolveComponent("MyComponent")"#;
+    assert_eq!(val.matches(my_component).count(), 1, "{}", val);
+    // runtime resolves kebab/camel/pascal variants from the raw name
+    let my_comp = r#"const _component_my_comp = _resolveComponent("my-comp")"#;
+    assert_eq!(val.matches(my_comp).count(), 1, "{}", val);
+    assert_eq!(val.matches("_resolveComponent(").count(), 2, "{}", val);
+    assert!(
+        val.contains("_createVNode(_component_MyComponent)"),
+        "{}",
+        val
+    );
+    assert!(val.contains("_createVNode(_component_my_comp)"), "{}", val);
+}
+
+#[test]
+fn test_dynamic_component_codegen() {
+    test_codegen(r#"<component :is="tab" />"#);
+    // static is resolves like a normal component asset
+    let val = base_compile("<component is='tab'/>");
+    assert!(val.contains(r#"_resolveComponent("tab")"#), "{}", val);
+    assert!(val.contains("_createVNode(_component_tab)"), "{}", val);
+    assert!(!val.contains("_resolveDynamicComponent"), "{}", val);
+    let val = base_compile("<component v-if='ok' :is='view'/>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains("_createBlock(_resolveDynamicComponent(view), {"),
+        "{}",
+        val
+    );
+    assert!(val.contains("key: 0"), "{}", val);
+    let val = base_compile("<div v-is='tab'/>");
+    assert!(
+        val.contains("_createBlock(_resolveDynamicComponent(tab))"),
+        "{}",
+        val
+    );
+    // dynamic component does not need resolveComponent
+    assert!(!val.contains("_resolveComponent("), "{}", val);
+}
+
+#[test]
+fn test_teleport_codegen() {
+    let val = base_compile(r##"<Teleport to="#modal"><div>hi</div></Teleport>"##);
+    assert_valid_js(&val);
+    assert!(val.contains("Teleport: _Teleport"), "{}", val);
+    assert!(
+        val.contains("(_openBlock(), _createBlock(_Teleport, {"),
+        "{}",
+        val
+    );
+    assert!(val.contains(r##"to: "#modal","##), "{}", val);
+    // children are passed as array instead of slots
+    assert!(val.contains("}, ["), "{}", val);
+    assert!(!val.contains("_withCtx"), "{}", val);
+    assert!(!val.contains("_resolveComponent"), "{}", val);
+    let val = base_compile("<teleport to='body'>{{msg}}</teleport>");
+    assert_valid_js(&val);
+    assert!(val.contains("_createBlock(_Teleport, {"), "{}", val);
+    assert!(
+        val.contains("_createTextVNode(_toDisplayString(msg)"),
+        "{}",
+        val
+    );
+    assert!(!val.contains("_resolveComponent"), "{}", val);
+}
+
+#[test]
+fn test_comment_codegen() {
+    let val = base_compile("<div><!-- debug info --><p/></div>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"_createCommentVNode(" debug info ")"#),
+        "{}",
+        val
+    );
+    // quotes in comment must not break out of the string literal
+    let cases = [
+        (
+            "<!-- ' + evil + ' -->",
+            r#"_createCommentVNode(" ' + evil + ' ")"#,
+        ),
+        (
+            r#"<!-- " + evil + " -->"#,
+            r#"_createCommentVNode(" \" + evil + \" ")"#,
+        ),
+    ];
+    for (case, expected) in cases {
+        let val = base_compile(case);
+        assert_valid_js(&val);
+        assert!(val.contains(expected), "{}", val);
+    }
+}
+
+#[test]
+fn test_hoist_noop_handler() {
+    // dynamic id keeps the button from being hoisted as a whole
+    let source = "<form @submit.prevent><button @click.stop='' :id='a'/></form>";
+    let val = compile_with(source, &Default::default(), |o| {
+        enable_hoist(o);
+        enable_handlers(o);
+    });
+    assert_valid_js(&val);
+    assert!(val.contains("const _hoisted_1 = () => {}"), "{}", val);
+    assert!(!val.contains("_hoisted_2"), "{}", val);
+    assert!(val.contains("onSubmit: _hoisted_1"), "{}", val);
+    assert!(val.contains("onClick: _hoisted_1"), "{}", val);
+}
+
+#[test]
+fn test_static_root_hoist() {
+    let source = "<p class='a'>hello</p><!--c--><div><span>world</span></div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    let hoist = "const _hoisted_1 = _createElementVNode(_Fragment, null, [";
+    assert!(val.contains(hoist), "{}", val);
+    assert!(val.contains("-1 /*HOISTED*/"), "{}", val);
+    assert!(val.contains("return _hoisted_1"), "{}", val);
+    // hoists are outside of with block, their helpers must be destructured too
+    let preamble = val.split("return function").next().unwrap();
+    assert!(preamble.contains("Fragment: _Fragment"), "{}", val);
+    assert!(
+        preamble.contains("createCommentVNode: _createCommentVNode"),
+        "{}",
+        val
+    );
+
+    let val = compile_with(
+        "<p>hello</p><p>{{world}}</p>",
+        &Default::default(),
+        enable_hoist,
+    );
+    assert_valid_js(&val);
+    assert!(!val.contains("return _hoisted_"), "{}", val);
+    assert!(
+        !val.contains("const _hoisted_1 = _createElementVNode(_Fragment"),
+        "{}",
+        val
+    );
+}
+
+fn ssr_compile(source: &str) -> String {
+    use compiler::codegen::RenderMode;
+    compile_with(source, &Default::default(), |o| {
+        o.render_mode = RenderMode::Ssr;
+        o.is_void_tag = |t| t == "br" || t == "input";
+    })
+}
+
+#[test]
+fn test_ssr_static_template() {
+    let source = "<p class='a'>hello &amp; `bye`</p><!--c--><div><span>world</span><br></div><i/>";
+    let val = ssr_compile(source);
+    assert_valid_js(&val);
+    assert_eq!(val.matches("_push(").count(), 1, "{}", val);
+    let start = val.find("_push(`").unwrap() + "_push(`".len();
+    let end = val.rfind("`)").unwrap();
+    let html =
+        r#"<p class="a">hello &amp; \`bye\`</p><!--c--><div><span>world</span><br></div><i></i>"#;
+    assert_eq!(&val[start..end], html);
+    // one interpolation makes the template dynamic
+    let val = ssr_compile("<p>hello</p><p>{{world}}</p>");
+    assert_valid_js(&val);
+    assert_eq!(val.matches("_push(").count(), 2, "{}", val);
+    assert!(val.contains("_push(`<p>hello</p>`)"), "{}", val);
+    assert!(
+        val.contains("_push(`<p>${_ssrInterpolate(world)}</p>`)"),
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_ssr_attrs() {
+    let val = ssr_compile("<p id='a' :title='t' :class='c' :style='s' @click='f' :key='k'/>");
+    assert_valid_js(&val);
+    let html = r#"<p id="a"${_ssrRenderAttr("title", t)} class="${_ssrRenderClass(c)}" style="${_ssrRenderStyle(s)}"></p>"#;
+    assert!(val.contains(html), "{}", val);
+    for helper in ["ssrRenderAttr", "ssrRenderClass", "ssrRenderStyle"] {
+        assert!(val.contains(&format!("{}: _{}", helper, helper)), "{}", val);
+    }
+    assert!(!val.contains("onClick"), "{}", val);
+    // dynamic key goes through the helper, never into the template literal
+    let val = ssr_compile("<p :[key]='v'/>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains("<p${_ssrRenderDynamicAttr((key) || '', v)}></p>"),
+        "{}",
+        val
+    );
+    assert!(!val.contains("${key}"), "{}", val);
+    // v-bind object keys are validated by ssrRenderAttrs
+    let val = ssr_compile("<p id='a' v-bind='obj' v-on='handlers'/>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"<p id="a"${_ssrRenderAttrs(obj)}></p>"#),
+        "{}",
+        val
+    );
+    assert!(val.contains("ssrRenderAttrs: _ssrRenderAttrs"), "{}", val);
+    assert!(!val.contains("handlers"), "{}", val);
+}
+
+#[test]
+fn test_ssr_forced_prop_and_attr() {
+    let source = "<input :value.prop='v' .title='t' :custom.attr='c' :[k].prop='x' :[k].attr='y'/>";
+    let val = ssr_compile(source);
+    assert_valid_js(&val);
+    // .value is not reflected, .title is
+    let attrs = [
+        r#"${_ssrRenderAttr("title", t)}"#,
+        r#"${_ssrRenderAttr("custom", c)}"#,
+        "${_ssrRenderDynamicAttr((k) || '', y)}",
+    ];
+    assert!(
+        val.contains(&format!("<input{}>", attrs.concat())),
+        "{}",
+        val
+    );
+    assert!(!val.contains("value"), "{}", val);
+    assert!(!val.contains('^'), "{}", val);
+    assert!(!val.contains("'.'"), "{}", val);
+    // forced className prop renders class, class prop is not reflected
+    let val = ssr_compile("<p :class.prop='a' .className='c' :id.attr='b'/>");
+    let html = r#"<p class="${_ssrRenderClass(c)}"${_ssrRenderAttr("id", b)}></p>"#;
+    assert!(val.contains(html), "{}", val);
+}
+
+#[test]
+fn test_ssr_css_vars() {
+    use compiler::codegen::RenderMode;
+    let source = "<p>a</p><div style='color: red'><i>b</i></div><span :id='c'/>";
+    let val = compile_with(source, &Default::default(), |o| {
+        o.render_mode = RenderMode::Ssr;
+        o.ssr_css_vars = Some(r#"{ "--abc-color": (_ctx.color) }"#.into());
+    });
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"const _cssVars = { style: { "--abc-color": (_ctx.color) } }"#),
+        "{}",
+        val
+    );
+    // only root elements are styled, existing style is merged
+    let pushes = [
+        "_push(`<p${_ssrRenderAttrs(_cssVars)}>a</p>`)",
+        r#"_push(`<div style="${_ssrRenderStyle(["color: red", _cssVars.style])}"><i>b</i></div>`)"#,
+        r#"_push(`<span${_ssrRenderAttr("id", c)}${_ssrRenderAttrs(_cssVars)}></span>`)"#,
+    ];
+    for push in pushes {
+        assert!(val.contains(push), "{}", val);
+    }
+}
+
+#[test]
+fn test_identical_static_nodes_hoist_once() {
+    let source = r#"<div>
+  <span class="a">x</span>{{ msg }}
+  <span class="a">x</span>
+  <span class="a">x</span>
+  <span class="b">x</span>
+</div>"#;
+    test_codegen_with(source, enable_hoist);
+    // the three identical spans share one binding
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert!(!val.contains("_hoisted_3"), "{}", val);
+}
+
+#[test]
+fn test_static_node_hoist() {
+    let source = "<div><p>hello</p>{{ msg }}<span class='a'/></div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    let (preamble, render) = val.split_once("return function render").unwrap();
+    let hoist = r#"const _hoisted_1 = _createElementVNode("p", null, "hello", -1 /*HOISTED*/)"#;
+    assert!(preamble.contains(hoist), "{}", val);
+    let hoist = r#"const _hoisted_2 = _createElementVNode("span", {"#;
+    assert!(preamble.contains(hoist), "{}", val);
+    assert!(render.contains("_hoisted_1,"), "{}", val);
+    assert!(render.contains("_hoisted_2,"), "{}", val);
+    assert!(!render.contains(r#""hello""#), "{}", val);
+    // dynamic node stays in render
+    let val = compile_with(
+        "<div><p>{{ msg }}</p></div>",
+        &Default::default(),
+        enable_hoist,
+    );
+    assert!(!val.contains("_hoisted_"), "{}", val);
+    // disabled by default
+    let val = base_compile("<div><p>hello</p>{{ msg }}</div>");
+    assert!(!val.contains("_hoisted_"), "{}", val);
+}
+
+#[test]
+fn test_hoist_static_pass() {
+    let source = r#"<div><p class="a">static</p><p>{{dyn}}</p></div>"#;
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    assert_eq!(val.matches("const _hoisted_").count(), 1, "{}", val);
+    assert!(
+        val.contains(r#"const _hoisted_1 = _createElementVNode("p", {"#),
+        "{}",
+        val
+    );
+    // nested static tree is hoisted as a whole
+    let source = "<div><section><p>a</p><p>b</p></section>{{dyn}}</div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    assert_eq!(val.matches("const _hoisted_").count(), 1, "{}", val);
+    assert!(
+        val.contains(r#"const _hoisted_1 = _createElementVNode("section""#),
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_hoist_static_props() {
+    let val = compile_with(
+        r#"<p id="a">{{ msg }}</p>"#,
+        &Default::default(),
+        enable_hoist,
+    );
+    assert_valid_js(&val);
+    assert!(val.contains("const _hoisted_1 = {"), "{}", val);
+    assert!(
+        val.contains(r#"_createElementVNode("p", _hoisted_1, "#),
+        "{}",
+        val
+    );
+    // elements with ref, dynamic key or runtime directive need patching
+    let source = r#"<div><p ref="r">a</p><p :key="k">b</p><p v-foo>c</p>{{ d }}</div>"#;
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    let (preamble, _) = val.split_once("return function render").unwrap();
+    assert!(!preamble.contains(r#"_createElementVNode("p""#), "{}", val);
+}
+
+#[test]
+fn test_with_block_helpers() {
+    let val = base_compile("<div>{{ msg }}</div>");
+    assert_valid_js(&val);
+    assert!(val.starts_with("const _Vue = Vue"), "{}", val);
+    let (_, body) = val.split_once("with (_ctx) {").unwrap();
+    assert!(
+        body.contains("createElementVNode: _createElementVNode"),
+        "{}",
+        val
+    );
+    assert!(
+        body.contains("toDisplayString: _toDisplayString"),
+        "{}",
+        val
+    );
+    assert!(body.contains("} = _Vue"), "{}", val);
+    // helpers are used after being declared
+    let decl = body.find("} = _Vue").unwrap();
+    assert!(
+        body.find("return _createElementVNode").unwrap() > decl,
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_writer_agnostic_output() {
+    let source = "<div v-for='a in b' :class='a'>{{ a }}<comp/></div>";
+    let sfc_info = Default::default();
+    let dest = || std::io::Cursor::new(vec![]);
+    let compiler = BaseCompiler::new(dest, get_base_passes, get_compile_option());
+    let cursor = compiler.compile(source, &sfc_info).unwrap();
+    let val = String::from_utf8(cursor.into_inner()).unwrap();
+    assert_eq!(val, base_compile(source));
+}
+
+#[test]
+fn test_script_mode_preamble() {
+    let source = "<div :id='a'>{{msg}}</div>";
+    let val = compile_with(source, &Default::default(), |o| o.mode = module_mode());
+    let parsed = parse_module(&val, 0);
+    assert!(parsed.errors().is_empty(), "{}", val);
+    assert!(val.starts_with("import {"), "{}", val);
+    assert!(
+        val.contains("toDisplayString as _toDisplayString"),
+        "{}",
+        val
+    );
+    assert!(val.contains(r#"} from "vue""#), "{}", val);
+    assert!(
+        val.contains("export function render(_ctx, _cache) {"),
+        "{}",
+        val
+    );
+    assert!(!val.contains("with ("), "{}", val);
+    assert!(val.contains("_ctx.msg"), "{}", val);
+
+    let function = ScriptMode::Function {
+        prefix_identifier: true,
+        runtime_global_name: "Vue".into(),
+    };
+    let val = compile_with(source, &Default::default(), |o| o.mode = function);
+    assert_valid_js(&val);
+    assert!(val.starts_with("const {"), "{}", val);
+    assert!(val.contains("toDisplayString: _toDisplayString"), "{}", val);
+    assert!(val.contains("} = Vue"), "{}", val);
+    assert!(
+        val.contains("return function render(_ctx, _cache) {"),
+        "{}",
+        val
+    );
+    assert!(!val.contains("with ("), "{}", val);
+    assert!(!val.contains("export "), "{}", val);
+}
+
+#[test]
+fn test_source_map() {
+    let source = "<div :id='a'>\n  {{ msg }}\n</div>";
+    let option = CompileOption {
+        source_map: true,
+        filename: "App.vue".into(),
+        ..get_compile_option()
+    };
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler.compile_with_source_map(source, &sfc_info).unwrap();
+    let code = String::from_utf8(output.code).unwrap();
+    assert_valid_js(&code);
+    let map = output.map.expect("should generate source map");
+    assert!(
+        map.starts_with(r#"{"version":3,"sources":["App.vue"]"#),
+        "{}",
+        map
+    );
+    let mappings = map.split(r#""mappings":""#).nth(1).unwrap();
+    // tag name, text, `a` and `msg` are all sliced from source
+    let segments = mappings
+        .trim_end_matches("\"}")
+        .split(|c| c == ';' || c == ',');
+    assert!(segments.filter(|s| !s.is_empty()).count() >= 3, "{}", map);
+
+    let compiler = get_compiler();
+    let output = compiler.compile_with_source_map(source, &sfc_info).unwrap();
+    assert!(output.map.is_none());
+}
+
+#[test]
+fn test_assets_declaration_codegen() {
+    let sfc_info = Default::default();
+    let source = "<div v-focus><MyComp/><my-comp/></div>";
+    let val = compile_with(source, &sfc_info, |o| o.is_native_tag = |s, _| s == "div");
+    assert_valid_js(&val);
+    let decls = [
+        r#"const _component_MyComp = _resolveComponent("MyComp")"#,
+        r#"const _component_my_comp = _resolveComponent("my-comp")"#,
+        r#"const _directive_focus = _resolveDirective("focus")"#,
+    ];
+    let with_block = val.find("with (_ctx) {").unwrap();
+    let ret = val.rfind("return ").unwrap();
+    for decl in decls {
+        let pos = val.find(decl).unwrap_or_else(|| panic!("{}", val));
+        // declared inside render and before return
+        assert!(with_block < pos && pos < ret, "{}", val);
+    }
+    assert!(val.contains("_withDirectives("), "{}", val);
+    assert!(val.contains("[_directive_focus]"), "{}", val);
+}
+
+#[test]
+fn test_self_reference_component() {
+    let sfc_info = compiler::SFCInfo {
+        self_name: "MyComp".into(),
+        ..Default::default()
+    };
+    let val = compile_with("<MyComp/><Other/>", &sfc_info, |_| {});
+    assert_valid_js(&val);
+    assert!(
+        val.contains(r#"_resolveComponent("MyComp", true)"#),
+        "{}",
+        val
+    );
+    assert!(val.contains(r#"_resolveComponent("Other")"#), "{}", val);
+}
+
+#[test]
+fn test_ts_signature() {
+    use compiler::{BindingMetadata, BindingTypes, SFCInfo};
+    use rustc_hash::FxHashMap;
+    let source = "<div :id='a'>{{msg}}</div>";
+    let val = compile_with(source, &Default::default(), |o| o.is_ts = true);
+    let ctx = "_ctx: InstanceType<typeof __VUE_COMPONENT__>";
+    let signature = format!("function render({}, _cache: any[]): VNode {{", ctx);
+    assert!(val.contains(&signature), "{}", val);
+    // TypeScript forbids with statement, identifiers are prefixed instead
+    assert!(!val.contains("with ("), "{}", val);
+    assert!(val.contains("_ctx.msg"), "{}", val);
+    assert!(val.contains("id: _ctx.a"), "{}", val);
+    assert_valid_ts(&val, false);
+    assert_snapshot!("ts_signature", val, source);
+    let mut map = FxHashMap::default();
+    map.insert("a", BindingTypes::Props);
+    let sfc_info = SFCInfo {
+        binding_metadata: BindingMetadata::new(map, false),
+        ..Default::default()
+    };
+    let val = compile_with(source, &sfc_info, |o| o.is_ts = true);
+    let bindings = "$props: any, $setup: any, $data: any, $options: any";
+    let signature = format!("({}, _cache: any[], {}): VNode {{", ctx, bindings);
+    assert!(val.contains(&signature), "{}", val);
+}
+
+/// decode source map mappings into (gen_line, gen_col, src_line, src_col)
+fn decode_mappings(mappings: &str) -> Vec<[i64; 4]> {
+    const B64: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut ret = vec![];
+    let mut state = [0i64; 4];
+    for (line, segments) in mappings.split(';').enumerate() {
+        state[0] = 0;
+        for segment in segments.split(',').filter(|s| !s.is_empty()) {
+            let (mut fields, mut value, mut shift) = (vec![], 0, 0);
+            for c in segment.chars() {
+                let digit = B64.find(c).unwrap() as i64;
+                value += (digit & 31) << shift;
+                shift += 5;
+                if digit & 32 == 0 {
+                    let v = if value & 1 == 1 {
+                        -(value >> 1)
+                    } else {
+                        value >> 1
+                    };
+                    fields.push(v);
+                    value = 0;
+                    shift = 0;
+                }
+            }
+            state[0] += fields[0];
+            state[2] += fields[2];
+            state[3] += fields[3];
+            ret.push([line as i64, state[0], state[2], state[3]]);
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_source_map_position() {
+    let source = "<div>\n  <p :title='tip'>{{ msg }}</p>\n</div>";
+    let option = CompileOption {
+        source_map: true,
+        ..get_compile_option()
+    };
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler.compile_with_source_map(source, &sfc_info).unwrap();
+    let code = String::from_utf8(output.code).unwrap();
+    let map = output.map.unwrap();
+    let mappings = map.split(r#""mappings":""#).nth(1).unwrap();
+    let decoded = decode_mappings(mappings.trim_end_matches("\"}"));
+    let find_gen = |needle: &str| {
+        let (line, text) = code
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.contains(needle))
+            .unwrap_or_else(|| panic!("{}", code));
+        [line as i64, text.find(needle).unwrap() as i64]
+    };
+    // interpolation content ` msg ` starts at line 1, column 20 of template
+    let [line, col] = find_gen("( msg )");
+    assert!(
+        decoded.contains(&[line, col + 1, 1, 20]),
+        "{}\n{:?}",
+        code,
+        decoded
+    );
+    // `tip` is at line 1, column 13 of template
+    let [line, col] = find_gen("title: tip");
+    assert!(
+        decoded.contains(&[line, col + 7, 1, 13]),
+        "{}\n{:?}",
+        code,
+        decoded
+    );
+}
+
+#[test]
+fn test_preserve_lines() {
+    let tags = ["div", "span", "p", "a", "b", "i", "u", "em", "ul", "li"];
+    let source = tags.map(|t| format!("<{}/>", t)).join("\n");
+    let option = CompileOption {
+        preserve_lines: true,
+        source_map: true,
+        ..get_compile_option()
+    };
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler
+        .compile_with_source_map(&source, &sfc_info)
+        .unwrap();
+    let code = String::from_utf8(output.code).unwrap();
+    assert_valid_js(&code);
+    let map = output.map.unwrap();
+    let mappings = map.split(r#""mappings":""#).nth(1).unwrap();
+    let decoded = decode_mappings(mappings.trim_end_matches("\"}"));
+    let mut last_line = 0;
+    for (src_line, tag) in tags.iter().enumerate() {
+        let call = format!("_createElementVNode(\"{}\"", tag);
+        let line = code.lines().position(|l| l.contains(&call));
+        let line = line.unwrap_or_else(|| panic!("{}", code));
+        // preamble may push early elements down, never up
+        assert!(line >= src_line, "{}: {}", tag, code);
+        assert!(line >= last_line, "{}: {}", tag, code);
+        last_line = line;
+        let (line, src_line) = (line as i64, src_line as i64);
+        assert!(
+            decoded.iter().any(|m| m[0] == line && m[2] == src_line),
+            "{}: {}\n{:?}",
+            tag,
+            code,
+            decoded
+        );
+    }
+    // once past the preamble, elements land on their own template line
+    assert_eq!(last_line, tags.len() - 1, "{}", code);
+}
+
+#[test]
+fn test_directive_source_map() {
+    let source = "<div>\n  <p v-show='ok'>a</p>\n</div>";
+    let option = CompileOption {
+        source_map: true,
+        ..get_compile_option()
+    };
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler.compile_with_source_map(source, &sfc_info).unwrap();
+    let code = String::from_utf8(output.code).unwrap();
+    let map = output.map.unwrap();
+    assert!(map.contains(r#""names":["v-show"]"#), "{}", map);
+    let mappings = map.split(r#""mappings":""#).nth(1).unwrap();
+    let decoded = decode_mappings(mappings.trim_end_matches("\"}"));
+    // `v-show` is at line 1, column 5 of template
+    for needle in ["_withDirectives(", "[_directive_show"] {
+        let (line, text) = code
+            .lines()
+            .enumerate()
+            .find(|(_, l)| l.contains(needle))
+            .unwrap_or_else(|| panic!("{}", code));
+        let col = text.find(needle).unwrap() as i64;
+        let expected = [line as i64, col, 1, 5];
+        assert!(decoded.contains(&expected), "{}\n{:?}", code, decoded);
+    }
+}
+
+#[test]
+fn test_template_v_for_key() {
+    // single element child: template is collapsed and key moves to child
+    let val = base_compile("<template v-for='x in xs' :key='x.id'><div>{{x}}</div></template>");
+    assert!(!val.contains("_Fragment, {"), "{}", val);
+    assert!(val.contains(r#"_createElementVNode("div", {"#), "{}", val);
+    assert!(val.contains("key: x.id"), "{}", val);
+    assert_valid_js(&val);
+    // multiple children: keyed fragment
+    let val = base_compile("<template v-for='x in xs' :key='x.id'><p/><span/></template>");
+    assert!(val.contains("_createElementBlock(_Fragment, {"), "{}", val);
+    assert!(val.contains("key: x.id"), "{}", val);
+    assert!(val.contains("64 /*STABLE_FRAGMENT*/"), "{}", val);
+    assert_valid_js(&val);
+}
+
+#[test]
+fn test_template_v_for_key_with_v_if() {
+    // v-if child keeps the keyed fragment, branches get their own keys
+    let val = base_compile(
+        "<template v-for='x in xs' :key='x.id'><p v-if='x.a'/><span v-else/></template>",
+    );
+    assert!(val.contains("_createElementBlock(_Fragment, {"), "{}", val);
+    assert!(val.contains("key: x.id"), "{}", val);
+    assert!(val.contains("key: 0"), "{}", val);
+    assert!(val.contains("key: 1"), "{}", val);
+    assert_valid_js(&val);
+    // v-if without else renders comment placeholder inside the fragment
+    let val = base_compile("<template v-for='x in xs' :key='x.id'><p v-if='x.a'/></template>");
+    assert!(val.contains("_Fragment, {"), "{}", val);
+    assert!(val.contains("_createCommentVNode('v-if', true)"), "{}", val);
+    assert_valid_js(&val);
+}
+
+#[test]
+fn test_cache_handlers() {
+    let cache_handlers = |mode: ScriptMode| {
+        move |o: &mut CompileOption| {
+            o.mode = mode;
+            o.cache_handlers = true;
+            enable_handlers(o);
+        }
+    };
+    let source = r#"<div @click="count++" @dblclick="onClick"/>"#;
+    let val = compile_with(source, &Default::default(), cache_handlers(module_mode()));
+    assert!(parse_module(&val, 0).errors().is_empty(), "{}", val);
+    let inline = "onClick: _cache[0] || (_cache[0] = $event => (_ctx.count++))";
+    assert!(val.contains(inline), "{}", val);
+    let member = "onDblclick: _cache[1] || (_cache[1] = (...args) => _ctx.onClick?.(...args))";
+    assert!(val.contains(member), "{}", val);
+    // handlers referencing v-for scope must be fresh on each render
+    let source = r#"<div v-for="(item, i) in list" @click="list[i].fn"/>"#;
+    let val = compile_with(source, &Default::default(), cache_handlers(module_mode()));
+    assert!(!val.contains("_cache["), "{}", val);
+    // caching requires prefix_identifier for scope analysis
+    let source = r#"<div @click="count++"/>"#;
+    let val = compile_with(source, &Default::default(), cache_handlers(function_mode()));
+    assert!(!val.contains("_cache["), "{}", val);
+}
+
+#[test]
+fn test_cache_slots_after_pruning() {
+    let mut option = CompileOption {
+        mode: ScriptMode::Module {
+            runtime_module_name: "vue".into(),
+        },
+        cache_handlers: true,
+        manifest: true,
+        ..get_compile_option()
+    };
+    option.directive_converters.insert(V_ON.0, V_ON.1);
+    let source = concat!(
+        r#"<div><p v-if="false" @click="a()"/><p @click="b()"/>"#,
+        r#"<i v-once>{{ c }}</i><p @click="d()"/></div>"#,
+    );
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let ast = compiler.parse(compiler.scan(source));
+    let mut ir = compiler.convert(ast, &sfc_info);
+    compiler.transform(&mut ir, &sfc_info);
+    // drop the dead branch as an optimization pass would
+    match &mut ir.body[0] {
+        IRNode::VNodeCall(div) => assert!(matches!(div.children.remove(0), IRNode::If(_))),
+        _ => panic!("root should be div"),
+    }
+    let ret = compiler.generate_output(ir, &sfc_info, source).unwrap();
+    let val = String::from_utf8(ret.code).unwrap();
+    assert!(!val.contains("_ctx.a()"), "{}", val);
+    assert_eq!(ret.manifest.unwrap().cache_slot_count, 3);
+    // survivors are numbered densely in source order
+    let b = val.find("_cache[0] = $event => (_ctx.b())").unwrap();
+    let c = val.find("_cache[1] = _createElementVNode(\"i\"").unwrap();
+    let d = val.find("_cache[2] = $event => (_ctx.d())").unwrap();
+    assert!(b < c && c < d, "{}", val);
+    assert!(!val.contains("_cache[3]"), "{}", val);
+}
+
+fn assert_valid_ts(val: &str, is_module: bool) {
+    // function mode has return outside function
+    let wrapped = if is_module {
+        val.to_string()
+    } else {
+        format!("function f() {{ {} }}", val)
+    };
+    let syntax = Syntax::default().typescript();
+    let parsed = parse_with_syntax(&wrapped, 0, syntax);
+    assert!(parsed.errors().is_empty(), "{}", val);
+}
+
+fn function_mode() -> ScriptMode {
+    ScriptMode::Function {
+        prefix_identifier: false,
+        runtime_global_name: "Vue".into(),
+    }
+}
+
+fn module_mode() -> ScriptMode {
+    ScriptMode::Module {
+        runtime_module_name: "vue".into(),
+    }
+}
+
+#[test]
+fn test_ts_output() {
+    let source = r#"<comp v-slot="{ item }">
+  <p @click="count++" @dblclick="handle">{{ item }}</p>
+  <input v-model="msg"/>
+</comp>"#;
+    for (mode, is_module) in [(function_mode(), false), (module_mode(), true)] {
+        let val = compile_with(source, &Default::default(), |o| {
+            o.mode = mode;
+            o.is_ts = true;
+            enable_handlers(o);
+        });
+        assert!(val.contains("({ item }: any) => ["), "{}", val);
+        assert!(val.contains("($event: any) => (_ctx.count++)"), "{}", val);
+        assert!(
+            val.contains("($event: any) => ((_ctx.msg) = $event)"),
+            "{}",
+            val
+        );
+        assert!(val.contains("_cache: any[]): VNode {"), "{}", val);
+        assert_valid_ts(&val, is_module);
+    }
+    // plain JS output is not annotated
+    let val = compile_with(source, &Default::default(), |o| {
+        o.mode = function_mode();
+        enable_handlers(o);
+    });
+    assert!(!val.contains(": any"), "{}", val);
+    assert!(val.contains("$event => (count++)"), "{}", val);
+}
+
+#[test]
+fn test_ts_output_per_mode() {
+    use compiler::{codegen::RenderMode, SFCInfo};
+    let source = r#"<p @click="count++">{{ msg }}</p>"#;
+    let inline = SFCInfo {
+        inline: true,
+        ..Default::default()
+    };
+    let cases = [
+        ("ts_function_mode", function_mode(), &SFCInfo::default()),
+        ("ts_module_mode", module_mode(), &SFCInfo::default()),
+        ("ts_inline_mode", module_mode(), &inline),
+    ];
+    for (name, mode, sfc_info) in cases {
+        let val = compile_with(source, sfc_info, |o| {
+            o.mode = mode;
+            o.is_ts = true;
+            enable_handlers(o);
+        });
+        assert!(val.contains("_cache: any[]): VNode"), "{}", val);
+        assert_snapshot!(name, val, source);
+    }
+    let source = "hello {{ msg }}";
+    let val = compile_with(source, &SFCInfo::default(), |o| {
+        o.mode = module_mode();
+        o.render_mode = RenderMode::Ssr;
+        o.is_ts = true;
+    });
+    assert!(val.contains("_attrs: any): void {"), "{}", val);
+    assert_snapshot!("ts_ssr_mode", val, source);
+}
+
+#[test]
+fn test_destructure_param_codegen() {
+    let source = "<p v-for='{ id, user: { name = fallback } } in items'>{{ id + name }}</p>";
+    let val = compile_with(source, &Default::default(), |o| o.mode = module_mode());
+    assert!(
+        val.contains("({ id, user: { name = _ctx.fallback } }) =>"),
+        "{}",
+        val
+    );
+    assert!(val.contains("id + name"), "{}", val);
+    let source = "<comp v-slot='[first, ...rest]'>{{ first }}{{ rest.length }}</comp>";
+    let val = compile_with(source, &Default::default(), |o| o.mode = module_mode());
+    assert!(val.contains("([first, ...rest]) =>"), "{}", val);
+    assert!(!val.contains("_ctx.first"), "{}", val);
+    assert!(!val.contains("_ctx.rest"), "{}", val);
+}
+
+#[test]
+fn test_v_once_codegen() {
+    let val = base_compile("<div v-once>{{ msg }}</div>");
+    assert_valid_js(&val);
+    assert!(val.contains("_cache[0] || ("), "{}", val);
+    assert!(val.contains("_setBlockTracking(-1)"), "{}", val);
+    assert!(
+        val.contains("_cache[0] = _createElementVNode(\"div\""),
+        "{}",
+        val
+    );
+    assert!(val.contains("_setBlockTracking(1)"), "{}", val);
+
+    let val = base_compile("<comp v-once/>");
+    assert_valid_js(&val);
+    assert!(
+        val.contains("_cache[0] = _createVNode(_component_comp"),
+        "{}",
+        val
+    );
+
+    // nested v-once does not double wrap
+    let val = base_compile("<div v-once><p v-once/></div>");
+    assert_valid_js(&val);
+    assert!(!val.contains("_cache[1]"), "{}", val);
+
+    // the whole conditional is cached
+    let val = base_compile("<div v-if='a' v-once/><p v-else/>");
+    assert_valid_js(&val);
+    assert!(val.contains("_cache[0] = (a)"), "{}", val);
+
+    // all list items would share one slot, so v-once is dropped
+    let val = base_compile("<template v-for='a in b'><p v-once/></template>");
+    assert_valid_js(&val);
+    assert!(!val.contains("_cache["), "{}", val);
+}
+
+#[test]
+fn test_v_once_hoisted() {
+    let source = "<div><p v-once class='a'>static</p>{{ msg }}</div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    assert!(val.contains("const _hoisted_1 = "), "{}", val);
+    let body = val.rsplit("return ").next().unwrap();
+    assert!(!body.contains("static"), "{}", val);
+    assert!(!val.contains("_cache["), "{}", val);
+    assert!(!val.contains("setBlockTracking"), "{}", val);
+    // bindings are evaluated per instance, so they stay cached
+    let source = "<div><p v-once>{{ msg }}</p>{{ msg }}</div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    assert!(val.contains("_cache[0] = "), "{}", val);
+    // ref needs patching, which is only known once patch flags are final
+    let source = "<div><p v-once ref='r'>static</p>{{ msg }}</div>";
+    let val = compile_with(source, &Default::default(), enable_hoist);
+    assert_valid_js(&val);
+    assert!(val.contains("_cache[0] = "), "{}", val);
+    assert!(val.contains("setBlockTracking"), "{}", val);
+}
+
+#[test]
+fn test_nested_cache_index() {
+    // outer cache takes its slot before the inner one
+    let val = base_compile("<div v-memo='[a]'><p v-once>{{ b }}</p></div>");
+    assert_valid_js(&val);
+    assert!(val.contains("_cache[1] = "), "{}", val);
+    assert!(val.contains(", _cache, 0)"), "{}", val);
+}
+
+#[test]
+fn test_emit_jsdoc() {
+    let source = "<div><p>static</p><span class='a'>{{ msg }}</span></div>";
+    let jsdoc = |mode: ScriptMode, emit_jsdoc: bool| {
+        move |o: &mut CompileOption| {
+            o.mode = mode;
+            o.emit_jsdoc = emit_jsdoc;
+            enable_hoist(o);
+        }
+    };
+    let module = ScriptMode::Module {
+        runtime_module_name: "@vue/runtime-dom".into(),
+    };
+    let val = compile_with(source, &Default::default(), jsdoc(module, true));
+    let parsed = parse_module(&val, 0);
+    assert!(parsed.errors().is_empty(), "{}", val);
+    let ctx = "@param {import('@vue/runtime-dom').ComponentPublicInstance} _ctx";
+    assert!(val.contains(ctx), "{}", val);
+    assert!(val.contains("@param {any[]} _cache"), "{}", val);
+    assert!(
+        val.contains("@returns {import('@vue/runtime-dom').VNode}"),
+        "{}",
+        val
+    );
+    assert!(val.contains(" */\nexport function render("), "{}", val);
+    let node = "/** @type {import('@vue/runtime-dom').VNode} */\nconst _hoisted_";
+    assert!(val.contains(node), "{}", val);
+    let props = "/** @type {import('@vue/runtime-dom').VNodeProps} */\nconst _hoisted_";
+    assert!(val.contains(props), "{}", val);
+
+    // function mode has no module name, type imports use vue
+    let val = compile_with(source, &Default::default(), jsdoc(function_mode(), true));
+    assert_valid_js(&val);
+    assert!(
+        val.contains("import('vue').ComponentPublicInstance"),
+        "{}",
+        val
+    );
+    assert!(val.contains(" */\nreturn function render("), "{}", val);
+
+    let val = compile_with(source, &Default::default(), jsdoc(function_mode(), false));
+    assert!(!val.contains("@param"), "{}", val);
+    assert!(!val.contains("@type"), "{}", val);
+}
+
+#[test]
+fn test_v_memo_codegen() {
+    let source = "<div v-memo='[a, b]'>{{ a }}</div>";
+    let val = compile_with(source, &Default::default(), |o| o.mode = module_mode());
+    assert!(parse_module(&val, 0).errors().is_empty(), "{}", val);
+    assert!(
+        val.contains("_withMemo([_ctx.a, _ctx.b], () => ("),
+        "{}",
+        val
+    );
+    assert!(val.contains("), _cache, 0)"), "{}", val);
+
+    let source = "<p v-for='item in 3' :key='item' v-memo='[item === sel]'>{{ item }}</p>";
+    let val = compile_with(source, &Default::default(), |o| o.mode = module_mode());
+    assert!(parse_module(&val, 0).errors().is_empty(), "{}", val);
+    // stable fragment still disables tracking since items can be reused
+    assert!(val.contains("_openBlock(true)"), "{}", val);
+    assert!(val.contains("const _memo=([item === _ctx.sel])"), "{}", val);
+    assert!(val.contains("_cached.key === item"), "{}", val);
+    assert!(val.contains("_isMemoSame(_cached, _memo)"), "{}", val);
+    assert!(val.contains("}, _cache, 0)"), "{}", val);
+    assert!(!val.contains("_withMemo"), "{}", val);
+}
+
+#[test]
+fn test_nested_v_memo() {
+    let val = base_compile("<div v-memo='[a]'><p v-memo='[b]'>{{ b }}</p></div>");
+    assert_valid_js(&val);
+    assert!(val.contains("_withMemo([a], () => "), "{}", val);
+    assert!(val.contains("_withMemo([b], () => "), "{}", val);
+    // inner memo is written later so it gets the next slot
+    assert!(val.contains(", _cache, 0)"), "{}", val);
+    assert!(val.contains(", _cache, 1)"), "{}", val);
+
+    let source = "<div v-for='i in list' :key='i' v-memo='[i]'><p v-memo='[sel]'/></div>";
+    let val = base_compile(source);
+    assert_valid_js(&val);
+    assert!(
+        val.contains("if (_cached && _cached.key === i && "),
+        "{}",
+        val
+    );
+    assert!(val.contains("_withMemo([sel], () => "), "{}", val);
+    assert!(val.contains(", _cache, 1)"), "{}", val);
+}
+
+#[test]
+fn test_component_is_setup_binding() {
+    use compiler::{BindingMetadata, BindingTypes, SFCInfo};
+    use rustc_hash::FxHashMap;
+    let mut map = FxHashMap::default();
+    map.insert("MyComp", BindingTypes::SetupConst);
+    map.insert("name", BindingTypes::SetupConst);
+    let bindings = BindingMetadata::new(map, true).with_components(vec!["MyComp"]);
+    let inline = SFCInfo {
+        inline: true,
+        binding_metadata: bindings.clone(),
+        ..Default::default()
+    };
+    let setup = SFCInfo {
+        binding_metadata: bindings,
+        ..Default::default()
+    };
+    let module = |o: &mut CompileOption| o.mode = module_mode();
+    let val = compile_with(r#"<component :is="MyComp" :msg="name"/>"#, &inline, module);
+    assert!(!val.contains("resolveDynamicComponent"), "{}", val);
+    // known component is not a dynamic block, same as vue-next
+    assert!(val.contains("_createVNode(MyComp, {"), "{}", val);
+    let val = compile_with(r#"<component :is="MyComp"/>"#, &setup, module);
+    assert_valid_js(&val);
+    assert!(!val.contains("resolveDynamicComponent"), "{}", val);
+    assert!(val.contains(r#"_createVNode($setup["MyComp"])"#), "{}", val);
+    // bindings not known as component may be component name string
+    let val = compile_with(r#"<component :is="name"/>"#, &inline, module);
+    assert!(val.contains("_resolveDynamicComponent(name)"), "{}", val);
+    let val = compile_with(r#"<component :is="name"/>"#, &setup, module);
+    assert!(
+        val.contains("_resolveDynamicComponent($setup.name)"),
+        "{}",
+        val
+    );
+}
+
+#[test]
+fn test_inline_render() {
+    use compiler::{BindingMetadata, BindingTypes, SFCInfo};
+    use rustc_hash::FxHashMap;
+    let inline = |ty: BindingTypes| {
+        let mut map = FxHashMap::default();
+        map.insert("count", ty);
+        SFCInfo {
+            inline: true,
+            binding_metadata: BindingMetadata::new(map, true),
+            ..Default::default()
+        }
+    };
+    let module = |o: &mut CompileOption| {
+        o.mode = module_mode();
+        enable_handlers(o);
+    };
+    let source = r#"{{count}} <button @click="count++">+</button>"#;
+    let val = compile_with(source, &inline(BindingTypes::SetupRef), module);
+    assert!(val.contains("(_ctx, _cache) => {"), "{}", val);
+    assert!(!val.contains("function render"), "{}", val);
+    assert!(!val.contains("with ("), "{}", val);
+    assert!(!val.contains("export "), "{}", val);
+    assert!(val.contains("_toDisplayString(count.value)"), "{}", val);
+    assert!(val.contains("$event => (count.value++)"), "{}", val);
+
+    let val = compile_with(source, &inline(BindingTypes::SetupLet), module);
+    assert!(val.contains("_toDisplayString(_unref(count))"), "{}", val);
+    assert!(
+        val.contains("_isRef(count)? count.value++: count++"),
+        "{}",
+        val
+    );
+    assert!(val.contains("unref as _unref"), "{}", val);
+    assert!(val.contains("isRef as _isRef"), "{}", val);
+}
+
+#[test]
+fn test_decode_entities_codegen() {
+    let source =
+        r#"<p title="a &amp; b" data-url="?a=1&copy=2">&lt;b&gt; &copy; &notanentity; & x</p>"#;
+    let val = base_compile(source);
+    assert_valid_js(&val);
+    assert!(val.contains(r#"title: "a & b""#), "{}", val);
+    assert!(val.contains(r#""data-url": "?a=1&copy=2""#), "{}", val);
+    assert!(val.contains(r#""<b> © ¬anentity; & x""#), "{}", val);
+}
+
+/// sample plugin renaming static `data-test` attributes
+struct DataTestPass;
+impl<'a> CorePass<BaseConvertInfo<'a>> for DataTestPass {
+    fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+        let props = match &mut vn.props {
+            Some(Js::Props(props)) => props,
+            _ => return,
+        };
+        for (key, val) in props.iter_mut() {
+            let is_static = matches!(val, Js::StrLit(_));
+            if matches!(key, Js::StrLit(k) if is_static && k.raw == "data-test") {
+                *key = Js::str_lit("data-testid");
+            }
+        }
+    }
+}
+
+#[test]
+fn test_plugin_pass() {
+    let sfc_info = Default::default();
+    let compiler = CompilerBuilder::new(Vec::new, get_base_passes, get_compile_option())
+        .add_pre_pass(|_, _| DataTestPass)
+        .build();
+    let source = r#"<div data-test="root"><p data-test="item"/><p :data-test="id"/></div>"#;
+    let ret = compiler.compile(source, &sfc_info).unwrap();
+    let val = String::from_utf8(ret).unwrap();
+    assert_valid_js(&val);
+    assert!(val.contains(r#""data-testid": "root""#), "{}", val);
+    assert!(val.contains(r#""data-testid": "item""#), "{}", val);
+    // dynamic bindings are left alone
+    assert!(val.contains(r#""data-test": id"#), "{}", val);
+}
+
+#[test]
+fn test_slots_types() {
+    let source = r#"<comp>
+  <template #header="{ t }">{{ t }}</template>
+  <template #footer="props">{{ props.year }}</template>
+  <template v-if="ok" #row="{ id }">{{ id }}</template>
+</comp>"#;
+    let slots_types = |is_ts: bool| {
+        move |o: &mut CompileOption| {
+            o.mode = module_mode();
+            o.is_ts = is_ts;
+            let types = &mut o.slots_types;
+            types.insert("header".into(), "{ t: string }".into());
+            types.insert("row".into(), "{ id: number }".into());
+        }
+    };
+    let inline = compiler::SFCInfo {
+        inline: true,
+        ..Default::default()
+    };
+    let val = compile_with(source, &inline, slots_types(true));
+    assert!(val.contains("({ t }: { t: string }) => ["), "{}", val);
+    assert!(val.contains("({ id }: { id: number }) => ["), "{}", val);
+    // unknown slot falls back to untyped param
+    assert!(val.contains("(props: any) => ["), "{}", val);
+    // non-inline render cannot see script types
+    let val = compile_with(source, &Default::default(), slots_types(true));
+    assert!(val.contains("({ t }: any) => ["), "{}", val);
+    // plain JS output ignores slot types
+    let val = compile_with(source, &inline, slots_types(false));
+    assert!(val.contains("({ t }) => ["), "{}", val);
+    assert!(!val.contains("string"), "{}", val);
+}
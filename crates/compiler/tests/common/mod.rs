@@ -1,8 +1,8 @@
 use compiler::converter::BaseConvertInfo;
 pub use compiler::error::NoopErrorHandler as TestErrorHandler;
 use compiler::transformer::CorePass;
-pub use compiler::{Position, SourceLocation};
-use compiler::compiler::{BaseCompiler, CompileOption, get_base_passes};
+pub use compiler::{Position, SourceLocation, SFCInfo};
+use compiler::compiler::{BaseCompiler, CompileOption, TemplateCompiler, get_base_passes};
 use compiler::scanner::TextMode;
 use serde::Serialize;
 use std::rc::Rc;
@@ -25,10 +25,10 @@ fn get_text_mode(s: &str) -> TextMode {
     }
 }
 
-fn get_compile_option() -> CompileOption {
+pub fn get_compile_option() -> CompileOption {
     CompileOption {
         get_text_mode,
-        is_native_tag: |s| s != "comp",
+        is_native_tag: |s, _| s != "comp",
         error_handler: Rc::new(TestErrorHandler),
         ..Default::default()
     }
@@ -38,3 +38,15 @@ pub fn get_compiler<'a>() -> BaseCompiler<'a, impl CorePass<BaseConvertInfo<'a>>
     let dest = Vec::new;
     BaseCompiler::new(dest, get_base_passes, get_compile_option())
 }
+
+/// Compiles with test options adjusted by `config`, e.g. `|o| o.hoist_static = true`.
+pub fn compile_with<'a, F>(source: &'a str, sfc_info: &'a SFCInfo<'a>, config: F) -> String
+where
+    F: FnOnce(&mut CompileOption),
+{
+    let mut option = get_compile_option();
+    config(&mut option);
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let ret = compiler.compile(source, sfc_info).unwrap();
+    String::from_utf8(ret).unwrap()
+}
@@ -0,0 +1,42 @@
+use super::common::get_compiler;
+use vue_compiler_core as compiler;
+use compiler::compiler::TemplateCompiler;
+use std::thread;
+
+const FIXTURE: &str = r#"
+<div id="app" :class="{ active }" @click="onClick">
+  <MyHeader v-focus :title="title">
+    <template #left="{ item }">{{ item.name }}</template>
+    <template v-for="n in slots" #[n]>{{ n }}</template>
+  </MyHeader>
+  <ul>
+    <li v-for="(todo, i) in todos" :key="todo.id" :data-x="i">{{ todo.text }}</li>
+  </ul>
+  <p v-if="ok">yes</p>
+  <p v-else-if="maybe">maybe</p>
+  <my-footer v-else :text="text" />
+  <slot name="tail" :msg="msg">fallback</slot>
+  <!-- comment -->
+</div>
+"#;
+
+fn compile_fixture() -> String {
+    // compiler holds Rc internally, so each thread owns its own instance
+    let sfc_info = Default::default();
+    let compiler = get_compiler();
+    let ret = compiler.compile(FIXTURE, &sfc_info).unwrap();
+    String::from_utf8(ret).unwrap()
+}
+
+#[test]
+fn test_concurrent_compile_is_deterministic() {
+    let expected = compile_fixture();
+    let handles: Vec<_> = (0..16)
+        .map(|_| thread::spawn(|| (0..20).map(|_| compile_fixture()).collect::<Vec<_>>()))
+        .collect();
+    for handle in handles {
+        for output in handle.join().unwrap() {
+            assert_eq!(output, expected);
+        }
+    }
+}
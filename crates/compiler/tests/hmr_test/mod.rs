@@ -0,0 +1,59 @@
+use vue_compiler_core as compiler;
+use super::common::get_compile_option;
+use compiler::compiler::{get_base_passes, BaseCompiler, CompileOption};
+use compiler::converter::v_on::V_ON;
+use compiler::hmr::{compare_renders, template_hash, HmrAction};
+
+fn compare(old: &str, new: &str) -> HmrAction {
+    let sfc_info = Default::default();
+    let mut option = CompileOption {
+        hmr: true,
+        ..get_compile_option()
+    };
+    option.directive_converters.insert(V_ON.0, V_ON.1);
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let old = compiler.compile_with_source_map(old, &sfc_info).unwrap();
+    let new = compiler.compile_with_source_map(new, &sfc_info).unwrap();
+    compare_renders(&old, &new)
+}
+
+#[test]
+fn test_whitespace_only_change() {
+    let old = "<div>\n  <p>{{ msg }}</p>\n</div>";
+    let new = "<div><p>{{ msg }}</p></div>";
+    assert_eq!(template_hash(old), template_hash(new));
+    // leading space of text is rendered
+    assert_ne!(template_hash("<p> a</p>"), template_hash("<p>a</p>"));
+}
+
+#[test]
+fn test_rerender_only() {
+    let old = "<div><p>{{ msg }}</p></div>";
+    let new = "<div><p>{{ msg }} {{ count }}</p></div>";
+    assert_ne!(template_hash(old), template_hash(new));
+    assert_eq!(compare(old, new), HmrAction::RerenderOnly);
+}
+
+#[test]
+fn test_reload() {
+    let old = "<div><p>{{ msg }}</p></div>";
+    let new = "<div><p>{{ msg }}</p><slot name='footer'/></div>";
+    assert_eq!(compare(old, new), HmrAction::Reload);
+    let new = "<div><p @click='$emit(\"close\")'>{{ msg }}</p></div>";
+    assert_eq!(compare(old, new), HmrAction::Reload);
+}
+
+#[test]
+fn test_emit_in_text() {
+    let old = "<div><p>{{ msg }}</p></div>";
+    let new = "<div><p title='$emit'>{{ msg }} $emit</p></div>";
+    assert_eq!(compare(old, new), HmrAction::RerenderOnly);
+}
+
+#[test]
+fn test_hmr_opt_in() {
+    let sfc_info = Default::default();
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, get_compile_option());
+    let output = compiler.compile_with_source_map("<p/>", &sfc_info).unwrap();
+    assert!(output.hmr.is_none());
+}
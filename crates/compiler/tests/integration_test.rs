@@ -1,6 +1,10 @@
 mod codegen_test;
 mod common;
+mod concurrency_test;
 mod converter_test;
+mod hmr_test;
+mod manifest_test;
 mod parser_test;
 mod scanner_test;
+mod text_test;
 mod transformer_test;
@@ -0,0 +1,146 @@
+use vue_compiler_core as compiler;
+use super::common::{get_compile_option, get_compiler};
+use compiler::compiler::{get_base_passes, BaseCompiler, CompileOption};
+use compiler::error::VecErrorHandler;
+use compiler::manifest::Manifest;
+use compiler::transformer::hoist_static::StaticSerializer;
+use std::rc::Rc;
+
+fn manifest_of(source: &str) -> (Manifest, usize) {
+    let sfc_info = Default::default();
+    let option = CompileOption {
+        manifest: true,
+        ..get_compile_option()
+    };
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler.compile_with_source_map(source, &sfc_info).unwrap();
+    (output.manifest.unwrap(), output.code.len())
+}
+
+#[test]
+fn test_manifest_simple() {
+    let (manifest, code_len) = manifest_of("<div>{{ msg }}</div>");
+    let expected = format!(
+        concat!(
+            r#"{{"schemaVersion":1,"inputHash":"506a25045d59b579","optionsFingerprint":"{}","#,
+            r#""bindingsFingerprint":"{}","#,
+            r#""helpers":["createElementVNode","toDisplayString"],"components":[],"directives":[],"#,
+            r#""features":[],"isStatic":false,"hoistCount":0,"cacheSlotCount":0,"errorCount":0,"outputBytes":{}}}"#,
+        ),
+        manifest.options_fingerprint, manifest.bindings_fingerprint, code_len,
+    );
+    assert_eq!(manifest.to_json(), expected);
+}
+
+#[test]
+fn test_manifest_entities() {
+    // v-once inside v-for is ignored, so keep it outside
+    let source = "<comp v-for='i in list' v-focus>{{ i }}</comp><p v-once>{{ msg }}</p>";
+    let (manifest, code_len) = manifest_of(source);
+    assert_eq!(manifest.components, vec!["comp"]);
+    assert_eq!(manifest.directives, vec!["focus"]);
+    assert_eq!(
+        manifest.features,
+        vec!["v-for", "directives", "slots", "v-once"]
+    );
+    assert!(manifest.helpers.contains(&"renderList"), "{:?}", manifest);
+    assert!(
+        manifest.helpers.contains(&"resolveComponent"),
+        "{:?}",
+        manifest
+    );
+    assert_eq!(manifest.cache_slot_count, 1, "{:?}", manifest);
+    assert_eq!(manifest.output_bytes, code_len);
+    // same input gives identical manifest
+    assert_eq!(manifest_of(source).0, manifest);
+}
+
+#[test]
+fn test_manifest_schema() {
+    let (manifest, _) = manifest_of("<p/>");
+    let json = manifest.to_json();
+    let keys = [
+        "schemaVersion",
+        "inputHash",
+        "optionsFingerprint",
+        "bindingsFingerprint",
+        "helpers",
+        "components",
+        "directives",
+        "features",
+        "isStatic",
+        "hoistCount",
+        "cacheSlotCount",
+        "errorCount",
+        "outputBytes",
+    ];
+    let mut last = 0;
+    for key in keys {
+        let quoted = format!("\"{}\":", key);
+        let pos = json
+            .find(&quoted)
+            .unwrap_or_else(|| panic!("{} in {}", key, json));
+        assert!(pos >= last, "{} out of order in {}", key, json);
+        last = pos;
+    }
+    assert_eq!(json.matches("\":").count(), keys.len(), "{}", json);
+}
+
+#[test]
+fn test_manifest_is_static() {
+    let (manifest, _) = manifest_of("<p class='a'>hello</p><div><span>world</span></div>");
+    assert!(manifest.is_static, "{:?}", manifest);
+    let (manifest, _) = manifest_of("<p>hello</p><p>{{ world }}</p>");
+    assert!(!manifest.is_static, "{:?}", manifest);
+}
+
+#[test]
+fn test_options_fingerprint() {
+    let base = get_compile_option().fingerprint();
+    assert_eq!(base, get_compile_option().fingerprint());
+    let hoist = CompileOption {
+        hoist_static: true,
+        ..get_compile_option()
+    };
+    assert_ne!(base, hoist.fingerprint());
+    let serializer: StaticSerializer = Rc::new(|_| String::new());
+    let custom = |static_serializer| CompileOption {
+        static_serializer,
+        ..get_compile_option()
+    };
+    let with_serializer = custom(Some(serializer.clone())).fingerprint();
+    assert_ne!(base, with_serializer);
+    assert_eq!(
+        with_serializer,
+        custom(Some(serializer.clone())).fingerprint()
+    );
+    let other: StaticSerializer = Rc::new(|_| String::new());
+    assert_ne!(with_serializer, custom(Some(other)).fingerprint());
+}
+
+#[test]
+fn test_manifest_error_count() {
+    let sfc_info = Default::default();
+    let err_handle = Rc::new(VecErrorHandler::default());
+    let option = CompileOption {
+        error_handler: err_handle.clone(),
+        manifest: true,
+        ..get_compile_option()
+    };
+    let compiler = BaseCompiler::new(Vec::new, get_base_passes, option);
+    let output = compiler
+        .compile_with_source_map("<div><p></div>", &sfc_info)
+        .unwrap();
+    let manifest = output.manifest.unwrap();
+    assert_eq!(manifest.error_count, err_handle.errors().len());
+    assert!(manifest.error_count > 0);
+}
+
+#[test]
+fn test_manifest_opt_in() {
+    let sfc_info = Default::default();
+    let output = get_compiler()
+        .compile_with_source_map("<p/>", &sfc_info)
+        .unwrap();
+    assert!(output.manifest.is_none());
+}
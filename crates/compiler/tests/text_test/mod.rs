@@ -0,0 +1,63 @@
+//! Text vnode splitting. Each `createTextVNode` maps to one DOM text node
+//! in SSR output, so the count must match upstream or hydration mismatches.
+use super::common::compile_with;
+
+fn compile(source: &str, is_dev: bool) -> String {
+    compile_with(source, &Default::default(), |o| o.is_dev = is_dev)
+}
+
+fn text_count(source: &str, is_dev: bool) -> usize {
+    compile(source, is_dev).matches("_createTextVNode(").count()
+}
+
+#[test]
+fn test_text_adjacency() {
+    // (template, text vnodes by upstream)
+    let cases = [
+        ("<div>a {{ b }} c<p/></div>", 1),
+        ("<div><p/>a {{ b }} c</div>", 1),
+        ("<div>{{ a }}{{ b }}<p/></div>", 1),
+        ("<div>a<p/>b</div>", 2),
+        ("<div>{{ a }}<p/>{{ b }}</div>", 2),
+        ("<div>a<p/>{{ b }} c<span/>d</div>", 3),
+        ("<div><p v-if='x'/>a {{ b }}<p/></div>", 1),
+        ("<div>a<p v-if='x'/><p v-else/>b</div>", 2),
+        ("<div>a<slot/>b</div>", 2),
+        ("<div>a<!--c-->b</div>", 2),
+        ("<comp>a {{ b }}<p/>c</comp>", 2),
+        ("<div v-for='i in l'>{{ i }}<p/>{{ i }} a</div>", 2),
+    ];
+    for (case, expected) in cases {
+        assert_eq!(text_count(case, true), expected, "{}", compile(case, true));
+    }
+}
+
+#[test]
+fn test_text_around_removed_comment() {
+    // comment is removed in production but still splits the texts
+    let cases = [
+        ("<div>a<!--c-->b</div>", 2),
+        ("<div>{{ a }}<!--c-->{{ b }}<p/></div>", 2),
+        ("<div>a <!--c--> {{ b }}<p/></div>", 2),
+        ("<div>a<!--c--><!--d-->b{{ c }}<p/></div>", 2),
+    ];
+    for (case, expected) in cases {
+        let val = compile(case, false);
+        assert!(!val.contains("_createCommentVNode"), "{}", val);
+        assert_eq!(text_count(case, false), expected, "{}", val);
+    }
+    let val = compile("<div>a<!--c-->b</div>", false);
+    assert!(val.contains("_createTextVNode(\"a\")"), "{}", val);
+    assert!(val.contains("_createTextVNode(\"b\")"), "{}", val);
+}
+
+#[test]
+fn test_single_text_child() {
+    // sole text child is set as textContent without text vnode
+    for case in ["<div>a {{ b }} c</div>", "<div>{{ a }}{{ b }}</div>"] {
+        let val = compile(case, true);
+        assert_eq!(text_count(case, true), 0, "{}", val);
+    }
+    // removed comment leaves two text children
+    assert_eq!(text_count("<div>a<!--c-->b{{ d }}</div>", false), 2);
+}
@@ -10,16 +10,16 @@ pub fn convert_v_html<'a>(
     e: &Element<'a>,
     eh: &dyn ErrorHandler,
 ) -> CoreDirConvRet<'a> {
-    let error_kind = CompilationErrorKind::extended(DomError::VHtmlNoExpression);
-    if let Some(err) = dir.check_empty_expr(error_kind) {
-        eh.on_error(err);
-        return DirectiveConvertResult::Dropped;
-    }
+    // children are not converted, see convert_element
     if !e.children.is_empty() {
         let error = CompilationError::extended(DomError::VHtmlWithChildren)
             .with_location(dir.location.clone());
         eh.on_error(error);
-        // TODO remove element children
+    }
+    let error_kind = CompilationErrorKind::extended(DomError::VHtmlNoExpression);
+    if let Some(err) = dir.check_empty_expr(error_kind) {
+        eh.on_error(err);
+        return DirectiveConvertResult::Dropped;
     }
     let val = dir.expression.take().unwrap().content;
     let props = vec![(Js::str_lit("innerHTML"), Js::simple(val))];
@@ -29,3 +29,58 @@ pub fn convert_v_html<'a>(
     }
 }
 pub const V_HTML: DirectiveConverter = ("html", convert_v_html);
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::compile_with_errors;
+
+    #[test]
+    fn test_v_html() {
+        let (val, errors) = compile_with_errors("<div v-html='raw'/>");
+        assert!(errors.is_empty(), "{}", errors[0]);
+        assert!(val.contains("innerHTML: raw"), "{}", val);
+        assert!(val.contains(r#"8 /*PROPS*/, ["innerHTML"]"#), "{}", val);
+    }
+
+    #[test]
+    fn test_children_conflict() {
+        let (val, errors) = compile_with_errors("<div v-html='raw'><p>child</p></div>");
+        assert_eq!(errors.len(), 1);
+        let msg = errors[0].to_string();
+        assert!(
+            msg.contains("v-html will override element children"),
+            "{}",
+            msg
+        );
+        assert_eq!(errors[0].location.start.offset, 5);
+        assert!(!val.contains("\"p\""), "{}", val);
+    }
+
+    #[test]
+    fn test_bound_inner_html_keeps_children() {
+        let (val, errors) = compile_with_errors("<div :innerHTML='raw'><p>child</p></div>");
+        assert!(errors.is_empty(), "{}", errors[0]);
+        assert!(val.contains("innerHTML: raw"), "{}", val);
+        assert!(val.contains("\"p\""), "{}", val);
+    }
+
+    #[test]
+    fn test_with_other_props() {
+        let (val, errors) = compile_with_errors("<div class='a' :id='foo' v-html='raw'/>");
+        assert!(errors.is_empty(), "{}", errors[0]);
+        assert!(val.contains(r#"class: "a","#), "{}", val);
+        assert!(
+            val.contains(r#"8 /*PROPS*/, ["id", "innerHTML"]"#),
+            "{}",
+            val
+        );
+    }
+
+    #[test]
+    fn test_content_prop_conflict() {
+        let (val, errors) = compile_with_errors("<div :innerHTML='a' v-html='b'/>");
+        assert!(errors.is_empty(), "{}", errors[0]);
+        assert!(val.contains("innerHTML: a"), "{}", val);
+        assert!(!val.contains("innerHTML: b"), "{}", val);
+    }
+}
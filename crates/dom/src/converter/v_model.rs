@@ -3,7 +3,7 @@ use compiler::converter::{CompilationError, v_model::convert_v_model_event};
 use compiler::flags::RuntimeHelper;
 use compiler::ir::JsExpr as Js;
 use compiler::parser::{ElemProp, DirectiveArg};
-use compiler::util::find_prop;
+use compiler::util::{find_prop, prop_finder};
 use super::DirectiveConvertResult;
 use crate::extension::{dom_helper as dh, DomError};
 use crate::options::is_native_tag;
@@ -63,7 +63,7 @@ type RuntimeResult = Result<RuntimeHelper, CompilationError>;
 fn compute_v_model_runtime(e: &Element, dir: &Directive) -> RuntimeResult {
     let tag = e.tag_name;
     // tag is not component nor native, so it must be custom
-    let is_custom_element = !is_native_tag(tag);
+    let is_custom_element = !is_native_tag(tag, e.namespace);
     if !["input", "select", "textarea"].contains(&tag) && !is_custom_element {
         let error = CompilationError::extended(DomError::VModelOnInvalidElement)
             .with_location(dir.location.clone());
@@ -77,7 +77,8 @@ fn compute_v_model_runtime(e: &Element, dir: &Directive) -> RuntimeResult {
     }
     debug_assert!(tag == "input" || is_custom_element);
     // input or custom_element
-    let ty = match find_prop(e, "type") {
+    // sibling `:type` is converted before v-model and its expression is taken
+    let ty = match prop_finder(e, "type").allow_empty().find() {
         Some(ty) => ty,
         None if has_dynamic_v_bind(e) => return Ok(dh::V_MODEL_DYNAMIC),
         None => return Ok(dh::V_MODEL_TEXT),
@@ -85,7 +86,10 @@ fn compute_v_model_runtime(e: &Element, dir: &Directive) -> RuntimeResult {
     let ty = ty.get_ref();
     let val = match ty {
         ElemProp::Dir(..) => return Ok(dh::V_MODEL_DYNAMIC),
-        ElemProp::Attr(attr) => attr.value.as_ref().expect("non empty"),
+        ElemProp::Attr(attr) => match &attr.value {
+            Some(val) => val,
+            None => return Ok(dh::V_MODEL_TEXT),
+        },
     };
     match val.content.raw {
         "radio" => Ok(dh::V_MODEL_RADIO),
@@ -115,3 +119,117 @@ pub fn has_dynamic_v_bind(e: &Element) -> bool {
 }
 
 pub const V_MODEL: DirectiveConverter = ("model", convert_v_model);
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::compile_with;
+    use compiler::codegen::ScriptMode;
+    use compiler::error::VecErrorHandler;
+    use std::rc::Rc;
+
+    fn compile_with_errors(s: &str, prefix_identifier: bool) -> (String, Vec<String>) {
+        let eh = Rc::new(VecErrorHandler::default());
+        let val = compile_with(s, eh.clone(), |o| {
+            o.mode = ScriptMode::Function {
+                prefix_identifier,
+                runtime_global_name: "Vue".into(),
+            }
+        });
+        let errors = eh.errors().iter().map(|e| e.to_string()).collect();
+        (val, errors)
+    }
+    fn compile(s: &str) -> String {
+        let (code, errors) = compile_with_errors(s, false);
+        assert!(errors.is_empty(), "{:?}", errors);
+        code
+    }
+
+    #[test]
+    fn test_text_input() {
+        let val = compile("<input v-model='foo'/>");
+        assert!(
+            val.contains("\"onUpdate:modelValue\": $event => ((foo) = $event)"),
+            "{}",
+            val
+        );
+        assert!(val.contains("[_vModelText, foo]"), "{}", val);
+        assert!(!val.contains("modelValue: foo"), "{}", val);
+        let val = compile("<textarea v-model='foo'/>");
+        assert!(val.contains("[_vModelText, foo]"), "{}", val);
+        let val = compile("<select v-model='foo'/>");
+        assert!(val.contains("[_vModelSelect, foo]"), "{}", val);
+    }
+
+    #[test]
+    fn test_checkbox() {
+        let val = compile("<input type='checkbox' v-model='foo'/>");
+        assert!(val.contains("[_vModelCheckbox, foo]"), "{}", val);
+        let val = compile("<input type='radio' v-model='foo'/>");
+        assert!(val.contains("[_vModelRadio, foo]"), "{}", val);
+    }
+
+    #[test]
+    fn test_dynamic_type() {
+        let val = compile("<input :type='ty' v-model='foo'/>");
+        assert!(val.contains("[_vModelDynamic, foo]"), "{}", val);
+        let val = compile("<input v-bind='attrs' v-model='foo'/>");
+        assert!(val.contains("[_vModelDynamic, foo]"), "{}", val);
+    }
+
+    #[test]
+    fn test_component_arg() {
+        let val = compile("<comp v-model:title='foo'/>");
+        assert!(val.contains("title: foo,"), "{}", val);
+        assert!(
+            val.contains("\"onUpdate:title\": $event => ((foo) = $event)"),
+            "{}",
+            val
+        );
+        assert!(!val.contains("_withDirectives"), "{}", val);
+        let val = compile("<comp v-model='foo'/>");
+        assert!(val.contains("modelValue: foo,"), "{}", val);
+    }
+
+    #[test]
+    fn test_modifiers() {
+        let val = compile("<input v-model.lazy.trim='foo'/>");
+        assert!(val.contains("[_vModelText, foo, void 0, {"), "{}", val);
+        assert!(val.contains("lazy: true,"), "{}", val);
+        assert!(val.contains("trim: true,"), "{}", val);
+        let val = compile("<comp v-model:title.number='foo'/>");
+        assert!(val.contains("\"titleModifiers\": {"), "{}", val);
+        assert!(val.contains("number: true,"), "{}", val);
+    }
+
+    #[test]
+    fn test_prefix_model_value() {
+        let (val, errors) = compile_with_errors("<input v-model='foo'/>", true);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert!(val.contains("$event => ((_ctx.foo) = $event)"), "{}", val);
+        assert!(val.contains("[_vModelText, _ctx.foo]"), "{}", val);
+    }
+
+    #[test]
+    fn test_v_model_errors() {
+        let (_, errors) = compile_with_errors("<input v-model/>", false);
+        assert!(errors[0].contains("missing expression"), "{:?}", errors);
+        let (_, errors) = compile_with_errors("<input type='file' v-model='f'/>", false);
+        assert!(errors[0].contains("file inputs"), "{:?}", errors);
+        let (_, errors) = compile_with_errors("<div v-model='f'/>", false);
+        assert!(errors[0].contains("can only be used on"), "{:?}", errors);
+    }
+
+    #[test]
+    fn test_v_model_prop_conflict() {
+        let (_, errors) = compile_with_errors("<input v-model='a' :value='b'/>", false);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert!(
+            errors[0].contains("Unnecessary value binding"),
+            "{:?}",
+            errors
+        );
+        let (val, errors) = compile_with_errors("<comp v-model='a' :modelValue='b'/>", false);
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(val.matches("modelValue:").count(), 1, "{}", val);
+    }
+}
@@ -60,9 +60,7 @@ fn resolve_modifiers<'a>(mods: &[&'a str], key: &Js<'a>) -> ResolvedMods<'a> {
             event_option.push(m);
         } else if MAYBE_KEY_MODS.contains(m) {
             if let Js::StrLit(k) = key {
-                let name = k.raw.trim_start_matches("on");
-                let is_key_event = KEYBOARD_EVENTS.iter().any(|n| n.eq_ignore_ascii_case(name));
-                if is_key_event {
+                if is_keyboard_event(k.raw) {
                     key_modifiers.push(m);
                 } else {
                     non_key_mods.push(m);
@@ -83,6 +81,11 @@ fn resolve_modifiers<'a>(mods: &[&'a str], key: &Js<'a>) -> ResolvedMods<'a> {
         key_modifiers,
     }
 }
+fn is_keyboard_event(name: &str) -> bool {
+    let name = name.trim_start_matches("on");
+    KEYBOARD_EVENTS.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
 fn apply_modifiers<'a>(event: &mut (Js<'a>, Js<'a>), resolved: ResolvedMods<'a>) {
     let ResolvedMods {
         event_option,
@@ -90,6 +93,10 @@ fn apply_modifiers<'a>(event: &mut (Js<'a>, Js<'a>), resolved: ResolvedMods<'a>)
         non_key_mods,
     } = resolved;
     let (key, value) = event;
+    let is_keyboard_event = match key {
+        Js::StrLit(k) => is_keyboard_event(k.raw),
+        _ => true,
+    };
     if non_key_mods.contains(&"right") {
         *key = convert_click(std::mem::take(key), "contextmenu");
     }
@@ -103,31 +110,174 @@ fn apply_modifiers<'a>(event: &mut (Js<'a>, Js<'a>), resolved: ResolvedMods<'a>)
             vec![std::mem::take(value), Js::Array(non_keys)],
         );
     }
-    if !event_option.is_empty() {
-        let postfix = event_option
-            .into_iter()
-            .map(|s| Js::str_lit(*VStr::raw(s).capitalize()))
-            .intersperse(Js::Src(" + "));
-        let mut new_key_vec = vec![Js::Src("("), std::mem::take(key), Js::Src(")")];
-        new_key_vec.extend(postfix);
-        *key = Js::Compound(new_key_vec);
+    // key modifiers only guard keyboard events or dynamic ones
+    if !key_modifiers.is_empty() && is_keyboard_event {
+        let keys = key_modifiers.into_iter().map(Js::str_lit).collect();
+        *value = Js::Call(
+            dom_helper::V_ON_WITH_KEYS,
+            vec![std::mem::take(value), Js::Array(keys)],
+        );
+    }
+    if event_option.is_empty() {
+        return;
+    }
+    // e.g. onClickCaptureOnce, a dynamic key becomes (key) + "Capture" + "Once"
+    if let Js::StrLit(k) = key {
+        for option in event_option {
+            k.suffix_event_option(option);
+        }
+        return;
+    }
+    let mut new_key_vec = vec![Js::Src("("), std::mem::take(key), Js::Src(")")];
+    for option in event_option {
+        new_key_vec.push(Js::Src(" + "));
+        new_key_vec.push(Js::str_lit(*VStr::raw(option).capitalize()));
     }
+    *key = Js::Compound(new_key_vec);
 }
 
+/// right/middle click fire contextmenu/mouseup instead of click
 fn convert_click<'a>(key: Js<'a>, name: &'a str) -> Js<'a> {
-    if let Js::StrLit(k) = key {
-        Js::str_lit(name)
-    } else {
-        Js::Compound(vec![
+    let event = Js::StrLit(*VStr::raw(name).be_handler());
+    match key {
+        Js::StrLit(k) if k.raw.eq_ignore_ascii_case("click") => event,
+        // other static events are not clicks
+        Js::StrLit(_) => key,
+        _ => Js::Compound(vec![
             Js::Src("("),
             key.clone(),
-            Js::Src(") === 'onClick' ? "),
-            Js::str_lit(name),
+            Js::Src(") === \"onClick\" ? "),
+            event,
             Js::Src(" : ("),
             key,
             Js::Src(")"),
-        ])
+        ]),
     }
 }
 
 pub const V_ON: DirectiveConverter = ("on", convert_v_on);
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::compile;
+
+    #[test]
+    fn test_non_key_modifiers() {
+        let val = compile("<div @click.stop.prevent='foo'/>");
+        assert!(val.contains("withModifiers: _withModifiers"), "{}", val);
+        assert!(
+            val.contains(r#"onClick: _withModifiers(foo, ["stop", "prevent"])"#),
+            "{}",
+            val
+        );
+        let val = compile("<div @click.exact.left='foo'/>");
+        assert!(
+            val.contains(r#"onClick: _withModifiers(foo, ["exact", "left"])"#),
+            "{}",
+            val
+        );
+        assert!(!val.contains("_withKeys"), "{}", val);
+    }
+
+    #[test]
+    fn test_mouse_button_modifiers() {
+        let val = compile("<div @click.right='foo'/>");
+        assert!(
+            val.contains(r#"onContextmenu: _withModifiers(foo, ["right"])"#),
+            "{}",
+            val
+        );
+        let val = compile("<div @click.middle='foo'/>");
+        assert!(
+            val.contains(r#"onMouseup: _withModifiers(foo, ["middle"])"#),
+            "{}",
+            val
+        );
+        // only click is converted
+        let val = compile("<div @mousedown.right='foo'/>");
+        assert!(
+            val.contains(r#"onMousedown: _withModifiers(foo, ["right"])"#),
+            "{}",
+            val
+        );
+        // dynamic event may be click or keyboard event
+        let val = compile("<div @[evt].right='foo'/>");
+        assert!(
+            val.contains(r#") === "onClick" ? "onContextmenu" : ("#),
+            "{}",
+            val
+        );
+        assert!(
+            val.contains(r#"_withKeys(_withModifiers(foo, ["right"]), ["right"])"#),
+            "{}",
+            val
+        );
+    }
+
+    #[test]
+    fn test_key_modifiers() {
+        let val = compile("<input @keyup.enter='foo'/>");
+        assert!(val.contains("withKeys: _withKeys"), "{}", val);
+        assert!(
+            val.contains(r#"onKeyup: _withKeys(foo, ["enter"])"#),
+            "{}",
+            val
+        );
+        let val = compile("<input @keydown.left='foo'/>");
+        assert!(
+            val.contains(r#"onKeydown: _withKeys(foo, ["left"])"#),
+            "{}",
+            val
+        );
+        // key modifiers do not guard non-keyboard events
+        let val = compile("<div @click.enter='foo'/>");
+        assert!(!val.contains("_withKeys"), "{}", val);
+    }
+
+    #[test]
+    fn test_event_option_modifiers() {
+        let val = compile("<div @click.capture.once='foo'/>");
+        assert!(val.contains("onClickCaptureOnce: foo"), "{}", val);
+        let val = compile("<div @update:value.once='foo'/>");
+        assert!(val.contains(r#""onUpdate:valueOnce": foo"#), "{}", val);
+        assert!(!val.contains("_withModifiers"), "{}", val);
+        let val = compile("<div @[evt].passive='foo'/>");
+        assert!(val.contains(r#") + "Passive"]: foo"#), "{}", val);
+    }
+
+    #[test]
+    fn test_combined_modifiers() {
+        let val = compile("<input @keyup.enter.exact.capture='foo'/>");
+        let handler = r#"_withKeys(_withModifiers(foo, ["exact"]), ["enter"])"#;
+        assert!(
+            val.contains(&format!("onKeyupCapture: {}", handler)),
+            "{}",
+            val
+        );
+    }
+
+    #[test]
+    fn test_modifier_handler_forms() {
+        // modifiers without handler guard a noop
+        let val = compile("<form @submit.prevent/>");
+        assert!(
+            val.contains(r#"_withModifiers(() => {}, ["prevent"])"#),
+            "{}",
+            val
+        );
+        // inline statement is wrapped as a whole
+        let val = compile("<input @keyup.enter='count++'/>");
+        assert!(
+            val.contains(r#"_withKeys($event => (count++), ["enter"])"#),
+            "{}",
+            val
+        );
+        let val = compile("<div @click.self.once='foo'/>");
+        let handler = r#"_withModifiers(foo, ["self"])"#;
+        assert!(
+            val.contains(&format!("onClickOnce: {}", handler)),
+            "{}",
+            val
+        );
+    }
+}
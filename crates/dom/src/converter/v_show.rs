@@ -20,3 +20,25 @@ pub fn convert_v_show<'a>(
     }
 }
 pub const V_SHOW: DirectiveConverter = ("show", convert_v_show);
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::compile_with_errors;
+
+    fn compile(s: &str) -> String {
+        let (val, errors) = compile_with_errors(s);
+        assert!(errors.is_empty(), "{}", errors[0]);
+        val
+    }
+
+    #[test]
+    fn test_v_show_with_style() {
+        let val = compile("<div v-show='ok' style='color: red' :style='s'/>");
+        assert_eq!(val.matches("style:").count(), 1, "{}", val);
+        assert!(val.contains(r#"style: _normalizeStyle([{"#), "{}", val);
+        assert!(val.contains(r#"color: "red""#), "{}", val);
+        assert!(val.contains("}, s])"), "{}", val);
+        assert!(val.contains("4 /*STYLE*/"), "{}", val);
+        assert!(val.contains("[_vShow, ok]"), "{}", val);
+    }
+}
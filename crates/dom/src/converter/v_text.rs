@@ -11,16 +11,17 @@ pub fn convert_v_text<'a>(
     e: &Element<'a>,
     eh: &dyn ErrorHandler,
 ) -> CoreDirConvRet<'a> {
-    let error_kind = CompilationErrorKind::extended(DomError::VTextNoExpression);
-    if let Some(err) = dir.check_empty_expr(error_kind) {
-        eh.on_error(err);
-        return DirectiveConvertResult::Dropped;
-    }
+    // children are not converted, see convert_element
     if !e.children.is_empty() {
         let error = CompilationError::extended(DomError::VTextWithChildren)
             .with_location(dir.location.clone());
         eh.on_error(error);
     }
+    let error_kind = CompilationErrorKind::extended(DomError::VTextNoExpression);
+    if let Some(err) = dir.check_empty_expr(error_kind) {
+        eh.on_error(err);
+        return DirectiveConvertResult::Dropped;
+    }
     let exp = dir
         .expression
         .take()
@@ -37,3 +38,47 @@ pub fn convert_v_text<'a>(
     }
 }
 pub const V_TEXT: DirectiveConverter = ("text", convert_v_text);
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::compile_with_errors;
+
+    #[test]
+    fn test_v_text() {
+        let (val, errors) = compile_with_errors("<div v-text='msg'/>");
+        assert!(errors.is_empty(), "{}", errors[0]);
+        assert!(
+            val.contains("textContent: _toDisplayString(msg)"),
+            "{}",
+            val
+        );
+        assert!(val.contains(r#"8 /*PROPS*/, ["textContent"]"#), "{}", val);
+    }
+
+    #[test]
+    fn test_children_conflict() {
+        let (val, errors) = compile_with_errors("<div v-text='msg'>{{ a }}</div>");
+        assert_eq!(errors.len(), 1);
+        let msg = errors[0].to_string();
+        assert!(
+            msg.contains("v-text will override element children"),
+            "{}",
+            msg
+        );
+        assert_eq!(errors[0].location.start.offset, 5);
+        assert!(!val.contains("_toDisplayString(a)"), "{}", val);
+        assert!(!val.contains("TEXT"), "{}", val);
+    }
+
+    #[test]
+    fn test_with_other_props() {
+        let (val, errors) = compile_with_errors("<div v-bind='attrs' v-text='msg'>a</div>");
+        assert_eq!(errors.len(), 1);
+        assert!(val.contains("_mergeProps(attrs, {"), "{}", val);
+        assert!(
+            val.contains("textContent: _toDisplayString(msg)"),
+            "{}",
+            val
+        );
+    }
+}
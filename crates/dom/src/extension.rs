@@ -38,7 +38,6 @@ pub enum DomError {
     VModelUnnecessaryValue,
     VShowNoExpression,
     TransitionInvalidChildren,
-    IgnoredSideEffectTag,
 }
 
 impl ErrorKind for DomError {
@@ -55,7 +54,22 @@ impl ErrorKind for DomError {
           VModelUnnecessaryValue => "Unnecessary value binding used alongside v-model. It will interfere with v-model's behavior.",
           VShowNoExpression => "v-show is missing expression.",
           TransitionInvalidChildren => "<Transition> expects exactly one child element or component.",
-          IgnoredSideEffectTag => "Tags with side effect (<script> and <style>) are ignored in client component templates."
         }
     }
+    /// `DOMErrorCodes` in @vue/compiler-dom, which starts at core's extend point.
+    fn code(&self) -> Option<u32> {
+        use DomError::*;
+        Some(match self {
+            VHtmlNoExpression => 50,
+            VHtmlWithChildren => 51,
+            VTextNoExpression => 52,
+            VTextWithChildren => 53,
+            VModelOnInvalidElement => 54,
+            VModelArgOnElement => 55,
+            VModelOnFileInputElement => 56,
+            VModelUnnecessaryValue => 57,
+            VShowNoExpression => 58,
+            TransitionInvalidChildren => 59,
+        })
+    }
 }
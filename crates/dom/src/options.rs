@@ -5,8 +5,7 @@ use compiler::{
 use crate::{converter::DOM_DIR_CONVERTERS, extension::dom_helper};
 use phf::{phf_set, Set};
 
-const NATIVE_TAGS: Set<&str> = phf_set! {
-    // HTML_TAGS
+const HTML_TAGS: Set<&str> = phf_set! {
     "html","body","base","head","link","meta","style","title","address","article","aside",
     "footer","header","h1","h2","h3","h4","h5","h6","nav","section","div","dd","dl","dt",
     "figcaption", "figure","picture","hr","img","li","main","ol","p","pre","ul","a","b",
@@ -17,7 +16,9 @@ const NATIVE_TAGS: Set<&str> = phf_set! {
     "tr","button","datalist","fieldset","form","input","label","legend","meter","optgroup",
     "option","output","progress","select","textarea","details","dialog","menu","summary",
     "template","blockquote","iframe","tfoot",
-    // SVG_TAGS
+};
+
+const SVG_TAGS: Set<&str> = phf_set! {
     "svg","animate","animateMotion","animateTransform","circle","clipPath","color-profile",
     "defs","desc","discard","ellipse","feBlend","feColorMatrix","feComponentTransfer",
     "feComposite","feConvolveMatrix","feDiffuseLighting","feDisplacementMap",
@@ -27,18 +28,28 @@ const NATIVE_TAGS: Set<&str> = phf_set! {
     "foreignObject","g","hatch","hatchpath","image","line","linearGradient","marker","mask",
     "mesh","meshgradient","meshpatch","meshrow","metadata","mpath","path","pattern",
     "polygon","polyline","radialGradient","rect","set","solidcolor","stop","switch","symbol",
-    "text","textPath","tspan","unknown","use","view", // "title"
-    // MATH ML
+    "text","textPath","tspan","unknown","use","view",
+    // shared with HTML
+    "a","script","style","title",
+};
+
+const MATH_TAGS: Set<&str> = phf_set! {
     "annotation-xml", "annotation", "maction", "maligngroup", "malignmark", "math", "menclose",
-    "merror", "mfenced", "mfrac", "mi", "mlongdiv", "mmultiscripts", "mo", "mover", "mpadded",
-    "mphantom", "mprescripts", "mroot", "mrow", "ms", "mscarries", "mscarry", "msgroup", "msline",
-    "mspace", "msqrt", "msrow", "mstack", "mstyle", "msub", "msubsup", "msup", "mtable", "mtd",
-    "mtext", "mtr", "munder", "munderover", "none", "semantics",
+    "merror", "mfenced", "mfrac", "mglyph", "mi", "mlongdiv", "mmultiscripts", "mn", "mo",
+    "mover", "mpadded", "mphantom", "mprescripts", "mroot", "mrow", "ms", "mscarries", "mscarry",
+    "msgroup", "msline", "mspace", "msqrt", "msrow", "mstack", "mstyle", "msub", "msubsup",
+    "msup", "mtable", "mtd", "mtext", "mtr", "munder", "munderover", "none", "semantics",
 };
 
-pub fn is_native_tag(tag: &str) -> bool {
-    NATIVE_TAGS.contains(tag)
+pub fn is_native_tag(tag: &str, ns: Namespace) -> bool {
+    match ns {
+        Namespace::Svg => SVG_TAGS.contains(tag),
+        Namespace::MathMl => MATH_TAGS.contains(tag),
+        // a component can render SVG or MathML fragment, e.g. <g> as root
+        _ => HTML_TAGS.contains(tag) || SVG_TAGS.contains(tag) || MATH_TAGS.contains(tag),
+    }
 }
+
 fn is_pre_tag(tag: &str) -> bool {
     tag.eq_ignore_ascii_case("pre")
 }
@@ -71,7 +82,7 @@ fn get_text_mode(tag: &str) -> TextMode {
 fn get_namespace(tag: &str, parent: Option<&Element>) -> Namespace {
     if let Some(p) = parent {
         if p.namespace == Namespace::MathMl {
-            if p.tag_name == "annotaion-xml" {
+            if p.tag_name == "annotation-xml" {
                 if tag == "svg" {
                     return Namespace::Svg;
                 } else {
@@ -105,6 +116,9 @@ fn get_namespace(tag: &str, parent: Option<&Element>) -> Namespace {
 }
 
 pub fn compile_option(error_handler: RcErrHandle) -> CompileOption {
+    // DOM converters extend, not replace, the core v-bind/v-on/v-model ones
+    let mut directive_converters = CompileOption::default().directive_converters;
+    directive_converters.extend(DOM_DIR_CONVERTERS.iter().copied());
     CompileOption {
         is_native_tag,
         get_text_mode,
@@ -113,7 +127,7 @@ pub fn compile_option(error_handler: RcErrHandle) -> CompileOption {
         get_builtin_component,
         get_namespace,
         delimiters: ("{{".to_string(), "}}".to_string()),
-        directive_converters: DOM_DIR_CONVERTERS.iter().copied().collect(),
+        directive_converters,
         helper_strs: dom_helper::DOM_HELPER_MAP,
         error_handler,
         mode: ScriptMode::Function {
@@ -123,3 +137,76 @@ pub fn compile_option(error_handler: RcErrHandle) -> CompileOption {
         ..Default::default()
     }
 }
+
+#[cfg(test)]
+pub mod test {
+    use super::*;
+    use crate::get_dom_pass;
+    use compiler::compiler::{BaseCompiler, TemplateCompiler};
+    use compiler::error::{CompilationError, NoopErrorHandler, VecErrorHandler};
+    use std::{mem, rc::Rc};
+
+    /// Compiles with DOM options adjusted by `config`, reporting to `error_handler`.
+    pub fn compile_with<F>(s: &str, error_handler: RcErrHandle, config: F) -> String
+    where
+        F: FnOnce(&mut CompileOption),
+    {
+        let mut option = compile_option(error_handler);
+        config(&mut option);
+        let sfc_info = Default::default();
+        let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+        let ret = compiler.compile(s, &sfc_info).unwrap();
+        String::from_utf8(ret).unwrap()
+    }
+
+    pub fn compile(s: &str) -> String {
+        compile_with(s, Rc::new(NoopErrorHandler), |_| {})
+    }
+
+    /// Compiles with default DOM options, returning code and reported errors.
+    pub fn compile_with_errors(s: &str) -> (String, Vec<CompilationError>) {
+        let eh = Rc::new(VecErrorHandler::default());
+        let val = compile_with(s, eh.clone(), |_| {});
+        let errors = mem::take(&mut *eh.error_mut());
+        (val, errors)
+    }
+
+    #[test]
+    fn test_svg_elements() {
+        let val = compile("<svg><filter/><text>hi</text></svg>");
+        assert!(val.contains(r#"_createElementBlock("svg""#), "{}", val);
+        assert!(val.contains(r#"_createElementVNode("filter""#), "{}", val);
+        assert!(
+            val.contains(r#"_createElementVNode("text", null, "hi""#),
+            "{}",
+            val
+        );
+        assert!(!val.contains("_resolveComponent"), "{}", val);
+        // SVG fragment rendered by a component root is native too
+        let val = compile("<g><path/></g>");
+        assert!(!val.contains("_resolveComponent"), "{}", val);
+    }
+
+    #[test]
+    fn test_component_in_svg() {
+        let val = compile("<svg><my-icon/><text>hi</text></svg>");
+        assert!(val.contains(r#"_resolveComponent("my-icon")"#), "{}", val);
+        assert!(val.contains("_createVNode(_component_my_icon)"), "{}", val);
+        assert!(val.contains(r#"_createElementVNode("text""#), "{}", val);
+        // HTML element is not native in SVG unless in foreignObject
+        let val = compile("<svg><div/><foreignObject><p/></foreignObject></svg>");
+        assert!(val.contains(r#"_resolveComponent("div")"#), "{}", val);
+        assert!(val.contains(r#"_createElementVNode("p""#), "{}", val);
+    }
+
+    #[test]
+    fn test_namespace() {
+        assert!(is_native_tag("text", Namespace::Svg));
+        assert!(!is_native_tag("div", Namespace::Svg));
+        assert!(is_native_tag("mi", Namespace::MathMl));
+        assert!(!is_native_tag("rect", Namespace::MathMl));
+        assert!(is_native_tag("rect", Namespace::Html));
+        let math = compile("<math><annotation-xml><svg/></annotation-xml><mi><p/></mi></math>");
+        assert!(!math.contains("_resolveComponent"), "{}", math);
+    }
+}
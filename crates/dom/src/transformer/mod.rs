@@ -1,20 +1,27 @@
+mod parse_style;
 mod stringify_static;
 mod warn_dom_usage;
 
+use parse_style::StaticStyleParser;
+use stringify_static::StaticStringifier;
 use warn_dom_usage::UsageWarner;
 use compiler::transformer::{
     CorePass,
     collect_entities::EntityCollector,
+    collect_refs::RefCollector,
     mark_patch_flag::PatchFlagMarker,
     mark_slot_flag::SlotFlagMarker,
     optimize_text::TextOptimizer,
     pass::{Scope, SharedInfoPasses},
     process_expression::ExpressionProcessor,
     normalize_props::NormalizeProp,
-    hoist_static::HoistStatic,
+    hoist_static::{HoistStatic, StaticRootMarker},
+    v_once::VOnceProcessor,
+    warn_self_ref::SelfRefWarner,
 };
 use compiler::converter::BaseConvertInfo;
 use compiler::{SFCInfo, chain};
+use compiler::codegen::RenderMode;
 use compiler::compiler::CompileOption;
 use std::marker::PhantomData;
 
@@ -22,26 +29,42 @@ pub fn get_dom_pass<'a>(
     sfc_info: &'a SFCInfo<'a>,
     opt: &CompileOption,
 ) -> impl CorePass<BaseConvertInfo<'a>> {
-    let prefix_identifier = opt.transforming().prefix_identifier;
+    let transform = opt.transforming(sfc_info);
+    let hoist_static = opt.hoist_static && opt.render_mode == RenderMode::Client;
     let shared = chain![
         SlotFlagMarker,
-        HoistStatic::new(opt.cache_handlers),
+        HoistStatic::new(transform.cache_handlers, hoist_static, opt.explain_hoist),
         ExpressionProcessor {
-            prefix_identifier,
+            prefix_identifier: transform.prefix_identifier,
             sfc_info,
             err_handle: opt.error_handler.clone(),
         },
     ];
+    // exit_root runs in reverse order, so stringifying sees hoisted nodes
+    let stringifier = StaticStringifier {
+        serializer: opt.static_serializer.clone(),
+        is_void_tag: opt.is_void_tag,
+        has_scope_id: sfc_info.scope_id.is_some(),
+        is_custom_element: opt.is_custom_element.clone(),
+        explain: opt.explain_hoist,
+    };
     chain![
+        stringifier,
+        StaticStyleParser,
         PatchFlagMarker,
         UsageWarner(opt.error_handler.clone()),
+        SelfRefWarner::new(opt.error_handler.clone(), opt.warn_self_reference),
         TextOptimizer,
         EntityCollector::default(),
+        RefCollector::default(),
         NormalizeProp,
+        StaticRootMarker,
         SharedInfoPasses {
             passes: shared,
             shared_info: Scope::default(),
             pd: PhantomData,
         },
+        // last pass exits first, after patch flags are final but before hoisting
+        VOnceProcessor { hoist_static },
     ]
 }
@@ -0,0 +1,155 @@
+// Static style="color: red" is parsed into an object at compile time so that
+// the runtime does not parse the string on every render. The object is made
+// of literals only and stays hoistable like the string it replaces.
+use compiler::converter::BaseConvertInfo as BaseInfo;
+use compiler::flags::RuntimeHelper as RH;
+use compiler::ir::JsExpr as Js;
+use compiler::transformer::{BaseVNode, CorePass};
+use compiler::util::VStr;
+
+pub struct StaticStyleParser;
+
+impl<'a> CorePass<BaseInfo<'a>> for StaticStyleParser {
+    fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+        if let Some(props) = vn.props.as_mut() {
+            parse_style_prop(props);
+        }
+    }
+}
+
+fn parse_style_prop(props: &mut Js) {
+    let ps = match props {
+        Js::Call(RH::MERGE_PROPS, args) => {
+            args.iter_mut().for_each(parse_style_prop);
+            return;
+        }
+        Js::Props(ps) => ps,
+        _ => return,
+    };
+    let style = ps.iter_mut().find_map(|(k, v)| match k {
+        Js::StrLit(k) if k.raw == "style" => Some(v),
+        _ => None,
+    });
+    let style = match style {
+        Some(v) => v,
+        None => return,
+    };
+    match style {
+        Js::StrLit(s) => {
+            if let Some(parsed) = parse_string_style(*s) {
+                *style = parsed;
+            }
+        }
+        // static style merged with :style
+        Js::Array(arr) => {
+            for v in arr.iter_mut() {
+                if let Js::StrLit(s) = v {
+                    if let Some(parsed) = parse_string_style(*s) {
+                        *v = parsed;
+                    }
+                }
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Same as `parseStringStyle` in @vue/shared.
+fn parse_string_style(style: VStr) -> Option<Js> {
+    // comments cannot be sliced out, leave them to runtime normalizeStyle
+    if style.raw.contains("/*") {
+        return None;
+    }
+    let mut props: Vec<(Js, Js)> = vec![];
+    for decl in split_declarations(style.raw) {
+        let (name, value) = match decl.split_once(':') {
+            Some(pair) => pair,
+            None => continue,
+        };
+        let name = name.trim();
+        let value = Js::StrLit(VStr {
+            raw: value.trim(),
+            ..style
+        });
+        // later declaration wins, like assigning to the same object key
+        let existing = props
+            .iter_mut()
+            .find(|(k, _)| matches!(k, Js::StrLit(k) if k.raw == name));
+        match existing {
+            Some(prop) => prop.1 = value,
+            None => props.push((Js::StrLit(VStr { raw: name, ..style }), value)),
+        }
+    }
+    Some(Js::Props(props))
+}
+
+/// Split by `;` outside of parentheses, e.g. `background: url(data:a;b)`.
+fn split_declarations(css: &str) -> impl Iterator<Item = &str> {
+    let mut depth = 0usize;
+    css.split(move |c| {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        c == ';' && depth == 0
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use crate::options::test::{compile, compile_with};
+    use compiler::error::NoopErrorHandler;
+    use std::rc::Rc;
+
+    #[test]
+    fn test_static_style() {
+        let val = compile("<div style='color: red; font-size:14px;'/>");
+        assert!(val.contains(r#"color: "red","#), "{}", val);
+        assert!(val.contains(r#""font-size": "14px""#), "{}", val);
+        assert!(!val.contains("_normalizeStyle"), "{}", val);
+        assert!(!val.contains("STYLE"), "{}", val);
+        let val = compile("<div style='background: url(data:a;b); color: red; color: blue'/>");
+        assert!(val.contains(r#"background: "url(data:a;b)","#), "{}", val);
+        assert!(val.contains(r#"color: "blue""#), "{}", val);
+        assert!(!val.contains(r#""red""#), "{}", val);
+        // comments are kept as string
+        let val = compile("<div style='/* c */color: red'/>");
+        assert!(val.contains(r#"style: "/* c */color: red""#), "{}", val);
+    }
+
+    #[test]
+    fn test_static_style_hoisted() {
+        let source = "<div><p style='color: red'/></div>";
+        let val = compile_with(source, Rc::new(NoopErrorHandler), |o| o.hoist_static = true);
+        assert!(val.contains("const _hoisted_1"), "{}", val);
+        assert!(val.contains(r#"color: "red""#), "{}", val);
+    }
+
+    #[test]
+    fn test_dynamic_class_style() {
+        let val = compile("<div :class='cls' :style='stl'/>");
+        assert!(val.contains("class: _normalizeClass(cls),"), "{}", val);
+        assert!(val.contains("style: _normalizeStyle(stl)"), "{}", val);
+        assert!(val.contains("6 /*CLASS | STYLE*/"), "{}", val);
+    }
+
+    #[test]
+    fn test_mixed_class_style() {
+        let val = compile("<div class='a' :class='cls' style='color: red' :style='stl'/>");
+        assert!(
+            val.contains(r#"class: _normalizeClass(["a", cls]),"#),
+            "{}",
+            val
+        );
+        let style = r#"style: _normalizeStyle([{"#;
+        assert!(val.contains(style), "{}", val);
+        assert!(val.contains(r#"color: "red""#), "{}", val);
+        assert!(val.contains("}, stl])"), "{}", val);
+        assert!(val.contains("6 /*CLASS | STYLE*/"), "{}", val);
+        let val = compile("<div class='a' :class='cls'/>");
+        assert!(val.contains("2 /*CLASS*/"), "{}", val);
+        let val = compile("<div style='color: red' :style='stl'/>");
+        assert!(val.contains("4 /*STYLE*/"), "{}", val);
+    }
+}
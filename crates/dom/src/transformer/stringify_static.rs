@@ -1 +1,457 @@
+// Large hoisted static subtrees are serialized into one createStaticVNode call.
+// The runtime mounts them by innerHTML instead of creating every vnode.
+// Embedders with their own static node format can plug in a serializer.
+use compiler::converter::{BaseConvertInfo as BaseInfo, BaseIR, BaseRoot, Hoist};
+use compiler::flags::RuntimeHelper as RH;
+use compiler::ir::{IRNode as IR, JsExpr as Js};
+use compiler::parser::IsCustomElement;
+use compiler::transformer::hoist_static::{BailReason, HoistBailout};
+use compiler::transformer::hoist_static::{StaticSerializer, StaticSubtree};
+use compiler::transformer::CorePass;
+use compiler::util::VStr;
 
+/// Threshold of nodes in a subtree to stringify.
+const NODE_COUNT: usize = 20;
+/// Threshold of elements with attributes in a subtree to stringify.
+const ELEMENT_WITH_BINDING_COUNT: usize = 5;
+
+pub struct StaticStringifier {
+    pub serializer: Option<StaticSerializer>,
+    pub is_void_tag: fn(&str) -> bool,
+    /// scope id attributes are not rendered into static HTML yet
+    pub has_scope_id: bool,
+    pub is_custom_element: IsCustomElement,
+    /// report large subtrees that are not stringified
+    pub explain: bool,
+}
+
+impl<'a> CorePass<BaseInfo<'a>> for StaticStringifier {
+    // HoistStatic lifts subtrees on root exit, which runs before this
+    // pass's exit_root since it is placed earlier in the chain.
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+        if self.has_scope_id {
+            return;
+        }
+        let mut need_helper = false;
+        // innerHTML of static content is parsed as HTML, not SVG or MathML
+        let mut foreign = vec![];
+        for ir in r.body.iter() {
+            collect_foreign_hoists(ir, false, &mut foreign);
+        }
+        let top = &mut r.top_scope;
+        for (i, hoist) in top.hoists.iter_mut().enumerate() {
+            let ir = match hoist {
+                Hoist::Node(ir) if should_stringify(ir) => ir,
+                _ => continue,
+            };
+            let subtree = if foreign.contains(&i) {
+                Err(BailReason::ForeignContent)
+            } else {
+                to_subtree(ir, &*self.is_custom_element)
+            };
+            let subtree = match (subtree, &*ir) {
+                (Ok(t), _) => t,
+                (Err(reason), IR::VNodeCall(vn)) if self.explain => {
+                    top.hoist_bailouts.push(HoistBailout::new(vn, reason));
+                    continue;
+                }
+                (Err(_), _) => continue,
+            };
+            let code = match &self.serializer {
+                Some(serialize) => serialize(&subtree),
+                None => {
+                    need_helper = true;
+                    self.create_static(&subtree)
+                }
+            };
+            *hoist = Hoist::Static(code);
+        }
+        if need_helper {
+            top.helpers.collect(RH::CREATE_STATIC);
+        }
+    }
+}
+
+impl StaticStringifier {
+    fn create_static(&self, subtree: &StaticSubtree) -> String {
+        let mut html = String::new();
+        write_html(subtree, self.is_void_tag, &mut html);
+        let mut content = VStr::raw(&html);
+        content.be_js_str();
+        let helper = RH::CREATE_STATIC.helper_str(&[]);
+        format!("_{}({}, 1)", helper, content.into_string())
+    }
+}
+
+fn is_foreign_root(tag: &Js) -> bool {
+    matches!(tag, Js::StrLit(t) if t.raw == "svg" || t.raw == "math")
+}
+
+fn is_table_part(tag: &str) -> bool {
+    matches!(
+        tag,
+        "caption" | "thead" | "tr" | "th" | "tbody" | "td" | "tfoot" | "colgroup" | "col"
+    )
+}
+
+/// Collect indices of hoisted nodes inside `<svg>` or `<math>`.
+fn collect_foreign_hoists(ir: &BaseIR, in_foreign: bool, hoists: &mut Vec<usize>) {
+    let mut collect = |ir: &BaseIR| collect_foreign_hoists(ir, in_foreign, hoists);
+    match ir {
+        IR::Hoisted(i) if in_foreign => hoists.push(*i),
+        IR::VNodeCall(v) => {
+            let in_foreign = in_foreign || is_foreign_root(&v.tag);
+            for child in v.children.iter() {
+                collect_foreign_hoists(child, in_foreign, hoists);
+            }
+        }
+        IR::If(i) => i.branches.iter().for_each(|b| collect(&*b.child)),
+        IR::For(f) => collect(&*f.child),
+        IR::CacheNode(c) => collect(&*c.child),
+        IR::RenderSlotCall(r) => r.fallbacks.iter().for_each(collect),
+        IR::VSlotUse(s) => {
+            for slot in s.stable_slots.iter() {
+                slot.body.iter().for_each(&mut collect);
+            }
+            s.alterable_slots.iter().for_each(collect);
+        }
+        IR::AlterableSlot(s) => s.body.iter().for_each(collect),
+        _ => (),
+    }
+}
+
+fn to_subtree(ir: &BaseIR, is_custom: &dyn Fn(&str) -> bool) -> Result<StaticSubtree, BailReason> {
+    let unstringifiable = |what: &str| Err(BailReason::Unstringifiable(what.into()));
+    match ir {
+        IR::VNodeCall(v) => {
+            let tag = match &v.tag {
+                Js::StrLit(t) if !v.is_component && v.directives.is_empty() => t.into_string(),
+                _ => return unstringifiable("fragment"),
+            };
+            if is_foreign_root(&v.tag) {
+                return Err(BailReason::ForeignContent);
+            }
+            // parsing innerHTML drops table parts outside of their parents
+            if is_table_part(&tag) {
+                return unstringifiable(&format!("table element <{}>", tag));
+            }
+            // runtime may set props of custom elements as DOM properties
+            if is_custom(&tag) {
+                return Err(BailReason::CustomElement(tag));
+            }
+            let props = match &v.props {
+                None => vec![],
+                Some(Js::Props(ps)) => ps.iter().map(to_attr).collect::<Result<_, _>>()?,
+                Some(_) => return unstringifiable("props object"),
+            };
+            let children = v
+                .children
+                .iter()
+                .map(|c| to_subtree(c, is_custom))
+                .collect::<Result<_, _>>()?;
+            Ok(StaticSubtree::Element {
+                tag,
+                props,
+                children,
+            })
+        }
+        IR::TextCall(t) => {
+            let mut text = String::new();
+            for s in t.texts.iter() {
+                match s {
+                    Js::StrLit(s) => text.push_str(&s.into_string()),
+                    _ => return unstringifiable("constant interpolation"),
+                }
+            }
+            Ok(StaticSubtree::Text(text))
+        }
+        IR::CommentCall(c) => Ok(StaticSubtree::Comment(c.to_string())),
+        _ => Err(BailReason::Dynamic),
+    }
+}
+
+/// Attributes that the runtime sets as DOM properties or handles
+/// specially cannot be rendered in HTML.
+fn to_attr(prop: &(Js, Js)) -> Result<(String, String), BailReason> {
+    let unstringifiable = |name: &str| {
+        let what = format!("prop `{}`", name);
+        Err(BailReason::Unstringifiable(what))
+    };
+    let name = match &prop.0 {
+        Js::StrLit(n) => n.into_string(),
+        _ => return unstringifiable("[computed]"),
+    };
+    let is_special = matches!(&*name, "key" | "innerHTML" | "textContent")
+        || name.starts_with(|c| c == '.' || c == '^')
+        || name.starts_with("on");
+    if is_special {
+        return unstringifiable(&name);
+    }
+    let value = match &prop.1 {
+        Js::StrLit(v) => v.into_string(),
+        // static style parsed into object
+        Js::Props(ps) if name == "style" => {
+            let mut style = String::new();
+            for (k, v) in ps {
+                match (k, v) {
+                    (Js::StrLit(k), Js::StrLit(v)) => {
+                        style.push_str(&format!("{}:{};", k.into_string(), v.into_string()))
+                    }
+                    _ => return unstringifiable(&name),
+                }
+            }
+            style
+        }
+        _ => return unstringifiable(&name),
+    };
+    Ok((name, value))
+}
+
+/// Counts nodes before converting, so small subtrees are left as is cheaply.
+fn should_stringify(ir: &BaseIR) -> bool {
+    fn count(ir: &BaseIR, nodes: &mut usize, elements: &mut usize) {
+        *nodes += 1;
+        if let IR::VNodeCall(v) = ir {
+            if matches!(&v.props, Some(Js::Props(ps)) if !ps.is_empty()) {
+                *elements += 1;
+            }
+            for child in v.children.iter() {
+                count(child, nodes, elements);
+            }
+        }
+    }
+    let (mut nodes, mut elements) = (0, 0);
+    count(ir, &mut nodes, &mut elements);
+    nodes >= NODE_COUNT || elements >= ELEMENT_WITH_BINDING_COUNT
+}
+
+fn write_html(t: &StaticSubtree, is_void_tag: fn(&str) -> bool, html: &mut String) {
+    match t {
+        StaticSubtree::Text(text) => escape_html(text, html),
+        StaticSubtree::Comment(c) => {
+            html.push_str("<!--");
+            html.push_str(c);
+            html.push_str("-->");
+        }
+        StaticSubtree::Element {
+            tag,
+            props,
+            children,
+        } => {
+            html.push('<');
+            html.push_str(tag);
+            for (name, value) in props {
+                html.push(' ');
+                html.push_str(name);
+                if !value.is_empty() {
+                    html.push_str("=\"");
+                    escape_html(value, html);
+                    html.push('"');
+                }
+            }
+            html.push('>');
+            if is_void_tag(tag) {
+                return;
+            }
+            for child in children {
+                write_html(child, is_void_tag, html);
+            }
+            html.push_str("</");
+            html.push_str(tag);
+            html.push('>');
+        }
+    }
+}
+
+fn escape_html(s: &str, html: &mut String) {
+    for c in s.chars() {
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::options::test::compile_with;
+    use crate::{compile_option, get_dom_pass};
+    use compiler::compiler::{BaseCompiler, CompileOption};
+    use compiler::error::NoopErrorHandler;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn compile_serialized(s: &str, serializer: Option<StaticSerializer>) -> String {
+        compile_with(s, Rc::new(NoopErrorHandler), |o| {
+            o.hoist_static = true;
+            o.static_serializer = serializer;
+        })
+    }
+    fn compile(s: &str) -> String {
+        compile_serialized(s, None)
+    }
+    fn bailouts_of(s: &str) -> Vec<HoistBailout> {
+        let option = CompileOption {
+            hoist_static: true,
+            explain_hoist: true,
+            is_custom_element: Rc::new(|t| t.starts_with("my-")),
+            ..compile_option(Rc::new(NoopErrorHandler))
+        };
+        let sfc_info = Default::default();
+        let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+        let ret = compiler.compile_with_source_map(s, &sfc_info).unwrap();
+        ret.hoist_bailouts
+    }
+
+    const SPANS: &str = "<span class='a'>1</span><span class='b'>2</span>\
+        <span class='c'>3</span><span class='d'>4</span><span class='e'>5</span>";
+
+    #[test]
+    fn test_stringify_static() {
+        let val = compile(&format!(
+            "<div>{}<br><p style='color: red'>a &amp; b</p></div>",
+            SPANS
+        ));
+        assert!(
+            val.contains("createStaticVNode: _createStaticVNode"),
+            "{}",
+            val
+        );
+        let html = concat!(
+            r#"const _hoisted_1 = _createStaticVNode("<div><span class=\"a\">1</span>"#,
+            r#"<span class=\"b\">2</span><span class=\"c\">3</span><span class=\"d\">4</span>"#,
+            r#"<span class=\"e\">5</span><br><p style=\"color:red;\">a &amp; b</p></div>", 1)"#,
+        );
+        assert!(val.contains(html), "{}", val);
+        assert!(!val.contains("_createElementVNode(\"span\""), "{}", val);
+    }
+
+    #[test]
+    fn test_stringify_threshold() {
+        // default path is unchanged for small subtrees
+        let val = compile("<div><span class='a'>1</span></div>");
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+        assert!(
+            val.contains(r#"_createElementVNode("div", null, ["#),
+            "{}",
+            val
+        );
+        let many_texts = "<p>a</p>".repeat(10);
+        let val = compile(&format!("<div>{}</div>", many_texts));
+        assert!(
+            val.contains(r#"_createStaticVNode("<div><p>a</p><p>a</p>"#),
+            "{}",
+            val
+        );
+    }
+
+    #[test]
+    fn test_stringify_eligibility() {
+        // dynamic part is not hoisted, static siblings are hoisted one by one
+        let val = compile(&format!("<div>{{{{ a }}}}{}</div>", SPANS));
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+        // attributes set as DOM props cannot be stringified
+        let val = compile(&format!("<div><p .title='t'/>{}</div>", SPANS));
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+    }
+
+    #[test]
+    fn test_stringify_table() {
+        let rows = "<tr><td class='a'>1</td></tr>".repeat(5);
+        let val = compile(&format!(
+            "<div><table><tbody>{}</tbody></table></div>",
+            rows
+        ));
+        assert!(val.contains("const _hoisted_1"), "{}", val);
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+    }
+
+    #[test]
+    fn test_stringify_foreign_content() {
+        let paths = "<path d='a'/>".repeat(20);
+        let val = compile(&format!("<div><svg>{}</svg></div>", paths));
+        assert!(val.contains("const _hoisted_1"), "{}", val);
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+        // static children of dynamic svg are hoisted, but not stringified
+        let val = compile(&format!("<svg :a='b'><g>{}</g></svg>", paths));
+        assert!(
+            val.contains(r#"_createElementVNode("g", null, ["#),
+            "{}",
+            val
+        );
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+    }
+
+    #[test]
+    fn test_custom_serializer() {
+        fn to_json(t: &StaticSubtree) -> String {
+            match t {
+                StaticSubtree::Element {
+                    tag,
+                    props,
+                    children,
+                } => {
+                    let props: Vec<_> = props
+                        .iter()
+                        .map(|(k, v)| format!("{:?}:{:?}", k, v))
+                        .collect();
+                    let children: Vec<_> = children.iter().map(to_json).collect();
+                    format!(
+                        r#"{{"tag":{:?},"props":{{{}}},"children":[{}]}}"#,
+                        tag,
+                        props.join(","),
+                        children.join(",")
+                    )
+                }
+                StaticSubtree::Text(t) => format!("{:?}", t),
+                StaticSubtree::Comment(_) => "null".into(),
+            }
+        }
+        let calls = Rc::new(Cell::new(0));
+        let counter = calls.clone();
+        let serializer: StaticSerializer = Rc::new(move |t: &StaticSubtree| {
+            counter.set(counter.get() + 1);
+            format!("createStaticJSON({})", to_json(t))
+        });
+        let source = format!("<div>{}</div>", SPANS);
+        let val = compile_serialized(&source, Some(serializer.clone()));
+        assert_eq!(calls.get(), 1);
+        let json = r#"createStaticJSON({"tag":"div","props":{},"children":[{"tag":"span","#;
+        assert!(val.contains(json), "{}", val);
+        assert!(
+            val.contains(r#""props":{"class":"a"},"children":["1"]"#),
+            "{}",
+            val
+        );
+        assert!(!val.contains("_createStaticVNode"), "{}", val);
+        // eligibility rules apply before the serializer is consulted
+        compile_serialized("<div><span class='a'>1</span></div>", Some(serializer));
+        assert_eq!(calls.get(), 1);
+    }
+
+    #[test]
+    fn test_stringify_bailouts() {
+        let bailouts = bailouts_of(&format!("<div><my-el class='a'/>{}</div>", SPANS));
+        assert_eq!(bailouts.len(), 1);
+        assert_eq!(bailouts[0].tag, "div");
+        assert_eq!(
+            bailouts[0].reason,
+            BailReason::CustomElement("my-el".into())
+        );
+        let paths = "<path d='a'/>".repeat(20);
+        let bailouts = bailouts_of(&format!("<div>\n<svg><g>{}</g></svg></div>", paths));
+        // svg itself is a block, its static child is hoisted but not stringified
+        assert_eq!(bailouts.len(), 2);
+        assert_eq!(bailouts[0].reason, BailReason::Block);
+        assert_eq!(bailouts[1].tag, "g");
+        assert_eq!(bailouts[1].location.start.line, 2);
+        assert_eq!(bailouts[1].reason, BailReason::ForeignContent);
+        // small subtrees are not worth reporting
+        assert!(bailouts_of("<div><my-el class='a'/></div>").is_empty());
+    }
+}
@@ -9,19 +9,12 @@ pub struct UsageWarner(pub RcErrHandle);
 
 impl<'a> CorePass<BaseInfo<'a>> for UsageWarner {
     fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
-        match vn.tag {
-            Js::Symbol(dom_helper::TRANSITION) => {
-                if has_multiple_children(&vn.children) != Multiplicity::Multi {
-                    return;
-                }
-                let error = CE::extended(DomError::TransitionInvalidChildren);
-                self.0.on_error(error);
-            }
-            Js::StrLit(s) if ["script", "style"].contains(&s.raw) => {
-                let error = CE::extended(DomError::IgnoredSideEffectTag);
-                self.0.on_error(error);
+        if let Js::Symbol(dom_helper::TRANSITION) = vn.tag {
+            if has_multiple_children(&vn.children) != Multiplicity::Multi {
+                return;
             }
-            _ => {}
+            let error = CE::extended(DomError::TransitionInvalidChildren);
+            self.0.on_error(error);
         }
     }
 }
@@ -87,6 +80,7 @@ fn ir_multilicity(ir: &BaseIR) -> Multiplicity {
             }
         }
         IRNode::RenderSlotCall(..) => One, // be lenient
+        IRNode::Hoisted(..) => One,
         IRNode::VNodeCall(vn) => {
             if let Js::Symbol(RuntimeHelper::FRAGMENT) = vn.tag {
                 has_multiple_children(&vn.children)
@@ -16,5 +16,8 @@ pub use parse_sfc::{
     SfcParseOptions, SfcDescriptor, SfcBlock, SfcScriptBlock, SfcTemplateBlock, SfcStyleBlock,
 };
 pub use script::SfcScriptCompileOptions;
-pub use template::{SfcTemplateCompileOptions, SfcTemplateCompileResults};
-pub use style::{SfcStyleCompileOptions, SfcStyleCompileResults};
+pub use template::{
+    AssetURLOptions, AssetURLTagConfig, SfcTemplateCompileOptions, SfcTemplateCompileResults,
+    TransformAssetUrlOption,
+};
+pub use style::{SfcStyleCompileOptions, SfcStyleCompileResults, StylePreprocessor};
@@ -46,7 +46,26 @@ pub struct SfcBlock<'a> {
 impl<'a> SfcBlock<'a> {
     fn new(element: Element<'a>, src: &'a str) -> Self {
         let loc = element.location;
-        let content = &src[loc.start.offset..loc.end.offset];
+        // content is between start tag and end tag, children may not
+        // cover all of it since the parser drops comments and whitespace
+        let content = if element.self_closing {
+            ""
+        } else {
+            let props = element.properties.iter();
+            let attr_end = props.map(|p| p.get_location().end.offset).max();
+            let attr_end = attr_end.unwrap_or(loc.start.offset);
+            let start = src[attr_end..]
+                .find('>')
+                .map_or(attr_end, |i| attr_end + i + 1);
+            let elem_src = &src[..loc.end.offset];
+            let tag_name = element.tag_name;
+            // a block missing its end tag runs to the end of file
+            let end = elem_src
+                .rfind("</")
+                .filter(|&i| i >= start && src[i + 2..].starts_with(tag_name))
+                .unwrap_or(loc.end.offset);
+            &src[start..end.max(start)]
+        };
         let attrs = element
             .properties
             .into_iter()
@@ -75,6 +94,8 @@ pub enum SfcError {
     SrcOnScriptSetup,
     ScrtipSrcWithScriptSetup,
     DuplicateBlock,
+    ScopedModuleStyle,
+    StylePreprocess,
 }
 
 impl ErrorKind for SfcError {
@@ -86,6 +107,8 @@ impl ErrorKind for SfcError {
             SrcOnScriptSetup => "<script setup> cannot use the 'src' attribute because its syntax will be ambiguous outside of the component.",
             ScrtipSrcWithScriptSetup => "<script> cannot use the 'src' attribute when <script setup> is also present because they must be processed together.",
             DuplicateBlock => "Single file component can contain only one element: ",
+            ScopedModuleStyle => "<style> cannot be both scoped and module.",
+            StylePreprocess => "Failed to preprocess <style>.",
         }
     }
 }
@@ -113,10 +136,29 @@ impl<'a> SfcScriptBlock<'a> {
 }
 
 pub struct SfcStyleBlock<'a> {
-    // pub scoped: bool,
-    // pub module: Option<&'a str>,
+    pub scoped: bool,
+    /// Binding name of CSS module, `$style` if `module` has no value.
+    pub module: Option<&'a str>,
+    /// Preprocessor language, e.g. scss, less or stylus.
+    pub lang: Option<&'a str>,
     pub block: SfcBlock<'a>,
 }
+
+impl<'a> SfcStyleBlock<'a> {
+    fn new(block: SfcBlock<'a>) -> Self {
+        let module = block
+            .attrs
+            .get("module")
+            .copied()
+            .map(|name| name.filter(|n| !n.is_empty()).unwrap_or("$style"));
+        Self {
+            scoped: block.attrs.contains_key("scoped"),
+            module,
+            lang: block.get_attr("lang"),
+            block,
+        }
+    }
+}
 pub struct SfcCustomBlock<'a> {
     pub custom_type: &'a str,
     pub block: SfcBlock<'a>,
@@ -178,7 +220,7 @@ pub fn parse_sfc(source: &str, option: SfcParseOptions) -> SfcParseResult<'_> {
 fn parse_ast(source: &str, err_handle: RcErrHandle) -> AstRoot {
     let compile_opt = CompileOption {
         is_pre_tag: |_| true,
-        is_native_tag: |_| true,
+        is_native_tag: |_, _| true,
         get_text_mode: |tag| {
             if tag == "template" {
                 TextMode::Data
@@ -246,10 +288,14 @@ fn assemble_descriptor<'a>(
             .attr_only()
             .find()
             .map(|vars| vars.get_ref().get_location().clone());
-        let block = SfcStyleBlock {
-            block: SfcBlock::new(element, src),
-        };
+        let location = element.location.clone();
+        let block = SfcStyleBlock::new(SfcBlock::new(element, src));
+        let scoped_module = block.scoped && block.module.is_some();
         descriptor.styles.push(block);
+        if scoped_module {
+            let error = CompilationError::extended(SfcError::ScopedModuleStyle);
+            return Some(error.with_location(location));
+        }
         has_vars
             .map(|loc| CompilationError::extended(SfcError::DeprecatedStyleVars).with_location(loc))
     } else {
@@ -265,7 +311,7 @@ fn assemble_descriptor<'a>(
 }
 
 fn is_empty(elem: &Element) -> bool {
-    elem.children.iter().any(|n| match n {
+    !elem.children.iter().any(|n| match n {
         AstNode::Text(t) => !t.is_all_whitespace(),
         _ => true,
     })
@@ -274,3 +320,48 @@ fn is_empty(elem: &Element) -> bool {
 fn has_src(elem: &Element) -> bool {
     prop_finder(elem, "src").attr_only().find().is_some()
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(source: &str) -> SfcParseResult {
+        parse_sfc(source, SfcParseOptions::default())
+    }
+
+    #[test]
+    fn test_style_block_attrs() {
+        let ret = parse("<style lang='scss' scoped>a {}</style><style module>b {}</style>");
+        assert!(ret.errors.is_empty());
+        let styles = &ret.descriptor.styles;
+        assert_eq!(styles.len(), 2);
+        assert!(styles[0].scoped);
+        assert_eq!(styles[0].lang, Some("scss"));
+        assert_eq!(styles[0].module, None);
+        assert!(!styles[1].scoped);
+        assert_eq!(styles[1].lang, None);
+        assert_eq!(styles[1].module, Some("$style"));
+        let ret = parse("<style module='classes'>a {}</style>");
+        assert_eq!(ret.descriptor.styles[0].module, Some("classes"));
+    }
+
+    #[test]
+    fn test_block_content() {
+        let source = "<template>\n  <!-- c -->\n  <div/>\n</template>\n<style>\n</style>";
+        let ret = parse(source);
+        let template = ret.descriptor.template.unwrap();
+        assert_eq!(template.block.content, "\n  <!-- c -->\n  <div/>\n");
+        let ret = parse("<script lang='ts' setup>let a = '>'</script>");
+        assert_eq!(ret.descriptor.scripts[0].block.content, "let a = '>'");
+    }
+
+    #[test]
+    fn test_scoped_module_style() {
+        let ret = parse("<style scoped module>a {}</style>");
+        assert_eq!(ret.errors.len(), 1);
+        let msg = ret.errors[0].to_string();
+        assert_eq!(msg, "<style> cannot be both scoped and module.");
+        // block is still recorded
+        assert_eq!(ret.descriptor.styles.len(), 1);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{SfcDescriptor, SfcTemplateCompileOptions};
+use compiler::error::CompileError;
 
 pub struct SfcScriptCompileOptions<'a> {
     /// Scope ID for prefixing injected CSS varialbes.
@@ -36,9 +37,16 @@ pub struct SfcScriptCompileOptions<'a> {
 //     is_used_in_template: bool,
 // }
 
+/// Script compilation is not implemented yet. SFCs without `<script>` are
+/// returned as is, others fail with [CompileError::UnsupportedSyntax].
 pub fn compile_script<'a>(
-    _sfc: SfcDescriptor<'a>,
+    sfc: SfcDescriptor<'a>,
     _options: SfcScriptCompileOptions<'a>,
-) -> SfcDescriptor<'a> {
-    todo!()
+) -> Result<SfcDescriptor<'a>, CompileError> {
+    if sfc.scripts.is_empty() {
+        return Ok(sfc);
+    }
+    Err(CompileError::UnsupportedSyntax {
+        description: "<script> compilation",
+    })
 }
@@ -1 +1,53 @@
+//! `v-bind()` in style is replaced by CSS variables that are injected by
+//! `useCssVars` in the component, e.g. `v-bind(color)` becomes `var(--abc-color)`.
+use compiler::manifest::hash_hex;
 
+pub fn rewrite_css_vars(css: &str, id: &str, is_prod: bool) -> String {
+    const V_BIND: &str = "v-bind(";
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    while let Some(start) = rest.find(V_BIND) {
+        let args = &rest[start + V_BIND.len()..];
+        let end = match args.find(')') {
+            Some(end) => end,
+            None => break,
+        };
+        out.push_str(&rest[..start]);
+        let raw = args[..end].trim().trim_matches(|c| c == '\'' || c == '"');
+        out.push_str(&format!("var(--{})", gen_var_name(id, raw, is_prod)));
+        rest = &args[end + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// Same as `genVarName` in @vue/compiler-sfc.
+pub fn gen_var_name(id: &str, raw: &str, is_prod: bool) -> String {
+    if is_prod {
+        let hash = hash_hex(format!("{}{}", id, raw).as_bytes());
+        return hash[..8].to_string();
+    }
+    let name: String = raw
+        .chars()
+        .map(|c| match c {
+            c if c.is_ascii_alphanumeric() || c == '_' || c == '-' => c,
+            _ => '_',
+        })
+        .collect();
+    format!("{}-{}", id, name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rewrite_css_vars() {
+        let css = "a { color: v-bind(color); width: v-bind('size.w') }";
+        let expected = "a { color: var(--abc-color); width: var(--abc-size_w) }";
+        assert_eq!(rewrite_css_vars(css, "abc", false), expected);
+        let prod = rewrite_css_vars("a { color: v-bind(color) }", "abc", true);
+        assert!(!prod.contains("color)"), "{}", prod);
+        assert_eq!(prod.len(), "a { color: var(--12345678) }".len());
+    }
+}
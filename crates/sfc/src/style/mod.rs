@@ -1,6 +1,8 @@
 mod css_module;
 mod css_vars;
 mod scoped;
+pub(crate) use css_vars::gen_var_name;
+use crate::parse_sfc::SfcError;
 use compiler::error::CompilationError;
 
 // pub enum PreprocessLang {
@@ -11,23 +13,172 @@ use compiler::error::CompilationError;
 //     Stylus,
 // }
 
+/// Compiles preprocessor source to plain CSS, called with `lang` and source.
+pub type StylePreprocessor<'a> = &'a dyn Fn(&str, &str) -> Result<String, String>;
+
 pub struct SfcStyleCompileOptions<'a> {
     pub source: &'a str,
     pub filename: &'a str,
+    /// Scope ID shared with `compile_template`, with or without `data-v-` prefix.
     pub id: &'a str,
     pub scoped: bool,
     pub trim: bool,
     pub is_prod: bool,
     // inMap?: RawSourceMap,
-    // preprocessLang: Option<PreprocessLang>,
+    /// `lang` of the style block. Source is preprocessed before
+    /// scoping unless it is plain CSS.
+    pub preprocess_lang: Option<&'a str>,
+    pub preprocessor: Option<StylePreprocessor<'a>>,
+    /// Binding name of `<style module>`, see `SfcStyleBlock::module`.
+    pub module: Option<&'a str>,
+}
+
+impl<'a> Default for SfcStyleCompileOptions<'a> {
+    fn default() -> Self {
+        Self {
+            source: "",
+            filename: "anonymous.vue",
+            id: "",
+            scoped: false,
+            trim: true,
+            is_prod: false,
+            preprocess_lang: None,
+            preprocessor: None,
+            module: None,
+        }
+    }
 }
+
 pub struct SfcStyleCompileResults<'a> {
-    pub code: &'a str,
+    pub code: String,
     pub errors: Vec<CompilationError>,
     // map: RawSourceMap | undefined
     // modules?: Record<string, string>
+    /// Binding injected for CSS module, e.g. `$style`. Script compiler
+    /// registers it so that template can access the module object.
+    pub module_binding: Option<&'a str>,
+}
+
+pub fn compile_style(options: SfcStyleCompileOptions) -> SfcStyleCompileResults {
+    let SfcStyleCompileOptions {
+        source,
+        id,
+        scoped,
+        trim,
+        is_prod,
+        preprocess_lang,
+        preprocessor,
+        module,
+        ..
+    } = options;
+    let mut errors = vec![];
+    let css = match preprocess(source, preprocess_lang, preprocessor) {
+        Ok(css) => css,
+        Err(error) => {
+            errors.push(error);
+            String::new()
+        }
+    };
+    let css = if trim { css.trim().to_string() } else { css };
+    let short_id = id.strip_prefix("data-v-").unwrap_or(id);
+    let mut code = css_vars::rewrite_css_vars(&css, short_id, is_prod);
+    if scoped {
+        code = scoped::scope_css(&code, &format!("data-v-{}", short_id));
+    }
+    SfcStyleCompileResults {
+        code,
+        errors,
+        module_binding: module,
+    }
 }
 
-pub fn compile_style<'a>(_source: &'a str, _filename: &'a str) -> SfcStyleCompileResults<'a> {
-    todo!()
+fn preprocess(
+    source: &str,
+    lang: Option<&str>,
+    preprocessor: Option<StylePreprocessor>,
+) -> Result<String, CompilationError> {
+    let lang = match lang {
+        Some(lang) if lang != "css" => lang,
+        _ => return Ok(source.into()),
+    };
+    let error =
+        |msg: String| CompilationError::extended(SfcError::StylePreprocess).with_context(msg);
+    let preprocessor = match preprocessor {
+        Some(p) => p,
+        None => return Err(error(format!("no preprocessor for lang=\"{}\"", lang))),
+    };
+    preprocessor(lang, source).map_err(error)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parse_sfc::{parse_sfc, SfcParseOptions};
+
+    // replaces scss variables only, enough for testing the hook
+    fn fake_scss(lang: &str, source: &str) -> Result<String, String> {
+        if lang != "scss" {
+            return Err(format!("unknown lang {}", lang));
+        }
+        let mut lines = source.lines();
+        let decl = lines.next().unwrap_or("");
+        let (name, value) = decl.trim().trim_end_matches(';').split_once(':').unwrap();
+        let rest: Vec<_> = lines.collect();
+        Ok(rest.join("\n").replace(name, value.trim()))
+    }
+
+    #[test]
+    fn test_preprocess_then_scope() {
+        let sfc = "<style lang='scss' scoped>$primary: red;\n.a { color: $primary }</style>";
+        let ret = parse_sfc(sfc, SfcParseOptions::default());
+        let style = &ret.descriptor.styles[0];
+        let ret = compile_style(SfcStyleCompileOptions {
+            source: style.block.content,
+            id: "abc",
+            scoped: style.scoped,
+            preprocess_lang: style.lang,
+            preprocessor: Some(&fake_scss),
+            ..Default::default()
+        });
+        assert!(ret.errors.is_empty());
+        assert_eq!(ret.code, ".a[data-v-abc] { color: red }");
+        assert!(ret.module_binding.is_none());
+    }
+
+    #[test]
+    fn test_preprocess_error() {
+        let ret = compile_style(SfcStyleCompileOptions {
+            source: ".a { color: $primary }",
+            preprocess_lang: Some("less"),
+            ..Default::default()
+        });
+        assert_eq!(ret.errors.len(), 1);
+        assert_eq!(
+            ret.errors[0].context.as_deref(),
+            Some("no preprocessor for lang=\"less\"")
+        );
+        assert!(ret.code.is_empty());
+        let ret = compile_style(SfcStyleCompileOptions {
+            source: ".a {}",
+            preprocess_lang: Some("less"),
+            preprocessor: Some(&fake_scss),
+            ..Default::default()
+        });
+        assert_eq!(ret.errors[0].context.as_deref(), Some("unknown lang less"));
+    }
+
+    #[test]
+    fn test_css_module_binding() {
+        let sfc = "<style module='classes'>.a { color: v-bind(c) }</style>";
+        let ret = parse_sfc(sfc, SfcParseOptions::default());
+        let style = &ret.descriptor.styles[0];
+        let ret = compile_style(SfcStyleCompileOptions {
+            source: style.block.content,
+            id: "data-v-abc",
+            module: style.module,
+            ..Default::default()
+        });
+        assert_eq!(ret.module_binding, Some("classes"));
+        assert_eq!(ret.code, ".a { color: var(--abc-c) }");
+    }
 }
@@ -1 +1,225 @@
+//! Scoped style adds the scope attribute to the last compound selector of
+//! every rule, e.g. `.a .b:hover` becomes `.a .b[data-v-xxx]:hover`.
 
+/// Kind of block opened by `{`.
+#[derive(Clone, Copy)]
+enum Block {
+    /// rules whose selectors are scoped, e.g. stylesheet root or `@media`
+    Rules,
+    /// declarations or rules left as is, e.g. `@keyframes` or `@font-face`
+    Opaque,
+}
+
+pub fn scope_css(css: &str, id: &str) -> String {
+    let attr = format!("[{}]", id);
+    let mut out = String::with_capacity(css.len());
+    // selector or at-rule prelude not written yet
+    let mut pending = String::new();
+    let mut stack = vec![Block::Rules];
+    let mut chars = css.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                let mut comment = String::from(c);
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    comment.push(c);
+                    if prev == '*' && c == '/' && comment.len() > 3 {
+                        break;
+                    }
+                    prev = c;
+                }
+                if pending.trim().is_empty() {
+                    out.push_str(&pending);
+                    out.push_str(&comment);
+                    pending.clear();
+                } else {
+                    pending.push_str(&comment);
+                }
+            }
+            '"' | '\'' => {
+                pending.push(c);
+                let mut escaped = false;
+                for s in chars.by_ref() {
+                    pending.push(s);
+                    if !escaped && s == c {
+                        break;
+                    }
+                    escaped = !escaped && s == '\\';
+                }
+            }
+            '{' => {
+                let top = *stack.last().expect("root block is never popped");
+                let block = match top {
+                    Block::Rules if pending.trim_start().starts_with('@') => {
+                        out.push_str(&pending);
+                        at_rule_block(pending.trim_start())
+                    }
+                    Block::Rules => {
+                        out.push_str(&scope_selector_list(&pending, &attr));
+                        Block::Opaque
+                    }
+                    Block::Opaque => {
+                        out.push_str(&pending);
+                        Block::Opaque
+                    }
+                };
+                pending.clear();
+                out.push(c);
+                stack.push(block);
+            }
+            ';' | '}' => {
+                out.push_str(&pending);
+                pending.clear();
+                out.push(c);
+                if c == '}' && stack.len() > 1 {
+                    stack.pop();
+                }
+            }
+            _ => pending.push(c),
+        }
+    }
+    out.push_str(&pending);
+    out
+}
+
+fn at_rule_block(prelude: &str) -> Block {
+    let name = prelude[1..]
+        .split(|c: char| !(c.is_alphanumeric() || c == '-'))
+        .next()
+        .unwrap_or("");
+    match name {
+        "media" | "supports" | "container" | "layer" | "document" => Block::Rules,
+        _ => Block::Opaque,
+    }
+}
+
+fn scope_selector_list(prelude: &str, attr: &str) -> String {
+    let mut depth = 0usize;
+    let selectors: Vec<_> = prelude
+        .split(|c| {
+            match c {
+                '(' | '[' => depth += 1,
+                ')' | ']' => depth = depth.saturating_sub(1),
+                _ => (),
+            }
+            c == ',' && depth == 0
+        })
+        .map(|sel| scope_selector(sel, attr))
+        .collect();
+    selectors.join(",")
+}
+
+/// `.a :deep(.b)` scopes the selector before `:deep` and unwraps its argument.
+fn scope_selector(sel: &str, attr: &str) -> String {
+    const DEEP: &str = ":deep(";
+    let start = match sel.find(DEEP) {
+        Some(start) => start,
+        None => return scope_compound(sel, attr),
+    };
+    let inner_start = start + DEEP.len();
+    let inner_end = match find_close_paren(&sel[inner_start..]) {
+        Some(end) => inner_start + end,
+        None => return scope_compound(sel, attr),
+    };
+    let before = sel[..start].trim_end();
+    let before = if before.trim_start().is_empty() {
+        format!("{}{}", before, attr)
+    } else {
+        scope_compound(before, attr)
+    };
+    let inner = sel[inner_start..inner_end].trim();
+    format!("{} {}{}", before, inner, &sel[inner_end + 1..])
+}
+
+fn find_close_paren(s: &str) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some(i),
+            ')' => depth -= 1,
+            _ => (),
+        }
+    }
+    None
+}
+
+/// Insert attribute after the last compound selector but before its
+/// pseudo classes and elements, like Vue's postcss plugin.
+fn scope_compound(sel: &str, attr: &str) -> String {
+    let end = sel.trim_end().len();
+    let s = &sel[..end];
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            '>' | '+' | '~' if depth == 0 => start = i + 1,
+            c if depth == 0 && c.is_whitespace() => start = i + 1,
+            _ => (),
+        }
+    }
+    let mut insert = end;
+    depth = 0;
+    for (i, c) in s[start..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth = depth.saturating_sub(1),
+            ':' if depth == 0 => {
+                insert = start + i;
+                break;
+            }
+            _ => (),
+        }
+    }
+    format!("{}{}{}", &sel[..insert], attr, &sel[insert..])
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn scope(css: &str) -> String {
+        scope_css(css, "data-v-abc")
+    }
+
+    #[test]
+    fn test_scope_selectors() {
+        assert_eq!(
+            scope("h1 { color: red; }"),
+            "h1[data-v-abc] { color: red; }"
+        );
+        assert_eq!(
+            scope(".a .b, .c>.d {}"),
+            ".a .b[data-v-abc], .c>.d[data-v-abc] {}"
+        );
+        assert_eq!(
+            scope(".a:hover::before {}"),
+            ".a[data-v-abc]:hover::before {}"
+        );
+        assert_eq!(scope("a[href^='x:y'] {}"), "a[href^='x:y'][data-v-abc] {}");
+        assert_eq!(scope(".a:not(.b, .c) {}"), ".a[data-v-abc]:not(.b, .c) {}");
+    }
+
+    #[test]
+    fn test_scope_deep() {
+        assert_eq!(scope(".a :deep(.b) {}"), ".a[data-v-abc] .b {}");
+        assert_eq!(scope(":deep(.b) {}"), "[data-v-abc] .b {}");
+    }
+
+    #[test]
+    fn test_scope_at_rules() {
+        let css = "@media (max-width: 600px) { .a { color: red } }";
+        let expected = "@media (max-width: 600px) { .a[data-v-abc] { color: red } }";
+        assert_eq!(scope(css), expected);
+        let css = "@keyframes fade { from { opacity: 0 } to { opacity: 1 } } .b {}";
+        let expected =
+            "@keyframes fade { from { opacity: 0 } to { opacity: 1 } } .b[data-v-abc] {}";
+        assert_eq!(scope(css), expected);
+        let css = "@import 'a.css'; /* .x { */ .a { content: '}' }";
+        let expected = "@import 'a.css'; /* .x { */ .a[data-v-abc] { content: '}' }";
+        assert_eq!(scope(css), expected);
+    }
+}
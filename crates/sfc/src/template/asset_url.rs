@@ -1,2 +1,238 @@
-pub struct AssetURLOptions {}
-pub struct AssetURLTagConfig {}
+// Relative asset urls in template, e.g. `<img src="./logo.png">`, are
+// rewritten into imports so that bundlers resolve and fingerprint them:
+// `import _imports_0 from "./logo.png"` and `src: _imports_0`.
+use super::src_set::transform_srcset;
+use compiler::converter::{BaseConvertInfo as BaseInfo, BaseRoot, ImportItem};
+use compiler::flags::RuntimeHelper as RH;
+use compiler::ir::JsExpr as Js;
+use compiler::transformer::{BaseVNode, CorePass};
+use compiler::util::VStr;
+use rustc_hash::FxHashMap;
+
+/// Tag name to attributes holding asset urls. `*` matches all tags.
+pub type AssetURLTagConfig = FxHashMap<String, Vec<String>>;
+
+pub struct AssetURLOptions {
+    /// Rewrite relative urls starting with `.` to urls under `base`
+    /// instead of importing them, e.g. `/static/` or `https://cdn.com/`.
+    pub base: Option<String>,
+    /// Also import absolute urls like `/logo.png`, not only relative ones.
+    pub include_absolute: bool,
+    pub tags: AssetURLTagConfig,
+}
+
+impl Default for AssetURLOptions {
+    fn default() -> Self {
+        // same as defaultAssetUrlOptions in @vue/compiler-sfc
+        let tags = [
+            ("video", &["src", "poster"][..]),
+            ("source", &["src"]),
+            ("img", &["src"]),
+            ("image", &["xlink:href", "href"]),
+            ("use", &["xlink:href", "href"]),
+        ];
+        let tags = tags
+            .iter()
+            .map(|(tag, attrs)| {
+                let attrs = attrs.iter().map(|a| a.to_string()).collect();
+                (tag.to_string(), attrs)
+            })
+            .collect();
+        Self {
+            base: None,
+            include_absolute: false,
+            tags,
+        }
+    }
+}
+
+pub struct AssetUrlTransform<'a> {
+    pub options: &'a AssetURLOptions,
+    pub imports: AssetImports<'a>,
+}
+
+impl<'a> AssetUrlTransform<'a> {
+    pub fn new(options: &'a AssetURLOptions) -> Self {
+        Self {
+            options,
+            imports: AssetImports::default(),
+        }
+    }
+}
+
+impl<'a> CorePass<BaseInfo<'a>> for AssetUrlTransform<'a> {
+    fn enter_vnode(&mut self, vn: &mut BaseVNode<'a>) {
+        let tag = match &vn.tag {
+            Js::StrLit(t) if !vn.is_component => t.raw,
+            _ => return,
+        };
+        if let Some(props) = vn.props.as_mut() {
+            self.transform_props(tag, props);
+        }
+    }
+    fn exit_root(&mut self, r: &mut BaseRoot<'a>) {
+        r.top_scope.imports.append(&mut self.imports.0);
+    }
+}
+
+impl<'a> AssetUrlTransform<'a> {
+    fn transform_props(&mut self, tag: &str, props: &mut Js<'a>) {
+        let ps = match props {
+            Js::Call(RH::MERGE_PROPS, args) => {
+                for arg in args {
+                    self.transform_props(tag, arg);
+                }
+                return;
+            }
+            Js::Props(ps) => ps,
+            _ => return,
+        };
+        let tags = &self.options.tags;
+        let is_asset_attr = |k: &str| {
+            let mut attrs = tags.get(tag).into_iter().chain(tags.get("*")).flatten();
+            attrs.any(|a| a == k)
+        };
+        for (key, value) in ps.iter_mut() {
+            let (k, v) = match (&key, &value) {
+                (Js::StrLit(k), Js::StrLit(v)) => (k.raw, v.raw),
+                _ => continue,
+            };
+            // entities are decoded at runtime, leave such urls as is
+            if v.contains('&') {
+                continue;
+            }
+            let transformed = if k == "srcset" && (tag == "img" || tag == "source") {
+                transform_srcset(v, self.options, &mut self.imports)
+            } else if is_asset_attr(k) {
+                self.transform_url(v)
+            } else {
+                None
+            };
+            if let Some(transformed) = transformed {
+                *value = transformed;
+            }
+        }
+    }
+
+    fn transform_url(&mut self, url: &'a str) -> Option<Js<'a>> {
+        let options = self.options;
+        if !should_transform(url, options) || url.starts_with('#') {
+            return None;
+        }
+        if let Some(base) = &options.base {
+            if url.starts_with('.') {
+                return Some(join_base(base, url));
+            }
+        }
+        let (path, hash) = parse_url(url);
+        if path.is_empty() {
+            return None;
+        }
+        let exp = self.imports.import(path);
+        if hash.is_empty() {
+            return Some(exp);
+        }
+        Some(Js::Compound(vec![
+            exp,
+            Js::Src(" + "),
+            Js::StrLit(VStr::raw(hash)),
+        ]))
+    }
+}
+
+/// Imported assets of a template, one binding per distinct path.
+#[derive(Default)]
+pub struct AssetImports<'a>(Vec<ImportItem<'a>>);
+
+impl<'a> AssetImports<'a> {
+    /// binding of `path`, e.g. `_imports_0`
+    pub fn import(&mut self, path: &'a str) -> Js<'a> {
+        let index = match self.0.iter().position(|i| i.path == path) {
+            Some(i) => i,
+            None => {
+                let exp = import_name(self.0.len());
+                self.0.push(ImportItem { exp, path });
+                self.0.len() - 1
+            }
+        };
+        import_name(index)
+    }
+}
+
+fn import_name<'a>(index: usize) -> Js<'a> {
+    Js::Compound(vec![Js::Src("_imports_"), Js::Num(index)])
+}
+
+pub fn should_transform(url: &str, options: &AssetURLOptions) -> bool {
+    !is_external_url(url) && !is_data_url(url) && (options.include_absolute || is_relative_url(url))
+}
+
+pub fn is_relative_url(url: &str) -> bool {
+    url.starts_with(|c| c == '.' || c == '~' || c == '@')
+}
+
+pub fn is_external_url(url: &str) -> bool {
+    url.starts_with("http://") || url.starts_with("https://") || url.starts_with("//")
+}
+
+pub fn is_data_url(url: &str) -> bool {
+    let url = url.trim_start();
+    url.len() >= 5 && url[..5].eq_ignore_ascii_case("data:")
+}
+
+/// Splits url into path and `#hash`. Leading `~` refers to
+/// a module in webpack, it is dropped so that the path is resolvable.
+pub fn parse_url(url: &str) -> (&str, &str) {
+    let url = match url.strip_prefix('~') {
+        Some(rest) => rest.strip_prefix('/').unwrap_or(rest),
+        None => url,
+    };
+    match url.find('#') {
+        Some(i) => url.split_at(i),
+        None => (url, ""),
+    }
+}
+
+/// Same as `path.posix.join(base, url)` for relative url, written as
+/// concatenated literals since both parts are borrowed from the input.
+pub fn join_base<'a>(base: &'a str, url: &'a str) -> Js<'a> {
+    // `..` never goes above host, e.g. `https://cdn.com`
+    let host_end = match base.find("//") {
+        Some(i) => base[i + 2..].find('/').map_or(base.len(), |j| i + 2 + j),
+        None => 0,
+    };
+    let mut dir = base;
+    let mut rest = url;
+    loop {
+        if let Some(r) = rest.strip_prefix("./") {
+            rest = r;
+        } else if let Some(r) = rest.strip_prefix("../") {
+            rest = r;
+            let parent = dir.trim_end_matches('/');
+            let cut = parent[host_end.min(parent.len())..]
+                .rfind('/')
+                .map_or(host_end, |i| host_end + i + 1);
+            dir = &base[..cut.min(parent.len())];
+        } else {
+            break;
+        }
+    }
+    let mut parts = vec![Js::StrLit(VStr::raw(dir))];
+    if !dir.ends_with('/') {
+        parts.push(Js::StrLit(VStr::raw("/")));
+    }
+    parts.push(Js::StrLit(VStr::raw(rest)));
+    Js::Compound(interleave_plus(parts))
+}
+
+/// joins expressions by ` + `
+pub fn interleave_plus(parts: Vec<Js>) -> Vec<Js> {
+    let mut ret = Vec::with_capacity(parts.len() * 2);
+    for part in parts {
+        if !ret.is_empty() {
+            ret.push(Js::Src(" + "));
+        }
+        ret.push(part);
+    }
+    ret
+}
@@ -1,12 +1,19 @@
-use compiler::{converter::BaseIR, error::CompilationError};
-use compiler::compiler::CompileOption;
+use compiler::codegen::{skip_generated_lines, RenderMode, ScriptMode};
+use compiler::compiler::{CompileOption, CompilerBuilder};
+use compiler::error::{CompilationError, CompileError, NoopErrorHandler, VecErrorHandler};
+use compiler::{BindingMetadata, BindingTypes, SFCInfo};
+use dom::{compile_option, get_dom_pass};
+use std::rc::Rc;
 mod asset_url;
 mod src_set;
 
-use asset_url::{AssetURLOptions, AssetURLTagConfig};
+use crate::style::gen_var_name;
+use asset_url::AssetUrlTransform;
+pub use asset_url::{AssetURLOptions, AssetURLTagConfig};
 
 pub enum TransformAssetUrlOption {
     Url(AssetURLOptions),
+    /// Same as `Url` with default options except `tags`.
     Tag(AssetURLTagConfig),
     NoTransform,
 }
@@ -14,15 +21,25 @@ pub enum TransformAssetUrlOption {
 pub struct SfcTemplateCompileOptions<'a> {
     pub source: &'a str,
     pub filename: &'a str,
+    /// Scope ID shared with `compile_style`, with or without `data-v-` prefix.
     pub id: &'a str,
     pub scoped: bool,
     pub slotted: bool,
     pub is_prod: bool,
     pub ssr: bool,
+    /// Variables bound by `v-bind()` in `<style>`, e.g. `color` or `theme.size`.
+    /// SSR renders them in the style of root elements. Ignored if not `ssr`.
     pub ssr_css_vars: Vec<&'a str>,
+    /// Bindings analyzed by `compile_script`.
+    pub binding_metadata: BindingMetadata<'a>,
+    /// Compile render as an arrow function inlined in setup().
+    /// Helper imports and hoists are returned in `preamble` separately.
+    pub inline: bool,
+    /// Options passed to the DOM compiler. Mode is always module,
+    /// `is_dev`, `filename` and `render_mode` are overridden by the fields above.
     pub compile_option: CompileOption,
     /// Configure what tags/attributes to transform into asset url imports,
-    /// or disable the transform altogether with `false`.
+    /// or disable the transform altogether with `NoTransform`.
     pub transform_asset_urls: TransformAssetUrlOption,
     // inMap?: RawSourceMap,
     // compiler: TemplateCompiler,
@@ -33,16 +50,408 @@ pub struct SfcTemplateCompileOptions<'a> {
     // /// passed to correctly resolve the preprocessors.
     // // preprocessCustomRequire?: (id: string) => any
 }
-pub struct SfcTemplateCompileResults<'a> {
+
+impl<'a> Default for SfcTemplateCompileOptions<'a> {
+    fn default() -> Self {
+        // same defaults as @vue/compiler-sfc
+        let compile_option = CompileOption {
+            mode: ScriptMode::Module {
+                runtime_module_name: "vue".into(),
+            },
+            hoist_static: true,
+            cache_handlers: true,
+            ..compile_option(Rc::new(NoopErrorHandler))
+        };
+        Self {
+            source: "",
+            filename: "anonymous.vue",
+            id: "",
+            scoped: false,
+            slotted: true,
+            is_prod: false,
+            ssr: false,
+            ssr_css_vars: vec![],
+            binding_metadata: BindingMetadata::default(),
+            inline: false,
+            compile_option,
+            transform_asset_urls: TransformAssetUrlOption::Url(AssetURLOptions::default()),
+        }
+    }
+}
+
+pub struct SfcTemplateCompileResults {
+    /// Render function. For inline template it is the arrow function only.
     pub code: String,
-    pub ast: Option<BaseIR<'a>>,
+    /// Helper imports and hoists of inline template, to be placed
+    /// at the module top level before the component.
     pub preamble: Option<String>,
     pub source: String,
+    /// Source map of `code`. For inline template it starts at the arrow function.
+    pub map: Option<String>,
     pub tips: Vec<String>,
     pub errors: Vec<CompilationError>,
-    // pub map: RawSourceMap
 }
 
-pub fn compile_template(_options: SfcTemplateCompileOptions) -> SfcTemplateCompileResults {
-    todo!()
+/// Compile SFC template with scope id, bindings and mode derived from
+/// the SFC options. Template errors are reported in `errors`, `Err` is
+/// only returned if no code can be generated at all.
+pub fn compile_template(
+    options: SfcTemplateCompileOptions,
+) -> Result<SfcTemplateCompileResults, CompileError> {
+    let SfcTemplateCompileOptions {
+        source,
+        filename,
+        id,
+        scoped,
+        slotted,
+        is_prod,
+        ssr,
+        ssr_css_vars,
+        binding_metadata,
+        inline,
+        compile_option,
+        transform_asset_urls,
+    } = options;
+    let asset_urls = match transform_asset_urls {
+        TransformAssetUrlOption::Url(opts) => Some(opts),
+        TransformAssetUrlOption::Tag(tags) => Some(AssetURLOptions {
+            tags,
+            ..Default::default()
+        }),
+        TransformAssetUrlOption::NoTransform => None,
+    };
+    let short_id = id.strip_prefix("data-v-").unwrap_or(id);
+    let ssr_css_vars = if ssr && !ssr_css_vars.is_empty() {
+        let vars = &ssr_css_vars;
+        Some(gen_css_vars_from_list(
+            vars,
+            short_id,
+            is_prod,
+            &binding_metadata,
+            inline,
+        ))
+    } else {
+        None
+    };
+    let err_handler = Rc::new(VecErrorHandler::default());
+    let mode = match compile_option.mode {
+        ScriptMode::Module {
+            runtime_module_name,
+        } => ScriptMode::Module {
+            runtime_module_name,
+        },
+        ScriptMode::Function { .. } => ScriptMode::Module {
+            runtime_module_name: "vue".into(),
+        },
+    };
+    let render_mode = if ssr {
+        RenderMode::Ssr
+    } else {
+        RenderMode::Client
+    };
+    let option = CompileOption {
+        mode,
+        render_mode,
+        ssr_css_vars,
+        is_dev: !is_prod,
+        filename: filename.into(),
+        source_map: true,
+        error_handler: err_handler.clone(),
+        ..compile_option
+    };
+    let sfc_info = SFCInfo {
+        inline,
+        slotted,
+        scope_id: scoped.then(|| format!("data-v-{}", short_id)),
+        binding_metadata,
+        self_name: filename.into(),
+    };
+    let mut builder = CompilerBuilder::new(Vec::new, get_dom_pass, option);
+    if let Some(asset_urls) = &asset_urls {
+        builder = builder.add_pre_pass(move |_, _| AssetUrlTransform::new(asset_urls));
+    }
+    let compiler = builder.build();
+    let ret = compiler.compile_with_source_map(source, &sfc_info)?;
+    let mut code = String::from_utf8(ret.code).expect("codegen writes valid utf8");
+    let mut map = ret.map;
+    let preamble = if inline {
+        let func = code.split_off(ret.preamble_bytes);
+        let preamble = std::mem::replace(&mut code, func);
+        // preamble always ends with a newline
+        let lines = preamble.matches('\n').count();
+        map = map.map(|m| skip_generated_lines(&m, lines));
+        Some(preamble)
+    } else {
+        None
+    };
+    let errors = std::mem::take(&mut *err_handler.error_mut());
+    Ok(SfcTemplateCompileResults {
+        code,
+        preamble,
+        source: source.into(),
+        map,
+        tips: vec![],
+        errors,
+    })
+}
+
+/// Same as `genCssVarsFromList` in @vue/compiler-sfc, except that
+/// variables are prefixed here since codegen writes them as is.
+fn gen_css_vars_from_list(
+    vars: &[&str],
+    id: &str,
+    is_prod: bool,
+    bindings: &BindingMetadata,
+    inline: bool,
+) -> String {
+    let entries: Vec<_> = vars
+        .iter()
+        .map(|var| {
+            let name = gen_var_name(id, var, is_prod);
+            format!(
+                "\"--{}\": ({})",
+                name,
+                prefix_css_var(var, bindings, inline)
+            )
+        })
+        .collect();
+    format!("{{ {} }}", entries.join(", "))
+}
+
+/// Prefix the leading identifier of a member path like `theme.color`
+/// the same way as template expressions.
+fn prefix_css_var(var: &str, bindings: &BindingMetadata, inline: bool) -> String {
+    use BindingTypes as BT;
+    let var = var.trim();
+    let is_id = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '$';
+    let (name, rest) = var.split_at(var.find(|c| !is_id(c)).unwrap_or(var.len()));
+    let obj = match bindings.get(name) {
+        None => "_ctx.",
+        Some(BT::Props) if inline => "__props.",
+        // SSR render cannot import unref, only known refs are unwrapped
+        Some(BT::SetupRef) if inline => return format!("{}.value{}", name, rest),
+        Some(BT::Data) | Some(BT::Options) if inline => "_ctx.",
+        Some(_) if inline => "",
+        Some(BT::Data) => "$data.",
+        Some(BT::Props) => "$props.",
+        Some(BT::Options) => "$options.",
+        Some(_) => "$setup.",
+    };
+    format!("{}{}", obj, var)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use compiler::BindingTypes;
+
+    #[test]
+    fn test_scoped_template() {
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div><p>static</p>{{ msg }}</div>",
+            id: "abc",
+            scoped: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(ret.preamble.is_none());
+        assert!(ret.errors.is_empty());
+        assert!(ret.map.is_some());
+        let code = ret.code;
+        assert!(code.contains("_pushScopeId(\"data-v-abc\")"), "{}", code);
+        assert!(code.contains("export function render(_ctx, _cache)"));
+        assert!(code.contains("_ctx.msg"));
+        // id already prefixed is not prefixed twice
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div><p>static</p></div>",
+            id: "data-v-abc",
+            scoped: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(ret.code.contains("\"data-v-abc\""));
+    }
+
+    #[test]
+    fn test_scoped_ssr_template() {
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "hello {{ msg }}",
+            id: "abc",
+            scoped: true,
+            ssr: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let code = ret.code;
+        assert!(code.contains("from \"vue/server-renderer\""), "{}", code);
+        assert!(code.contains("export function ssrRender("));
+        assert!(code.contains("_ssrInterpolate(_ctx.msg)"));
+        assert!(ret.tips.is_empty());
+        // css vars are rendered in the style of root element
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div>{{ msg }}</div>",
+            id: "abc",
+            ssr: true,
+            ssr_css_vars: vec!["color", "theme.size"],
+            ..Default::default()
+        })
+        .unwrap();
+        let code = ret.code;
+        let vars = r#"{ "--abc-color": (_ctx.color), "--abc-theme_size": (_ctx.theme.size) }"#;
+        let css_vars = format!("const _cssVars = {{ style: {} }}", vars);
+        assert!(code.contains(&css_vars), "{}", code);
+        assert!(
+            code.contains("<div${_ssrRenderAttrs(_cssVars)}>"),
+            "{}",
+            code
+        );
+    }
+
+    #[test]
+    fn test_asset_urls() {
+        let source = r#"<img src="./logo.png"><img src="~/logo.png#x"><img src="https://a.com/b.png">
+<img srcset="./a.png 1x, ./b.png 2x"><video poster="@/p.png"></video><p src="./c.png"/>"#;
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source,
+            ..Default::default()
+        })
+        .unwrap();
+        let code = ret.code;
+        assert!(
+            code.contains(r#"import _imports_0 from "./logo.png""#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"import _imports_1 from "logo.png""#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"import _imports_2 from "./a.png""#),
+            "{}",
+            code
+        );
+        assert!(
+            code.contains(r#"import _imports_4 from "@/p.png""#),
+            "{}",
+            code
+        );
+        assert!(code.contains("src: _imports_0"), "{}", code);
+        assert!(code.contains(r##"src: _imports_1 + "#x""##), "{}", code);
+        assert!(code.contains(r#"src: "https://a.com/b.png""#), "{}", code);
+        let srcset = r#"srcset: _imports_2 + " 1x, " + _imports_3 + " 2x""#;
+        assert!(code.contains(srcset), "{}", code);
+        assert!(code.contains(r#"src: "./c.png""#), "{}", code);
+        // base rewrites relative urls, tags limit transformed attributes
+        let mut tags = AssetURLTagConfig::default();
+        tags.insert("p".into(), vec!["src".into()]);
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source,
+            transform_asset_urls: TransformAssetUrlOption::Url(AssetURLOptions {
+                base: Some("/static/img".into()),
+                tags,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .unwrap();
+        let code = ret.code;
+        assert!(!code.contains("_imports_"), "{}", code);
+        assert!(
+            code.contains(r#"src: "/static/img" + "/" + "c.png""#),
+            "{}",
+            code
+        );
+        assert!(code.contains(r#"src: "./logo.png""#), "{}", code);
+        // SSR renders imports with ssrRenderAttr
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: r#"<img src="./logo.png">"#,
+            ssr: true,
+            ..Default::default()
+        })
+        .unwrap();
+        let code = ret.code;
+        assert!(
+            code.contains(r#"import _imports_0 from "./logo.png""#),
+            "{}",
+            code
+        );
+        let img = r#"<img${_ssrRenderAttr("src", _imports_0)}>"#;
+        assert!(code.contains(img), "{}", code);
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: r#"<img src="./logo.png">"#,
+            transform_asset_urls: TransformAssetUrlOption::NoTransform,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!ret.code.contains("import _imports"), "{}", ret.code);
+    }
+
+    #[test]
+    fn test_inline_template() {
+        let bindings = std::iter::once(("count", BindingTypes::SetupRef)).collect();
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div><p>static</p>{{ count }}</div>",
+            inline: true,
+            binding_metadata: BindingMetadata::new(bindings, true),
+            ..Default::default()
+        })
+        .unwrap();
+        let preamble = ret.preamble.unwrap();
+        assert!(preamble.starts_with("import {"), "{}", preamble);
+        assert!(preamble.contains("const _hoisted_1"));
+        let code = ret.code;
+        assert!(code.starts_with("(_ctx, _cache) => {"), "{}", code);
+        assert!(!code.contains("import"));
+        assert!(!code.contains("export"));
+        assert!(code.contains("count.value"));
+        // map skips preamble lines, the last mapping is the interpolation
+        let map = ret.map.unwrap();
+        let mappings = map.split(r#""mappings":""#).nth(1).unwrap();
+        let last_line = mappings.matches(';').count();
+        let line = code.lines().nth(last_line).unwrap();
+        assert!(line.contains("count.value"), "{}\n{}", map, code);
+    }
+
+    #[test]
+    fn test_inline_v_model() {
+        let bindings = [
+            ("count", BindingTypes::SetupLet),
+            ("r", BindingTypes::SetupRef),
+        ];
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<input v-model='count'/><comp v-model='r'/>",
+            inline: true,
+            binding_metadata: BindingMetadata::new(bindings.iter().cloned().collect(), true),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(ret.errors.is_empty());
+        let code = ret.code;
+        // setup let may or may not hold a ref
+        let assign_let = "$event => (_isRef(count)? count.value = $event: count = $event)";
+        assert!(code.contains(assign_let), "{}", code);
+        assert!(code.contains("[_vModelText, "), "{}", code);
+        assert!(code.contains("$event => (r.value = $event)"), "{}", code);
+        assert!(code.contains("modelValue: r.value,"), "{}", code);
+    }
+
+    #[test]
+    fn test_template_errors() {
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div><p></div>",
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!ret.errors.is_empty());
+        assert!(ret.code.contains("export function render"));
+        let ret = compile_template(SfcTemplateCompileOptions {
+            source: "<div v-if=\"a +\">{{ b }}</div>",
+            is_prod: true,
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(!ret.errors.is_empty());
+    }
 }
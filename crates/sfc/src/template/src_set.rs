@@ -1 +1,92 @@
+// `srcset` lists candidate urls with descriptors, e.g. `./a.png 1x, ./b.png 2x`.
+// Each qualified url is imported like `src`, and the attribute becomes
+// `_imports_0 + " 1x, " + _imports_1 + " 2x"`.
+use super::asset_url::{
+    interleave_plus, is_data_url, join_base, parse_url, should_transform, AssetImports,
+    AssetURLOptions,
+};
+use compiler::ir::JsExpr as Js;
+use compiler::util::VStr;
 
+/// A url in srcset and the text up to the next url,
+/// i.e. its descriptor and the comma.
+struct Candidate<'a> {
+    url: &'a str,
+    rest: &'a str,
+}
+
+fn parse_candidates(srcset: &str) -> Vec<Candidate> {
+    let mut urls = vec![];
+    let mut pos = 0;
+    // data url contains comma, it continues in the next part
+    let mut data_url_start = None;
+    for part in srcset.split(',') {
+        let part_start = pos;
+        pos += part.len() + 1;
+        let url_start = part_start + part.len() - part.trim_start().len();
+        let url_len = part.trim_start().find(char::is_whitespace);
+        let url_end = url_len.map_or(part_start + part.trim_end().len(), |l| url_start + l);
+        if url_start >= url_end {
+            continue;
+        }
+        let start = data_url_start.take().unwrap_or(url_start);
+        let url = &srcset[start..url_end];
+        if is_data_url(url) && !url.contains(',') {
+            data_url_start = Some(start);
+            continue;
+        }
+        urls.push((start, url_end));
+    }
+    let mut candidates = Vec::with_capacity(urls.len());
+    for (i, &(start, end)) in urls.iter().enumerate() {
+        let next = urls.get(i + 1).map_or(srcset.trim_end().len(), |u| u.0);
+        candidates.push(Candidate {
+            url: &srcset[start..end],
+            rest: &srcset[end..next],
+        });
+    }
+    candidates
+}
+
+pub fn transform_srcset<'a>(
+    srcset: &'a str,
+    options: &'a AssetURLOptions,
+    imports: &mut AssetImports<'a>,
+) -> Option<Js<'a>> {
+    let candidates = parse_candidates(srcset);
+    if !candidates.iter().any(|c| should_transform(c.url, options)) {
+        return None;
+    }
+    let mut parts = vec![];
+    for Candidate { url, rest } in candidates {
+        let base = options.base.as_deref();
+        match base {
+            Some(base) if url.starts_with('.') => parts.push(join_base(base, url)),
+            None if should_transform(url, options) => {
+                // hash is meaningless for images
+                let (path, _) = parse_url(url);
+                parts.push(imports.import(path));
+            }
+            _ => parts.push(Js::StrLit(VStr::raw(url))),
+        }
+        if !rest.is_empty() {
+            parts.push(Js::StrLit(VStr::raw(rest)));
+        }
+    }
+    Some(Js::Compound(interleave_plus(parts)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_candidates() {
+        let srcset = " ./a.png 1x,data:image/png;base64,AAA 2x , /b.png";
+        let candidates = parse_candidates(srcset);
+        let urls: Vec<_> = candidates.iter().map(|c| c.url).collect();
+        assert_eq!(urls, ["./a.png", "data:image/png;base64,AAA", "/b.png"]);
+        let rests: Vec<_> = candidates.iter().map(|c| c.rest).collect();
+        assert_eq!(rests, [" 1x,", " 2x , ", ""]);
+    }
+}
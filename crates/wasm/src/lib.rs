@@ -1,15 +1,76 @@
 use wasm_bindgen::prelude::*;
-use compiler::compiler::{BaseCompiler, TemplateCompiler, get_base_passes};
+use compiler::codegen::ScriptMode;
+use compiler::compiler::{BaseCompiler, CompileOption, TemplateCompiler, get_base_passes};
+use serde::Deserialize;
 
+#[cfg(target_arch = "wasm32")]
 #[global_allocator]
 static ALLOC: wee_alloc::WeeAlloc = wee_alloc::WeeAlloc::INIT;
 
 #[wasm_bindgen(js_name = baseCompile)]
-pub fn base_compile(source: &str) -> String {
+pub fn base_compile(source: &str) -> Result<String, JsValue> {
+    compile_with(source, Default::default())
+}
+
+/// Options accepted by `compile`, a subset of @vue/compiler-core options.
+/// Missing fields keep the `CompileOption` defaults.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompileOptions {
+    /// "module" or "function"
+    pub mode: Option<String>,
+    pub prefix_identifiers: Option<bool>,
+    pub hoist_static: Option<bool>,
+    pub cache_handlers: Option<bool>,
+    pub is_ts: Option<bool>,
+    pub is_dev: Option<bool>,
+    pub filename: Option<String>,
+}
+
+impl From<CompileOptions> for CompileOption {
+    fn from(opts: CompileOptions) -> Self {
+        let mode = match opts.mode.as_deref() {
+            Some("module") => ScriptMode::Module {
+                runtime_module_name: "vue".into(),
+            },
+            _ => ScriptMode::Function {
+                prefix_identifier: opts.prefix_identifiers.unwrap_or(false),
+                runtime_global_name: "Vue".into(),
+            },
+        };
+        let default = CompileOption::default();
+        CompileOption {
+            mode,
+            hoist_static: opts.hoist_static.unwrap_or(default.hoist_static),
+            cache_handlers: opts.cache_handlers.unwrap_or(default.cache_handlers),
+            is_ts: opts.is_ts.unwrap_or(default.is_ts),
+            is_dev: opts.is_dev.unwrap_or(default.is_dev),
+            filename: opts.filename.unwrap_or_default(),
+            ..default
+        }
+    }
+}
+
+/// Compiles template to render function code. `options` is a plain object
+/// like `{ mode: "module", hoistStatic: true }`, or undefined for defaults.
+#[wasm_bindgen]
+pub fn compile(source: &str, options: JsValue) -> Result<String, JsValue> {
+    let opts: CompileOptions = if options.is_undefined() || options.is_null() {
+        CompileOptions::default()
+    } else {
+        options
+            .into_serde()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?
+    };
+    compile_with(source, opts.into())
+}
+
+fn compile_with(source: &str, option: CompileOption) -> Result<String, JsValue> {
     let sfc_info = Default::default();
-    let option = Default::default();
     let dest = Vec::new;
     let compiler = BaseCompiler::new(dest, get_base_passes, option);
-    let ret = compiler.compile(source, &sfc_info).unwrap();
-    String::from_utf8(ret).unwrap()
+    let ret = compiler
+        .compile(source, &sfc_info)
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(String::from_utf8(ret).unwrap())
 }
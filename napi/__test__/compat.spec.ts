@@ -0,0 +1,135 @@
+import test from 'ava'
+import { compileTemplate } from '@vue/compiler-sfc'
+
+import { compile, parse } from '../compiler-dom'
+
+test('compile returns compiler-sfc shaped result', (t) => {
+  const { code, errors, tips } = compile('<div>{{ msg }}</div>')
+  t.true(code.includes('function render'))
+  t.deepEqual(errors, [])
+  t.deepEqual(tips, [])
+})
+
+test('module mode with prefixed identifiers', (t) => {
+  const { code } = compile('<div>{{ msg }}</div>', {
+    mode: 'module',
+    prefixIdentifiers: true,
+    hoistStatic: true,
+    cacheHandlers: true,
+  })
+  t.true(code.includes('from "vue"'))
+  t.true(code.includes('_ctx.msg'))
+})
+
+test('source map', (t) => {
+  const { map } = compile('<div>{{ msg }}</div>', { sourceMap: true, filename: 'App.vue' })
+  t.is(map.version, 3)
+  t.deepEqual(map.sources, ['App.vue'])
+  t.true(map.mappings.length > 0)
+  t.is(compile('<div/>').map, undefined)
+})
+
+test('build manifest', (t) => {
+  const { manifest } = compile('<comp v-for="i in list"/>', { manifest: true })
+  t.is(manifest.schemaVersion, 1)
+  t.deepEqual(manifest.components, ['comp'])
+  t.deepEqual(manifest.features, ['v-for'])
+  t.is(manifest.errorCount, 0)
+  t.is(compile('<div/>').manifest, undefined)
+})
+
+test('binding metadata', (t) => {
+  const { code } = compile('<div>{{ count }}</div>', {
+    mode: 'module',
+    bindingMetadata: { count: 'setup-ref', __isScriptSetup: true },
+  })
+  t.true(code.includes('$setup.count'))
+})
+
+test('unknown options are reported', (t) => {
+  const warnings: string[] = []
+  const { tips } = compile('<div/>', {
+    whatever: true,
+    onWarn: (w: { message: string }) => warnings.push(w.message),
+  } as any)
+  t.is(tips.length, 1)
+  t.true(tips[0].includes('whatever'))
+  t.deepEqual(warnings, tips)
+})
+
+test('isCustomElement accepts array, regex and function', (t) => {
+  const template = '<my-el></my-el>'
+  for (const isCustomElement of [['my-el'], /^my-/, (tag: string) => tag.startsWith('my-')]) {
+    const { code } = compile(template, { isCustomElement })
+    t.false(code.includes('resolveComponent'), code)
+  }
+  const { code } = compile(template)
+  t.true(code.includes('resolveComponent'))
+})
+
+test('errors are forwarded to onError', (t) => {
+  const errors: Error[] = []
+  const result = compile('<div v-show></div>', { onError: (e: Error) => errors.push(e) })
+  t.is(result.errors.length, 1)
+  t.is(errors[0], result.errors[0])
+})
+
+test('errors carry help and doc slug', (t) => {
+  const { errors } = compile('<div><span></div>')
+  const [error] = errors as Array<SyntaxError & { slug: string; help: string }>
+  t.is(error.slug, 'missing-end-tag')
+  t.true(error.help.includes('</span>'))
+})
+
+test('errors carry vue-next code and source span', (t) => {
+  const source = '<div v-else>a</div>'
+  const { errors } = compile(source)
+  const [error] = errors as Array<SyntaxError & { code: number; loc: any }>
+  t.is(error.code, 30)
+  t.is(source.slice(error.loc.start.offset, error.loc.end.offset), 'v-else')
+  const emoji = '<p>😀</p><div v-else>a</div>'
+  const [shifted] = compile(emoji).errors as Array<SyntaxError & { loc: any }>
+  t.is(emoji.slice(shifted.loc.start.offset, shifted.loc.end.offset), 'v-else')
+})
+
+test('parse returns compiler-core shaped ast', (t) => {
+  const ast = parse('<div :id="a">{{ msg }}</div>')
+  const [div] = ast.children
+  t.is(div.type, 1)
+  t.is(div.tag, 'div')
+  const [dir] = div.props
+  t.is(dir.type, 7)
+  t.is(dir.name, 'bind')
+  t.is(dir.arg.content, 'id')
+  t.is(dir.exp.content, 'a')
+  const [interpolation] = div.children
+  t.is(interpolation.type, 5)
+  t.is(interpolation.content.content, 'msg')
+  t.is(interpolation.content.loc.source, 'msg')
+})
+
+test('ast locations count UTF-16 code units', (t) => {
+  const source = '<p>😀</p>\n<i/>'
+  const [p, i] = parse(source).children
+  t.deepEqual(i.loc.start, { offset: 10, line: 2, column: 1 })
+  t.is(source.slice(p.loc.start.offset, p.loc.end.offset), '<p>😀</p>')
+})
+
+test('compile exposes ast', (t) => {
+  const { ast } = compile('<div>hello</div>')
+  t.is(ast.type, 0)
+  t.is(ast.children[0].tag, 'div')
+})
+
+// the way @vitejs/plugin-vue compiles template with `template.compiler` option
+test('compiler-sfc compileTemplate with custom compiler', (t) => {
+  const { code, errors } = compileTemplate({
+    source: '<div class="app">{{ msg }}</div>',
+    filename: 'App.vue',
+    id: 'data-v-app',
+    compiler: { compile, parse } as any,
+  })
+  t.deepEqual(errors, [])
+  t.true(code.includes('function render'))
+  t.true(code.includes('"app"'))
+})
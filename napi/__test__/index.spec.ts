@@ -1,6 +1,18 @@
 import test from 'ava'
 
-import { compileSync } from '../index'
+import {
+  clearCompileCache,
+  compareRenders,
+  CompileFailure,
+  compile,
+  compileMany,
+  compileSync,
+  compileTemplate,
+  compileTemplateAsync,
+  compileWithOptions,
+  irToJson,
+  templateHash,
+} from '../index'
 
 test('sync function from native code', (t) => {
   const fixture = "hello world"
@@ -13,8 +25,173 @@ return function render(_ctx, _cache) {
   t.is(compileSync(fixture), expected)
 })
 
-// test('sleep function from native code', async (t) => {
-//   const timeToSleep = 200
-//   const value = await sleep(timeToSleep)
-//   t.is(value, timeToSleep * 2)
-// })
+test('async compile resolves with the same code', async (t) => {
+  const fixture = 'hello world'
+  t.is(await compile(fixture), compileSync(fixture))
+})
+
+test('async compile rejects with an Error', async (t) => {
+  const err = await t.throwsAsync(compile('<div/>', { mode: 'esm' }), { instanceOf: Error })
+  t.is(err.message, 'unknown mode "esm"')
+})
+
+test('async compile runs concurrently with options', async (t) => {
+  const templates = Array.from({ length: 16 }, (_, i) => `<div>{{ msg${i} }}</div>`)
+  const results = await Promise.all(templates.map((tpl) => compile(tpl, { mode: 'module' })))
+  results.forEach((code, i) => {
+    t.true(code.includes('export function render'))
+    t.true(code.includes(`_ctx.msg${i}`))
+  })
+})
+
+test('useCache serves every compile API with diagnostics', async (t) => {
+  clearCompileCache()
+  const source = '<div><p></div>'
+  const sync = compileWithOptions(source, { useCache: true })
+  t.true(sync.errors.length > 0)
+  t.deepEqual(await compileTemplateAsync(source, { useCache: true }), sync)
+  const [batch] = await compileMany([source], { useCache: true })
+  t.is(batch.code, sync.code)
+  // opt-in outputs are part of the cache key
+  t.truthy(compileWithOptions(source, { useCache: true, manifest: true }).manifest)
+})
+
+test('modifier-only handlers are hoisted and shared', (t) => {
+  const fixture = `<form @submit.prevent>
+  <a @click.stop.prevent></a>
+  <b @click.stop.prevent></b>
+  <input @keyup.enter>
+</form>`
+  const { code } = compileWithOptions(fixture, { mode: 'module', hoistStatic: true })
+  t.true(code.includes('const _hoisted_1 = _withModifiers(() => {}, ["stop", "prevent"])'))
+  t.true(code.includes('const _hoisted_2 = _withKeys(() => {}, ["enter"])'))
+  t.true(code.includes('const _hoisted_3 = _withModifiers(() => {}, ["prevent"])'))
+  t.is(code.split('onClick: _hoisted_1').length, 3)
+  t.true(code.includes('onKeyup: _hoisted_2'))
+  t.true(code.includes('onSubmit: _hoisted_3'))
+})
+
+test('compileMany returns results in source order', async (t) => {
+  const templates = Array.from({ length: 32 }, (_, i) => `<p>{{ item${i} }}</p>`)
+  const results = await compileMany(templates, { mode: 'module' })
+  t.is(results.length, templates.length)
+  results.forEach((ret, i) => {
+    t.falsy(ret.error)
+    t.true(ret.code!.includes(`_ctx.item${i}`))
+  })
+  t.deepEqual(await compileMany([]), [])
+})
+
+test('compileMany reports diagnostics per template', async (t) => {
+  const [ok, bad] = await compileMany(['<p/>', '<div><p></div>'])
+  t.deepEqual(ok.diagnostics, [])
+  t.falsy(bad.error)
+  t.deepEqual(bad.diagnostics, compileWithOptions('<div><p></div>').diagnostics)
+  t.true(bad.diagnostics.length > 0)
+})
+
+test('compileMany uses bindings of each file', async (t) => {
+  const templates = ['<p>{{ a }}</p>', '<p>{{ a }}</p>']
+  const files = {
+    filenames: ['A.vue', 'B.vue'],
+    bindings: {
+      'A.vue': [{ name: 'a', kind: 'setup-const' }],
+      'B.vue': [{ name: 'a', kind: 'props' }],
+    },
+  }
+  const [a, b] = await compileMany(templates, { mode: 'module' }, files)
+  t.true(a.code!.includes('$setup.a'))
+  t.true(b.code!.includes('$props.a'))
+  t.not(a.cacheKey, b.cacheKey)
+  // changed bindings of one file invalidate only its cache key
+  files.bindings['B.vue'] = [{ name: 'a', kind: 'data' }]
+  const [a2, b2] = await compileMany(templates, { mode: 'module' }, files)
+  t.is(a2.cacheKey, a.cacheKey)
+  t.not(b2.cacheKey, b.cacheKey)
+  t.true(b2.code!.includes('$data.a'))
+})
+
+test('compareRenders decides hot reload strategy', (t) => {
+  const hmr = (source: string) => compileWithOptions(source, { hmr: true }).hmr!
+  const old = hmr('<p>{{ msg }}</p>')
+  const text = hmr('<p>{{ msg }} {{ count }}</p>')
+  const slot = hmr('<p>{{ msg }}<slot name="footer"/></p>')
+  t.is(compareRenders(old, text), 'rerender')
+  t.is(compareRenders(old, slot), 'reload')
+  t.falsy(compileWithOptions('<p/>').hmr)
+  t.is(templateHash('<div>\n  <p>a</p>\n</div>'), templateHash('<div><p>a</p></div>'))
+})
+
+test('compileTemplate derives scope id and splits inline preamble', (t) => {
+  const scoped = compileTemplate('<div><p>static</p>{{ msg }}</div>', { id: 'abc', scoped: true })
+  t.true(scoped.code.includes('_pushScopeId("data-v-abc")'))
+  t.true(scoped.code.includes('export function render'))
+  t.falsy(scoped.preamble)
+  const inline = compileTemplate('<p>{{ count }}</p>', {
+    inline: true,
+    bindings: [{ name: 'count', kind: 'setup-ref' }],
+  })
+  t.true(inline.preamble!.startsWith('import {'))
+  t.true(inline.code.startsWith('(_ctx, _cache) => {'))
+  t.true(inline.code.includes('count.value'))
+  t.true(compileTemplate('<div><p></div>').errors.length > 0)
+  t.throws(() => compileTemplate('<div/>', { ssr: true }))
+})
+
+test('compileWithOptions maps options and rejects invalid ones', (t) => {
+  const { code, diagnostics } = compileWithOptions('<div>{{msg}}</div>', { prefixIdentifiers: true })
+  t.true(code.includes('_ctx.msg'))
+  t.deepEqual(diagnostics, [])
+  // TS output cannot use `with` scope
+  t.false(compileWithOptions('<div>{{msg}}</div>', { isTs: true }).code.includes('with (_ctx)'))
+  t.throws(() => compileWithOptions('<div/>', { mode: 'esm' }), { message: 'unknown mode "esm"' })
+  const bindings = [{ name: 'msg', kind: 'setup-cosnt' }]
+  t.throws(() => compileWithOptions('<div/>', { bindings }), { message: /unknown binding kind/ })
+})
+
+test('irToJson exposes transformed IR', (t) => {
+  const ir = JSON.parse(irToJson('<p v-if="ok" :id="a">{{ msg }}</p>', { prefixIdentifiers: true }))
+  const vnode = ir.body[0].If.branches[0].child.VNodeCall
+  t.true(vnode.is_block)
+  t.deepEqual(Object.keys(ir.top_scope).sort(), [
+    'components',
+    'directives',
+    'helpers',
+    'hoist_bailouts',
+    'hoists',
+    'imports',
+    'is_static',
+    'temps',
+  ])
+  t.throws(() => irToJson('<div/>', { mode: 'esm' }))
+})
+
+test('compileTemplateAsync resolves with full result', async (t) => {
+  const fixture = '<div><p>{{ msg }}</div>'
+  const ret = await compileTemplateAsync(fixture, { mode: 'module' })
+  t.deepEqual(ret, compileWithOptions(fixture, { mode: 'module' }))
+  t.true(ret.diagnostics.length > 0)
+  const err = await t.throwsAsync(compileTemplateAsync('<div/>', { mode: 'esm' }), {
+    instanceOf: Error,
+    message: 'unknown mode "esm"',
+  })
+  const { code, start, end } = err as Error & CompileFailure
+  t.deepEqual({ code, start, end }, { code: 'InvalidOption', start: 0, end: 6 })
+})
+
+test('compileTemplateAsync handles large and concurrent templates', async (t) => {
+  const row = (i: number) => `<li :id="id${i}" @click="pick(${i})">{{ label${i} }} static text</li>`
+  const large = `<ul>${Array.from({ length: 6000 }, (_, i) => row(i)).join('\n')}</ul>`
+  t.true(large.length > 300 * 1024)
+  const templates = Array.from({ length: 8 }, (_, i) => `<p :class="c${i}">{{ m${i} }}</p>`)
+  const [big, ...rets] = await Promise.all([
+    compileTemplateAsync(large, { mode: 'module' }),
+    ...templates.map((tpl) => compileTemplateAsync(tpl, { mode: 'module' })),
+  ])
+  t.deepEqual(big.errors, [])
+  t.true(big.code.includes('_ctx.label5999'))
+  rets.forEach((ret, i) => {
+    t.is(ret.code, compileWithOptions(templates[i], { mode: 'module' }).code)
+    t.is(ret.code.match(/_ctx\.m\d+/g)!.join(), `_ctx.m${i}`)
+  })
+})
@@ -0,0 +1,167 @@
+/**
+ * Drop-in replacement for the `compile` and `parse` API of @vue/compiler-dom.
+ * Options are translated to the native binding, and options without a native
+ * counterpart are reported through `onWarn` and `tips` instead of being ignored.
+ */
+const { compileWithOptions, parseToCompatJson } = require('./index')
+
+const BINDING_TYPES = new Set(['data', 'props', 'setup-let', 'setup-const', 'setup-maybe-ref', 'setup-ref', 'options'])
+
+// options that only affect JS side or are irrelevant to output
+const IGNORED_OPTIONS = new Set(['onError', 'onWarn', 'isProd'])
+
+const TAG_RE = /<([a-zA-Z][^\s/>]*)/g
+
+// isCustomElement can be array, regex or function in JS. Native side only
+// accepts a list, so we test every tag appearing in the template beforehand.
+function collectCustomElements(template, isCustomElement) {
+  let test
+  if (Array.isArray(isCustomElement)) {
+    test = (tag) => isCustomElement.includes(tag)
+  } else if (isCustomElement instanceof RegExp) {
+    test = (tag) => {
+      isCustomElement.lastIndex = 0
+      return isCustomElement.test(tag)
+    }
+  } else if (typeof isCustomElement === 'function') {
+    test = isCustomElement
+  } else {
+    return null
+  }
+  const tags = new Set()
+  for (const [, tag] of template.matchAll(TAG_RE)) {
+    if (test(tag)) {
+      tags.add(tag)
+    }
+  }
+  return [...tags]
+}
+
+function translateBindings(metadata, native, warn) {
+  native.bindings = []
+  for (const [name, type] of Object.entries(metadata)) {
+    if (name === '__isScriptSetup') {
+      native.scriptSetup = !!type
+    } else if (BINDING_TYPES.has(type)) {
+      native.bindings.push({ name, kind: type })
+    } else {
+      warn(`unknown binding type "${type}" for "${name}" is ignored`)
+    }
+  }
+}
+
+function translateOptions(template, options, warn) {
+  const native = {}
+  for (const [key, value] of Object.entries(options)) {
+    if (value === undefined || IGNORED_OPTIONS.has(key)) {
+      continue
+    }
+    switch (key) {
+      case 'mode':
+        if (value === 'module' || value === 'function') {
+          native.mode = value
+        } else {
+          warn(`unknown mode "${value}", fallback to "function"`)
+        }
+        break
+      case 'prefixIdentifiers':
+      case 'hoistStatic':
+      case 'cacheHandlers':
+      case 'sourceMap':
+      case 'inline':
+      case 'slotted':
+      case 'manifest':
+        native[key] = !!value
+        break
+      case 'isTS':
+        native.isTs = !!value
+        break
+      case 'scopeId':
+      case 'filename':
+        native[key] = String(value)
+        break
+      case 'bindingMetadata':
+        translateBindings(value, native, warn)
+        break
+      case 'isCustomElement': {
+        const tags = collectCustomElements(template, value)
+        if (tags) {
+          native.customElements = tags
+        } else {
+          warn('isCustomElement should be an array, a RegExp or a function')
+        }
+        break
+      }
+      case 'nodeTransforms':
+      case 'directiveTransforms':
+        if (Object.keys(value).length) {
+          warn(`option "${key}" is not supported and is ignored`)
+        }
+        break
+      default:
+        // falsy values keep the default behavior, e.g. ssr: false
+        if (value) {
+          warn(`option "${key}" is not supported and is ignored`)
+        }
+    }
+  }
+  return native
+}
+
+function toCompilerError({ message, slug, help, code, start, end }) {
+  return Object.assign(new SyntaxError(message), {
+    slug,
+    help,
+    code,
+    // only offsets are known, enough for generateCodeFrame
+    loc: { start: { offset: start }, end: { offset: end } },
+  })
+}
+
+function compile(template, options = {}) {
+  const tips = []
+  const warn = (message) => {
+    tips.push(message)
+    if (options.onWarn) {
+      options.onWarn({ message })
+    }
+  }
+  const native = translateOptions(template, options, warn)
+  const { code, map, diagnostics, warnings, manifest } = compileWithOptions(template, native)
+  const errors = diagnostics.map(toCompilerError)
+  if (options.onError) {
+    errors.forEach(options.onError)
+  }
+  for (const warning of warnings.map(toCompilerError)) {
+    tips.push(warning.message)
+    if (options.onWarn) {
+      options.onWarn(warning)
+    }
+  }
+  const result = {
+    code,
+    map: map ? JSON.parse(map) : undefined,
+    errors,
+    tips,
+    manifest: manifest ? JSON.parse(manifest) : undefined,
+  }
+  // parsed again on first access only, most callers read code alone.
+  // Unlike @vue/compiler-dom it is the AST before transform, no codegenNode.
+  let ast
+  Object.defineProperty(result, 'ast', {
+    enumerable: true,
+    get: () => (ast = ast || parse(template)),
+  })
+  return result
+}
+
+function parse(template, options = {}) {
+  for (const key of Object.keys(options)) {
+    if (!IGNORED_OPTIONS.has(key) && options.onWarn) {
+      options.onWarn({ message: `parse option "${key}" is not supported and is ignored` })
+    }
+  }
+  return JSON.parse(parseToCompatJson(template))
+}
+
+module.exports = { compile, parse }
@@ -1 +1,102 @@
+export interface CompileOptions {
+  mode?: string
+  prefixIdentifiers?: boolean
+  hoistStatic?: boolean
+  cacheHandlers?: boolean
+  sourceMap?: boolean
+  scopeId?: string
+  filename?: string
+  isTs?: boolean
+  inline?: boolean
+  slotted?: boolean
+  bindings?: Array<Binding>
+  scriptSetup?: boolean
+  customElements?: Array<string>
+  manifest?: boolean
+  hmr?: boolean
+  useCache?: boolean
+}
+export interface Binding {
+  name: string
+  kind: string
+}
+export interface CompileResult {
+  code: string
+  map?: string | undefined | null
+  errors: Array<string>
+  diagnostics: Array<Diagnostic>
+  warnings: Array<Diagnostic>
+  manifest?: string | undefined | null
+  cacheKey: string
+  isStatic: boolean
+  hmr?: HmrInfo | undefined | null
+}
+export interface HmrInfo {
+  templateHash: string
+  hasSlots: boolean
+  usesEmit: boolean
+  scopeId?: string | undefined | null
+}
+export interface CompileFailure {
+  code: string
+  message: string
+  start: number
+  end: number
+}
+export interface BatchCompileResult {
+  code?: string | undefined | null
+  error?: string | undefined | null
+  cacheKey?: string | undefined | null
+  diagnostics: Array<Diagnostic>
+  warnings: Array<Diagnostic>
+}
+export interface BatchFiles {
+  filenames: Array<string>
+  bindings: Record<string, Array<Binding>>
+}
+export interface TemplateCompileOptions {
+  filename?: string
+  id?: string
+  scoped?: boolean
+  slotted?: boolean
+  isProd?: boolean
+  ssr?: boolean
+  ssrCssVars?: Array<string>
+  transformAssetUrls?: boolean
+  assetUrls?: AssetUrlOptions
+  inline?: boolean
+  bindings?: Array<Binding>
+  compilerOptions?: CompileOptions
+}
+export interface AssetUrlOptions {
+  base?: string
+  includeAbsolute?: boolean
+  tags?: Record<string, Array<string>>
+}
+export interface TemplateCompileResult {
+  code: string
+  preamble?: string | undefined | null
+  map?: string | undefined | null
+  errors: Array<string>
+  tips: Array<string>
+}
+export interface Diagnostic {
+  message: string
+  slug?: string | undefined | null
+  help?: string | undefined | null
+  code?: number | undefined | null
+  start: number
+  end: number
+}
+export function clearCompileCache(): void
+export function compileWithOptions(source: string, options?: CompileOptions | undefined | null): CompileResult
+export function compile(source: string, options?: CompileOptions | undefined | null): Promise<string>
+export function compileTemplateAsync(source: string, options?: CompileOptions | undefined | null): Promise<CompileResult>
+export function compileMany(sources: Array<string>, options?: CompileOptions | undefined | null, files?: BatchFiles | undefined | null): Promise<Array<BatchCompileResult>>
+export function compileTemplate(source: string, options?: TemplateCompileOptions | undefined | null): TemplateCompileResult
+export function templateHash(source: string): string
+export function compareRenders(old: HmrInfo, new: HmrInfo): string
+export function parseToJson(source: string): string
+export function parseToCompatJson(source: string): string
+export function irToJson(source: string, options?: CompileOptions | undefined | null): string
 export function compileSync(source: string): string
@@ -0,0 +1,186 @@
+//! AST in the shape of @vue/compiler-core, returned by `parse` of compiler-dom.js.
+//! Offsets and columns count UTF-16 code units like JS strings do.
+
+use compiler::codegen::utf16_len;
+use compiler::parser::{AstNode, AstRoot, Directive, DirectiveArg, ElemProp, Element, ElementType};
+use compiler::scanner::Attribute;
+use compiler::{Namespace, SourceLocation};
+use serde_json::{json, Value};
+
+// NodeTypes of @vue/compiler-core
+const ROOT: u8 = 0;
+const ELEMENT: u8 = 1;
+const TEXT: u8 = 2;
+const COMMENT: u8 = 3;
+const SIMPLE_EXPRESSION: u8 = 4;
+const INTERPOLATION: u8 = 5;
+const ATTRIBUTE: u8 = 6;
+const DIRECTIVE: u8 = 7;
+
+pub struct CompatAst<'a> {
+    source: &'a str,
+    /// byte offset and UTF-16 offset of each line start
+    lines: Vec<(usize, usize)>,
+}
+
+impl<'a> CompatAst<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let mut lines = vec![(0, 0)];
+        let mut utf16 = 0;
+        for (i, c) in source.char_indices() {
+            utf16 += c.len_utf16();
+            if c == '\n' {
+                lines.push((i + 1, utf16));
+            }
+        }
+        Self { source, lines }
+    }
+
+    pub fn root(&self, root: &AstRoot) -> Value {
+        json!({
+            "type": ROOT,
+            "children": self.children(&root.children),
+            "helpers": [],
+            "components": [],
+            "directives": [],
+            "hoists": [],
+            "imports": [],
+            "cached": 0,
+            "temps": 0,
+            "loc": self.loc(&root.location),
+        })
+    }
+
+    fn children(&self, nodes: &[AstNode]) -> Vec<Value> {
+        nodes.iter().map(|n| self.node(n)).collect()
+    }
+
+    fn node(&self, node: &AstNode) -> Value {
+        match node {
+            AstNode::Element(e) => self.element(e),
+            AstNode::Text(t) => {
+                let content: String = t.text.iter().map(|&s| s.into_string()).collect();
+                json!({ "type": TEXT, "content": content, "loc": self.loc(&t.location) })
+            }
+            AstNode::Comment(c) => json!({
+                "type": COMMENT,
+                "content": c.source,
+                "loc": self.loc(&c.location),
+            }),
+            AstNode::Interpolation(i) => json!({
+                "type": INTERPOLATION,
+                "content": self.expression(i.source.trim(), false),
+                "loc": self.loc(&i.location),
+            }),
+        }
+    }
+
+    fn element(&self, e: &Element) -> Value {
+        let ns = match e.namespace {
+            Namespace::Svg => 1,
+            Namespace::MathMl => 2,
+            _ => 0,
+        };
+        let tag_type = match e.tag_type {
+            ElementType::Plain => 0,
+            ElementType::Component => 1,
+            ElementType::SlotOutlet => 2,
+            ElementType::Template => 3,
+        };
+        let props: Vec<_> = e
+            .properties
+            .iter()
+            .map(|p| match p {
+                ElemProp::Attr(a) => self.attribute(a),
+                ElemProp::Dir(d) => self.directive(d),
+            })
+            .collect();
+        json!({
+            "type": ELEMENT,
+            "ns": ns,
+            "tag": e.tag_name,
+            "tagType": tag_type,
+            "props": props,
+            "isSelfClosing": e.self_closing,
+            "children": self.children(&e.children),
+            "loc": self.loc(&e.location),
+        })
+    }
+
+    fn attribute(&self, a: &Attribute) -> Value {
+        let value = a.value.as_ref().map(|v| {
+            json!({
+                "type": TEXT,
+                "content": v.content.into_string(),
+                "loc": self.loc(&v.location),
+            })
+        });
+        json!({
+            "type": ATTRIBUTE,
+            "name": a.name,
+            "value": value,
+            "loc": self.loc(&a.location),
+        })
+    }
+
+    fn directive(&self, d: &Directive) -> Value {
+        let arg = d.argument.as_ref().map(|arg| match arg {
+            DirectiveArg::Static(name) => self.expression(name, true),
+            DirectiveArg::Dynamic(name) => self.expression(name, false),
+        });
+        let exp = d
+            .expression
+            .as_ref()
+            .map(|v| self.expression(v.content.raw, false));
+        json!({
+            "type": DIRECTIVE,
+            "name": d.name,
+            "exp": exp,
+            "arg": arg,
+            "modifiers": d.modifiers,
+            "loc": self.loc(&d.location),
+        })
+    }
+
+    fn expression(&self, content: &str, is_static: bool) -> Value {
+        // parser keeps expressions as slices of source, locate them by address
+        let start = (content.as_ptr() as usize).wrapping_sub(self.source.as_ptr() as usize);
+        let end = start.wrapping_add(content.len());
+        let loc = if start <= end && end <= self.source.len() {
+            self.span(start, end)
+        } else {
+            Value::Null
+        };
+        json!({
+            "type": SIMPLE_EXPRESSION,
+            "content": content,
+            "isStatic": is_static,
+            // CAN_STRINGIFY for static args, NOT_CONSTANT otherwise
+            "constType": if is_static { 3 } else { 0 },
+            "loc": loc,
+        })
+    }
+
+    fn loc(&self, loc: &SourceLocation) -> Value {
+        self.span(loc.start.offset, loc.end.offset)
+    }
+
+    fn span(&self, start: usize, end: usize) -> Value {
+        json!({
+            "start": self.position(start),
+            "end": self.position(end),
+            "source": &self.source[start..end],
+        })
+    }
+
+    fn position(&self, offset: usize) -> Value {
+        let line = self.lines.partition_point(|&(start, _)| start <= offset);
+        let (line_start, line_utf16) = self.lines[line - 1];
+        let column = utf16_len(&self.source[line_start..offset]);
+        json!({
+            "offset": line_utf16 + column,
+            "line": line,
+            "column": column + 1,
+        })
+    }
+}
@@ -1,10 +1,26 @@
 #![deny(clippy::all)]
 
+mod compat;
+
+use compat::CompatAst;
 use napi_derive::napi;
 use napi::bindgen_prelude::*;
-use compiler::compiler::{BaseCompiler, TemplateCompiler};
-use compiler::error::VecErrorHandler;
+use napi::{Env, Task};
+use compiler::codegen::{utf16_len, ScriptMode};
+use compiler::compiler::{BaseCompiler, CompileOption, TemplateCompiler};
+use compiler::error::{CompilationError, CompileError, RcErrHandle, VecErrorHandler};
+use compiler::hmr;
+use compiler::manifest;
+use compiler::parser::IsCustomElement;
+use compiler::project::{BindingsCache, CompileCache};
+use compiler::{BindingMetadata, BindingTypes, SFCInfo};
 use dom::{get_dom_pass, compile_option};
+use rayon::prelude::*;
+use sfc::{AssetURLOptions, SfcTemplateCompileOptions, TransformAssetUrlOption};
+use rustc_hash::FxHashMap;
+use std::collections::HashMap;
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
 
 #[cfg(all(
@@ -16,45 +32,688 @@ use std::rc::Rc;
 #[global_allocator]
 static ALLOC: mimalloc::MiMalloc = mimalloc::MiMalloc;
 
-// struct AsyncTask(u32);
+#[napi]
+fn compile_sync(source: String) -> Result<String> {
+    let sfc_info = Default::default();
+    let err_handler = VecErrorHandler::default();
+    let option = compile_option(Rc::new(err_handler));
+    let dest = Vec::new;
+    let compiler = BaseCompiler::new(dest, get_dom_pass, option);
+    let ret = compiler
+        .compile(&source, &sfc_info)
+        .map_err(to_napi_error)?;
+    String::from_utf8(ret).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
+
+/// Unsupported syntax rejects with the description instead of aborting node.
+fn to_napi_error(e: CompileError) -> Error {
+    let status = match e {
+        CompileError::Io(_) => Status::GenericFailure,
+        _ => Status::InvalidArg,
+    };
+    Error::new(status, e.to_string())
+}
 
-// impl Task for AsyncTask {
-//   type Output = u32;
-//   type JsValue = JsNumber;
+/// Options accepted by `compileWithOptions` and `compile`.
+/// compiler-dom.js translates @vue/compiler-dom options to this shape.
+#[napi(object)]
+#[derive(Default)]
+pub struct CompileOptions {
+    /// "module" or "function"
+    pub mode: Option<String>,
+    pub prefix_identifiers: Option<bool>,
+    pub hoist_static: Option<bool>,
+    pub cache_handlers: Option<bool>,
+    pub source_map: Option<bool>,
+    pub scope_id: Option<String>,
+    pub filename: Option<String>,
+    /// expressions are TypeScript, e.g. `foo as string` in interpolation
+    pub is_ts: Option<bool>,
+    pub inline: Option<bool>,
+    pub slotted: Option<bool>,
+    pub bindings: Option<Vec<Binding>>,
+    /// bindingMetadata.__isScriptSetup
+    pub script_setup: Option<bool>,
+    /// tags in the template that isCustomElement accepts
+    pub custom_elements: Option<Vec<String>>,
+    /// embed build manifest JSON in the result
+    pub manifest: Option<bool>,
+    /// collect `hmr` info in the result
+    pub hmr: Option<bool>,
+    /// reuse results of unchanged templates across calls, see `clearCompileCache`
+    pub use_cache: Option<bool>,
+}
 
-//   fn compute(&mut self) -> Result<Self::Output> {
-//     use std::thread::sleep;
-//     use std::time::Duration;
-//     sleep(Duration::from_millis(self.0 as u64));
-//     Ok(self.0 * 2)
-//   }
+#[napi(object)]
+pub struct Binding {
+    pub name: String,
+    /// binding type string used by @vue/compiler-core, e.g. "setup-const"
+    pub kind: String,
+}
 
-//   fn resolve(self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
-//     env.create_uint32(output)
-//   }
-// }
+#[napi(object)]
+#[derive(Clone)]
+pub struct CompileResult {
+    pub code: String,
+    /// source map JSON, present if `sourceMap` option is on
+    pub map: Option<String>,
+    pub errors: Vec<String>,
+    /// same errors as `errors`, with help text and doc slug
+    pub diagnostics: Vec<Diagnostic>,
+    /// issues not affecting the output, e.g. self-closing `<div/>`
+    pub warnings: Vec<Diagnostic>,
+    /// build manifest JSON, present if `manifest` option is on
+    pub manifest: Option<String>,
+    /// hash of source, options and bindings, for caching the output
+    pub cache_key: String,
+    /// template has no binding, render output never changes
+    pub is_static: bool,
+    /// pass to `compareRenders` to decide hot reload strategy,
+    /// present if `hmr` option is on
+    pub hmr: Option<HmrInfo>,
+}
 
-// #[module_exports]
-// fn init(mut exports: JsObject) -> Result<()> {
-//   exports.create_named_method("sleep", sleep)?;
-//   Ok(())
-// }
+#[napi(object)]
+#[derive(Clone)]
+pub struct HmrInfo {
+    pub template_hash: String,
+    pub has_slots: bool,
+    pub uses_emit: bool,
+    pub scope_id: Option<String>,
+}
 
+impl From<hmr::HmrInfo> for HmrInfo {
+    fn from(info: hmr::HmrInfo) -> Self {
+        Self {
+            template_hash: info.template_hash,
+            has_slots: info.has_slots,
+            uses_emit: info.uses_emit,
+            scope_id: info.scope_id,
+        }
+    }
+}
+
+impl From<HmrInfo> for hmr::HmrInfo {
+    fn from(info: HmrInfo) -> Self {
+        Self {
+            template_hash: info.template_hash,
+            has_slots: info.has_slots,
+            uses_emit: info.uses_emit,
+            scope_id: info.scope_id,
+        }
+    }
+}
+
+#[napi(object)]
+#[derive(Clone)]
+pub struct Diagnostic {
+    pub message: String,
+    /// stable identifier for documentation, e.g. "v-slot-misplaced"
+    pub slug: Option<String>,
+    /// actionable suggestion to fix the error
+    pub help: Option<String>,
+    /// numeric code shared with @vue/compiler-core, e.g. 30 for X_V_ELSE_NO_ADJACENT_IF
+    pub code: Option<u32>,
+    /// UTF-16 offsets of the offending source span, same as JS string index
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Diagnostic {
+    fn new(e: &CompilationError, source: &str) -> Self {
+        let offset = |byte: usize| utf16_len(&source[..byte.min(source.len())]) as u32;
+        Self {
+            message: e.to_string(),
+            slug: e.slug().map(String::from),
+            help: e.help(),
+            code: e.code(),
+            start: offset(e.location.start.offset),
+            end: offset(e.location.end.offset),
+        }
+    }
+}
+
+/// Templates kept by `useCache`, the least recently used are evicted.
+const COMPILE_CACHE_SIZE: usize = 1000;
+
+lazy_static::lazy_static! {
+    // shared by libuv and rayon workers, see `useCache` option
+    static ref COMPILE_CACHE: CompileCache<CompileResult> =
+        CompileCache::bounded(COMPILE_CACHE_SIZE);
+}
+
+/// Drop all results kept by `useCache`, e.g. after a dependency upgrade.
 #[napi]
-fn compile_sync(source: String) -> String {
-    let sfc_info = Default::default();
+fn clear_compile_cache() {
+    COMPILE_CACHE.clear();
+}
+
+/// Tags accepted by user's isCustomElement, collected by compiler-dom.js.
+fn is_custom_element(tags: Option<&Vec<String>>) -> IsCustomElement {
+    let tags = tags.cloned().unwrap_or_default();
+    Rc::new(move |tag: &str| tags.iter().any(|t| t == tag))
+}
+
+fn binding_type(kind: &str) -> Option<BindingTypes> {
+    use BindingTypes as BT;
+    Some(match kind {
+        "data" => BT::Data,
+        "props" => BT::Props,
+        "setup-let" => BT::SetupLet,
+        "setup-const" => BT::SetupConst,
+        "setup-maybe-ref" => BT::SetupMaybeRef,
+        "setup-ref" => BT::SetupRef,
+        "options" => BT::Options,
+        _ => return None,
+    })
+}
+
+fn to_compile_option(opts: &CompileOptions, eh: RcErrHandle) -> CompileOption {
+    let mode = match opts.mode.as_deref() {
+        Some("module") => ScriptMode::Module {
+            runtime_module_name: "vue".into(),
+        },
+        _ => ScriptMode::Function {
+            prefix_identifier: opts.prefix_identifiers.unwrap_or(false),
+            runtime_global_name: "Vue".into(),
+        },
+    };
+    CompileOption {
+        mode,
+        hoist_static: opts.hoist_static.unwrap_or(false),
+        cache_handlers: opts.cache_handlers.unwrap_or(false),
+        source_map: opts.source_map.unwrap_or(false),
+        filename: opts.filename.clone().unwrap_or_default(),
+        is_ts: opts.is_ts.unwrap_or(false),
+        is_custom_element: is_custom_element(opts.custom_elements.as_ref()),
+        manifest: opts.manifest.unwrap_or(false),
+        hmr: opts.hmr.unwrap_or(false),
+        ..compile_option(eh)
+    }
+}
+
+/// Typos in mode or binding kind would silently compile with the
+/// defaults, so they are rejected before compiling.
+fn validate_options(opts: &CompileOptions) -> Result<()> {
+    let invalid = |msg: String| Err(Error::new(Status::InvalidArg, msg));
+    match opts.mode.as_deref() {
+        None | Some("module") | Some("function") => {}
+        Some(mode) => return invalid(format!("unknown mode \"{}\"", mode)),
+    }
+    let bindings = opts.bindings.as_deref().unwrap_or_default();
+    if let Some(b) = bindings.iter().find(|b| binding_type(&b.kind).is_none()) {
+        return invalid(format!("unknown binding kind \"{}\" of {}", b.kind, b.name));
+    }
+    Ok(())
+}
+
+fn to_binding_metadata(bindings: &[Binding], from_setup: bool) -> BindingMetadata<'_> {
+    let map: FxHashMap<_, _> = bindings
+        .iter()
+        .filter_map(|b| Some((b.name.as_str(), binding_type(&b.kind)?)))
+        .collect();
+    BindingMetadata::new(map, from_setup)
+}
+
+fn to_sfc_info(opts: &CompileOptions) -> SFCInfo<'_> {
+    let bindings = opts.bindings.as_deref().unwrap_or_default();
+    let from_setup = opts.script_setup.unwrap_or(false);
+    SFCInfo {
+        inline: opts.inline.unwrap_or(false),
+        slotted: opts.slotted.unwrap_or(true),
+        scope_id: opts.scope_id.clone(),
+        binding_metadata: to_binding_metadata(bindings, from_setup),
+        self_name: opts.filename.clone().unwrap_or_default(),
+    }
+}
+
+#[napi]
+fn compile_with_options(source: String, options: Option<CompileOptions>) -> Result<CompileResult> {
+    let options = options.unwrap_or_default();
+    compile_impl(&source, &options, &to_sfc_info(&options))
+}
+
+pub struct CompileTask {
+    source: String,
+    options: CompileOptions,
+}
+
+impl Task for CompileTask {
+    type Output = String;
+    type JsValue = String;
+    // runs on libuv thread pool
+    fn compute(&mut self) -> Result<Self::Output> {
+        let sfc_info = to_sfc_info(&self.options);
+        let ret = compile_impl(&self.source, &self.options, &sfc_info)?;
+        Ok(ret.code)
+    }
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+pub struct CompileResultTask {
+    source: String,
+    options: CompileOptions,
+}
+
+/// Fields of the `Error` rejected by `compileTemplateAsync`. Template errors resolve as
+/// `diagnostics`, only failures producing no code reject. They are not
+/// caused by one node, so `start` and `end` span the whole template.
+#[napi(object)]
+pub struct CompileFailure {
+    /// "InvalidOption", "UnsupportedSyntax" or "IoError"
+    pub code: String,
+    pub message: String,
+    pub start: u32,
+    pub end: u32,
+}
+
+impl CompileFailure {
+    fn new(code: &str, message: String, source: &str) -> Self {
+        Self {
+            code: code.into(),
+            message,
+            start: 0,
+            end: utf16_len(source) as u32,
+        }
+    }
+    fn from_compile_error(e: CompileError, source: &str) -> Self {
+        let code = match &e {
+            CompileError::UnsupportedSyntax { .. } => "UnsupportedSyntax",
+            CompileError::Io(_) => "IoError",
+        };
+        Self::new(code, e.to_string(), source)
+    }
+}
+
+impl Task for CompileResultTask {
+    type Output = std::result::Result<CompileResult, CompileFailure>;
+    type JsValue = CompileResult;
+    // runs on libuv thread pool
+    fn compute(&mut self) -> Result<Self::Output> {
+        let source = &self.source;
+        if let Err(e) = validate_options(&self.options) {
+            return Ok(Err(CompileFailure::new("InvalidOption", e.reason, source)));
+        }
+        let sfc_info = to_sfc_info(&self.options);
+        let ret = compile_validated(source, &self.options, &sfc_info);
+        Ok(ret.map_err(|e| CompileFailure::from_compile_error(e, source)))
+    }
+    fn resolve(&mut self, env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        let failure = match output {
+            Ok(ret) => return Ok(ret),
+            Err(failure) => failure,
+        };
+        // reject with an Error carrying the failure fields
+        let error = Error::new(Status::GenericFailure, failure.message);
+        let mut obj = env.create_error(error)?;
+        obj.set_named_property("code", env.create_string(&failure.code)?)?;
+        obj.set_named_property("start", env.create_uint32(failure.start)?)?;
+        obj.set_named_property("end", env.create_uint32(failure.end)?)?;
+        Err(Error::from(obj.into_unknown()))
+    }
+}
+
+/// Compile template off the main thread and resolve with generated code.
+/// `compileTemplateAsync` also resolves with source map and diagnostics.
+#[napi]
+fn compile(source: String, options: Option<CompileOptions>) -> AsyncTask<CompileTask> {
+    AsyncTask::new(CompileTask {
+        source,
+        options: options.unwrap_or_default(),
+    })
+}
+
+/// Like `compileWithOptions` but off the main thread, so that large
+/// templates do not block the event loop. Rejects with an `Error`
+/// holding the fields of `CompileFailure`.
+#[napi]
+fn compile_template_async(
+    source: String,
+    options: Option<CompileOptions>,
+) -> AsyncTask<CompileResultTask> {
+    AsyncTask::new(CompileResultTask {
+        source,
+        options: options.unwrap_or_default(),
+    })
+}
+
+/// Outcome of one template in `compileMany`.
+#[napi(object)]
+pub struct BatchCompileResult {
+    pub code: Option<String>,
+    /// message of compiler failure, the other templates are still compiled
+    pub error: Option<String>,
+    pub cache_key: Option<String>,
+    /// errors in the template, see `CompileResult.diagnostics`
+    pub diagnostics: Vec<Diagnostic>,
+    pub warnings: Vec<Diagnostic>,
+}
+
+impl From<Result<CompileResult>> for BatchCompileResult {
+    fn from(ret: Result<CompileResult>) -> Self {
+        match ret {
+            Ok(ret) => Self {
+                code: Some(ret.code),
+                error: None,
+                cache_key: Some(ret.cache_key),
+                diagnostics: ret.diagnostics,
+                warnings: ret.warnings,
+            },
+            Err(e) => Self {
+                code: None,
+                error: Some(e.reason),
+                cache_key: None,
+                diagnostics: vec![],
+                warnings: vec![],
+            },
+        }
+    }
+}
+
+/// Per-file inputs of `compileMany`, e.g. bindings computed by vue-tsc.
+#[napi(object)]
+pub struct BatchFiles {
+    /// filename of each source, in the same order
+    pub filenames: Vec<String>,
+    /// bindings keyed by filename, overriding `options.bindings`
+    pub bindings: HashMap<String, Vec<Binding>>,
+}
+
+pub struct CompileManyTask {
+    sources: Vec<String>,
+    options: CompileOptions,
+    files: Option<BatchFiles>,
+}
+
+impl CompileManyTask {
+    // resolved before compiling since the cache is not shared across threads
+    fn sfc_infos(&self) -> Vec<SFCInfo<'_>> {
+        let files = match &self.files {
+            Some(files) => files,
+            None => {
+                return self
+                    .sources
+                    .iter()
+                    .map(|_| to_sfc_info(&self.options))
+                    .collect()
+            }
+        };
+        let from_setup = self.options.script_setup.unwrap_or(false);
+        let mut cache = BindingsCache::new(|filename: &str| {
+            let bindings = files.bindings.get(filename)?;
+            Some(to_binding_metadata(bindings, from_setup))
+        });
+        (0..self.sources.len())
+            .map(|i| {
+                let mut sfc_info = to_sfc_info(&self.options);
+                if let Some(filename) = files.filenames.get(i) {
+                    if let Some(bindings) = cache.get(filename) {
+                        sfc_info.binding_metadata = bindings.clone();
+                    }
+                    sfc_info.self_name = filename.clone();
+                }
+                sfc_info
+            })
+            .collect()
+    }
+}
+
+impl Task for CompileManyTask {
+    type Output = Vec<BatchCompileResult>;
+    type JsValue = Vec<BatchCompileResult>;
+    fn compute(&mut self) -> Result<Self::Output> {
+        let options = &self.options;
+        let sfc_infos = self.sfc_infos();
+        let results = self
+            .sources
+            .par_iter()
+            .zip(sfc_infos.par_iter())
+            .map(|(source, sfc_info)| {
+                // a panicking template should not abort the whole batch
+                let compile = || compile_impl(source, options, sfc_info);
+                let ret = panic::catch_unwind(AssertUnwindSafe(compile));
+                ret.unwrap_or_else(|_| {
+                    let msg = "compiler panicked".to_string();
+                    Err(Error::new(Status::GenericFailure, msg))
+                })
+                .into()
+            })
+            .collect();
+        Ok(results)
+    }
+    fn resolve(&mut self, _env: Env, output: Self::Output) -> Result<Self::JsValue> {
+        Ok(output)
+    }
+}
+
+/// Compile templates in parallel on rayon thread pool.
+/// Results are in the same order as sources.
+#[napi]
+fn compile_many(
+    sources: Vec<String>,
+    options: Option<CompileOptions>,
+    files: Option<BatchFiles>,
+) -> AsyncTask<CompileManyTask> {
+    AsyncTask::new(CompileManyTask {
+        sources,
+        options: options.unwrap_or_default(),
+        files,
+    })
+}
+
+fn compile_impl(source: &str, opts: &CompileOptions, sfc_info: &SFCInfo) -> Result<CompileResult> {
+    validate_options(opts)?;
+    compile_validated(source, opts, sfc_info).map_err(to_napi_error)
+}
+
+fn compile_validated(
+    source: &str,
+    opts: &CompileOptions,
+    sfc_info: &SFCInfo,
+) -> std::result::Result<CompileResult, CompileError> {
+    let err_handler = Rc::new(VecErrorHandler::default());
+    let option = to_compile_option(opts, err_handler.clone());
+    let fingerprint = option.fingerprint();
+    let cache_key = manifest::cache_key(
+        source,
+        &fingerprint,
+        &sfc_info.binding_metadata.fingerprint(),
+    );
+    let cached = opts.use_cache.unwrap_or(false).then(|| {
+        // custom elements and extra outputs are not in the option fingerprint
+        let options_key = format!(
+            "{}|{}|{}|{:?}",
+            fingerprint, option.manifest, option.hmr, opts.custom_elements
+        );
+        CompileCache::key(source, &options_key, sfc_info)
+    });
+    if let Some(ret) = cached.as_ref().and_then(|key| COMPILE_CACHE.get(key)) {
+        return Ok(ret);
+    }
+    let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+    let ir = compiler.compile_to_ir(source, sfc_info);
+    // render code is rarely shorter than its template, grow from there
+    let code = Vec::with_capacity(source.len());
+    let ret = compiler.generate_output_into(ir, sfc_info, source, code)?;
+    let errors = err_handler.errors();
+    let result = CompileResult {
+        code: String::from_utf8(ret.code)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+        map: ret.map,
+        errors: errors.iter().map(|e| e.to_string()).collect(),
+        diagnostics: errors.iter().map(|e| Diagnostic::new(e, source)).collect(),
+        warnings: err_handler
+            .warnings()
+            .iter()
+            .map(|e| Diagnostic::new(e, source))
+            .collect(),
+        cache_key,
+        is_static: ret.is_static,
+        manifest: ret.manifest.map(|m| m.to_json()),
+        hmr: ret.hmr.map(HmrInfo::from),
+    };
+    if let Some(key) = cached {
+        COMPILE_CACHE.insert(key, result.clone());
+    }
+    Ok(result)
+}
+
+/// Options of `compileTemplate`, same as @vue/compiler-sfc's compileTemplate.
+#[napi(object)]
+#[derive(Default)]
+pub struct TemplateCompileOptions {
+    pub filename: Option<String>,
+    /// scope id shared with styles, `data-v-` prefix is optional
+    pub id: Option<String>,
+    pub scoped: Option<bool>,
+    pub slotted: Option<bool>,
+    pub is_prod: Option<bool>,
+    pub ssr: Option<bool>,
+    /// variables bound by `v-bind()` in `<style>`, rendered on SSR root elements
+    pub ssr_css_vars: Option<Vec<String>>,
+    /// `false` keeps asset urls as is, default is true
+    pub transform_asset_urls: Option<bool>,
+    /// configure the asset url transform, default tags are used if absent
+    pub asset_urls: Option<AssetUrlOptions>,
+    /// compile render for inlining in setup(), see `preamble` in result
+    pub inline: Option<bool>,
+    pub bindings: Option<Vec<Binding>>,
+    /// only `hoistStatic`, `cacheHandlers` and `customElements` are used,
+    /// other options are derived from the SFC options above
+    pub compiler_options: Option<CompileOptions>,
+}
+
+/// Same as `AssetURLOptions` of @vue/compiler-sfc.
+#[napi(object)]
+pub struct AssetUrlOptions {
+    /// rewrite relative urls to absolute ones under base instead of importing
+    pub base: Option<String>,
+    pub include_absolute: Option<bool>,
+    /// tag name to attributes holding urls, `*` matches all tags
+    pub tags: Option<HashMap<String, Vec<String>>>,
+}
+
+#[napi(object)]
+pub struct TemplateCompileResult {
+    pub code: String,
+    /// helper imports and hoists, present if `inline` option is on
+    pub preamble: Option<String>,
+    pub map: Option<String>,
+    pub errors: Vec<String>,
+    pub tips: Vec<String>,
+}
+
+#[napi]
+fn compile_template(
+    source: String,
+    options: Option<TemplateCompileOptions>,
+) -> Result<TemplateCompileResult> {
+    let opts = options.unwrap_or_default();
+    let mut sfc_opts = SfcTemplateCompileOptions {
+        source: &source,
+        filename: opts.filename.as_deref().unwrap_or("anonymous.vue"),
+        id: opts.id.as_deref().unwrap_or_default(),
+        scoped: opts.scoped.unwrap_or(false),
+        slotted: opts.slotted.unwrap_or(true),
+        is_prod: opts.is_prod.unwrap_or(false),
+        ssr: opts.ssr.unwrap_or(false),
+        inline: opts.inline.unwrap_or(false),
+        ..Default::default()
+    };
+    if let Some(vars) = &opts.ssr_css_vars {
+        sfc_opts.ssr_css_vars = vars.iter().map(String::as_str).collect();
+    }
+    sfc_opts.transform_asset_urls = to_asset_url_option(opts.transform_asset_urls, opts.asset_urls);
+    if let Some(bindings) = &opts.bindings {
+        // bindings of SFC are always analyzed from script setup
+        sfc_opts.binding_metadata = to_binding_metadata(bindings, true);
+    }
+    if let Some(compiler_opts) = &opts.compiler_options {
+        let option = &mut sfc_opts.compile_option;
+        option.hoist_static = compiler_opts.hoist_static.unwrap_or(option.hoist_static);
+        option.cache_handlers = compiler_opts
+            .cache_handlers
+            .unwrap_or(option.cache_handlers);
+        option.is_custom_element = is_custom_element(compiler_opts.custom_elements.as_ref());
+    }
+    let ret = sfc::compile_template(sfc_opts).map_err(to_napi_error)?;
+    Ok(TemplateCompileResult {
+        code: ret.code,
+        preamble: ret.preamble,
+        map: ret.map,
+        errors: ret.errors.iter().map(|e| e.to_string()).collect(),
+        tips: ret.tips,
+    })
+}
+
+fn to_asset_url_option(
+    enabled: Option<bool>,
+    opts: Option<AssetUrlOptions>,
+) -> TransformAssetUrlOption {
+    if enabled == Some(false) {
+        return TransformAssetUrlOption::NoTransform;
+    }
+    let opts = match opts {
+        Some(opts) => opts,
+        None => return TransformAssetUrlOption::Url(Default::default()),
+    };
+    let mut ret = AssetURLOptions {
+        base: opts.base,
+        include_absolute: opts.include_absolute.unwrap_or(false),
+        ..Default::default()
+    };
+    if let Some(tags) = opts.tags {
+        ret.tags = tags.into_iter().collect();
+    }
+    TransformAssetUrlOption::Url(ret)
+}
+
+/// Hash of template ignoring formatting whitespace. Skip recompiling if unchanged.
+#[napi]
+fn template_hash(source: String) -> String {
+    hmr::template_hash(&source)
+}
+
+/// Returns "rerender" if swapping render function is enough, otherwise "reload".
+#[napi]
+fn compare_renders(old: HmrInfo, new: HmrInfo) -> String {
+    match hmr::compare_hmr_info(&old.into(), &new.into()) {
+        hmr::HmrAction::RerenderOnly => "rerender".into(),
+        hmr::HmrAction::Reload => "reload".into(),
+    }
+}
+
+/// Parse template and return the AST serialized as JSON
+#[napi]
+fn parse_to_json(source: String) -> Result<String> {
     let err_handler = VecErrorHandler::default();
     let option = compile_option(Rc::new(err_handler));
-    let dest = Vec::new;
-    let compiler = BaseCompiler::new(dest, get_dom_pass, option);
-    let ret = compiler.compile(&source, &sfc_info).unwrap();
-    String::from_utf8(ret).unwrap()
+    let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+    let tokens = compiler.scan(&source);
+    let ast = compiler.parse(tokens);
+    serde_json::to_string(&ast).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}
+
+/// Parse template and return the AST in @vue/compiler-core shape as JSON
+#[napi]
+fn parse_to_compat_json(source: String) -> String {
+    let option = compile_option(Rc::new(VecErrorHandler::default()));
+    let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+    let ast = compiler.parse(compiler.scan(&source));
+    CompatAst::new(&source).root(&ast).to_string()
 }
 
-// #[js_function(1)]
-// fn sleep(ctx: CallContext) -> Result<JsObject> {
-//   let argument: u32 = ctx.get::<JsNumber>(0)?.try_into()?;
-//   let task = AsyncTask(argument);
-//   let async_task = ctx.env.spawn(task)?;
-//   Ok(async_task.promise_object())
-// }
+/// Compile template and return the transformed IR serialized as JSON,
+/// for tools inspecting templates without reimplementing the compiler.
+#[napi]
+fn ir_to_json(source: String, options: Option<CompileOptions>) -> Result<String> {
+    let opts = options.unwrap_or_default();
+    validate_options(&opts)?;
+    let sfc_info = to_sfc_info(&opts);
+    let option = to_compile_option(&opts, Rc::new(VecErrorHandler::default()));
+    let compiler = BaseCompiler::new(Vec::new, get_dom_pass, option);
+    let ir = compiler.compile_to_ir(&source, &sfc_info);
+    serde_json::to_string(&ir).map_err(|e| Error::new(Status::GenericFailure, e.to_string()))
+}